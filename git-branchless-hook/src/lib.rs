@@ -532,7 +532,7 @@ pub fn command_main(ctx: CommandContext, args: HookArgs) -> EyreExitOr<()> {
         }
 
         HookSubcommand::PreAutoGc => {
-            gc(&effects)?;
+            gc(&effects, None)?;
         }
 
         HookSubcommand::PostApplypatch => {
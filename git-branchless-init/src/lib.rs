@@ -82,7 +82,9 @@ echo 'branchless: This is a bug. Please report it.'
     ),
 ];
 
-const ALL_ALIASES: &[(&str, &str)] = &[
+/// The aliases to install, as pairs of the alias name and the `git-branchless`
+/// subcommand which it invokes.
+pub const ALL_ALIASES: &[(&str, &str)] = &[
     ("amend", "amend"),
     ("hide", "hide"),
     ("move", "move"),
@@ -116,17 +118,56 @@ pub enum Hook {
         /// The path to the hook script.
         path: PathBuf,
     },
+
+    /// A hook script run by a dispatcher hook, which runs each executable in
+    /// the `<hook-type>.d` directory next to it. This lets `git-branchless`
+    /// coexist with other tools which install hooks.
+    DispatchedHook {
+        /// The path to the dispatcher hook script.
+        dispatcher_path: PathBuf,
+
+        /// The path to the hook script.
+        path: PathBuf,
+    },
+}
+
+impl Hook {
+    /// The path to the script which contains the `git-branchless` hook.
+    pub fn get_path(&self) -> &Path {
+        match self {
+            Hook::RegularHook { path }
+            | Hook::MultiHook { path }
+            | Hook::DispatchedHook {
+                dispatcher_path: _,
+                path,
+            } => path,
+        }
+    }
+}
+
+fn get_dispatcher_dir(hooks_dir: &Path, hook_type: &str) -> PathBuf {
+    hooks_dir.join(format!("{hook_type}.d"))
 }
 
 /// Determine the path where all hooks are installed.
+///
+/// If the `<hook-type>.d` directory exists in the hooks directory, as created
+/// by `git branchless init --hook-dispatcher`, then the hook is installed into
+/// it, to be run by a dispatcher hook.
 #[instrument]
 pub fn determine_hook_path(repo: &Repo, hooks_dir: &Path, hook_type: &str) -> eyre::Result<Hook> {
     let multi_hooks_path = repo.get_path().join("hooks_multi");
+    let dispatcher_dir = get_dispatcher_dir(hooks_dir, hook_type);
     let hook = if multi_hooks_path.exists() {
         let path = multi_hooks_path
             .join(format!("{hook_type}.d"))
             .join("00_local_branchless");
         Hook::MultiHook { path }
+    } else if dispatcher_dir.is_dir() {
+        Hook::DispatchedHook {
+            dispatcher_path: hooks_dir.join(hook_type),
+            path: dispatcher_dir.join(DISPATCHED_HOOK_NAME),
+        }
     } else {
         let path = hooks_dir.join(hook_type);
         Hook::RegularHook { path }
@@ -137,6 +178,29 @@ pub fn determine_hook_path(repo: &Repo, hooks_dir: &Path, hook_type: &str) -> ey
 const SHEBANG: &str = "#!/bin/sh";
 const UPDATE_MARKER_START: &str = "## START BRANCHLESS CONFIG";
 const UPDATE_MARKER_END: &str = "## END BRANCHLESS CONFIG";
+const DISPATCHER_MARKER: &str = "## BRANCHLESS HOOK DISPATCHER";
+const DISPATCHED_HOOK_NAME: &str = "branchless";
+const PREVIOUS_HOOK_SUFFIX: &str = "branchless-previous";
+
+/// Run the previous hooks and then each executable in the `<hook-type>.d`
+/// directory with the arguments and standard input of the hook. The standard
+/// input is saved to a temporary file so that it can be provided to each
+/// executable.
+///
+/// The previous hooks are kept in the hooks directory itself, since they may
+/// refer to other files relative to `$(dirname "$0")`.
+const DISPATCHER_SCRIPT: &str = r#"
+stdin_file=$(mktemp) || exit 1
+trap 'rm -f "$stdin_file"' EXIT
+cat >"$stdin_file"
+exit_code=0
+for hook in "$0".branchless-previous* "$0".d/*; do
+    if [ -f "$hook" ] && [ -x "$hook" ]; then
+        "$hook" "$@" <"$stdin_file" || exit_code=$?
+    fi
+done
+exit "$exit_code"
+"#;
 
 fn append_hook(new_lines: &mut String, hook_contents: &str) {
     new_lines.push_str(UPDATE_MARKER_START);
@@ -146,6 +210,22 @@ fn append_hook(new_lines: &mut String, hook_contents: &str) {
     new_lines.push('\n');
 }
 
+/// Get the lines between the branchless markers in a hook script, if any.
+fn get_between_lines(lines: &str) -> Option<String> {
+    let mut result: Option<String> = None;
+    for line in lines.lines() {
+        if line == UPDATE_MARKER_START {
+            result = Some(String::new());
+        } else if line == UPDATE_MARKER_END {
+            return result;
+        } else if let Some(result) = result.as_mut() {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    None
+}
+
 fn update_between_lines(lines: &str, updated_lines: &str) -> String {
     let mut new_lines = String::new();
     let mut found_marker = false;
@@ -170,6 +250,14 @@ fn update_between_lines(lines: &str, updated_lines: &str) -> String {
     new_lines
 }
 
+fn get_script_contents(contents: &str) -> String {
+    if should_use_separate_command_binary("hook") {
+        contents.replace("branchless hook", "branchless-hook")
+    } else {
+        contents.to_string()
+    }
+}
+
 #[instrument]
 fn write_script(path: &Path, contents: &str) -> eyre::Result<()> {
     let script_dir = path
@@ -177,11 +265,7 @@ fn write_script(path: &Path, contents: &str) -> eyre::Result<()> {
         .ok_or_else(|| eyre::eyre!("No parent for dir {:?}", path))?;
     std::fs::create_dir_all(script_dir).wrap_err("Creating script dir")?;
 
-    let contents = if should_use_separate_command_binary("hook") {
-        contents.replace("branchless hook", "branchless-hook")
-    } else {
-        contents.to_string()
-    };
+    let contents = get_script_contents(contents);
     std::fs::write(path, contents).wrap_err("Writing script contents")?;
 
     // Setting hook file as executable only supported on Unix systems.
@@ -220,6 +304,13 @@ fn update_hook_contents(hook: &Hook, hook_contents: &str) -> eyre::Result<()> {
             }
         },
         Hook::MultiHook { path } => (path, format!("{SHEBANG}\n{hook_contents}")),
+        Hook::DispatchedHook {
+            dispatcher_path,
+            path,
+        } => {
+            install_dispatcher(dispatcher_path)?;
+            (path, format!("{SHEBANG}\n{hook_contents}"))
+        }
     };
 
     write_script(hook_path, &hook_contents).wrap_err("Writing hook script")?;
@@ -227,8 +318,98 @@ fn update_hook_contents(hook: &Hook, hook_contents: &str) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Remove the lines between the branchless markers in a hook script, along
+/// with the markers themselves.
+fn remove_between_lines(lines: &str) -> String {
+    let mut new_lines = String::new();
+    let mut is_between_markers = false;
+    for line in lines.lines() {
+        if line == UPDATE_MARKER_START {
+            is_between_markers = true;
+        } else if line == UPDATE_MARKER_END {
+            is_between_markers = false;
+        } else if !is_between_markers {
+            new_lines.push_str(line);
+            new_lines.push('\n');
+        }
+    }
+    new_lines
+}
+
+/// Move the existing hook at `hook_path` to an unused
+/// `<hook-type>.branchless-previous` path next to it. The hook is renamed
+/// rather than rewritten, so that its contents and permissions are preserved,
+/// unless it contains a `git-branchless` hook from a previous installation,
+/// which is removed.
+fn move_previous_hook(hook_path: &Path) -> eyre::Result<()> {
+    let hook_file_name = hook_path
+        .file_name()
+        .ok_or_else(|| eyre::eyre!("No file name for hook {:?}", hook_path))?
+        .to_string_lossy();
+    let previous_hook_path = (0..)
+        .map(|i| match i {
+            0 => hook_path.with_file_name(format!("{hook_file_name}.{PREVIOUS_HOOK_SUFFIX}")),
+            i => hook_path.with_file_name(format!("{hook_file_name}.{PREVIOUS_HOOK_SUFFIX}-{i}")),
+        })
+        .find(|path| !path.exists())
+        .unwrap();
+    std::fs::rename(hook_path, &previous_hook_path).wrap_err("Moving previous hook script")?;
+
+    match std::fs::read_to_string(&previous_hook_path) {
+        Ok(lines) if lines.lines().any(|line| line == UPDATE_MARKER_START) => {
+            let previous_lines = remove_between_lines(&lines);
+            if previous_lines.trim() == SHEBANG || previous_lines.trim().is_empty() {
+                std::fs::remove_file(&previous_hook_path)
+                    .wrap_err("Removing previous hook script")?;
+            } else {
+                std::fs::write(&previous_hook_path, previous_lines)
+                    .wrap_err("Writing previous hook script")?;
+            }
+        }
+        Ok(_) => {}
+        // The hook isn't text, so it doesn't contain a `git-branchless` hook.
+        Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {}
+        Err(err) => return Err(eyre::eyre!(err)),
+    }
+    Ok(())
+}
+
+/// Write the dispatcher hook script at `dispatcher_path`. If there was already
+/// a hook there which isn't a dispatcher, it's moved to
+/// `<hook-type>.branchless-previous` in the same directory so that the
+/// dispatcher keeps running it.
+#[instrument]
+fn install_dispatcher(dispatcher_path: &Path) -> eyre::Result<()> {
+    match std::fs::read(dispatcher_path) {
+        Ok(contents) => {
+            let is_dispatcher = contents
+                .windows(DISPATCHER_MARKER.len())
+                .any(|window| window == DISPATCHER_MARKER.as_bytes());
+            if !is_dispatcher {
+                move_previous_hook(dispatcher_path)?;
+            }
+        }
+        Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(other) => {
+            return Err(eyre::eyre!(other));
+        }
+    }
+
+    write_script(
+        dispatcher_path,
+        &format!(
+            "{SHEBANG}\n{DISPATCHER_MARKER}\n{}",
+            DISPATCHER_SCRIPT.trim_start()
+        ),
+    )
+    .wrap_err("Writing dispatcher script")?;
+    Ok(())
+}
+
+/// Install the given hook script for the given hook type, preserving any
+/// other contents of an existing hook.
 #[instrument]
-fn install_hook(
+pub fn install_hook(
     repo: &Repo,
     hooks_dir: &Path,
     hook_type: &str,
@@ -239,8 +420,139 @@ fn install_hook(
     Ok(())
 }
 
+/// Read the contents of the script at the given path, or `None` if it doesn't
+/// exist or isn't executable.
+fn read_executable_script(path: &Path) -> eyre::Result<Option<String>> {
+    let lines = match std::fs::read(path) {
+        Ok(contents) => String::from_utf8_lossy(&contents).into_owned(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(eyre::eyre!(err)),
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(path).wrap_err("Reading script permissions")?;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(lines))
+}
+
+/// Determine whether the given hook is installed, is executable, and runs the
+/// given hook script.
 #[instrument]
-fn install_hooks(effects: &Effects, git_run_info: &GitRunInfo, repo: &Repo) -> eyre::Result<()> {
+pub fn is_hook_installed(hook: &Hook, hook_script: &str) -> eyre::Result<bool> {
+    if let Hook::DispatchedHook {
+        dispatcher_path,
+        path: _,
+    } = hook
+    {
+        match read_executable_script(dispatcher_path)? {
+            Some(lines) if lines.contains(DISPATCHER_MARKER) => {}
+            Some(_) | None => return Ok(false),
+        }
+    }
+
+    let lines = match read_executable_script(hook.get_path())? {
+        Some(lines) => lines,
+        None => return Ok(false),
+    };
+    let installed_script = match hook {
+        Hook::RegularHook { path: _ } => get_between_lines(&lines),
+        Hook::MultiHook { path: _ }
+        | Hook::DispatchedHook {
+            dispatcher_path: _,
+            path: _,
+        } => lines
+            .strip_prefix(SHEBANG)
+            .map(|installed_script| installed_script.to_owned()),
+    };
+    let expected_script = get_script_contents(hook_script);
+    Ok(installed_script
+        .map(|installed_script| installed_script.trim() == expected_script.trim())
+        .unwrap_or(false))
+}
+
+/// A tool which manages the hooks of a repository, and which may overwrite the
+/// hooks installed by `git-branchless`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookFramework {
+    /// <https://typicode.github.io/husky/>
+    Husky,
+
+    /// <https://github.com/evilmartians/lefthook>
+    Lefthook,
+
+    /// <https://pre-commit.com/>
+    PreCommit,
+}
+
+impl std::fmt::Display for HookFramework {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookFramework::Husky => write!(f, "husky"),
+            HookFramework::Lefthook => write!(f, "lefthook"),
+            HookFramework::PreCommit => write!(f, "pre-commit"),
+        }
+    }
+}
+
+/// Detect the tools which manage the hooks in the given hooks directory, based
+/// on their configuration files in the working copy and the contents of the
+/// hooks which they generate.
+#[instrument]
+pub fn detect_hook_frameworks(repo: &Repo, hooks_dir: &Path) -> eyre::Result<Vec<HookFramework>> {
+    let has_working_copy_file = |file_names: &[&str]| match repo.get_working_copy_path() {
+        Some(working_copy_path) => file_names
+            .iter()
+            .any(|file_name| working_copy_path.join(file_name).exists()),
+        None => false,
+    };
+
+    let mut hook_contents = String::new();
+    for (hook_type, _hook_script) in ALL_HOOKS {
+        match std::fs::read(hooks_dir.join(hook_type)) {
+            Ok(contents) => hook_contents.push_str(&String::from_utf8_lossy(&contents)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(eyre::eyre!(err)),
+        }
+    }
+
+    let mut result = Vec::new();
+    if has_working_copy_file(&[".husky"])
+        || hooks_dir
+            .components()
+            .any(|component| component.as_os_str() == ".husky")
+    {
+        result.push(HookFramework::Husky);
+    }
+    if has_working_copy_file(&[
+        "lefthook.yml",
+        "lefthook.yaml",
+        ".lefthook.yml",
+        ".lefthook.yaml",
+    ]) || hook_contents.contains("lefthook")
+    {
+        result.push(HookFramework::Lefthook);
+    }
+    if has_working_copy_file(&[".pre-commit-config.yaml"])
+        || hook_contents.contains("File generated by pre-commit")
+    {
+        result.push(HookFramework::PreCommit);
+    }
+    Ok(result)
+}
+
+#[instrument]
+fn install_hooks(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    hook_dispatcher: bool,
+) -> eyre::Result<()> {
     writeln!(
         effects.get_output_stream(),
         "Installing hooks: {}",
@@ -250,10 +562,50 @@ fn install_hooks(effects: &Effects, git_run_info: &GitRunInfo, repo: &Repo) -> e
             .join(", ")
     )?;
     let hooks_dir = get_hooks_dir(git_run_info, repo, None)?;
+    let hook_frameworks = detect_hook_frameworks(repo, &hooks_dir)?;
+    if hook_dispatcher {
+        for (hook_type, _hook_script) in ALL_HOOKS {
+            let dispatcher_dir = get_dispatcher_dir(&hooks_dir, hook_type);
+            std::fs::create_dir_all(&dispatcher_dir)
+                .wrap_err_with(|| format!("Creating hook dispatcher dir {dispatcher_dir:?}"))?;
+        }
+    }
     for (hook_type, hook_script) in ALL_HOOKS {
         install_hook(repo, &hooks_dir, hook_type, hook_script)?;
     }
 
+    let mut uninstalled_hook_types = Vec::new();
+    for (hook_type, hook_script) in ALL_HOOKS {
+        let hook = determine_hook_path(repo, &hooks_dir, hook_type)?;
+        if !is_hook_installed(&hook, hook_script)? {
+            uninstalled_hook_types.push(hook_type);
+        }
+    }
+    if !uninstalled_hook_types.is_empty() {
+        writeln!(
+            effects.get_output_stream(),
+            "{}: these hooks do not invoke `git branchless hook` after installation: {}",
+            style("Warning").yellow().bold(),
+            uninstalled_hook_types.into_iter().join(", ")
+        )?;
+    }
+
+    if !hook_frameworks.is_empty() {
+        writeln!(
+            effects.get_output_stream(),
+            "Detected other tools which manage Git hooks: {}",
+            hook_frameworks.iter().join(", ")
+        )?;
+        if !hook_dispatcher {
+            writeln!(
+                effects.get_output_stream(),
+                "\
+They may overwrite the hooks above. To run the hooks from all tools, run:
+git branchless init --hook-dispatcher"
+            )?;
+        }
+    }
+
     let default_hooks_dir = get_default_hooks_dir(repo);
     if hooks_dir != default_hooks_dir {
         writeln!(
@@ -312,6 +664,16 @@ fn should_use_wrapped_command_alias() -> bool {
     cfg!(feature = "man-pages")
 }
 
+/// Get the value of the alias which invokes the given `git-branchless`
+/// subcommand.
+pub fn get_alias_value(command: &str) -> String {
+    if should_use_wrapped_command_alias() {
+        format!("branchless-{command}")
+    } else {
+        format!("branchless {command}")
+    }
+}
+
 #[instrument]
 fn install_alias(
     effects: &Effects,
@@ -337,17 +699,14 @@ fn install_alias(
         return Ok(());
     }
 
-    let alias = if should_use_wrapped_command_alias() {
-        format!("branchless-{to}")
-    } else {
-        format!("branchless {to}")
-    };
-    config.set(&alias_key, alias)?;
+    config.set(&alias_key, get_alias_value(to))?;
     Ok(())
 }
 
+/// Guess the name of the main branch from the local branches which exist in
+/// the repository.
 #[instrument]
-fn detect_main_branch_name(repo: &Repo) -> eyre::Result<Option<String>> {
+pub fn detect_main_branch_name(repo: &Repo) -> eyre::Result<Option<String>> {
     if let Some(default_branch_name) = get_default_branch_name(repo)? {
         if repo
             .find_branch(&default_branch_name, BranchType::Local)?
@@ -373,6 +732,30 @@ fn detect_main_branch_name(repo: &Repo) -> eyre::Result<Option<String>> {
     Ok(None)
 }
 
+/// The first version of Git which supports the `reference-transaction` hook,
+/// which is needed to track branch updates.
+pub const MIN_REFERENCE_TRANSACTION_GIT_VERSION: GitVersion = GitVersion(2, 29, 0);
+
+/// Get the version of Git which will be invoked, along with the raw output of
+/// `git version`.
+#[instrument]
+pub fn get_git_version(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+) -> eyre::Result<(GitVersion, String)> {
+    let version_str = git_run_info
+        .run_silent(repo, None, &["version"], Default::default())
+        .wrap_err("Determining Git version")?
+        .stdout;
+    let version_str =
+        String::from_utf8(version_str).wrap_err("Decoding stdout from Git subprocess")?;
+    let version_str = version_str.trim().to_owned();
+    let version: GitVersion = version_str
+        .parse()
+        .wrap_err_with(|| format!("Parsing Git version string: {version_str}"))?;
+    Ok((version, version_str))
+}
+
 #[instrument]
 fn install_aliases(
     effects: &Effects,
@@ -385,17 +768,9 @@ fn install_aliases(
         install_alias(effects, repo, config, default_config, from, to)?;
     }
 
-    let version_str = git_run_info
-        .run_silent(repo, None, &["version"], Default::default())
-        .wrap_err("Determining Git version")?
-        .stdout;
-    let version_str =
-        String::from_utf8(version_str).wrap_err("Decoding stdout from Git subprocess")?;
-    let version_str = version_str.trim();
-    let version: GitVersion = version_str
-        .parse()
-        .wrap_err_with(|| format!("Parsing Git version string: {version_str}"))?;
-    if version < GitVersion(2, 29, 0) {
+    let (version, version_str) = get_git_version(git_run_info, repo)?;
+    let version_str = version_str.as_str();
+    if version < MIN_REFERENCE_TRANSACTION_GIT_VERSION {
         write!(
             effects.get_output_stream(),
             "\
@@ -601,17 +976,18 @@ fn command_init(
     effects: &Effects,
     git_run_info: &GitRunInfo,
     main_branch_name: Option<&str>,
+    hook_dispatcher: bool,
 ) -> EyreExitOr<()> {
     let mut in_ = BufReader::new(stdin());
     let repo = Repo::from_current_dir()?;
     let mut repo = repo.open_worktree_parent_repo()?.unwrap_or(repo);
 
     let default_config = Config::open_default()?;
-    let readonly_config = repo.get_readonly_config()?;
-    let mut config = create_isolated_config(effects, &repo, readonly_config.into_config())?;
+    let local_config = repo.get_local_config()?;
+    let mut config = create_isolated_config(effects, &repo, local_config)?;
 
     set_configs(&mut in_, effects, &repo, &mut config, main_branch_name)?;
-    install_hooks(effects, git_run_info, &repo)?;
+    install_hooks(effects, git_run_info, &repo, hook_dispatcher)?;
     install_aliases(
         effects,
         &mut repo,
@@ -658,8 +1034,8 @@ fn command_init(
 #[instrument]
 fn command_uninstall(effects: &Effects, git_run_info: &GitRunInfo) -> EyreExitOr<()> {
     let repo = Repo::from_current_dir()?;
-    let readonly_config = repo.get_readonly_config().wrap_err("Getting repo config")?;
-    delete_isolated_config(effects, &repo, readonly_config.into_config())?;
+    let local_config = repo.get_local_config().wrap_err("Getting repo config")?;
+    delete_isolated_config(effects, &repo, local_config)?;
     uninstall_hooks(effects, git_run_info, &repo)?;
     Ok(Ok(()))
 }
@@ -675,11 +1051,18 @@ pub fn command_main(ctx: CommandContext, args: InitArgs) -> EyreExitOr<()> {
         InitArgs {
             uninstall: false,
             main_branch_name,
-        } => command_init(&effects, &git_run_info, main_branch_name.as_deref()),
+            hook_dispatcher,
+        } => command_init(
+            &effects,
+            &git_run_info,
+            main_branch_name.as_deref(),
+            hook_dispatcher,
+        ),
 
         InitArgs {
             uninstall: true,
             main_branch_name: _,
+            hook_dispatcher: _,
         } => command_uninstall(&effects, &git_run_info),
     }
 }
@@ -16,20 +16,16 @@
 use std::any::Any;
 use std::convert::TryInto;
 use std::ffi::OsString;
-use std::fmt::Write;
 use std::path::PathBuf;
 use std::time::SystemTime;
 
 use clap::{CommandFactory, FromArgMatches, Parser};
-use cursive_core::theme::BaseColor;
-use cursive_core::utils::markup::StyledString;
 use eyre::Context;
 use git_branchless_opts::{ColorSetting, GlobalArgs};
 use lib::core::config::env_vars::get_path_to_git;
 use lib::core::effects::Effects;
 use lib::core::formatting::Glyphs;
 use lib::git::GitRunInfo;
-use lib::git::{Repo, RepoError};
 use lib::util::{ExitCode, EyreExitOr};
 use tracing::level_filters::LevelFilter;
 use tracing::{info, instrument, warn};
@@ -126,41 +122,6 @@ fn install_libgit2_tracing() {
     }
 }
 
-#[instrument]
-fn check_unsupported_config_options(effects: &Effects) -> eyre::Result<Option<ExitCode>> {
-    let _repo = match Repo::from_current_dir() {
-        Ok(repo) => repo,
-        Err(RepoError::UnsupportedExtensionWorktreeConfig(_)) => {
-            writeln!(
-                effects.get_output_stream(),
-                "\
-{error}
-
-Usually, this configuration setting is enabled when initializing a sparse
-checkout. See https://github.com/arxanas/git-branchless/issues/278 for more
-information.
-
-Here are some options:
-
-- To unset the configuration option, run: git config --unset extensions.worktreeConfig
-  - This is safe unless you created another worktree also using a sparse checkout.
-- Try upgrading to Git v2.36+ and reinitializing your sparse checkout.",
-                error = effects.get_glyphs().render(StyledString::styled(
-                    "\
-Error: the Git configuration setting `extensions.worktreeConfig` is enabled in
-this repository. Due to upstream libgit2 limitations, git-branchless does not
-support repositories with this configuration option enabled.",
-                    BaseColor::Red.light()
-                ))?,
-            )?;
-            return Ok(Some(ExitCode(1)));
-        }
-        Err(_) => return Ok(None),
-    };
-
-    Ok(None)
-}
-
 /// Wrapper function for `main` to ensure that `Drop` is called for local
 /// variables, since `std::process::exit` will skip them. You probably want to
 /// call `invoke_subcommand_main` instead.
@@ -205,11 +166,6 @@ pub fn do_main_and_drop_locals<T: Parser>(
     let _tracing_guard = install_tracing(effects.clone());
     install_libgit2_tracing();
 
-    if let Some(ExitCode(exit_code)) = check_unsupported_config_options(&effects)? {
-        let exit_code: i32 = exit_code.try_into()?;
-        return Ok(exit_code);
-    }
-
     let ctx = CommandContext {
         effects,
         git_run_info,
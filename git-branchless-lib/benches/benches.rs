@@ -1,15 +1,21 @@
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use branchless::core::dag::{CommitSet, Dag};
 use branchless::core::effects::Effects;
-use branchless::core::eventlog::{EventLogDb, EventReplayer};
+use branchless::core::eventlog::testing::{
+    from_event_log_db_with_checkpoint_interval, new_event_transaction_id,
+};
+use branchless::core::eventlog::{Event, EventLogDb, EventReplayer};
 use branchless::core::formatting::Glyphs;
 use branchless::core::repo_ext::RepoExt;
 use branchless::core::rewrite::{
     BuildRebasePlanOptions, RebasePlanBuilder, RebasePlanPermissions, RepoResource,
 };
-use branchless::git::{CherryPickFastOptions, Commit, Diff, Repo};
+use branchless::git::{
+    CherryPickFastOptions, Commit, Diff, MaybeZeroOid, NonZeroOid, ReferenceName, Repo,
+};
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use rayon::ThreadPoolBuilder;
 
@@ -163,12 +169,91 @@ fn bench_get_paths_touched_by_commits(c: &mut Criterion) {
     });
 }
 
+/// Make a synthetic event log of commits, branch updates, and hidden commits.
+fn make_synthetic_events(range: std::ops::Range<usize>) -> Vec<Event> {
+    range
+        .map(|i| {
+            let event_tx_id = new_event_transaction_id((i / 4).try_into().unwrap());
+            let commit_oid = NonZeroOid::from_str(&format!("{:040x}", i / 4 + 1)).unwrap();
+            match i % 4 {
+                0 => Event::CommitEvent {
+                    timestamp: 0.0,
+                    event_tx_id,
+                    commit_oid,
+                },
+                1 => Event::RefUpdateEvent {
+                    timestamp: 0.0,
+                    event_tx_id,
+                    ref_name: ReferenceName::from("HEAD"),
+                    old_oid: MaybeZeroOid::Zero,
+                    new_oid: MaybeZeroOid::NonZero(commit_oid),
+                    message: None,
+                },
+                2 => Event::RefUpdateEvent {
+                    timestamp: 0.0,
+                    event_tx_id,
+                    ref_name: ReferenceName::from(format!("refs/heads/branch-{}", i % 100)),
+                    old_oid: MaybeZeroOid::Zero,
+                    new_oid: MaybeZeroOid::NonZero(commit_oid),
+                    message: None,
+                },
+                _ => Event::ObsoleteEvent {
+                    timestamp: 0.0,
+                    event_tx_id,
+                    commit_oid,
+                },
+            }
+        })
+        .collect()
+}
+
+fn bench_event_replayer(c: &mut Criterion) {
+    const NUM_EVENTS: usize = 500_000;
+    const NUM_EVENTS_AFTER_CHECKPOINT: usize = 1_000;
+
+    let mut group = c.benchmark_group("event-replayer");
+    group.sample_size(10);
+    group.bench_function("EventReplayer::from_event_log_db_with_history", |b| {
+        let repo = get_repo();
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let event_log_db = EventLogDb::new(&conn).unwrap();
+        event_log_db
+            .add_events(make_synthetic_events(0..NUM_EVENTS))
+            .unwrap();
+
+        b.iter(|| {
+            EventReplayer::from_event_log_db_with_history(&effects, &repo, &event_log_db).unwrap()
+        });
+    });
+    group.bench_function("EventReplayer::from_event_log_db", |b| {
+        let repo = get_repo();
+        let effects = Effects::new_suppress_for_test(Glyphs::text());
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let event_log_db = EventLogDb::new(&conn).unwrap();
+        event_log_db
+            .add_events(make_synthetic_events(
+                0..NUM_EVENTS - NUM_EVENTS_AFTER_CHECKPOINT,
+            ))
+            .unwrap();
+        from_event_log_db_with_checkpoint_interval(&effects, &repo, &event_log_db, 1).unwrap();
+        event_log_db
+            .add_events(make_synthetic_events(
+                NUM_EVENTS - NUM_EVENTS_AFTER_CHECKPOINT..NUM_EVENTS,
+            ))
+            .unwrap();
+
+        b.iter(|| EventReplayer::from_event_log_db(&effects, &repo, &event_log_db).unwrap());
+    });
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default().sample_size(10);
     targets =
         bench_cherry_pick_fast,
         bench_diff_fast,
+        bench_event_replayer,
         bench_get_paths_touched_by_commits,
         bench_rebase_plan,
 );
@@ -76,6 +76,28 @@ pub fn get_main_branch_name(repo: &Repo) -> eyre::Result<String> {
     Ok("master".to_string())
 }
 
+/// Get the patterns for branches which should be treated as public in addition
+/// to the main branch, such as release branches.
+///
+/// These are read from `branchless.core.publicBranches`, which may be specified
+/// multiple times. Each value is a whitespace-separated list of branch names or
+/// glob patterns, like `release/*`, which are matched against local branch
+/// names.
+#[instrument]
+pub fn get_public_branch_patterns(repo: &Repo) -> eyre::Result<Vec<glob::Pattern>> {
+    let config = repo.get_readonly_config()?;
+    let mut result = Vec::new();
+    for (_key, value) in config.list(r"^branchless\.core\.publicbranches$")? {
+        for pattern in value.split_whitespace() {
+            let pattern = glob::Pattern::new(pattern).wrap_err_with(|| {
+                format!("Parsing pattern {pattern:?} in branchless.core.publicBranches")
+            })?;
+            result.push(pattern);
+        }
+    }
+    Ok(result)
+}
+
 /// If `true`, switch to the branch associated with a target commit instead of
 /// the commit directly.
 ///
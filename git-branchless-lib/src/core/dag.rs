@@ -143,6 +143,11 @@ pub struct Dag {
     /// A set containing the commit that the main branch currently points to.
     pub main_branch_commit: CommitSet,
 
+    /// A set containing the commits that the public branches currently point
+    /// to. This includes the main branch, as well as any branches matching
+    /// `branchless.core.publicBranches`.
+    pub public_branch_commits: CommitSet,
+
     /// A set containing all commits currently pointed to by local branches.
     pub branch_commits: CommitSet,
 
@@ -168,6 +173,7 @@ impl Dag {
             inner,
             head_commit: self.head_commit.clone(),
             main_branch_commit: self.main_branch_commit.clone(),
+            public_branch_commits: self.public_branch_commits.clone(),
             branch_commits: self.branch_commits.clone(),
             observed_commits: self.observed_commits.clone(),
             obsolete_commits: self.obsolete_commits.clone(),
@@ -239,12 +245,17 @@ impl Dag {
             None => CommitSet::empty(),
         };
         let main_branch_commit = CommitSet::from(*main_branch_oid);
+        let public_branch_commits: CommitSet = references_snapshot
+            .get_public_branch_oids(repo)?
+            .into_iter()
+            .collect();
         let branch_commits: CommitSet = branch_oid_to_names.keys().copied().collect();
 
         Ok(Self {
             inner: dag,
             head_commit,
             main_branch_commit,
+            public_branch_commits,
             branch_commits,
             observed_commits,
             obsolete_commits,
@@ -271,7 +282,7 @@ impl Dag {
     /// Update the DAG with all commits reachable from branches.
     #[instrument]
     fn sync(&mut self, effects: &Effects, repo: &Repo) -> eyre::Result<()> {
-        let master_heads = self.main_branch_commit.clone();
+        let master_heads = self.public_branch_commits.clone();
         let non_master_heads = self
             .observed_commits
             .union(&self.head_commit)
@@ -347,6 +358,7 @@ impl Dag {
             head_commit: self.head_commit.clone(),
             branch_commits: self.branch_commits.clone(),
             main_branch_commit: self.main_branch_commit.clone(),
+            public_branch_commits: self.public_branch_commits.clone(),
             observed_commits: self.observed_commits.clone(),
             obsolete_commits: CommitSet::empty(),
             draft_commits: Default::default(),
@@ -403,14 +415,14 @@ impl Dag {
     }
 
     /// Determine whether or not the given commit is a public commit (i.e. is an
-    /// ancestor of the main branch).
+    /// ancestor of the main branch or another public branch).
     #[instrument]
     pub fn is_public_commit(&self, commit_oid: NonZeroOid) -> eyre::Result<bool> {
-        let main_branch_commits = self.commit_set_to_vec(&self.main_branch_commit)?;
-        for main_branch_commit in main_branch_commits {
+        let public_branch_commits = self.commit_set_to_vec(&self.public_branch_commits)?;
+        for public_branch_commit in public_branch_commits {
             if self.run_blocking(
                 self.inner
-                    .is_ancestor(commit_oid.into(), main_branch_commit.into()),
+                    .is_ancestor(commit_oid.into(), public_branch_commit.into()),
             )? {
                 return Ok(true);
             }
@@ -464,7 +476,7 @@ impl Dag {
     pub fn query_public_commits_slow(&self) -> eyre::Result<&CommitSet> {
         self.public_commits.get_or_try_init(|| {
             let public_commits =
-                self.run_blocking(self.inner.ancestors(self.main_branch_commit.clone()))?;
+                self.run_blocking(self.inner.ancestors(self.public_branch_commits.clone()))?;
             Ok(public_commits)
         })
     }
@@ -478,7 +490,7 @@ impl Dag {
             let visible_heads = CommitSet::empty()
                 .union(&self.observed_commits.difference(&self.obsolete_commits))
                 .union(&self.head_commit)
-                .union(&self.main_branch_commit)
+                .union(&self.public_branch_commits)
                 .union(&self.branch_commits);
             let visible_heads = self.run_blocking(self.inner.heads(visible_heads))?;
             Ok(visible_heads)
@@ -522,7 +534,7 @@ impl Dag {
             let visible_heads = self.query_visible_heads()?;
             let draft_commits = self.run_blocking(
                 self.inner
-                    .only(visible_heads.clone(), self.main_branch_commit.clone()),
+                    .only(visible_heads.clone(), self.public_branch_commits.clone()),
             )?;
             Ok(draft_commits)
         })
@@ -215,6 +215,29 @@ impl Event {
     }
 }
 
+impl Row {
+    /// Read the event columns from the given database row.
+    fn from_sql_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let timestamp: f64 = row.get("timestamp")?;
+        let event_tx_id: isize = row.get("event_tx_id")?;
+        let type_: String = row.get("type")?;
+        let ref_name: Option<String> = row.get("ref_name")?;
+        let old_ref: Option<String> = row.get("old_ref")?;
+        let new_ref: Option<String> = row.get("new_ref")?;
+        let message: Option<String> = row.get("message")?;
+
+        Ok(Row {
+            timestamp,
+            event_tx_id,
+            type_,
+            ref_name: ref_name.map(ReferenceName::from),
+            ref1: old_ref.map(ReferenceName::from),
+            ref2: new_ref.map(ReferenceName::from),
+            message: message.map(ReferenceName::from),
+        })
+    }
+}
+
 impl From<Event> for Row {
     fn from(event: Event) -> Row {
         match event {
@@ -460,8 +483,7 @@ CREATE TABLE IF NOT EXISTS event_transactions (
 
     -- Set as `PRIMARY KEY` to have SQLite select a value automatically. Set as
     -- `AUTOINCREMENT` to ensure that SQLite doesn't reuse the value later if a
-    -- row is deleted. (We don't plan to delete rows right now, but maybe
-    -- later?)
+    -- row is deleted by `EventLogDb::prune_events`.
     event_tx_id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
 
     message TEXT
@@ -471,6 +493,94 @@ CREATE TABLE IF NOT EXISTS event_transactions (
     )
     .wrap_err("Creating `event_transactions` table")?;
 
+    conn.execute(
+        "
+CREATE TABLE IF NOT EXISTS event_replayer_checkpoint (
+    -- Either `latest` or `baseline`. See `CheckpointKind`.
+    kind TEXT NOT NULL,
+
+    -- The `rowid` of the last row in `event_log` which was replayed.
+    event_log_rowid INTEGER NOT NULL,
+
+    -- The ID which the replayer will assign to the next event.
+    event_id INTEGER NOT NULL,
+
+    head_oid TEXT
+)
+",
+        rusqlite::params![],
+    )
+    .wrap_err("Creating `event_replayer_checkpoint` table")?;
+
+    conn.execute(
+        "
+CREATE TABLE IF NOT EXISTS event_replayer_checkpoint_events (
+    kind TEXT NOT NULL,
+
+    -- The commit which this is the latest event for, or `NULL` if this is the
+    -- last event replayed before the checkpoint.
+    commit_oid TEXT,
+    event_id INTEGER NOT NULL,
+
+    -- Either `show` or `hide`, or `NULL` if `commit_oid` is `NULL`.
+    classification TEXT,
+
+    -- Same as the corresponding columns in `event_log`.
+    timestamp REAL NOT NULL,
+    type TEXT NOT NULL,
+    event_tx_id INTEGER NOT NULL,
+    old_ref TEXT,
+    new_ref TEXT,
+    ref_name TEXT,
+    message TEXT
+)
+",
+        rusqlite::params![],
+    )
+    .wrap_err("Creating `event_replayer_checkpoint_events` table")?;
+
+    conn.execute(
+        "
+CREATE TABLE IF NOT EXISTS event_replayer_checkpoint_refs (
+    kind TEXT NOT NULL,
+    ref_name TEXT NOT NULL,
+
+    -- The most recent non-zero OID which the reference pointed to.
+    oid TEXT NOT NULL,
+
+    -- Whether the reference was deleted after it last pointed to `oid`.
+    is_deleted INTEGER NOT NULL
+)
+",
+        rusqlite::params![],
+    )
+    .wrap_err("Creating `event_replayer_checkpoint_refs` table")?;
+
+    // Older databases have checkpoint tables without a `kind` column. They
+    // could only store the latest checkpoint, so treat existing rows as such.
+    // The column is added at the end of the table, so rows are inserted with
+    // explicit column names.
+    for table in [
+        "event_replayer_checkpoint",
+        "event_replayer_checkpoint_events",
+        "event_replayer_checkpoint_refs",
+    ] {
+        let has_kind_column: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info(:table) WHERE name = 'kind'",
+                rusqlite::named_params! {":table": table},
+                |row| row.get(0),
+            )
+            .wrap_err_with(|| format!("Checking columns of `{table}` table"))?;
+        if !has_kind_column {
+            conn.execute(
+                &format!("ALTER TABLE {table} ADD COLUMN kind TEXT NOT NULL DEFAULT 'latest'"),
+                rusqlite::params![],
+            )
+            .wrap_err_with(|| format!("Adding `kind` column to `{table}` table"))?;
+        }
+    }
+
     Ok(())
 }
 
@@ -536,38 +646,417 @@ INSERT INTO event_log VALUES (
     ///
     /// Returns: All the events in the database, ordered from oldest to newest.
     #[instrument]
-
     pub fn get_events(&self) -> eyre::Result<Vec<Event>> {
+        let events = self.get_events_after_rowid(0)?;
+        Ok(events.into_iter().map(|(_rowid, event)| event).collect())
+    }
+
+    /// Get the events in the database which were added after the row with the
+    /// given `rowid`, along with their `rowid`s.
+    fn get_events_after_rowid(&self, rowid: i64) -> eyre::Result<Vec<(i64, Event)>> {
         let mut stmt = self.conn.prepare(
             "
-SELECT timestamp, type, event_tx_id, old_ref, new_ref, ref_name, message
+SELECT rowid, timestamp, type, event_tx_id, old_ref, new_ref, ref_name, message
 FROM event_log
+WHERE rowid > :rowid
 ORDER BY rowid ASC
 ",
         )?;
-        let rows: rusqlite::Result<Vec<Row>> = stmt
-            .query_map(rusqlite::params![], |row| {
-                let timestamp: f64 = row.get("timestamp")?;
-                let event_tx_id: isize = row.get("event_tx_id")?;
-                let type_: String = row.get("type")?;
-                let ref_name: Option<String> = row.get("ref_name")?;
-                let old_ref: Option<String> = row.get("old_ref")?;
-                let new_ref: Option<String> = row.get("new_ref")?;
-                let message: Option<String> = row.get("message")?;
-
-                Ok(Row {
-                    timestamp,
-                    event_tx_id,
-                    type_,
-                    ref_name: ref_name.map(ReferenceName::from),
-                    ref1: old_ref.map(ReferenceName::from),
-                    ref2: new_ref.map(ReferenceName::from),
-                    message: message.map(ReferenceName::from),
-                })
+        let rows: rusqlite::Result<Vec<(i64, Row)>> = stmt
+            .query_map(rusqlite::named_params! {":rowid": rowid}, |row| {
+                let rowid: i64 = row.get("rowid")?;
+                Ok((rowid, Row::from_sql_row(row)?))
             })?
             .collect();
         let rows = rows?;
-        rows.into_iter().map(Event::try_from).collect()
+        rows.into_iter()
+            .map(|(rowid, row)| Ok((rowid, Event::try_from(row)?)))
+            .collect()
+    }
+
+    /// Restore the most recent replayer state saved by
+    /// `save_replayer_checkpoint`, if any. The latest checkpoint is only
+    /// considered if `include_latest` is set; otherwise, only the baseline
+    /// checkpoint is restored.
+    #[instrument]
+    fn load_replayer_checkpoint(
+        &self,
+        replayer: &mut EventReplayer,
+        include_latest: bool,
+    ) -> eyre::Result<()> {
+        let mut stmt = self.conn.prepare(
+            "
+SELECT kind, event_log_rowid, event_id, head_oid
+FROM event_replayer_checkpoint
+WHERE kind = :baseline OR (:include_latest AND kind = :latest)
+ORDER BY event_log_rowid DESC
+LIMIT 1
+",
+        )?;
+        let checkpoint: Option<(String, i64, isize, Option<String>)> = stmt
+            .query_map(
+                rusqlite::named_params! {
+                    ":baseline": CheckpointKind::Baseline.as_str(),
+                    ":latest": CheckpointKind::Latest.as_str(),
+                    ":include_latest": include_latest,
+                },
+                |row| {
+                    Ok((
+                        row.get("kind")?,
+                        row.get("event_log_rowid")?,
+                        row.get("event_id")?,
+                        row.get("head_oid")?,
+                    ))
+                },
+            )?
+            .next()
+            .transpose()?;
+        let (kind, event_log_rowid, event_id, head_oid) = match checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        let head_oid = match head_oid {
+            Some(head_oid) => Some(NonZeroOid::from_str(&head_oid)?),
+            None => None,
+        };
+
+        let mut stmt = self.conn.prepare(
+            "
+SELECT commit_oid, event_id, classification,
+    timestamp, type, event_tx_id, old_ref, new_ref, ref_name, message
+FROM event_replayer_checkpoint_events
+WHERE kind = :kind
+",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::named_params! {":kind": &kind}, |row| {
+                let commit_oid: Option<String> = row.get("commit_oid")?;
+                let event_id: isize = row.get("event_id")?;
+                let classification: Option<String> = row.get("classification")?;
+                Ok((
+                    commit_oid,
+                    event_id,
+                    classification,
+                    Row::from_sql_row(row)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut last_event = None;
+        let mut commit_history = HashMap::new();
+        for (commit_oid, id, classification, row) in rows {
+            let event = Event::try_from(row)?;
+            match commit_oid {
+                None => last_event = Some(event),
+                Some(commit_oid) => {
+                    let event_classification = match classification.as_deref() {
+                        Some("show") => EventClassification::Show,
+                        Some("hide") => EventClassification::Hide,
+                        other => {
+                            eyre::bail!("Unknown event classification in checkpoint: {other:?}")
+                        }
+                    };
+                    commit_history.insert(
+                        NonZeroOid::from_str(&commit_oid)?,
+                        vec![EventInfo {
+                            id,
+                            event,
+                            event_classification,
+                        }],
+                    );
+                }
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "
+SELECT ref_name, oid, is_deleted
+FROM event_replayer_checkpoint_refs
+WHERE kind = :kind
+",
+        )?;
+        let rows: rusqlite::Result<Vec<(String, String, bool)>> = stmt
+            .query_map(rusqlite::named_params! {":kind": &kind}, |row| {
+                Ok((
+                    row.get("ref_name")?,
+                    row.get("oid")?,
+                    row.get("is_deleted")?,
+                ))
+            })?
+            .collect();
+        let mut ref_oids = HashMap::new();
+        let mut ref_locations = HashMap::new();
+        for (ref_name, oid, is_deleted) in rows? {
+            let ref_name = ReferenceName::from(ref_name);
+            let oid = NonZeroOid::from_str(&oid)?;
+            if !is_deleted {
+                ref_locations.insert(ref_name.clone(), oid);
+            }
+            ref_oids.insert(ref_name, oid);
+        }
+
+        replayer.id_counter = event_id;
+        replayer.event_log_rowid = event_log_rowid;
+        replayer.commit_history = commit_history;
+        replayer.ref_locations = ref_locations.clone();
+        replayer.checkpoint = ReplayerCheckpoint {
+            event_id,
+            last_event,
+            head_oid,
+            ref_oids,
+            ref_locations,
+        };
+        Ok(())
+    }
+
+    /// Save the state of the replayer, as of the last event it processed, so
+    /// that later replays only need to process the events after it. Replaces
+    /// any existing checkpoint of the same kind.
+    #[instrument]
+    fn save_replayer_checkpoint(
+        &self,
+        replayer: &EventReplayer,
+        kind: CheckpointKind,
+    ) -> eyre::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        Self::write_replayer_checkpoint(&tx, replayer, kind)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write_replayer_checkpoint(
+        conn: &rusqlite::Connection,
+        replayer: &EventReplayer,
+        kind: CheckpointKind,
+    ) -> eyre::Result<()> {
+        let cursor = replayer.make_default_cursor();
+        let head_oid = replayer.get_cursor_head_oid(cursor);
+        let last_event = replayer.get_event_before_cursor(cursor);
+        let mut ref_oids = replayer.checkpoint.ref_oids.clone();
+        for event in replayer.events.iter() {
+            if let Event::RefUpdateEvent {
+                ref_name,
+                new_oid: MaybeZeroOid::NonZero(new_oid),
+                ..
+            } = event
+            {
+                ref_oids.insert(ref_name.clone(), *new_oid);
+            }
+        }
+
+        Self::delete_replayer_checkpoint(conn, kind)?;
+        let kind = kind.as_str();
+        conn.execute(
+            "
+INSERT INTO event_replayer_checkpoint (
+    kind,
+    event_log_rowid,
+    event_id,
+    head_oid
+) VALUES (
+    :kind,
+    :event_log_rowid,
+    :event_id,
+    :head_oid
+)
+",
+            rusqlite::named_params! {
+                ":kind": kind,
+                ":event_log_rowid": replayer.event_log_rowid,
+                ":event_id": replayer.id_counter,
+                ":head_oid": head_oid.map(|oid| oid.to_string()),
+            },
+        )?;
+
+        let mut insert_event = conn.prepare(
+            "
+INSERT INTO event_replayer_checkpoint_events (
+    kind,
+    commit_oid,
+    event_id,
+    classification,
+    timestamp,
+    type,
+    event_tx_id,
+    old_ref,
+    new_ref,
+    ref_name,
+    message
+) VALUES (
+    :kind,
+    :commit_oid,
+    :event_id,
+    :classification,
+    :timestamp,
+    :type,
+    :event_tx_id,
+    :old_ref,
+    :new_ref,
+    :ref_name,
+    :message
+)
+",
+        )?;
+        let latest_events = replayer.commit_history.iter().filter_map(|(oid, history)| {
+            let EventInfo {
+                id,
+                event,
+                event_classification,
+            } = history.last()?;
+            let classification = match event_classification {
+                EventClassification::Show => "show",
+                EventClassification::Hide => "hide",
+            };
+            Some((Some(oid.to_string()), *id, Some(classification), event))
+        });
+        let last_event = last_event.map(|(event_id, event)| (None, event_id - 1, None, event));
+        for (commit_oid, event_id, classification, event) in latest_events.chain(last_event) {
+            let Row {
+                timestamp,
+                type_,
+                event_tx_id,
+                ref1,
+                ref2,
+                ref_name,
+                message,
+            } = Row::from(event.clone());
+            insert_event.execute(rusqlite::named_params! {
+                ":kind": kind,
+                ":commit_oid": commit_oid,
+                ":event_id": event_id,
+                ":classification": classification,
+                ":timestamp": timestamp,
+                ":type": &type_,
+                ":event_tx_id": event_tx_id,
+                ":old_ref": ref1.as_ref().map(|x| x.as_str()),
+                ":new_ref": ref2.as_ref().map(|x| x.as_str()),
+                ":ref_name": ref_name.as_ref().map(|x| x.as_str()),
+                ":message": message.as_ref().map(|x| x.as_str()),
+            })?;
+        }
+        drop(insert_event);
+
+        let mut insert_ref = conn.prepare(
+            "
+INSERT INTO event_replayer_checkpoint_refs (
+    kind,
+    ref_name,
+    oid,
+    is_deleted
+) VALUES (
+    :kind,
+    :ref_name,
+    :oid,
+    :is_deleted
+)
+",
+        )?;
+        for (ref_name, oid) in ref_oids.iter() {
+            insert_ref.execute(rusqlite::named_params! {
+                ":kind": kind,
+                ":ref_name": ref_name.as_str(),
+                ":oid": oid.to_string(),
+                ":is_deleted": !replayer.ref_locations.contains_key(ref_name),
+            })?;
+        }
+        drop(insert_ref);
+
+        Ok(())
+    }
+
+    fn delete_replayer_checkpoint(
+        conn: &rusqlite::Connection,
+        kind: CheckpointKind,
+    ) -> eyre::Result<()> {
+        for table in [
+            "event_replayer_checkpoint",
+            "event_replayer_checkpoint_events",
+            "event_replayer_checkpoint_refs",
+        ] {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE kind = :kind"),
+                rusqlite::named_params! {":kind": kind.as_str()},
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Remove the events which happened before `horizon` from the event log,
+    /// along with their transactions.
+    ///
+    /// The state as of the removed events is saved as a baseline, so the
+    /// replayer still knows which commits were visible or obsolete, but
+    /// events before the baseline can no longer be undone. Only whole
+    /// transactions are removed.
+    ///
+    /// Returns: The number of events removed.
+    #[instrument]
+    pub fn prune_events(
+        &self,
+        effects: &Effects,
+        repo: &Repo,
+        horizon: SystemTime,
+    ) -> eyre::Result<usize> {
+        let (_effects, _progress) = effects.start_operation(OperationType::ProcessEvents);
+        let horizon_timestamp = horizon
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .wrap_err("Calculating event log horizon")?
+            .as_secs_f64();
+
+        let tx = self.conn.unchecked_transaction()?;
+        let main_branch_reference_name = repo.get_main_branch()?.get_reference_name()?;
+        let mut replayer = EventReplayer::new(main_branch_reference_name);
+        self.load_replayer_checkpoint(&mut replayer, false)?;
+
+        let events = self.get_events_after_rowid(replayer.event_log_rowid)?;
+        let mut num_pruned_events = events
+            .iter()
+            .take_while(|(_rowid, event)| event.get_timestamp() < horizon)
+            .count();
+        // Don't split a transaction between the baseline and the event log.
+        while num_pruned_events > 0 {
+            let last_tx_id = events[num_pruned_events - 1].1.get_event_tx_id();
+            match events.get(num_pruned_events) {
+                Some((_rowid, next_event)) if next_event.get_event_tx_id() == last_tx_id => {
+                    num_pruned_events -= 1;
+                }
+                _ => break,
+            }
+        }
+        if num_pruned_events == 0 {
+            return Ok(0);
+        }
+
+        let num_events = events.len();
+        for (rowid, event) in events.into_iter().take(num_pruned_events) {
+            replayer.process_event(&event);
+            replayer.event_log_rowid = rowid;
+        }
+        let max_pruned_rowid = replayer.event_log_rowid;
+        if num_pruned_events == num_events {
+            // SQLite reuses `rowid`s starting from 1 once the table is empty,
+            // so the events added later would be skipped when replaying from
+            // the baseline.
+            replayer.event_log_rowid = 0;
+        }
+        Self::write_replayer_checkpoint(&tx, &replayer, CheckpointKind::Baseline)?;
+        // The latest checkpoint may refer to events which are being removed,
+        // so it will be rebuilt from the baseline later.
+        Self::delete_replayer_checkpoint(&tx, CheckpointKind::Latest)?;
+
+        tx.execute(
+            "DELETE FROM event_log WHERE rowid <= :rowid",
+            rusqlite::named_params! {":rowid": max_pruned_rowid},
+        )?;
+        tx.execute(
+            "
+DELETE FROM event_transactions
+WHERE timestamp < :horizon
+AND event_tx_id NOT IN (SELECT event_tx_id FROM event_log)
+",
+            rusqlite::named_params! {":horizon": horizon_timestamp},
+        )?;
+        tx.commit()?;
+        Ok(num_pruned_events)
     }
 
     #[instrument]
@@ -621,6 +1110,19 @@ ORDER BY rowid ASC
         self.make_transaction_id_inner(now, message.as_ref())
     }
 
+    /// Get the ID of the most recently-created transaction, if any. This can be
+    /// polled to detect when another process has changed the repository.
+    pub fn get_latest_transaction_id(&self) -> eyre::Result<Option<EventTransactionId>> {
+        let mut stmt = self.conn.prepare(
+            "
+SELECT MAX(event_tx_id)
+FROM event_transactions
+",
+        )?;
+        let event_tx_id: Option<isize> = stmt.query_row([], |row| row.get(0))?;
+        Ok(event_tx_id.map(EventTransactionId))
+    }
+
     /// Get the message associated with the given transaction.
     pub fn get_transaction_message(&self, event_tx_id: EventTransactionId) -> eyre::Result<String> {
         let EventTransactionId(event_tx_id) = event_tx_id;
@@ -713,14 +1215,71 @@ pub struct EventCursor {
     event_id: isize,
 }
 
+/// Save a checkpoint of the replayer state after replaying at least this many
+/// new events from the event log.
+const CHECKPOINT_INTERVAL: usize = 10_000;
+
+/// The kinds of replayer checkpoints stored in the database. At most one
+/// checkpoint of each kind is stored.
+#[derive(Clone, Copy, Debug)]
+enum CheckpointKind {
+    /// A snapshot of the replayer state saved to speed up later replays. It
+    /// can be discarded at any time.
+    Latest,
+
+    /// The state as of the events which were removed by
+    /// `EventLogDb::prune_events`. Replays of the full history start from
+    /// here.
+    Baseline,
+}
+
+impl CheckpointKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CheckpointKind::Latest => "latest",
+            CheckpointKind::Baseline => "baseline",
+        }
+    }
+}
+
+/// The replayer state as of a certain event, restored from the database so
+/// that the events before it don't need to be replayed again.
+///
+/// The history before the checkpoint isn't available, so cursors can't be
+/// moved to before it.
+#[derive(Debug, Default)]
+struct ReplayerCheckpoint {
+    /// The ID of the first event after the checkpoint.
+    event_id: isize,
+
+    /// The last event before the checkpoint.
+    last_event: Option<Event>,
+
+    /// The OID of `HEAD` as of the checkpoint.
+    head_oid: Option<NonZeroOid>,
+
+    /// The most recent non-zero OID of each reference as of the checkpoint,
+    /// even if the reference was later deleted.
+    ref_oids: HashMap<ReferenceName, NonZeroOid>,
+
+    /// The references which existed as of the checkpoint.
+    ref_locations: HashMap<ReferenceName, NonZeroOid>,
+}
+
 /// Processes events in order and determine the repo's visible commits.
 pub struct EventReplayer {
     /// Events are numbered starting from zero.
     id_counter: isize,
 
-    /// The list of observed events.
+    /// The list of observed events after the checkpoint.
     events: Vec<Event>,
 
+    /// The state before the first event in `events`.
+    checkpoint: ReplayerCheckpoint,
+
+    /// The `rowid` of the last row processed from the event log database.
+    event_log_rowid: i64,
+
     /// The name of the reference representing the main branch.
     main_branch_reference_name: ReferenceName,
 
@@ -751,13 +1310,19 @@ impl EventReplayer {
         EventReplayer {
             id_counter: 0,
             events: vec![],
+            checkpoint: Default::default(),
+            event_log_rowid: 0,
             main_branch_reference_name,
             commit_history: HashMap::new(),
             ref_locations: HashMap::new(),
         }
     }
 
-    /// Construct the replayer from all the events in the database.
+    /// Construct the replayer from the events in the database.
+    ///
+    /// Only the events after the most recent checkpoint are replayed, so the
+    /// returned replayer can't move its cursor to before the checkpoint. Use
+    /// `from_event_log_db_with_history` if the full history is needed.
     ///
     /// Args:
     /// * `event_log_db`: The database to query events from.
@@ -768,13 +1333,50 @@ impl EventReplayer {
         effects: &Effects,
         repo: &Repo,
         event_log_db: &EventLogDb,
+    ) -> eyre::Result<Self> {
+        Self::from_event_log_db_inner(effects, repo, event_log_db, Some(CHECKPOINT_INTERVAL))
+    }
+
+    /// Construct the replayer from all the events in the database, starting
+    /// from the baseline left by pruning old events, if any.
+    ///
+    /// Args:
+    /// * `event_log_db`: The database to query events from.
+    ///
+    /// Returns: The constructed replayer.
+    #[instrument]
+    pub fn from_event_log_db_with_history(
+        effects: &Effects,
+        repo: &Repo,
+        event_log_db: &EventLogDb,
+    ) -> eyre::Result<Self> {
+        Self::from_event_log_db_inner(effects, repo, event_log_db, None)
+    }
+
+    fn from_event_log_db_inner(
+        effects: &Effects,
+        repo: &Repo,
+        event_log_db: &EventLogDb,
+        checkpoint_interval: Option<usize>,
     ) -> eyre::Result<Self> {
         let (_effects, _progress) = effects.start_operation(OperationType::ProcessEvents);
 
         let main_branch_reference_name = repo.get_main_branch()?.get_reference_name()?;
         let mut result = EventReplayer::new(main_branch_reference_name);
-        for event in event_log_db.get_events()? {
+        event_log_db.load_replayer_checkpoint(&mut result, checkpoint_interval.is_some())?;
+
+        let events = event_log_db.get_events_after_rowid(result.event_log_rowid)?;
+        let num_events = events.len();
+        for (rowid, event) in events {
             result.process_event(&event);
+            result.event_log_rowid = rowid;
+        }
+
+        match checkpoint_interval {
+            Some(checkpoint_interval) if num_events >= checkpoint_interval => {
+                event_log_db.save_replayer_checkpoint(&result, CheckpointKind::Latest)?;
+            }
+            _ => {}
         }
         Ok(result)
     }
@@ -929,7 +1531,8 @@ impl EventReplayer {
             _ => event,
         };
 
-        match (event, self.events.last()) {
+        let last_event = self.events.last().or(self.checkpoint.last_event.as_ref());
+        match (event, last_event) {
             // Sometimes, Git v2.31 will issue multiple delete reference
             // transactions (one for the unpacked refs, and one for the packed
             // refs). Ignore the duplicate second one, for determinism in
@@ -1028,7 +1631,7 @@ impl EventReplayer {
 
     /// Create an event cursor pointing to immediately after the last event.
     pub fn make_default_cursor(&self) -> EventCursor {
-        self.make_cursor(self.events.len().try_into().unwrap())
+        self.make_cursor(self.id_counter)
     }
 
     /// Create an event cursor pointing to immediately after the provided event ID.
@@ -1036,16 +1639,28 @@ impl EventReplayer {
     /// If the event ID is too low or too high, it will be clamped to the valid
     /// range for event IDs.
     pub fn make_cursor(&self, event_id: isize) -> EventCursor {
-        let event_id = if event_id < 0 { 0 } else { event_id };
-        let num_events: isize = self.events.len().try_into().unwrap();
-        let event_id = if event_id > num_events {
-            num_events
+        let first_event_id = self.checkpoint.event_id;
+        let event_id = if event_id < first_event_id {
+            first_event_id
+        } else {
+            event_id
+        };
+        let event_id = if event_id > self.id_counter {
+            self.id_counter
         } else {
             event_id
         };
         EventCursor { event_id }
     }
 
+    /// Get the events replayed since the checkpoint, split at the cursor.
+    fn split_events_at_cursor(&self, cursor: EventCursor) -> (&[Event], &[Event]) {
+        let index: usize = (cursor.event_id - self.checkpoint.event_id)
+            .try_into()
+            .unwrap();
+        self.events.split_at(index)
+    }
+
     /// Advance the event cursor by the specified number of events.
     ///
     /// Args:
@@ -1139,8 +1754,8 @@ impl EventReplayer {
     /// Returns: The OID pointed to by `HEAD` at that time, or `None` if `HEAD`
     /// was never observed.
     fn get_cursor_head_oid(&self, cursor: EventCursor) -> Option<NonZeroOid> {
-        let cursor_event_id: usize = cursor.event_id.try_into().unwrap();
-        self.events[0..cursor_event_id]
+        let (events_before_cursor, _) = self.split_events_at_cursor(cursor);
+        events_before_cursor
             .iter()
             .rev()
             .find_map(|event| {
@@ -1171,6 +1786,7 @@ impl EventReplayer {
                     | Event::UnobsoleteEvent { .. } => None,
                 }
             })
+            .or(self.checkpoint.head_oid)
     }
 
     fn get_cursor_branch_oid(
@@ -1178,8 +1794,8 @@ impl EventReplayer {
         cursor: EventCursor,
         reference_name: &ReferenceName,
     ) -> eyre::Result<Option<NonZeroOid>> {
-        let cursor_event_id: usize = cursor.event_id.try_into().unwrap();
-        let oid = self.events[0..cursor_event_id]
+        let (events_before_cursor, _) = self.split_events_at_cursor(cursor);
+        let oid = events_before_cursor
             .iter()
             .rev()
             .find_map(|event| match &event {
@@ -1189,7 +1805,8 @@ impl EventReplayer {
                     ..
                 } if ref_name == reference_name => Some(*new_oid),
                 _ => None,
-            });
+            })
+            .or_else(|| self.checkpoint.ref_oids.get(reference_name).copied());
         Ok(oid)
     }
 
@@ -1238,9 +1855,14 @@ impl EventReplayer {
         cursor: EventCursor,
         repo: &Repo,
     ) -> eyre::Result<HashMap<NonZeroOid, HashSet<ReferenceName>>> {
-        let mut ref_name_to_oid: HashMap<&ReferenceName, NonZeroOid> = HashMap::new();
-        let cursor_event_id: usize = cursor.event_id.try_into().unwrap();
-        for event in self.events[..cursor_event_id].iter() {
+        let mut ref_name_to_oid: HashMap<&ReferenceName, NonZeroOid> = self
+            .checkpoint
+            .ref_locations
+            .iter()
+            .map(|(ref_name, oid)| (ref_name, *oid))
+            .collect();
+        let (events_before_cursor, _) = self.split_events_at_cursor(cursor);
+        for event in events_before_cursor.iter() {
             match event {
                 Event::RefUpdateEvent {
                     new_oid: MaybeZeroOid::NonZero(new_oid),
@@ -1301,12 +1923,11 @@ impl EventReplayer {
     /// Returns: A tuple of event ID and the event that most recently happened.
     /// If no event was before the event cursor, returns `None` instead.
     pub fn get_event_before_cursor(&self, cursor: EventCursor) -> Option<(isize, &Event)> {
-        if cursor.event_id == 0 {
-            None
-        } else {
-            let previous_cursor_event_id: usize = (cursor.event_id - 1).try_into().unwrap();
-            Some((cursor.event_id, &self.events[previous_cursor_event_id]))
-        }
+        let (events_before_cursor, _) = self.split_events_at_cursor(cursor);
+        let event = events_before_cursor
+            .last()
+            .or(self.checkpoint.last_event.as_ref())?;
+        Some((cursor.event_id, event))
     }
 
     /// Get all the events in the transaction immediately before the cursor.
@@ -1323,8 +1944,9 @@ impl EventReplayer {
         let EventCursor {
             event_id: curr_event_id,
         } = cursor;
-        let tx_events =
-            &self.events[prev_event_id.try_into().unwrap()..curr_event_id.try_into().unwrap()];
+        let first_event_id = self.checkpoint.event_id;
+        let tx_events = &self.events[(prev_event_id - first_event_id).try_into().unwrap()
+            ..(curr_event_id - first_event_id).try_into().unwrap()];
         match tx_events {
             [] => None,
             events => Some((prev_event_id + 1, events)),
@@ -1336,8 +1958,8 @@ impl EventReplayer {
     /// Returns: An ordered list of events that have happened since the event
     /// cursor, from least recent to most recent.
     pub fn get_events_since_cursor(&self, cursor: EventCursor) -> &[Event] {
-        let cursor_event_id: usize = cursor.event_id.try_into().unwrap();
-        &self.events[cursor_event_id..]
+        let (_, events_since_cursor) = self.split_events_at_cursor(cursor);
+        events_since_cursor
     }
 }
 
@@ -1385,6 +2007,22 @@ pub mod testing {
         event
     }
 
+    /// Construct the replayer from the events in the database, saving a
+    /// checkpoint if at least `checkpoint_interval` events were replayed.
+    pub fn from_event_log_db_with_checkpoint_interval(
+        effects: &Effects,
+        repo: &Repo,
+        event_log_db: &EventLogDb,
+        checkpoint_interval: usize,
+    ) -> eyre::Result<EventReplayer> {
+        EventReplayer::from_event_log_db_inner(
+            effects,
+            repo,
+            event_log_db,
+            Some(checkpoint_interval),
+        )
+    }
+
     /// Get the events stored inside an `EventReplayer`.
     pub fn get_event_replayer_events(event_replayer: &EventReplayer) -> &Vec<Event> {
         &event_replayer.events
@@ -4,11 +4,17 @@
 //! it or one of its descendants. However, the branchless workflow requires
 //! keeping such commits reachable until the user has obsoleted them.
 //!
-//! This module is responsible for adding extra references to Git, so that Git's
-//! garbage collection doesn't collect commits which branchless thinks are still
-//! active.
+//! This module is responsible for keeping those commits reachable, so that
+//! Git's garbage collection doesn't collect commits which branchless thinks are
+//! still active. Rather than creating one reference per commit, which slows
+//! down every Git command that lists references, a single reference points to
+//! a chain of synthetic "keep-alive" commits. The first parent of each
+//! keep-alive commit is the previous keep-alive commit in the chain (if any),
+//! and its remaining parents are the commits being kept alive.
 
+use std::collections::HashSet;
 use std::fmt::Write;
+use std::time::{Duration, SystemTime};
 
 use eyre::Context;
 use tracing::instrument;
@@ -18,31 +24,181 @@ use crate::core::eventlog::{
     is_gc_ref, CommitActivityStatus, EventCursor, EventLogDb, EventReplayer,
 };
 use crate::core::formatting::Pluralize;
-use crate::git::{NonZeroOid, Reference, Repo};
+use crate::git::{
+    make_empty_tree, Commit, MaybeZeroOid, NonZeroOid, ReferenceName, Repo, Signature,
+};
+
+/// The reference which keeps alive the commits that branchless considers
+/// active.
+pub const KEEP_ALIVE_REF_NAME: &str = "refs/branchless/keep-alive";
+
+/// The message of the synthetic commits in the keep-alive chain, used to tell
+/// them apart from the commits being kept alive.
+const KEEP_ALIVE_COMMIT_MESSAGE: &str = "branchless: keep-alive\n";
+
+/// The maximum number of commits kept alive by a single keep-alive commit when
+/// rebuilding the chain.
+const MAX_KEEP_ALIVE_PARENTS: usize = 100;
+
+/// The number of times to try updating the keep-alive reference before giving
+/// up, in case other processes are updating it at the same time.
+const MAX_KEEP_ALIVE_UPDATE_ATTEMPTS: usize = 10;
+
+fn is_keep_alive_commit(commit: &Commit) -> bool {
+    commit.get_message_raw() == KEEP_ALIVE_COMMIT_MESSAGE
+}
+
+fn get_keep_alive_tip(repo: &Repo) -> eyre::Result<Option<Commit>> {
+    let reference = match repo.find_reference(&KEEP_ALIVE_REF_NAME.into())? {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
+    let commit = reference.peel_to_commit()?;
+    Ok(commit)
+}
 
-/// Find references under `refs/branchless/` which point to commits which are no
-/// longer active. These are safe to remove.
-pub fn find_dangling_references<'repo>(
+/// Split a keep-alive commit into the previous keep-alive commit in the chain
+/// (if any) and the commits which it keeps alive. Only the first parent is
+/// loaded, since it's the only one which can be a keep-alive commit.
+fn split_keep_alive_commit<'repo>(
     repo: &'repo Repo,
-    event_replayer: &EventReplayer,
-    event_cursor: EventCursor,
-) -> eyre::Result<Vec<Reference<'repo>>> {
-    let mut result = Vec::new();
-    for reference in repo.get_all_references()? {
-        let reference_name = reference.get_name()?;
-        if !is_gc_ref(&reference_name) {
-            continue;
+    commit: &Commit,
+) -> eyre::Result<(Option<Commit<'repo>>, Vec<NonZeroOid>)> {
+    let mut parent_oids = commit.get_parent_oids();
+    let previous = match parent_oids.first() {
+        Some(parent_oid) => repo.find_commit(*parent_oid)?.filter(is_keep_alive_commit),
+        None => None,
+    };
+    if previous.is_some() {
+        parent_oids.remove(0);
+    }
+    Ok((previous, parent_oids))
+}
+
+/// Walk the keep-alive chain starting from `tip`.
+///
+/// Returns: The commits kept alive by the chain, and the number of keep-alive
+/// commits in the chain.
+fn walk_keep_alive_chain(
+    repo: &Repo,
+    tip: Option<Commit>,
+) -> eyre::Result<(Vec<NonZeroOid>, usize)> {
+    let mut kept_oids = Vec::new();
+    let mut seen_oids = HashSet::new();
+    let mut num_links = 0;
+    let mut current = tip;
+    while let Some(commit) = current {
+        num_links += 1;
+        let (previous, parent_oids) = split_keep_alive_commit(repo, &commit)?;
+        for parent_oid in parent_oids {
+            if seen_oids.insert(parent_oid) {
+                kept_oids.push(parent_oid);
+            }
         }
+        current = previous;
+    }
+    Ok((kept_oids, num_links))
+}
 
-        // The graph only contains commits, so we don't need to handle the
-        // case of the reference not peeling to a valid commit. (It might be
-        // a reference to a different kind of object.)
-        let commit = match reference.peel_to_commit()? {
-            Some(commit) => commit,
-            None => continue,
-        };
+/// Get the commits which are currently kept alive by the keep-alive
+/// reference.
+pub fn get_kept_alive_commits(repo: &Repo) -> eyre::Result<Vec<NonZeroOid>> {
+    let tip = get_keep_alive_tip(repo)?;
+    let (kept_oids, _num_links) = walk_keep_alive_chain(repo, tip)?;
+    Ok(kept_oids)
+}
+
+/// Append keep-alive commits for the given commits onto `previous`.
+///
+/// Returns: The new tip of the chain, or zero if the chain is empty.
+fn extend_keep_alive_chain(
+    repo: &Repo,
+    previous: Option<Commit>,
+    commits: &[Commit],
+) -> eyre::Result<MaybeZeroOid> {
+    let tree = make_empty_tree(repo)?;
+    let mut previous = previous;
+    for chunk in commits.chunks(MAX_KEEP_ALIVE_PARENTS) {
+        let parents: Vec<&Commit> = previous.iter().chain(chunk.iter()).collect();
+
+        // Date the keep-alive commit no earlier than its parents. Git walks
+        // commits in date order when checking whether commits are being left
+        // behind (such as in `git checkout`), so it might otherwise visit a
+        // kept commit before finding out that it's reachable.
+        let timestamp = parents
+            .iter()
+            .filter_map(|parent| parent.get_time().to_system_time().ok())
+            .max()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let signature = Signature::automated()?.update_timestamp(timestamp)?;
+        let oid = repo.create_commit(
+            None,
+            &signature,
+            &signature,
+            KEEP_ALIVE_COMMIT_MESSAGE,
+            &tree,
+            parents,
+        )?;
+        previous = Some(repo.find_commit_or_fail(oid)?);
+    }
+    Ok(previous.map(|commit| commit.get_oid()).into())
+}
 
-        match event_replayer.get_cursor_commit_activity_status(event_cursor, commit.get_oid()) {
+/// Update the keep-alive reference to point to the tip returned by
+/// `make_new_tip`, which is passed the current tip. Retries if another process
+/// updated the reference in the meantime.
+fn update_keep_alive_chain(
+    repo: &Repo,
+    make_new_tip: impl Fn(Option<Commit>) -> eyre::Result<MaybeZeroOid>,
+) -> eyre::Result<()> {
+    let ref_name = ReferenceName::from(KEEP_ALIVE_REF_NAME);
+    for _ in 0..MAX_KEEP_ALIVE_UPDATE_ATTEMPTS {
+        let tip = get_keep_alive_tip(repo)?;
+        let old_tip_oid: MaybeZeroOid = tip.as_ref().map(|commit| commit.get_oid()).into();
+        let new_tip_oid = make_new_tip(tip)?;
+        if new_tip_oid == old_tip_oid {
+            return Ok(());
+        }
+        if repo.update_reference_if_unchanged(
+            &ref_name,
+            old_tip_oid,
+            new_tip_oid,
+            "branchless: updating keep-alive commits",
+        )? {
+            return Ok(());
+        }
+    }
+    eyre::bail!(
+        "Could not update {KEEP_ALIVE_REF_NAME}, as it was repeatedly modified by another process"
+    )
+}
+
+/// Stop keeping the given commits alive. The keep-alive chain is rebuilt
+/// from scratch, which also compacts it.
+#[instrument]
+fn unmark_commits_reachable(repo: &Repo, commit_oids: &HashSet<NonZeroOid>) -> eyre::Result<()> {
+    update_keep_alive_chain(repo, |tip| {
+        let (kept_oids, _num_links) = walk_keep_alive_chain(repo, tip)?;
+        let mut kept_commits = Vec::new();
+        for oid in kept_oids {
+            if !commit_oids.contains(&oid) {
+                kept_commits.push(repo.find_commit_or_fail(oid)?);
+            }
+        }
+        extend_keep_alive_chain(repo, None, &kept_commits)
+    })
+}
+
+/// Find commits kept alive by the keep-alive reference which are no longer
+/// active. These are safe to stop keeping alive.
+pub fn find_dangling_references(
+    repo: &Repo,
+    event_replayer: &EventReplayer,
+    event_cursor: EventCursor,
+) -> eyre::Result<Vec<NonZeroOid>> {
+    let mut result = Vec::new();
+    for oid in get_kept_alive_commits(repo)? {
+        match event_replayer.get_cursor_commit_activity_status(event_cursor, oid) {
             CommitActivityStatus::Active => {
                 // Do nothing.
             }
@@ -52,13 +208,109 @@ pub fn find_dangling_references<'repo>(
             }
             CommitActivityStatus::Obsolete => {
                 // This commit was explicitly hidden by some operation.
-                result.push(reference)
+                result.push(oid)
             }
         }
     }
     Ok(result)
 }
 
+/// Move the commits kept alive by the legacy `refs/branchless/<oid>`
+/// references, which earlier versions created for each commit, into the
+/// keep-alive chain, and delete those references.
+///
+/// Returns: The number of references migrated.
+#[instrument]
+pub fn migrate_keep_alive_references(repo: &Repo) -> eyre::Result<usize> {
+    let mut legacy_references = Vec::new();
+    let mut commit_oids = Vec::new();
+    for reference in repo.get_all_references()? {
+        let reference_name = reference.get_name()?;
+        if !is_gc_ref(&reference_name) {
+            continue;
+        }
+        let is_legacy_reference = reference_name
+            .as_str()
+            .strip_prefix("refs/branchless/")
+            .map(|suffix| suffix.parse::<NonZeroOid>().is_ok())
+            .unwrap_or(false);
+        if !is_legacy_reference {
+            continue;
+        }
+
+        if let Some(commit) = reference.peel_to_commit()? {
+            commit_oids.push(commit.get_oid());
+        }
+        legacy_references.push(reference);
+    }
+    if legacy_references.is_empty() {
+        return Ok(0);
+    }
+
+    mark_commits_reachable(repo, &commit_oids)?;
+
+    let num_migrated_references = legacy_references.len();
+    for mut reference in legacy_references {
+        reference.delete()?;
+    }
+    Ok(num_migrated_references)
+}
+
+/// Mark commits as reachable.
+///
+/// Once marked as reachable, the commits won't be collected by Git's garbage
+/// collection mechanism until first garbage-collected by branchless itself
+/// (using the `gc` function).
+///
+/// Commits which don't exist (such as if they were already garbage-collected)
+/// or which are already marked as reachable are skipped.
+///
+/// Args:
+/// * `repo`: The Git repository.
+/// * `commit_oids`: The commit OIDs to mark as reachable.
+#[instrument]
+pub fn mark_commits_reachable(repo: &Repo, commit_oids: &[NonZeroOid]) -> eyre::Result<()> {
+    let mut commits = Vec::new();
+    for commit_oid in commit_oids {
+        if let Some(commit) = repo.find_commit(*commit_oid)? {
+            commits.push(commit);
+        }
+    }
+    if commits.is_empty() {
+        return Ok(());
+    }
+
+    // Commands such as `git smartlog` mark the same commits on every run, so
+    // check against every commit which is already kept alive, and leave the
+    // chain unchanged if there's nothing new. Walking the chain only loads
+    // the keep-alive commits themselves, of which there is one per
+    // `MAX_KEEP_ALIVE_PARENTS` kept commits, since the new commits are folded
+    // into the tip while it has room.
+    update_keep_alive_chain(repo, |tip| {
+        let (kept_oids, _num_links) = walk_keep_alive_chain(repo, tip.clone())?;
+        let mut seen_oids: HashSet<NonZeroOid> = kept_oids.into_iter().collect();
+        let new_commits: Vec<&Commit> = commits
+            .iter()
+            .filter(|commit| seen_oids.insert(commit.get_oid()))
+            .collect();
+        if new_commits.is_empty() {
+            return Ok(tip.map(|tip| tip.get_oid()).into());
+        }
+
+        let (previous, tip_kept_oids) = match &tip {
+            Some(tip) => split_keep_alive_commit(repo, tip)?,
+            None => (None, Vec::new()),
+        };
+        let mut kept_commits = Vec::new();
+        for oid in tip_kept_oids {
+            kept_commits.push(repo.find_commit_or_fail(oid)?);
+        }
+        kept_commits.extend(new_commits.into_iter().cloned());
+        extend_keep_alive_chain(repo, previous, &kept_commits)
+    })
+    .wrap_err("Marking commits as reachable")
+}
+
 /// Mark a commit as reachable.
 ///
 /// Once marked as reachable, the commit won't be collected by Git's garbage
@@ -72,43 +324,55 @@ pub fn find_dangling_references<'repo>(
 /// * `commit_oid`: The commit OID to mark as reachable.
 #[instrument]
 pub fn mark_commit_reachable(repo: &Repo, commit_oid: NonZeroOid) -> eyre::Result<()> {
-    let ref_name = format!("refs/branchless/{commit_oid}");
-    eyre::ensure!(
-        Reference::is_valid_name(&ref_name),
-        format!("Invalid ref name to mark commit as reachable: {ref_name}")
-    );
-
-    // NB: checking for the commit first with `find_commit` is racy, as the `create_reference` call
-    // could still fail if the commit is deleted by then, but it's too hard to propagate whether the
-    // commit was not found from `create_reference`.
-    if repo.find_commit(commit_oid)?.is_some() {
-        repo.create_reference(
-            &ref_name.into(),
-            commit_oid,
-            true,
-            "branchless: marking commit as reachable",
-        )
-        .wrap_err("Creating reference")?;
-    }
-
-    Ok(())
+    mark_commits_reachable(repo, &[commit_oid])
 }
 
 /// Run branchless's garbage collection.
 ///
 /// Frees any references to commits which are no longer visible in the smartlog.
+/// If `prune_events_older_than` is set, also removes the events older than
+/// that from the event log first.
 #[instrument]
-pub fn gc(effects: &Effects) -> eyre::Result<()> {
+pub fn gc(effects: &Effects, prune_events_older_than: Option<Duration>) -> eyre::Result<()> {
     let repo = Repo::from_current_dir()?;
     let conn = repo.get_db_conn()?;
     let event_log_db = EventLogDb::new(&conn)?;
-    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
-    let event_cursor = event_replayer.make_default_cursor();
 
     writeln!(
         effects.get_output_stream(),
         "branchless: collecting garbage"
     )?;
+    if let Some(older_than) = prune_events_older_than {
+        let horizon = SystemTime::now()
+            .checked_sub(older_than)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let num_pruned_events = event_log_db.prune_events(effects, &repo, horizon)?;
+        writeln!(
+            effects.get_output_stream(),
+            "branchless: {} pruned",
+            Pluralize {
+                determiner: None,
+                amount: num_pruned_events,
+                unit: ("event", "events"),
+            }
+        )?;
+    }
+
+    let num_migrated_references = migrate_keep_alive_references(&repo)?;
+    if num_migrated_references > 0 {
+        writeln!(
+            effects.get_output_stream(),
+            "branchless: {} migrated to {KEEP_ALIVE_REF_NAME}",
+            Pluralize {
+                determiner: None,
+                amount: num_migrated_references,
+                unit: ("keep-alive reference", "keep-alive references"),
+            }
+        )?;
+    }
+
+    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
     let dangling_references = find_dangling_references(&repo, &event_replayer, event_cursor)?;
     let num_dangling_references = Pluralize {
         determiner: None,
@@ -116,8 +380,11 @@ pub fn gc(effects: &Effects) -> eyre::Result<()> {
         unit: ("dangling reference", "dangling references"),
     }
     .to_string();
-    for mut reference in dangling_references.into_iter() {
-        reference.delete()?;
+    let (kept_oids, num_links) = walk_keep_alive_chain(&repo, get_keep_alive_tip(&repo)?)?;
+    let num_compacted_links =
+        (kept_oids.len() + MAX_KEEP_ALIVE_PARENTS - 1) / MAX_KEEP_ALIVE_PARENTS;
+    if !dangling_references.is_empty() || num_links > num_compacted_links {
+        unmark_commits_reachable(&repo, &dangling_references.into_iter().collect())?;
     }
 
     writeln!(
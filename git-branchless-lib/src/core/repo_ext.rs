@@ -10,7 +10,7 @@ use crate::git::{
     Branch, BranchType, CategorizedReferenceName, ConfigRead, NonZeroOid, ReferenceName, Repo,
 };
 
-use super::config::get_main_branch_name;
+use super::config::{get_main_branch_name, get_public_branch_patterns};
 
 /// A snapshot of all the positions of references we care about in the repository.
 #[derive(Debug)]
@@ -25,6 +25,41 @@ pub struct RepoReferencesSnapshot {
     pub branch_oid_to_names: HashMap<NonZeroOid, HashSet<ReferenceName>>,
 }
 
+impl RepoReferencesSnapshot {
+    /// Get the locations of the public branches, i.e. the main branch and any
+    /// local branches matching `branchless.core.publicBranches`. The main
+    /// branch comes first, followed by the other public branches in order of
+    /// branch name.
+    #[instrument]
+    pub fn get_public_branch_oids(&self, repo: &Repo) -> eyre::Result<Vec<NonZeroOid>> {
+        let patterns = get_public_branch_patterns(repo)?;
+        let mut public_branches: Vec<(String, NonZeroOid)> = Vec::new();
+        if !patterns.is_empty() {
+            for (oid, names) in self.branch_oid_to_names.iter() {
+                for name in names {
+                    let name = match CategorizedReferenceName::new(name) {
+                        name @ CategorizedReferenceName::LocalBranch { .. } => name.render_suffix(),
+                        CategorizedReferenceName::RemoteBranch { .. }
+                        | CategorizedReferenceName::OtherRef { .. } => continue,
+                    };
+                    if patterns.iter().any(|pattern| pattern.matches(&name)) {
+                        public_branches.push((name, *oid));
+                    }
+                }
+            }
+        }
+        public_branches.sort();
+
+        let mut result = vec![self.main_branch_oid];
+        for (_name, oid) in public_branches {
+            if !result.contains(&oid) {
+                result.push(oid);
+            }
+        }
+        Ok(result)
+    }
+}
+
 /// Helper functions on [`Repo`].
 pub trait RepoExt {
     /// Get the `Branch` for the main branch for the repository.
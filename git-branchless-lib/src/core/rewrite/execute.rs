@@ -430,7 +430,7 @@ mod in_memory {
 
     use crate::core::effects::{Effects, OperationIcon, OperationType};
     use crate::core::eventlog::EventLogDb;
-    use crate::core::gc::mark_commit_reachable;
+    use crate::core::gc::mark_commits_reachable;
     use crate::core::rewrite::execute::check_out_updated_head;
     use crate::core::rewrite::move_branches;
     use crate::core::rewrite::plan::{OidOrLabel, RebaseCommand, RebasePlan};
@@ -871,11 +871,14 @@ mod in_memory {
         let rewritten_oids_map: HashMap<NonZeroOid, MaybeZeroOid> =
             rewritten_oids.iter().copied().collect();
 
-        for new_oid in rewritten_oids_map.values() {
-            if let MaybeZeroOid::NonZero(new_oid) = new_oid {
-                mark_commit_reachable(repo, *new_oid)?;
-            }
-        }
+        let new_oids: Vec<NonZeroOid> = rewritten_oids_map
+            .values()
+            .filter_map(|new_oid| match new_oid {
+                MaybeZeroOid::NonZero(new_oid) => Some(*new_oid),
+                MaybeZeroOid::Zero => None,
+            })
+            .collect();
+        mark_commits_reachable(repo, &new_oids)?;
 
         let head_info = repo.get_head_info()?;
         if head_info.oid.is_some() {
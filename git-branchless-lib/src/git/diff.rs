@@ -15,6 +15,21 @@ pub struct Diff<'repo> {
     pub(super) inner: git2::Diff<'repo>,
 }
 
+impl Diff<'_> {
+    /// Render a summary of the files touched by this diff, in the style of
+    /// `git diff --stat`. Lines are limited to approximately `width` columns.
+    pub fn render_stat(&self, width: usize) -> eyre::Result<String> {
+        let stats = self.inner.stats().wrap_err("Calculating diff stats")?;
+        let buf = stats
+            .to_buf(
+                git2::DiffStatsFormat::FULL | git2::DiffStatsFormat::INCLUDE_SUMMARY,
+                width,
+            )
+            .wrap_err("Rendering diff stats")?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct GitHunk {
     old_start: usize,
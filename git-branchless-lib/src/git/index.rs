@@ -74,6 +74,16 @@ impl Index {
         self.get_entry_in_stage(path, Stage::Stage0)
     }
 
+    /// Whether the given path is excluded from the working copy by a sparse
+    /// checkout, in which case it's not expected to be present on disk.
+    pub fn is_skip_worktree(&self, path: &Path) -> bool {
+        match self.inner.get_path(path, i32::from(Stage::Stage0)) {
+            Some(entry) => git2::IndexEntryExtendedFlag::from_bits_truncate(entry.flags_extended)
+                .is_skip_worktree(),
+            None => false,
+        }
+    }
+
     /// Get the entry for the given path in the given stage.
     pub fn get_entry_in_stage(&self, path: &Path, stage: Stage) -> Option<IndexEntry> {
         self.inner
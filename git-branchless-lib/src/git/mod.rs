@@ -9,6 +9,7 @@ mod reference;
 mod repo;
 mod run;
 mod snapshot;
+mod sparse;
 mod status;
 mod test;
 mod tree;
@@ -21,6 +22,7 @@ pub use oid::{MaybeZeroOid, NonZeroOid};
 pub use reference::{
     Branch, BranchType, CategorizedReferenceName, Reference, ReferenceName, ReferenceTarget,
 };
+pub(crate) use repo::Signature;
 pub use repo::{
     message_prettify, AmendFastOptions, CherryPickFastError, CherryPickFastOptions,
     Error as RepoError, GitVersion, PatchId, Repo, ResolvedReferenceInfo, Result as RepoResult,
@@ -28,10 +30,14 @@ pub use repo::{
 };
 pub use run::{GitRunInfo, GitRunOpts, GitRunResult};
 pub use snapshot::{WorkingCopyChangesType, WorkingCopySnapshot};
+pub use sparse::SparseCheckout;
 pub use status::{FileMode, FileStatus, StatusEntry};
 pub use test::{
-    get_latest_test_command_path, get_test_locks_dir, get_test_tree_dir, get_test_worktrees_dir,
-    make_test_command_slug, SerializedNonZeroOid, SerializedTestResult, TestCommand,
-    TEST_ABORT_EXIT_CODE, TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
+    get_bisect_state_path, get_latest_test_command_path, get_test_locks_dir, get_test_tree_dir,
+    get_test_tree_dir_for_tree_oid, get_test_worktrees_dir, make_test_command_slug,
+    SerializedNonZeroOid, SerializedTestResult, TestCommand, TEST_ABORT_EXIT_CODE,
+    TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE, TEST_TIMED_OUT_EXIT_CODE,
+};
+pub use tree::{
+    dehydrate_tree, get_changed_paths_between_trees, hydrate_tree, make_empty_tree, Tree,
 };
-pub use tree::{dehydrate_tree, get_changed_paths_between_trees, hydrate_tree, Tree};
@@ -41,6 +41,7 @@ use crate::git::{Branch, BranchType, Commit, Reference, ReferenceName};
 
 use super::index::{Index, IndexEntry};
 use super::snapshot::WorkingCopySnapshot;
+use super::sparse::SparseCheckout;
 use super::status::FileMode;
 use super::{tree, Diff, StatusEntry};
 
@@ -74,6 +75,9 @@ pub enum Error {
     #[error("could not read config: {0}")]
     ReadConfig(#[source] git2::Error),
 
+    #[error("could not read sparse checkout patterns at {path}: {source}")]
+    ReadSparseCheckout { source: io::Error, path: PathBuf },
+
     #[error("could not set HEAD (detached) to {oid}: {source}")]
     SetHead {
         source: git2::Error,
@@ -176,6 +180,26 @@ pub enum Error {
     #[error("could not resolve reference: {0}")]
     ResolveReference(#[source] git2::Error),
 
+    #[error("could not read notes in '{}': {source}", notes_ref.as_str())]
+    ReadNotes {
+        source: git2::Error,
+        notes_ref: ReferenceName,
+    },
+
+    #[error("could not find note for object {oid} in '{}': {source}", notes_ref.as_str())]
+    FindNote {
+        source: git2::Error,
+        notes_ref: ReferenceName,
+        oid: NonZeroOid,
+    },
+
+    #[error("could not create note for object {oid} in '{}': {source}", notes_ref.as_str())]
+    CreateNote {
+        source: git2::Error,
+        notes_ref: ReferenceName,
+        oid: NonZeroOid,
+    },
+
     #[error("could not diff trees {old_tree} and {new_tree}: {source}")]
     DiffTreeToTree {
         source: git2::Error,
@@ -198,6 +222,12 @@ pub enum Error {
     #[error("could not create reference: {0}")]
     CreateReference(#[source] git2::Error),
 
+    #[error("could not update reference '{}': {source}", name.as_str())]
+    UpdateReference {
+        source: git2::Error,
+        name: ReferenceName,
+    },
+
     #[error("could not calculate changed paths: {0}")]
     GetChangedPaths(#[source] super::tree::Error),
 
@@ -460,10 +490,29 @@ impl std::fmt::Debug for Repo {
     }
 }
 
+/// Declare to libgit2 that we support the `extensions.worktreeConfig`
+/// extension, which libgit2 otherwise refuses to open repositories with. The
+/// extension only makes Git read the `config.worktree` file in addition to the
+/// usual configuration files, which is handled by `Repo::get_config_snapshot`.
+fn register_supported_extensions() {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        // SAFETY: the extensions are stored in a global variable in libgit2,
+        // which is only written here, exactly once.
+        if let Err(err) = unsafe { git2::opts::set_extensions(&["worktreeconfig"]) } {
+            warn!(
+                ?err,
+                "Could not register support for extensions.worktreeConfig"
+            );
+        }
+    });
+}
+
 impl Repo {
     /// Get the Git repository associated with the given directory.
     #[instrument]
     pub fn from_dir(path: &Path) -> Result<Self> {
+        register_supported_extensions();
         let repo = match git2::Repository::discover(path) {
             Ok(repo) => repo,
             Err(err)
@@ -557,10 +606,74 @@ impl Repo {
     /// directory.
     #[instrument]
     pub fn get_readonly_config(&self) -> Result<impl ConfigRead> {
-        let config = self.inner.config().map_err(Error::ReadConfig)?;
+        let config = self.get_config_snapshot()?;
         Ok(Config::from(config))
     }
 
+    /// Get a read-only snapshot of the repository's configuration. If
+    /// `extensions.worktreeConfig` is enabled, it also includes the
+    /// configuration file for the current worktree, since libgit2 doesn't read
+    /// it by itself.
+    fn get_config_snapshot(&self) -> Result<git2::Config> {
+        let mut config = self
+            .inner
+            .config()
+            .and_then(|mut config| config.snapshot())
+            .map_err(Error::ReadConfig)?;
+        let is_worktree_config_enabled = config
+            .get_bool("extensions.worktreeConfig")
+            .unwrap_or(false);
+        if is_worktree_config_enabled {
+            // libgit2 doesn't have a level for worktree-specific configuration,
+            // but the application level also takes precedence over the
+            // repository's configuration file, as the worktree level does in
+            // Git. The file is only added to this snapshot, rather than to the
+            // repository's own configuration, so that writes never go to it.
+            let worktree_config_path = self.get_path().join("config.worktree");
+            config
+                .add_file(&worktree_config_path, git2::ConfigLevel::App, false)
+                .map_err(Error::ReadConfig)?;
+        }
+        Ok(config)
+    }
+
+    /// Get the configuration file of the repository itself, i.e. `.git/config`,
+    /// for writing. Unlike `get_readonly_config`, this excludes the global
+    /// configuration files and the worktree-specific configuration file.
+    #[instrument]
+    pub fn get_local_config(&self) -> Result<Config> {
+        let config = self
+            .inner
+            .config()
+            .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+            .map_err(Error::ReadConfig)?;
+        Ok(Config::from(config))
+    }
+
+    /// Get the sparse checkout patterns for the current worktree, or `None` if
+    /// it's not a sparse checkout.
+    #[instrument]
+    pub fn get_sparse_checkout(&self) -> Result<Option<SparseCheckout>> {
+        let config = self.get_config_snapshot()?;
+        let get_bool = |key: &str| match config.get_bool(key) {
+            Ok(value) => Ok(value),
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(false),
+            Err(err) => Err(Error::ReadConfig(err)),
+        };
+        if !get_bool("core.sparseCheckout")? {
+            return Ok(None);
+        }
+        let is_cone = get_bool("core.sparseCheckoutCone")?;
+
+        let path = self.get_path().join("info").join("sparse-checkout");
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(Error::ReadSparseCheckout { source, path }),
+        };
+        Ok(Some(SparseCheckout::parse(&contents, is_cone)))
+    }
+
     /// Get the file where git-branchless-specific Git configuration is stored.
     #[instrument]
     pub fn get_config_path(&self) -> PathBuf {
@@ -770,6 +883,22 @@ impl Repo {
         old_tree: Option<&Tree>,
         new_tree: &Tree,
         num_context_lines: usize,
+    ) -> Result<Diff> {
+        self.get_diff_between_trees_for_paths(effects, old_tree, new_tree, num_context_lines, &[])
+    }
+
+    /// Get the diff between two trees, like [`Repo::get_diff_between_trees`],
+    /// but only for the paths which match any of the given pathspecs. The
+    /// pathspecs are relative to the root of the repository. If no pathspecs
+    /// are provided, then all paths are included.
+    #[instrument]
+    pub fn get_diff_between_trees_for_paths(
+        &self,
+        effects: &Effects,
+        old_tree: Option<&Tree>,
+        new_tree: &Tree,
+        num_context_lines: usize,
+        pathspecs: &[String],
     ) -> Result<Diff> {
         let (effects, _progress) = effects.start_operation(OperationType::CalculateDiff);
         let _effects = effects;
@@ -777,13 +906,14 @@ impl Repo {
         let old_tree = old_tree.map(|tree| &tree.inner);
         let new_tree = Some(&new_tree.inner);
 
+        let mut diff_options = DiffOptions::new();
+        diff_options.context_lines(num_context_lines.try_into().unwrap());
+        for pathspec in pathspecs {
+            diff_options.pathspec(pathspec);
+        }
         let diff = self
             .inner
-            .diff_tree_to_tree(
-                old_tree,
-                new_tree,
-                Some(DiffOptions::new().context_lines(num_context_lines.try_into().unwrap())),
-            )
+            .diff_tree_to_tree(old_tree, new_tree, Some(&mut diff_options))
             .map_err(|err| Error::DiffTreeToTree {
                 source: err,
                 old_tree: old_tree
@@ -990,6 +1120,52 @@ impl Repo {
         Ok(Reference { inner: reference })
     }
 
+    /// Atomically update the reference to point to `new_oid`, or delete it if
+    /// `new_oid` is zero, but only if it currently points to
+    /// `expected_old_oid` (or doesn't exist, if `expected_old_oid` is zero).
+    ///
+    /// Returns: Whether the reference was updated. If `false`, the reference
+    /// was changed by someone else, and the caller may want to retry.
+    #[instrument]
+    pub fn update_reference_if_unchanged(
+        &self,
+        name: &ReferenceName,
+        expected_old_oid: MaybeZeroOid,
+        new_oid: MaybeZeroOid,
+        log_message: &str,
+    ) -> Result<bool> {
+        let map_err = |source| Error::UpdateReference {
+            source,
+            name: name.clone(),
+        };
+        let mut transaction = self.inner.transaction().map_err(map_err)?;
+        transaction.lock_ref(name.as_str()).map_err(map_err)?;
+        let current_oid: MaybeZeroOid = match self.find_reference(name)? {
+            Some(reference) => match reference.inner.target() {
+                Some(oid) => oid.into(),
+                None => MaybeZeroOid::Zero,
+            },
+            None => MaybeZeroOid::Zero,
+        };
+        if current_oid != expected_old_oid {
+            return Ok(false);
+        }
+
+        match (current_oid, new_oid) {
+            (_, MaybeZeroOid::NonZero(new_oid)) => {
+                transaction
+                    .set_target(name.as_str(), new_oid.inner, None, log_message)
+                    .map_err(map_err)?;
+            }
+            (MaybeZeroOid::NonZero(_), MaybeZeroOid::Zero) => {
+                transaction.remove(name.as_str()).map_err(map_err)?;
+            }
+            (MaybeZeroOid::Zero, MaybeZeroOid::Zero) => {}
+        }
+        transaction.commit().map_err(map_err)?;
+        Ok(true)
+    }
+
     /// Get a list of all remote names.
     #[instrument]
     pub fn get_all_remote_names(&self) -> Result<Vec<String>> {
@@ -1021,6 +1197,80 @@ impl Repo {
         }
     }
 
+    /// Get the OIDs of all objects which have a note attached in the given
+    /// notes reference. Returns an empty list if the notes reference doesn't
+    /// exist.
+    #[instrument]
+    pub fn get_all_noted_objects(&self, notes_ref: &ReferenceName) -> Result<Vec<NonZeroOid>> {
+        let notes = match self.inner.notes(Some(notes_ref.as_str())) {
+            Ok(notes) => notes,
+            Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(Error::ReadNotes {
+                    source: err,
+                    notes_ref: notes_ref.clone(),
+                })
+            }
+        };
+        let mut result = Vec::new();
+        for note in notes {
+            let (_note_oid, annotated_oid) = note.map_err(|err| Error::ReadNotes {
+                source: err,
+                notes_ref: notes_ref.clone(),
+            })?;
+            result.push(make_non_zero_oid(annotated_oid));
+        }
+        Ok(result)
+    }
+
+    /// Look up the message of the note attached to the given object in the
+    /// given notes reference. Returns `None` if there is no such note.
+    #[instrument]
+    pub fn find_note(&self, notes_ref: &ReferenceName, oid: NonZeroOid) -> Result<Option<String>> {
+        match self.inner.find_note(Some(notes_ref.as_str()), oid.inner) {
+            Ok(note) => match note.message() {
+                Some(message) => Ok(Some(message.to_owned())),
+                None => Err(Error::DecodeUtf8 {
+                    item: "note message",
+                }),
+            },
+            Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(err) => Err(Error::FindNote {
+                source: err,
+                notes_ref: notes_ref.clone(),
+                oid,
+            }),
+        }
+    }
+
+    /// Attach a note with the given message to the given object in the given
+    /// notes reference, replacing any existing note for that object.
+    #[instrument]
+    pub fn create_note(
+        &self,
+        notes_ref: &ReferenceName,
+        oid: NonZeroOid,
+        message: &str,
+        now: SystemTime,
+    ) -> Result<()> {
+        let signature = Signature::automated()?.update_timestamp(now)?;
+        self.inner
+            .note(
+                &signature.inner,
+                &signature.inner,
+                Some(notes_ref.as_str()),
+                oid.inner,
+                message,
+                true,
+            )
+            .map_err(|err| Error::CreateNote {
+                source: err,
+                notes_ref: notes_ref.clone(),
+                oid,
+            })?;
+        Ok(())
+    }
+
     /// Get all local branches in the repository.
     #[instrument]
     pub fn get_all_local_branches(&self) -> Result<Vec<Branch>> {
@@ -121,7 +121,12 @@ impl<'repo> WorkingCopySnapshot<'repo> {
         let head_reference_name: Option<ReferenceName> = head_info.reference_name.clone();
 
         let commit_unstaged_oid: NonZeroOid = {
-            Self::create_commit_for_unstaged_changes(repo, head_commit.as_ref(), status_entries)?
+            Self::create_commit_for_unstaged_changes(
+                repo,
+                index,
+                head_commit.as_ref(),
+                status_entries,
+            )?
         };
 
         let commit_stage0 = Self::create_commit_for_stage(
@@ -299,6 +304,7 @@ branchless: automated working copy snapshot
     #[instrument]
     fn create_commit_for_unstaged_changes(
         repo: &Repo,
+        index: &Index,
         head_commit: Option<&Commit>,
         status_entries: &[StatusEntry],
     ) -> eyre::Result<NonZeroOid> {
@@ -342,6 +348,15 @@ branchless: automated working copy snapshot
                     // existence on disk because it's no longer being tracked by
                     // the index.
                     None
+                } else if index.is_skip_worktree(&path) {
+                    // The file is outside of the sparse checkout, so it's not
+                    // on disk, and its contents are the same as in the index.
+                    index
+                        .get_entry(&path)
+                        .and_then(|IndexEntry { oid, file_mode }| match oid {
+                            MaybeZeroOid::NonZero(oid) => Some((oid, file_mode)),
+                            MaybeZeroOid::Zero => None,
+                        })
                 } else {
                     repo.create_blob_from_path(&path)?
                         .map(|blob_oid| (blob_oid, file_mode))
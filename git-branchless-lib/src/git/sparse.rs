@@ -0,0 +1,119 @@
+//! Reading the configuration of sparse checkouts, as set up by `git
+//! sparse-checkout`.
+
+/// The patterns which determine which paths are present in the working copy
+/// of a sparse checkout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SparseCheckout {
+    /// In cone mode, the paths in the listed directories are included
+    /// recursively, along with the files directly inside their ancestor
+    /// directories.
+    Cone {
+        /// The directories to include, relative to the root of the repository.
+        directories: Vec<String>,
+    },
+
+    /// Otherwise, the paths matching the gitignore-style patterns are included.
+    Patterns {
+        /// The patterns, as written in the `info/sparse-checkout` file.
+        patterns: Vec<String>,
+    },
+}
+
+impl SparseCheckout {
+    /// Parse the contents of the `info/sparse-checkout` file.
+    pub fn parse(contents: &str, is_cone: bool) -> Self {
+        let patterns = contents
+            .lines()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        if !is_cone {
+            return SparseCheckout::Patterns {
+                patterns: patterns.into_iter().map(|line| line.to_owned()).collect(),
+            };
+        }
+
+        // In cone mode, a directory `/foo/` is included recursively unless it's
+        // followed by the `!/foo/*/` pattern, which means that only the files
+        // directly inside it are included, as for the ancestors of a
+        // recursively-included directory.
+        let directories = patterns
+            .iter()
+            .filter(|line| !line.starts_with('!') && **line != "/*")
+            .filter_map(|line| {
+                let directory = line.strip_prefix('/')?.strip_suffix('/')?;
+                let parent_pattern = format!("!/{directory}/*/");
+                if patterns.contains(&parent_pattern.as_str()) {
+                    None
+                } else {
+                    Some(unescape_cone_pattern(directory))
+                }
+            })
+            .collect();
+        SparseCheckout::Cone { directories }
+    }
+
+    /// The arguments to pass to `git sparse-checkout set` to reproduce these
+    /// patterns.
+    pub fn get_set_args(&self) -> Vec<String> {
+        let (mode_arg, patterns) = match self {
+            SparseCheckout::Cone { directories } => ("--cone", directories),
+            SparseCheckout::Patterns { patterns } => ("--no-cone", patterns),
+        };
+        std::iter::once(mode_arg.to_owned())
+            .chain(std::iter::once("--".to_owned()))
+            .chain(patterns.iter().cloned())
+            .collect()
+    }
+}
+
+/// Git escapes glob characters in the directory names written to the
+/// `info/sparse-checkout` file in cone mode.
+fn unescape_cone_pattern(pattern: &str) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cone_patterns() {
+        let contents = "\
+/*
+!/*/
+/foo/
+!/foo/*/
+/foo/bar/
+/baz\\*/
+";
+        assert_eq!(
+            SparseCheckout::parse(contents, true),
+            SparseCheckout::Cone {
+                directories: vec!["foo/bar".to_owned(), "baz*".to_owned()]
+            }
+        );
+        assert_eq!(
+            SparseCheckout::parse(contents, false),
+            SparseCheckout::Patterns {
+                patterns: vec![
+                    "/*".to_owned(),
+                    "!/*/".to_owned(),
+                    "/foo/".to_owned(),
+                    "!/foo/*/".to_owned(),
+                    "/foo/bar/".to_owned(),
+                    "/baz\\*/".to_owned(),
+                ]
+            }
+        );
+    }
+}
@@ -9,7 +9,7 @@ use std::{fmt::Display, path::PathBuf};
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-use super::{Commit, NonZeroOid, Repo};
+use super::{Commit, MaybeZeroOid, NonZeroOid, Repo};
 
 /// The exit status to use when a test command succeeds.
 pub const TEST_SUCCESS_EXIT_CODE: i32 = 0;
@@ -38,6 +38,11 @@ pub const TEST_INDETERMINATE_EXIT_CODE: i32 = 125;
 /// shouldn't be too confusing in practice.
 pub const TEST_ABORT_EXIT_CODE: i32 = 127;
 
+/// The exit status recorded for a test command which was killed because it ran
+/// for longer than its timeout. This matches the exit status used by the
+/// `timeout` utility from GNU coreutils.
+pub const TEST_TIMED_OUT_EXIT_CODE: i32 = 124;
+
 /// Convert a command string into a string that's safe to use as a filename.
 pub fn make_test_command_slug(command: String) -> String {
     command.replace(['/', ' ', '\n'], "__")
@@ -101,6 +106,18 @@ pub struct SerializedTestResult {
     pub snapshot_tree_oid: Option<SerializedNonZeroOid>,
     #[serde(default)]
     pub interactive: bool,
+    #[serde(default)]
+    pub timed_out: bool,
+    #[serde(default)]
+    pub flaky: bool,
+    /// The arguments to `git sparse-checkout set` which reproduce the sparse
+    /// checkout that the test ran in, or `None` if it ran in a full checkout.
+    #[serde(default)]
+    pub sparse_checkout: Option<Vec<String>>,
+    /// How long the test command took to run, in milliseconds, or `None` if
+    /// the result was recorded before durations were stored.
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
 }
 
 /// Get the directory where the results of running tests are stored.
@@ -114,7 +131,13 @@ fn get_test_dir(repo: &Repo) -> PathBuf {
 /// hash. This means that we can cache the results of tests for commits that
 /// have been amended or rebased.
 pub fn get_test_tree_dir(repo: &Repo, commit: &Commit) -> PathBuf {
-    get_test_dir(repo).join(commit.get_tree_oid().to_string())
+    get_test_tree_dir_for_tree_oid(repo, commit.get_tree_oid())
+}
+
+/// Like `get_test_tree_dir`, but for when only the tree OID is available (such
+/// as when importing test results shared by someone else).
+pub fn get_test_tree_dir_for_tree_oid(repo: &Repo, tree_oid: MaybeZeroOid) -> PathBuf {
+    get_test_dir(repo).join(tree_oid.to_string())
 }
 
 /// Get the directory where the locks for running tests are stored.
@@ -131,3 +154,9 @@ pub fn get_test_worktrees_dir(repo: &Repo) -> PathBuf {
 pub fn get_latest_test_command_path(repo: &Repo) -> PathBuf {
     get_test_dir(repo).join("latest-command")
 }
+
+/// Get the path to the file where the state of the current bisection (started
+/// with `git branchless bisect start`) is stored.
+pub fn get_bisect_state_path(repo: &Repo) -> PathBuf {
+    get_test_dir(repo).join("bisect.json")
+}
@@ -458,6 +458,7 @@ pub fn hydrate_tree(
     Ok(make_non_zero_oid(tree_oid))
 }
 
+/// Create a tree with no entries.
 pub fn make_empty_tree(repo: &Repo) -> Result<Tree> {
     let tree_oid = hydrate_tree(repo, None, Default::default())?;
     repo.find_tree_or_fail(tree_oid)
@@ -1,9 +1,14 @@
 use std::str::FromStr;
 
-use branchless::core::eventlog::testing::{new_event_cursor, new_event_transaction_id};
+use branchless::core::effects::Effects;
+use branchless::core::eventlog::testing::{
+    from_event_log_db_with_checkpoint_interval, get_event_replayer_events, new_event_cursor,
+    new_event_transaction_id,
+};
 use branchless::core::eventlog::{
-    testing::new_event_replayer, Event, EventLogDb, EventTransactionId,
+    testing::new_event_replayer, Event, EventLogDb, EventReplayer, EventTransactionId,
 };
+use branchless::core::formatting::Glyphs;
 use branchless::git::{MaybeZeroOid, NonZeroOid, ReferenceName};
 use git_branchless_testing::make_git;
 
@@ -160,3 +165,146 @@ fn test_advance_cursor_by_transaction() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_event_replayer_checkpoint() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    let test1_oid = git.commit_file("test1", 1)?;
+    git.run(&["branch", "foo"])?;
+    git.run(&["branch", "bar"])?;
+    git.commit_file("test2", 2)?;
+    git.branchless("hide", &[&test1_oid.to_string()])?;
+    git.run(&["branch", "-D", "bar"])?;
+
+    let effects = Effects::new_suppress_for_test(Glyphs::text());
+    let repo = git.get_repo()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    // Save a checkpoint as of the events so far.
+    from_event_log_db_with_checkpoint_interval(&effects, &repo, &event_log_db, 1)?;
+
+    git.commit_file("test3", 3)?;
+    git.branchless("unhide", &[&test1_oid.to_string()])?;
+    git.run(&["branch", "-f", "foo", "HEAD"])?;
+
+    let checkpoint_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+    let full_replayer =
+        EventReplayer::from_event_log_db_with_history(&effects, &repo, &event_log_db)?;
+    let checkpoint_cursor = checkpoint_replayer.make_default_cursor();
+    let full_cursor = full_replayer.make_default_cursor();
+    assert_eq!(checkpoint_cursor, full_cursor);
+    assert_eq!(
+        checkpoint_replayer.get_event_before_cursor(checkpoint_cursor),
+        full_replayer.get_event_before_cursor(full_cursor),
+    );
+
+    // The checkpointed replayer only has the events after the checkpoint.
+    assert!(
+        get_event_replayer_events(&checkpoint_replayer).len()
+            < get_event_replayer_events(&full_replayer).len()
+    );
+    assert_eq!(
+        checkpoint_replayer.make_cursor(0),
+        checkpoint_replayer.advance_cursor(checkpoint_cursor, -1000),
+    );
+    assert_ne!(
+        checkpoint_replayer.make_cursor(0),
+        full_replayer.make_cursor(0)
+    );
+
+    let checkpoint_oids = checkpoint_replayer.get_cursor_oids(checkpoint_cursor);
+    let full_oids = full_replayer.get_cursor_oids(full_cursor);
+    assert_eq!(checkpoint_oids, full_oids);
+    for oid in full_oids {
+        assert_eq!(
+            format!(
+                "{:?}",
+                checkpoint_replayer.get_cursor_commit_activity_status(checkpoint_cursor, oid)
+            ),
+            format!(
+                "{:?}",
+                full_replayer.get_cursor_commit_activity_status(full_cursor, oid)
+            ),
+        );
+        assert_eq!(
+            checkpoint_replayer.get_cursor_commit_latest_event(checkpoint_cursor, oid),
+            full_replayer.get_cursor_commit_latest_event(full_cursor, oid),
+        );
+    }
+
+    let checkpoint_snapshot =
+        checkpoint_replayer.get_references_snapshot(&repo, checkpoint_cursor)?;
+    let full_snapshot = full_replayer.get_references_snapshot(&repo, full_cursor)?;
+    assert_eq!(checkpoint_snapshot.head_oid, full_snapshot.head_oid);
+    assert_eq!(
+        checkpoint_snapshot.main_branch_oid,
+        full_snapshot.main_branch_oid
+    );
+    assert_eq!(
+        checkpoint_snapshot.branch_oid_to_names,
+        full_snapshot.branch_oid_to_names
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_event_replayer_checkpoint_tables_without_kind() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    let effects = Effects::new_suppress_for_test(Glyphs::text());
+    let repo = git.get_repo()?;
+    let conn = repo.get_db_conn()?;
+    // Recreate the checkpoint tables as they were before checkpoints had kinds.
+    conn.execute_batch(
+        "
+DROP TABLE event_replayer_checkpoint;
+DROP TABLE event_replayer_checkpoint_events;
+DROP TABLE event_replayer_checkpoint_refs;
+CREATE TABLE event_replayer_checkpoint (
+    event_log_rowid INTEGER NOT NULL,
+    event_id INTEGER NOT NULL,
+    head_oid TEXT
+);
+CREATE TABLE event_replayer_checkpoint_events (
+    commit_oid TEXT,
+    event_id INTEGER NOT NULL,
+    classification TEXT,
+    timestamp REAL NOT NULL,
+    type TEXT NOT NULL,
+    event_tx_id INTEGER NOT NULL,
+    old_ref TEXT,
+    new_ref TEXT,
+    ref_name TEXT,
+    message TEXT
+);
+CREATE TABLE event_replayer_checkpoint_refs (
+    ref_name TEXT NOT NULL,
+    oid TEXT NOT NULL,
+    is_deleted INTEGER NOT NULL
+);
+",
+    )?;
+
+    let event_log_db = EventLogDb::new(&conn)?;
+    from_event_log_db_with_checkpoint_interval(&effects, &repo, &event_log_db, 1)?;
+    git.commit_file("test3", 3)?;
+
+    let checkpoint_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+    let full_replayer =
+        EventReplayer::from_event_log_db_with_history(&effects, &repo, &event_log_db)?;
+    assert_eq!(
+        checkpoint_replayer.get_cursor_oids(checkpoint_replayer.make_default_cursor()),
+        full_replayer.get_cursor_oids(full_replayer.make_default_cursor()),
+    );
+
+    Ok(())
+}
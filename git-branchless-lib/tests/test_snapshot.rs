@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::time::SystemTime;
 
 use branchless::core::effects::Effects;
@@ -58,3 +59,61 @@ fn test_has_conflicts() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_snapshot_sparse_checkout() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.write_file_txt("included/foo", "foo")?;
+    git.write_file_txt("excluded/bar", "bar")?;
+    git.run(&["add", "."])?;
+    git.run(&["commit", "-m", "add files"])?;
+    if git.run(&["sparse-checkout", "set", "included"]).is_err() {
+        return Ok(());
+    }
+
+    // Stage a change to a file outside of the sparse checkout, which isn't
+    // present on disk.
+    let (blob_oid, _stderr) = git.run_with_options(
+        &["hash-object", "-w", "--stdin"],
+        &GitRunOptions {
+            input: Some("updated bar".to_string()),
+            ..Default::default()
+        },
+    )?;
+    git.run(&[
+        "update-index",
+        "--cacheinfo",
+        &format!("100644,{},excluded/bar.txt", blob_oid.trim()),
+    ])?;
+    git.run(&["update-index", "--skip-worktree", "excluded/bar.txt"])?;
+    assert!(!git.repo_path.join("excluded").exists());
+
+    let glyphs = Glyphs::text();
+    let effects = Effects::new_suppress_for_test(glyphs);
+    let git_run_info = git.get_git_run_info();
+    let repo = git.get_repo()?;
+    let index = repo.get_index()?;
+    let head_info = repo.get_head_info()?;
+    let (snapshot, status) = repo.get_status(&effects, &git_run_info, &index, &head_info, None)?;
+    insta::assert_debug_snapshot!(status, @r###"
+    [
+        StatusEntry {
+            index_status: Modified,
+            working_copy_status: Unmodified,
+            working_copy_file_mode: Blob,
+            path: "excluded/bar.txt",
+            orig_path: None,
+        },
+    ]
+    "###);
+
+    let tree = snapshot.commit_unstaged.get_tree()?;
+    let entry = tree
+        .get_path(Path::new("excluded/bar.txt"))?
+        .map(|entry| entry.get_oid().to_string());
+    assert_eq!(entry, Some(blob_oid.trim().to_string()));
+
+    Ok(())
+}
@@ -25,21 +25,22 @@ use lib::core::repo_ext::RepoExt;
 use lib::util::{ExitCode, EyreExitOr};
 use tracing::{instrument, warn};
 
-use git_branchless_opts::{SwitchOptions, TraverseCommitsOptions};
-use git_branchless_revset::resolve_default_smartlog_commits;
-use git_branchless_smartlog::make_smartlog_graph;
-use lib::core::config::get_next_interactive;
-use lib::core::dag::{sorted_commit_set, CommitSet, Dag};
+use git_branchless_hide::hide;
+use git_branchless_opts::{ResolveRevsetOptions, Revset, SwitchOptions, TraverseCommitsOptions};
+use git_branchless_revset::resolve_commits;
+use git_branchless_smartlog::{make_smartlog_graph, render_graph, SmartlogGraph};
+use lib::core::config::{get_next_interactive, get_smartlog_default_revset};
+use lib::core::dag::{sorted_commit_set, union_all, CommitSet, Dag};
 use lib::core::effects::Effects;
-use lib::core::eventlog::{EventLogDb, EventReplayer};
-use lib::core::formatting::Pluralize;
+use lib::core::eventlog::{EventLogDb, EventReplayer, EventTransactionId};
+use lib::core::formatting::{Pluralize, StyledStringBuilder};
 use lib::core::node_descriptors::{
     BranchesDescriptor, CommitMessageDescriptor, CommitOidDescriptor,
     DifferentialRevisionDescriptor, NodeDescriptor, Redactor, RelativeTimeDescriptor,
 };
-use lib::git::{GitRunInfo, NonZeroOid, Repo};
+use lib::git::{Commit, GitRunInfo, NonZeroOid, Repo};
 
-use crate::prompt::prompt_select_commit;
+use crate::prompt::{prompt_select_commit, prompt_switch_commit, prompt_text, SwitchAction};
 
 /// The command being invoked, indicating which direction to traverse commits.
 #[derive(Clone, Copy, Debug)]
@@ -108,7 +109,7 @@ fn advance(
         }
     };
 
-    let public_commits = dag.query_ancestors(dag.main_branch_commit.clone())?;
+    let public_commits = dag.query_ancestors(dag.public_branch_commits.clone())?;
 
     let glyphs = effects.get_glyphs();
     let mut current_oid = current_oid;
@@ -480,6 +481,8 @@ pub fn switch(
         merge,
         target,
         detach,
+        revset,
+        resolve_revset_options,
     } = switch_options;
 
     let now = SystemTime::now();
@@ -499,16 +502,6 @@ pub fn switch(
         &references_snapshot,
     )?;
 
-    let commits = resolve_default_smartlog_commits(effects, &repo, &mut dag)?;
-    let graph = make_smartlog_graph(
-        effects,
-        &repo,
-        &dag,
-        &event_replayer,
-        event_cursor,
-        &commits,
-    )?;
-
     let initial_query = match switch_options {
         SwitchOptions {
             interactive: true,
@@ -516,6 +509,8 @@ pub fn switch(
             force: _,
             merge: _,
             detach: _,
+            revset: _,
+            resolve_revset_options: _,
             target,
         } => Some(target.clone().unwrap_or_default()),
         SwitchOptions {
@@ -524,16 +519,55 @@ pub fn switch(
             force: _,
             merge: _,
             detach: _,
+            revset: _,
+            resolve_revset_options: _,
             target: _,
         } => None,
     };
     let target: Option<CheckoutTarget> = match initial_query {
         None => target.clone().map(CheckoutTarget::Unknown),
         Some(initial_query) => {
-            match prompt_select_commit(
-                None,
+            let revset = match revset {
+                Some(revset) => revset.clone(),
+                None => Revset(get_smartlog_default_revset(&repo)?),
+            };
+            let commits = match resolve_commits(
+                effects,
+                &repo,
+                &mut dag,
+                &[revset],
+                resolve_revset_options,
+            ) {
+                Ok(commit_sets) => union_all(&commit_sets),
+                Err(err) => {
+                    err.describe(effects)?;
+                    return Ok(Err(ExitCode(1)));
+                }
+            };
+            let graph = make_smartlog_graph(
+                effects,
+                &repo,
+                &dag,
+                &event_replayer,
+                event_cursor,
+                &commits,
+            )?;
+
+            // The smartlog graph also includes commits connecting the
+            // requested commits to the main branch, but only the requested
+            // commits should be offered for selection.
+            let mut candidates = Vec::new();
+            for commit in graph.get_commits() {
+                if dag.set_contains(&commits, commit.get_oid())? {
+                    let preview = make_switch_preview(effects, &repo, &dag, &graph, &commit)?;
+                    candidates.push((commit, preview));
+                }
+            }
+
+            match prompt_switch_commit(
+                Some("enter: switch, ctrl-b: create branch, ctrl-x: hide"),
                 &initial_query,
-                graph.get_commits(),
+                candidates,
                 &mut [
                     &mut CommitOidDescriptor::new(true)?,
                     &mut RelativeTimeDescriptor::new(&repo, SystemTime::now())?,
@@ -547,7 +581,26 @@ pub fn switch(
                     &mut CommitMessageDescriptor::new(&Redactor::Disabled)?,
                 ],
             )? {
-                Some(oid) => Some(CheckoutTarget::Oid(oid)),
+                Some((SwitchAction::Switch, oid)) => Some(CheckoutTarget::Oid(oid)),
+                Some((SwitchAction::CreateBranch, oid)) => {
+                    return create_branch_at_commit(
+                        effects,
+                        git_run_info,
+                        event_tx_id,
+                        branch_name.as_deref(),
+                        oid,
+                    );
+                }
+                Some((SwitchAction::Hide, oid)) => {
+                    return hide(
+                        effects,
+                        git_run_info,
+                        vec![Revset(oid.to_string())],
+                        &ResolveRevsetOptions::default(),
+                        false,
+                        false,
+                    );
+                }
                 None => return Ok(Err(ExitCode(1))),
             }
         }
@@ -586,3 +639,71 @@ pub fn switch(
     )?;
     Ok(exit_code)
 }
+
+/// Render the preview shown for a commit in the interactive switcher: the
+/// commit's metadata and diffstat, followed by the smartlog with the commit's
+/// position highlighted.
+fn make_switch_preview(
+    effects: &Effects,
+    repo: &Repo,
+    dag: &Dag,
+    graph: &SmartlogGraph,
+    commit: &Commit,
+) -> eyre::Result<StyledString> {
+    let diff_stat = match repo.get_patch_for_commit(effects, commit)? {
+        Some(diff) => diff.render_stat(80)?,
+        None => "(merge commit; no diffstat available)\n".to_string(),
+    };
+    let graph_lines = render_graph(
+        effects,
+        repo,
+        dag,
+        graph,
+        Some(commit.get_oid()),
+        &mut [
+            &mut CommitOidDescriptor::new(true)?,
+            &mut CommitMessageDescriptor::new(&Redactor::Disabled)?,
+        ],
+    )?;
+
+    let mut lines = vec![
+        commit.friendly_preview()?,
+        StyledString::new(),
+        StyledString::plain(diff_stat.trim_end().to_owned()),
+        StyledString::new(),
+    ];
+    lines.extend(graph_lines);
+    Ok(StyledStringBuilder::from_lines(lines))
+}
+
+/// Create a branch pointing to the commit selected in the interactive
+/// switcher. If no branch name was provided on the command-line, prompt for
+/// one.
+fn create_branch_at_commit(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    event_tx_id: EventTransactionId,
+    branch_name: Option<&str>,
+    oid: NonZeroOid,
+) -> EyreExitOr<()> {
+    let branch_name = match branch_name {
+        Some(branch_name) => branch_name.to_owned(),
+        None => match prompt_text("Enter a name for the new branch:")? {
+            Some(branch_name) => branch_name.trim().to_owned(),
+            None => String::new(),
+        },
+    };
+    if branch_name.is_empty() {
+        writeln!(
+            effects.get_error_stream(),
+            "No branch name provided; not creating a branch."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    git_run_info.run(
+        effects,
+        Some(event_tx_id),
+        &["branch", &branch_name, &oid.to_string()],
+    )
+}
@@ -1,8 +1,22 @@
 //! An interactive prompt to select a commit.
 
+use cursive::utils::markup::StyledString;
 use lib::core::node_descriptors::NodeDescriptor;
 use lib::git::{Commit, NonZeroOid};
 
+/// An action to carry out on the commit selected in the interactive switcher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwitchAction {
+    /// Check out the selected commit (bound to `enter`).
+    Switch,
+
+    /// Create a new branch pointing to the selected commit (bound to `ctrl-b`).
+    CreateBranch,
+
+    /// Hide the selected commit (bound to `ctrl-x`).
+    Hide,
+}
+
 /// Prompt the user to select a commit from the provided list
 /// of commits, and returns the OID of the selected commit.
 #[cfg(unix)]
@@ -15,6 +29,26 @@ pub fn prompt_select_commit(
     skim::prompt_skim(header, initial_query, commits, commit_descriptors)
 }
 
+/// Prompt the user to select a commit from the provided list of commits, each
+/// paired with the preview to display for it. Returns the OID of the selected
+/// commit, along with the action that the user chose to carry out on it.
+#[cfg(unix)]
+pub fn prompt_switch_commit(
+    header: Option<&str>,
+    initial_query: &str,
+    commits: Vec<(Commit, StyledString)>,
+    commit_descriptors: &mut [&mut dyn NodeDescriptor],
+) -> eyre::Result<Option<(SwitchAction, NonZeroOid)>> {
+    skim::prompt_skim_switch(header, initial_query, commits, commit_descriptors)
+}
+
+/// Prompt the user to enter a line of text, such as a branch name. Returns
+/// `None` if the user aborted the prompt.
+#[cfg(unix)]
+pub fn prompt_text(header: &str) -> eyre::Result<Option<String>> {
+    skim::prompt_skim_text(header)
+}
+
 #[cfg(not(unix))]
 pub fn prompt_select_commit(
     header: Option<&str>,
@@ -25,6 +59,21 @@ pub fn prompt_select_commit(
     unimplemented!("Non-unix targets are currently unsupported for prompting")
 }
 
+#[cfg(not(unix))]
+pub fn prompt_switch_commit(
+    header: Option<&str>,
+    initial_query: &str,
+    commits: Vec<(Commit, StyledString)>,
+    commit_descriptors: &mut [&mut dyn NodeDescriptor],
+) -> eyre::Result<Option<(SwitchAction, NonZeroOid)>> {
+    unimplemented!("Non-unix targets are currently unsupported for prompting")
+}
+
+#[cfg(not(unix))]
+pub fn prompt_text(header: &str) -> eyre::Result<Option<String>> {
+    unimplemented!("Non-unix targets are currently unsupported for prompting")
+}
+
 #[cfg(unix)]
 mod skim {
     use eyre::eyre;
@@ -32,6 +81,7 @@ mod skim {
     use std::convert::TryFrom;
     use std::sync::Arc;
 
+    use cursive::utils::markup::StyledString;
     use itertools::Itertools;
 
     use lib::core::formatting::Glyphs;
@@ -39,10 +89,13 @@ mod skim {
     use lib::git::{Commit, NonZeroOid};
 
     use skim::{
-        prelude::SkimOptionsBuilder, AnsiString, DisplayContext, ItemPreview, Matches,
-        PreviewContext, Skim, SkimItem, SkimItemReceiver, SkimItemSender,
+        prelude::{Key, SkimOptionsBuilder},
+        AnsiString, DisplayContext, ItemPreview, Matches, PreviewContext, Skim, SkimItem,
+        SkimItemReceiver, SkimItemSender,
     };
 
+    use super::SwitchAction;
+
     #[derive(Debug)]
     pub struct CommitSkimItem {
         pub oid: NonZeroOid,
@@ -98,6 +151,7 @@ mod skim {
     impl CommitSkimItem {
         fn from_descriptors(
             commit: &Commit,
+            preview: StyledString,
             commit_descriptors: &mut [&mut dyn NodeDescriptor],
         ) -> eyre::Result<Self> {
             let glyphs = Glyphs::pretty();
@@ -112,34 +166,99 @@ mod skim {
             Ok(CommitSkimItem {
                 oid: commit.get_oid(),
                 styled_summary: glyphs.render(styled_summary)?,
-                styled_preview: Glyphs::pretty().render(commit.friendly_preview()?)?,
+                styled_preview: glyphs.render(preview)?,
             })
         }
     }
 
-    #[cfg(unix)]
     pub fn prompt_skim(
         header: Option<&str>,
         initial_query: &str,
         commits: Vec<Commit>,
         commit_descriptors: &mut [&mut dyn NodeDescriptor],
     ) -> eyre::Result<Option<NonZeroOid>> {
+        let items: Vec<CommitSkimItem> = commits
+            .iter()
+            .map(|commit| -> eyre::Result<CommitSkimItem> {
+                CommitSkimItem::from_descriptors(
+                    commit,
+                    commit.friendly_preview()?,
+                    commit_descriptors,
+                )
+            })
+            .try_collect()?;
+        let selected = run_skim(header, initial_query, items, &[])?;
+        Ok(selected.map(|(_key, oid)| oid))
+    }
+
+    pub fn prompt_skim_switch(
+        header: Option<&str>,
+        initial_query: &str,
+        commits: Vec<(Commit, StyledString)>,
+        commit_descriptors: &mut [&mut dyn NodeDescriptor],
+    ) -> eyre::Result<Option<(SwitchAction, NonZeroOid)>> {
+        let items: Vec<CommitSkimItem> = commits
+            .into_iter()
+            .map(|(commit, preview)| {
+                CommitSkimItem::from_descriptors(&commit, preview, commit_descriptors)
+            })
+            .try_collect()?;
+        let selected = run_skim(header, initial_query, items, &["ctrl-b", "ctrl-x"])?;
+        Ok(selected.map(|(key, oid)| {
+            let action = match key {
+                Key::Ctrl('b') => SwitchAction::CreateBranch,
+                Key::Ctrl('x') => SwitchAction::Hide,
+                _ => SwitchAction::Switch,
+            };
+            (action, oid)
+        }))
+    }
+
+    /// Run the selector without any items, and return the query which the
+    /// user entered.
+    pub fn prompt_skim_text(header: &str) -> eyre::Result<Option<String>> {
+        let options = SkimOptionsBuilder::default()
+            .height(Some("100%"))
+            .bind(vec!["Enter:accept"])
+            .header(Some(header))
+            .build()
+            .map_err(|e| eyre!("building Skim options failed: {}", e))?;
+        let rx_item = {
+            let (_tx_item, rx_item): (SkimItemSender, SkimItemReceiver) =
+                skim::prelude::unbounded();
+            rx_item
+        };
+        match Skim::run_with(&options, Some(rx_item)) {
+            Some(result) if !result.is_abort => Ok(Some(result.query)),
+            Some(_) | None => Ok(None),
+        }
+    }
+
+    /// Run the selector over the provided items. `expect_keys` are additional
+    /// keys which accept the current selection; the key used to accept the
+    /// selection is returned alongside it.
+    fn run_skim(
+        header: Option<&str>,
+        initial_query: &str,
+        items: Vec<CommitSkimItem>,
+        expect_keys: &[&str],
+    ) -> eyre::Result<Option<(Key, NonZeroOid)>> {
         let options = SkimOptionsBuilder::default()
             .height(Some("100%"))
             .preview(Some(""))
             .preview_window(Some("up:70%"))
             .sync(true) // Consume all items before displaying selector.
             .bind(vec!["Enter:accept"])
+            .expect(if expect_keys.is_empty() {
+                None
+            } else {
+                Some(expect_keys.join(","))
+            })
             .header(header)
             .query(Some(initial_query))
             .build()
             .map_err(|e| eyre!("building Skim options failed: {}", e))?;
 
-        let items: Vec<CommitSkimItem> = commits
-            .iter()
-            .map(|commit| CommitSkimItem::from_descriptors(commit, commit_descriptors))
-            .try_collect()?;
-
         let rx_item = {
             let (tx_item, rx_item): (SkimItemSender, SkimItemReceiver) = skim::prelude::unbounded();
             for i in items {
@@ -157,7 +276,7 @@ mod skim {
                     .selected_items
                     .first()
                     .and_then(|item| (*item).as_any().downcast_ref::<CommitSkimItem>());
-                Ok(selected.map(|c| c.oid))
+                Ok(selected.map(|c| (result.final_key, c.oid)))
             }
             None => Ok(None),
         }
@@ -13,6 +13,7 @@ use std::ffi::OsString;
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use clap::{Args, Command as ClapCommand, CommandFactory, Parser, ValueEnum};
 use lib::git::NonZeroOid;
@@ -167,6 +168,16 @@ pub struct SwitchOptions {
     #[clap(action, short = 'd', long = "detach")]
     pub detach: bool,
 
+    /// When interactively selecting a commit, only offer the commits in this
+    /// revset. Defaults to the smartlog revset
+    /// (`branchless.smartlog.defaultRevset`).
+    #[clap(value_parser, short = 'r', long = "revset")]
+    pub revset: Option<Revset>,
+
+    /// Options for resolving revset expressions.
+    #[clap(flatten)]
+    pub resolve_revset_options: ResolveRevsetOptions,
+
     /// The commit or branch to check out.
     ///
     /// If this is not provided, then interactive commit selection starts as
@@ -256,6 +267,15 @@ pub struct InitArgs {
     /// then you will be prompted to enter a value for the main branch name.
     #[clap(value_parser, long = "main-branch", conflicts_with = "uninstall")]
     pub main_branch_name: Option<String>,
+
+    /// Install each hook into a `<hook>.d` directory in the hooks directory,
+    /// and replace the hook itself with a dispatcher which runs every script
+    /// in that directory. Any existing hook is renamed to
+    /// `<hook>.branchless-previous` and run by the dispatcher as well. Use
+    /// this if your hooks are also managed by another tool, such as husky,
+    /// lefthook, or pre-commit.
+    #[clap(action, long = "hook-dispatcher", conflicts_with = "uninstall")]
+    pub hook_dispatcher: bool,
 }
 
 /// Install git-branchless's man-pages to the given path.
@@ -320,6 +340,30 @@ pub struct RecordArgs {
     /// if any.
     #[clap(action, short = 'I', long = "insert")]
     pub insert: bool,
+
+    /// Only commit the changed lines which match this regular expression,
+    /// rather than all of the changes.
+    #[clap(value_parser, long = "hunks-matching", conflicts_with("interactive"))]
+    pub hunks_matching: Option<String>,
+
+    /// Rather than creating a new commit, amend the selected changes into the
+    /// given ancestor commit and restack its descendants. The unselected
+    /// changes are left in the working copy.
+    #[clap(
+        value_parser,
+        long = "into",
+        requires("interactive"),
+        conflicts_with_all(&["message", "create", "detach", "insert"])
+    )]
+    pub into: Option<Revset>,
+
+    /// Options for resolving revset expressions.
+    #[clap(flatten)]
+    pub resolve_revset_options: ResolveRevsetOptions,
+
+    /// Only commit the changes to files which match these pathspecs.
+    #[clap(value_parser, last = true)]
+    pub pathspecs: Vec<String>,
 }
 
 /// Display a nice graph of the commits you've recently worked on.
@@ -406,6 +450,15 @@ pub struct TestArgs {
     pub subcommand: TestSubcommand,
 }
 
+/// Search for the commit which introduced a bug, marking commits as good or
+/// bad by hand.
+#[derive(Debug, Parser)]
+pub struct BisectArgs {
+    /// The subcommand to run.
+    #[clap(subcommand)]
+    pub subcommand: BisectSubcommand,
+}
+
 /// FIXME: write man-page text
 #[derive(Debug, Parser)]
 pub enum Command {
@@ -422,12 +475,40 @@ pub enum Command {
         reparent: bool,
     },
 
+    /// Search for the commit which introduced a bug, marking commits as good or
+    /// bad by hand.
+    Bisect(BisectArgs),
+
     /// Gather information about recent operations to upload as part of a bug
     /// report.
     BugReport,
 
+    /// Check for common problems with the git-branchless setup of this
+    /// repository, such as hooks which were overwritten by other tools, and
+    /// optionally fix them.
+    Doctor {
+        /// Fix the problems which were found, where possible.
+        #[clap(action, long = "fix")]
+        fix: bool,
+    },
+
     /// Run internal garbage collection.
-    Gc,
+    Gc {
+        /// Also remove old events from the event log. The state of the
+        /// repository as of the removed events is kept, but the operations
+        /// they recorded can no longer be undone.
+        #[clap(action, long = "prune-events", requires = "older_than")]
+        prune_events: bool,
+
+        /// With `--prune-events`, remove the events older than this, such as
+        /// `30d` or `12h`.
+        #[clap(
+            value_parser = parse_duration,
+            long = "older-than",
+            requires = "prune_events"
+        )]
+        older_than: Option<Duration>,
+    },
 
     /// Hide the provided commits from the smartlog.
     Hide {
@@ -643,7 +724,10 @@ pub enum Command {
         move_options: MoveOptions,
 
         /// The commits whose stacks will be moved on top of the main branch. If
-        /// no commits are provided, all draft commits will be synced.
+        /// no commits are provided, all draft commits will be synced. Stacks
+        /// which forked from another public branch (as configured by
+        /// `branchless.core.publicBranches`) are moved on top of that branch
+        /// instead.
         #[clap(value_parser)]
         revsets: Vec<Revset>,
 
@@ -718,6 +802,17 @@ pub enum TestExecutionStrategy {
     /// invalidating build artifacts in the current directory, or if you want to run tests while
     /// your working copy is dirty.
     Worktree,
+
+    /// Hand each commit off to the command configured with `git config
+    /// branchless.test.remoteCommand <command>`, such as one which runs the
+    /// tests on another host via `ssh` or in a container. The command is
+    /// invoked with `BRANCHLESS_TEST_COMMIT` set to the commit to test,
+    /// `BRANCHLESS_TEST_BUNDLE` set to the path of a Git bundle containing
+    /// that commit and its ancestors which aren't on the main branch (the
+    /// remote is expected to have the main branch already), and
+    /// `BRANCHLESS_TEST_COMMAND` set to the test command to run. Its exit code
+    /// and output are treated as those of the test command.
+    Remote,
 }
 
 /// How to conduct searches on the commit graph.
@@ -734,6 +829,98 @@ pub enum TestSearchStrategy {
     /// Visit commits starting from the middle of the commit graph and exit
     /// early when a failing commit is found.
     Binary,
+
+    /// Like `binary`, but tolerate flaky test results by re-testing commits
+    /// until the first failing commit is known with the confidence set by
+    /// `branchless.test.search.confidence` (default 0.95). Set the chance that
+    /// the test fails on a commit with the bug with
+    /// `branchless.test.search.failureRate` (default 0.5), and the chance that
+    /// it fails on a commit without the bug with
+    /// `branchless.test.search.falseFailureRate` (default 0). Test results
+    /// aren't cached with this strategy.
+    Probabilistic,
+}
+
+/// The format of a report of test results.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum TestReportFormat {
+    /// A JUnit XML report, as understood by most CI systems.
+    Junit,
+
+    /// A JSON report.
+    Json,
+}
+
+/// A report of test results to write to a file. Parsed from an argument of
+/// the form `<format>=<path>`.
+#[derive(Clone, Debug)]
+pub struct TestReport {
+    /// The format of the report.
+    pub format: TestReportFormat,
+
+    /// The path to write the report to.
+    pub path: PathBuf,
+}
+
+impl FromStr for TestReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected a value of the form <format>=<path>: {s}"))?;
+        let format = TestReportFormat::from_str(format, true)?;
+        Ok(Self {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// Parse a duration such as `30s`, `5m`, `1h`, or `2w`. A bare number is
+/// interpreted as a number of seconds.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (amount, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => s.split_at(index),
+        None => (s, "s"),
+    };
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("expected a duration such as 30s, 5m, or 1h: {s}"))?;
+    let duration = match unit {
+        "ms" => Some(Duration::from_millis(amount)),
+        "s" => Some(Duration::from_secs(amount)),
+        "m" => amount.checked_mul(60).map(Duration::from_secs),
+        "h" => amount.checked_mul(60 * 60).map(Duration::from_secs),
+        "d" => amount.checked_mul(60 * 60 * 24).map(Duration::from_secs),
+        "w" => amount
+            .checked_mul(60 * 60 * 24 * 7)
+            .map(Duration::from_secs),
+        unit => {
+            return Err(format!(
+                "unknown unit {unit:?} in duration (expected one of: ms, s, m, h, d, w): {s}"
+            ))
+        }
+    };
+    duration.ok_or_else(|| format!("duration is too large: {s}"))
+}
+
+/// Parse a duration for `--timeout`, such as `30s`, `5m`, or `1h`.
+fn parse_test_timeout(s: &str) -> Result<Duration, String> {
+    let duration = parse_duration(s)?;
+    if duration.is_zero() {
+        return Err("the timeout must be greater than zero".to_string());
+    }
+    Ok(duration)
+}
+
+/// Parse a duration for `--interval`, such as `500ms` or `2s`.
+fn parse_watch_interval(s: &str) -> Result<Duration, String> {
+    let duration = parse_duration(s)?;
+    if duration.is_zero() {
+        return Err("the interval must be greater than zero".to_string());
+    }
+    Ok(duration)
 }
 
 /// Arguments which apply to all commands. Used during setup.
@@ -781,6 +968,105 @@ pub enum SnapshotSubcommand {
     },
 }
 
+/// `bisect` subcommands.
+#[derive(Debug, Parser)]
+pub enum BisectSubcommand {
+    /// Start a new bisection over the given set of commits, and check out the
+    /// first commit to test.
+    Start {
+        /// The set of commits to search. The commits don't need to form a
+        /// linear history.
+        #[clap(value_parser, default_value = "stack() | @")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+    },
+
+    /// Mark the given commits as good, and check out the next commit to test.
+    Good {
+        /// The commits to mark.
+        #[clap(value_parser, default_value = "@")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+    },
+
+    /// Mark the given commits as bad, and check out the next commit to test.
+    Bad {
+        /// The commits to mark.
+        #[clap(value_parser, default_value = "@")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+    },
+
+    /// Mark the given commits as untestable, and check out the next commit to
+    /// test.
+    Skip {
+        /// The commits to mark.
+        #[clap(value_parser, default_value = "@")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+    },
+
+    /// Show the progress of the current bisection.
+    Status,
+
+    /// Finish the current bisection automatically by running a command on the
+    /// remaining commits, as with `git test run --bisect`.
+    Run {
+        /// An ad-hoc command to execute on each commit.
+        #[clap(value_parser, short = 'x', long = "exec")]
+        exec: Option<String>,
+
+        /// The test command alias for the command to execute on each commit. Set with
+        /// `git config branchless.test.alias.<name> <command>`.
+        #[clap(value_parser, short = 'c', long = "command", conflicts_with("exec"))]
+        command: Option<String>,
+
+        /// Show the test output as well.
+        #[clap(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+        verbosity: u8,
+
+        /// How to execute the tests.
+        #[clap(short = 's', long = "strategy")]
+        strategy: Option<TestExecutionStrategy>,
+
+        /// Don't read or write to the cache when executing the test commands.
+        #[clap(long = "no-cache")]
+        no_cache: bool,
+
+        /// How many jobs to execute in parallel. The value `0` indicates to use all CPUs.
+        #[clap(short = 'j', long = "jobs")]
+        jobs: Option<usize>,
+
+        /// Kill the test command if it runs for longer than the given duration
+        /// (such as `30s`, `5m`, or `1h`) on a commit, and mark that commit as
+        /// timed out.
+        #[clap(value_parser = parse_test_timeout, long = "timeout")]
+        timeout: Option<Duration>,
+
+        /// Re-run the test command up to this many times on a commit if it
+        /// fails or times out. Commits which only pass on a retry are marked
+        /// as flaky.
+        #[clap(long = "retries", default_value_t = 0)]
+        retries: usize,
+    },
+
+    /// Abandon the current bisection, and check out the commit or branch which
+    /// was checked out when it was started.
+    Reset,
+}
+
 /// `test` subcommands.
 #[derive(Debug, Parser)]
 pub enum TestSubcommand {
@@ -802,9 +1088,11 @@ pub enum TestSubcommand {
         exec: Option<String>,
 
         /// The test command alias for the command to execute on each commit. Set with
-        /// `git config branchless.test.alias.<name> <command>`.
+        /// `git config branchless.test.alias.<name> <command>`. Can be specified multiple
+        /// times to run several commands on each commit. Can also be the name of a suite of
+        /// aliases, set with `git config branchless.test.suite.<name> "<alias> <alias>..."`.
         #[clap(value_parser, short = 'c', long = "command", conflicts_with("exec"))]
-        command: Option<String>,
+        command: Vec<String>,
 
         /// The set of commits to test.
         #[clap(value_parser, default_value = "stack() | @")]
@@ -827,7 +1115,8 @@ pub enum TestSubcommand {
         #[clap(short = 'S', long = "search")]
         search: Option<TestSearchStrategy>,
 
-        /// Shorthand for `--search binary`.
+        /// Shorthand for `--search binary`. If a bisection started with `git
+        /// branchless bisect start` is in progress, then continue it instead.
         #[clap(short = 'b', long = "bisect", conflicts_with("search"))]
         bisect: bool,
 
@@ -843,6 +1132,24 @@ pub enum TestSubcommand {
         /// How many jobs to execute in parallel. The value `0` indicates to use all CPUs.
         #[clap(short = 'j', long = "jobs")]
         jobs: Option<usize>,
+
+        /// Kill the test command if it runs for longer than the given duration
+        /// (such as `30s`, `5m`, or `1h`) on a commit, and mark that commit as
+        /// timed out.
+        #[clap(value_parser = parse_test_timeout, long = "timeout")]
+        timeout: Option<Duration>,
+
+        /// Re-run the test command up to this many times on a commit if it
+        /// fails or times out. Commits which only pass on a retry are marked
+        /// as flaky.
+        #[clap(long = "retries", default_value_t = 0)]
+        retries: usize,
+
+        /// Write a report of the test results to a file, in addition to
+        /// printing the summary. Of the form `<format>=<path>`, where
+        /// `<format>` is `junit` or `json`. Can be specified multiple times.
+        #[clap(value_parser, long = "report")]
+        reports: Vec<TestReport>,
     },
 
     /// Show the results of a set of previous test runs.
@@ -869,6 +1176,63 @@ pub enum TestSubcommand {
         verbosity: u8,
     },
 
+    /// Watch for changes to the commits in a set, and run a given command on
+    /// each commit which doesn't have a cached result yet. The results are
+    /// shown in a table which is refreshed after each change.
+    Watch {
+        /// An ad-hoc command to execute on each commit.
+        #[clap(value_parser, short = 'x', long = "exec")]
+        exec: Option<String>,
+
+        /// The test command alias for the command to execute on each commit. Set with
+        /// `git config branchless.test.alias.<name> <command>`.
+        #[clap(value_parser, short = 'c', long = "command", conflicts_with("exec"))]
+        command: Option<String>,
+
+        /// The set of commits to test.
+        #[clap(value_parser, default_value = "stack() | @")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+
+        /// Show the test output as well.
+        #[clap(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+        verbosity: u8,
+
+        /// How to execute the tests. Defaults to `worktree`, so that the working
+        /// copy can be edited while watching.
+        #[clap(short = 's', long = "strategy")]
+        strategy: Option<TestExecutionStrategy>,
+
+        /// How many jobs to execute in parallel. The value `0` indicates to use all CPUs.
+        #[clap(short = 'j', long = "jobs")]
+        jobs: Option<usize>,
+
+        /// Kill the test command if it runs for longer than the given duration
+        /// (such as `30s`, `5m`, or `1h`) on a commit, and mark that commit as
+        /// timed out.
+        #[clap(value_parser = parse_test_timeout, long = "timeout")]
+        timeout: Option<Duration>,
+
+        /// Re-run the test command up to this many times on a commit if it
+        /// fails or times out. Commits which only pass on a retry are marked
+        /// as flaky.
+        #[clap(long = "retries", default_value_t = 0)]
+        retries: usize,
+
+        /// How long to wait between checks for changes to the commits (such
+        /// as `500ms` or `2s`).
+        #[clap(value_parser = parse_watch_interval, long = "interval", default_value = "1s")]
+        interval: Duration,
+
+        /// Stop after checking for changes this many times. By default, keep
+        /// watching until interrupted.
+        #[clap(value_parser, long = "iterations")]
+        iterations: Option<usize>,
+    },
+
     /// Run a given command on a set of commits and present the successes and failures.
     Fix {
         /// An ad-hoc command to execute on each commit.
@@ -884,6 +1248,13 @@ pub enum TestSubcommand {
         #[clap(value_parser, short = 'n', long = "dry-run")]
         dry_run: bool,
 
+        /// Show the changes that each fix would make, and ask whether to
+        /// apply it, skip it, or select which of its changes to apply. (Unlike
+        /// `git test run --interactive`, this doesn't start an interactive
+        /// shell.)
+        #[clap(value_parser, long = "confirm")]
+        confirm: bool,
+
         /// The set of commits to test.
         #[clap(value_parser, default_value = "stack()")]
         revset: Revset,
@@ -908,10 +1279,48 @@ pub enum TestSubcommand {
         #[clap(short = 'j', long = "jobs")]
         jobs: Option<usize>,
 
+        /// Kill the test command if it runs for longer than the given duration
+        /// (such as `30s`, `5m`, or `1h`) on a commit, and mark that commit as
+        /// timed out.
+        #[clap(value_parser = parse_test_timeout, long = "timeout")]
+        timeout: Option<Duration>,
+
+        /// Re-run the test command up to this many times on a commit if it
+        /// fails or times out. Commits which only pass on a retry are marked
+        /// as flaky.
+        #[clap(long = "retries", default_value_t = 0)]
+        retries: usize,
+
         /// Options for moving commits.
         #[clap(flatten)]
         move_options: MoveOptions,
     },
+
+    /// Share cached test results with others by pushing them to a remote as
+    /// Git notes (under `refs/notes/branchless-test`).
+    PushResults {
+        /// The remote to push the test results to. Defaults to the push remote
+        /// of the main branch.
+        #[clap(value_parser, long = "remote")]
+        remote: Option<String>,
+
+        /// The set of commits whose test results should be pushed.
+        #[clap(value_parser, default_value = "stack() | @")]
+        revset: Revset,
+
+        /// Options for resolving revset expressions.
+        #[clap(flatten)]
+        resolve_revset_options: ResolveRevsetOptions,
+    },
+
+    /// Fetch test results shared by others with `git test push-results` and
+    /// add them to the local cache of test results.
+    FetchResults {
+        /// The remote to fetch the test results from. Defaults to the push
+        /// remote of the main branch.
+        #[clap(value_parser, long = "remote")]
+        remote: Option<String>,
+    },
 }
 
 /// Generate and write man-pages into the specified directory.
@@ -1022,8 +1431,29 @@ pub fn rewrite_args(args: Vec<OsString>) -> Vec<OsString> {
 
 #[cfg(test)]
 mod tests {
-    use super::rewrite_args;
+    use super::{parse_duration, rewrite_args};
     use std::ffi::OsString;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(
+            parse_duration("2w"),
+            Ok(Duration::from_secs(2 * 7 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            parse_duration("3y"),
+            Err(
+                "unknown unit \"y\" in duration (expected one of: ms, s, m, h, d, w): 3y"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse_duration("18446744073709551615w"),
+            Err("duration is too large: 18446744073709551615w".to_string())
+        );
+    }
 
     #[test]
     fn test_rewrite_args() {
@@ -12,10 +12,12 @@
 use std::collections::HashSet;
 use std::ffi::OsString;
 use std::fmt::Write;
+use std::path::{Component, PathBuf};
 use std::time::SystemTime;
 
 use git_branchless_invoke::CommandContext;
-use git_branchless_opts::RecordArgs;
+use git_branchless_opts::{RecordArgs, ResolveRevsetOptions, Revset};
+use git_branchless_revset::resolve_commits;
 use itertools::Itertools;
 use lib::core::check_out::{check_out_commit, CheckOutCommitOptions};
 use lib::core::config::get_restack_preserve_timestamps;
@@ -30,14 +32,18 @@ use lib::core::rewrite::{
     RepoResource,
 };
 use lib::git::{
-    process_diff_for_record, update_index, CategorizedReferenceName, FileMode, GitRunInfo,
-    MaybeZeroOid, NonZeroOid, Repo, ResolvedReferenceInfo, Stage, UpdateIndexCommand,
+    hydrate_tree, process_diff_for_record, update_index, CategorizedReferenceName,
+    CherryPickFastError, CherryPickFastOptions, ConfigRead, FileMode, GitRunInfo, MaybeZeroOid,
+    NonZeroOid, Repo, ResolvedReferenceInfo, Stage, Tree, UpdateIndexCommand,
     WorkingCopyChangesType, WorkingCopySnapshot,
 };
 use lib::try_exit_code;
 use lib::util::{ExitCode, EyreExitOr};
 use rayon::ThreadPoolBuilder;
-use scm_record::{EventSource, RecordError, RecordState, Recorder, SelectedContents};
+use regex::Regex;
+use scm_record::{
+    EventSource, File, Keymap, RecordError, RecordState, Recorder, Section, SelectedContents,
+};
 use tracing::{instrument, warn};
 
 /// Commit changes in the working copy.
@@ -53,6 +59,10 @@ pub fn command_main(ctx: CommandContext, args: RecordArgs) -> EyreExitOr<()> {
         create,
         detach,
         insert,
+        hunks_matching,
+        into,
+        resolve_revset_options,
+        pathspecs,
     } = args;
     record(
         &effects,
@@ -62,6 +72,10 @@ pub fn command_main(ctx: CommandContext, args: RecordArgs) -> EyreExitOr<()> {
         create,
         detach,
         insert,
+        hunks_matching,
+        into,
+        &resolve_revset_options,
+        pathspecs,
     )
 }
 
@@ -74,6 +88,10 @@ fn record(
     branch_name: Option<String>,
     detach: bool,
     insert: bool,
+    hunks_matching: Option<String>,
+    into: Option<Revset>,
+    resolve_revset_options: &ResolveRevsetOptions,
+    pathspecs: Vec<String>,
 ) -> EyreExitOr<()> {
     let now = SystemTime::now();
     let repo = Repo::from_dir(&git_run_info.working_directory)?;
@@ -112,6 +130,18 @@ fn record(
         (snapshot, working_copy_changes_type)
     };
 
+    let hunks_matching = match hunks_matching.as_deref().map(Regex::new).transpose() {
+        Ok(hunks_matching) => hunks_matching,
+        Err(err) => {
+            writeln!(
+                effects.get_output_stream(),
+                "Invalid regular expression for --hunks-matching: {err}"
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    let pathspecs = resolve_pathspecs(git_run_info, &repo, &pathspecs)?;
+
     if let Some(branch_name) = branch_name {
         try_exit_code!(check_out_commit(
             effects,
@@ -139,6 +169,19 @@ fn record(
                 "Either commit or unstage your changes and try again. Aborting."
             )?;
             return Ok(Err(ExitCode(1)));
+        } else if let Some(into) = into {
+            try_exit_code!(record_into(
+                effects,
+                git_run_info,
+                now,
+                &repo,
+                &event_log_db,
+                &snapshot,
+                event_tx_id,
+                &into,
+                resolve_revset_options,
+                &pathspecs,
+            )?);
         } else {
             try_exit_code!(record_interactive(
                 effects,
@@ -147,6 +190,30 @@ fn record(
                 &snapshot,
                 event_tx_id,
                 message.as_deref(),
+                &pathspecs,
+            )?);
+        }
+    } else if !pathspecs.is_empty() || hunks_matching.is_some() {
+        if working_copy_changes_type == WorkingCopyChangesType::Staged {
+            writeln!(
+                effects.get_output_stream(),
+                "Cannot select changes by path or pattern while there are already staged changes."
+            )?;
+            writeln!(
+                effects.get_output_stream(),
+                "Either commit or unstage your changes and try again. Aborting."
+            )?;
+            return Ok(Err(ExitCode(1)));
+        } else {
+            try_exit_code!(record_matching(
+                effects,
+                git_run_info,
+                &repo,
+                &snapshot,
+                event_tx_id,
+                message.as_deref(),
+                &pathspecs,
+                hunks_matching.as_ref(),
             )?);
         }
     } else {
@@ -216,6 +283,10 @@ fn record(
     Ok(Ok(()))
 }
 
+/// The config section used to override the key bindings of `git record -i`,
+/// such as `branchless.record.keys.toggleItem`.
+const KEY_BINDINGS_CONFIG_PREFIX: &str = "branchless.record.keys";
+
 #[instrument]
 fn record_interactive(
     effects: &Effects,
@@ -224,25 +295,309 @@ fn record_interactive(
     snapshot: &WorkingCopySnapshot,
     event_tx_id: EventTransactionId,
     message: Option<&str>,
+    pathspecs: &[String],
+) -> EyreExitOr<()> {
+    let old_tree = snapshot.commit_stage0.get_tree()?;
+    let new_tree = snapshot.commit_unstaged.get_tree()?;
+    let files = try_exit_code!(select_changes_interactively(
+        effects, repo, &old_tree, &new_tree, pathspecs
+    )?);
+    commit_selected_changes(
+        git_run_info,
+        repo,
+        &old_tree,
+        &new_tree,
+        event_tx_id,
+        message,
+        files,
+    )
+}
+
+/// Amend the interactively-selected changes into the commit designated by
+/// `into`, which must be an ancestor of `HEAD`, and restack its descendants.
+/// The unselected changes are left in the working copy.
+#[instrument]
+fn record_into(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    now: SystemTime,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    snapshot: &WorkingCopySnapshot,
+    event_tx_id: EventTransactionId,
+    into: &Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+    pathspecs: &[String],
 ) -> EyreExitOr<()> {
+    let event_replayer = EventReplayer::from_event_log_db(effects, repo, event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        effects,
+        repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+
+    let head_info = repo.get_head_info()?;
+    let head_oid = match head_info.oid {
+        Some(head_oid) => head_oid,
+        None => {
+            writeln!(
+                effects.get_output_stream(),
+                "No commit is currently checked out. Check out a commit and then try again."
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    let target_oid = match resolve_commits(
+        effects,
+        repo,
+        &mut dag,
+        &[into.clone()],
+        resolve_revset_options,
+    ) {
+        Ok(commit_sets) => match dag.commit_set_to_vec(&commit_sets[0])?.as_slice() {
+            [only_commit_oid] => *only_commit_oid,
+            other => {
+                let Revset(expr) = into;
+                writeln!(
+                    effects.get_error_stream(),
+                    "Expected revset to expand to exactly 1 commit (got {}): {}",
+                    other.len(),
+                    expr,
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+        },
+        Err(err) => {
+            err.describe(effects)?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    let target_commit = repo.find_commit_or_fail(target_oid)?;
+    if !dag.query_is_ancestor(target_oid, head_oid)? {
+        writeln!(
+            effects.get_output_stream(),
+            "The commit to record into must be an ancestor of the current commit: {}",
+            effects
+                .get_glyphs()
+                .render(target_commit.friendly_describe(effects.get_glyphs())?)?,
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let build_options = BuildRebasePlanOptions {
+        force_rewrite_public_commits: false,
+        dump_rebase_constraints: false,
+        dump_rebase_plan: false,
+        detect_duplicate_commits_via_patch_id: true,
+    };
+    let commits_to_rewrite = dag.query_descendants(CommitSet::from(target_oid))?;
+    let commits_to_rewrite = dag.filter_visible_commits(commits_to_rewrite)?;
+    let permissions = match RebasePlanPermissions::verify_rewrite_set(
+        &dag,
+        build_options,
+        &commits_to_rewrite,
+    )? {
+        Ok(permissions) => permissions,
+        Err(err) => {
+            err.describe(effects, repo, &dag)?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+
     let old_tree = snapshot.commit_stage0.get_tree()?;
     let new_tree = snapshot.commit_unstaged.get_tree()?;
-    let files = {
-        let (effects, _progress) = effects.start_operation(OperationType::CalculateDiff);
-        let diff = repo.get_diff_between_trees(
-            &effects,
-            Some(&old_tree),
-            &new_tree,
-            // We manually add context to the git-record output, so suppress the context lines here.
-            0,
+    let files = try_exit_code!(select_changes_interactively(
+        effects, repo, &old_tree, &new_tree, pathspecs
+    )?);
+    let selected_tree = make_selected_tree(repo, &old_tree, &new_tree, files)?;
+    if selected_tree.get_oid() == old_tree.get_oid() {
+        writeln!(
+            effects.get_output_stream(),
+            "No changes were selected. Nothing to record."
         )?;
-        process_diff_for_record(repo, &diff)?
+        return Ok(Ok(()));
+    }
+
+    // Apply the selected changes to the target commit by cherry-picking them
+    // in memory, which detects any conflicts before anything is rewritten.
+    let head_commit = repo.find_commit_or_fail(head_oid)?;
+    let selected_commit = {
+        let selected_commit_oid = repo.create_commit(
+            None,
+            &head_commit.get_author(),
+            &head_commit.get_committer(),
+            "git-branchless: selected changes",
+            &selected_tree,
+            vec![&head_commit],
+        )?;
+        repo.find_commit_or_fail(selected_commit_oid)?
+    };
+    let amended_tree = match repo.cherry_pick_fast(
+        &selected_commit,
+        &target_commit,
+        &CherryPickFastOptions {
+            reuse_parent_tree_if_possible: false,
+        },
+    ) {
+        Ok(amended_tree) => amended_tree,
+        Err(CherryPickFastError::MergeConflict { conflicting_paths }) => {
+            writeln!(
+                effects.get_output_stream(),
+                "The selected changes conflict with commit {} in these paths:",
+                effects
+                    .get_glyphs()
+                    .render(target_commit.friendly_describe(effects.get_glyphs())?)?,
+            )?;
+            for path in conflicting_paths.iter().sorted() {
+                writeln!(effects.get_output_stream(), "- {}", path.display())?;
+            }
+            writeln!(effects.get_output_stream(), "Aborting.")?;
+            return Ok(Err(ExitCode(1)));
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let (author, committer) = (target_commit.get_author(), target_commit.get_committer());
+    let (author, committer) = if get_restack_preserve_timestamps(repo)? {
+        (author, committer)
+    } else {
+        (
+            author.update_timestamp(now)?,
+            committer.update_timestamp(now)?,
+        )
+    };
+    let amended_commit_oid = target_commit.amend_commit(
+        None,
+        Some(&author),
+        Some(&committer),
+        None,
+        Some(&amended_tree),
+    )?;
+
+    // Rewrite the target commit and restack its descendants as part of the
+    // same rebase plan, so that nothing is rewritten if the descendants can't
+    // be restacked.
+    let rebase_plan = {
+        let mut builder = RebasePlanBuilder::new(&dag, permissions);
+        builder.move_subtree(target_oid, target_commit.get_parent_oids())?;
+        builder.replace_commit(target_oid, amended_commit_oid)?;
+        let thread_pool = ThreadPoolBuilder::new().build()?;
+        let repo_pool = RepoResource::new_pool(repo)?;
+        match builder.build(effects, &thread_pool, &repo_pool)? {
+            Ok(Some(rebase_plan)) => rebase_plan,
+            Ok(None) => {
+                eyre::bail!(
+                    "BUG: rebase plan indicates nothing to do, but recording changes should always do something."
+                );
+            }
+            Err(err) => {
+                err.describe(effects, repo, &dag)?;
+                return Ok(Err(ExitCode(1)));
+            }
+        }
+    };
+
+    let execute_options = ExecuteRebasePlanOptions {
+        now,
+        event_tx_id,
+        preserve_timestamps: get_restack_preserve_timestamps(repo)?,
+        force_in_memory: true,
+        force_on_disk: false,
+        resolve_merge_conflicts: false,
+        check_out_commit_options: CheckOutCommitOptions {
+            additional_args: Default::default(),
+            // Keep the unselected changes in the working copy.
+            reset: true,
+            render_smartlog: false,
+        },
+    };
+    match execute_rebase_plan(
+        effects,
+        git_run_info,
+        repo,
+        event_log_db,
+        &rebase_plan,
+        &execute_options,
+    )? {
+        ExecuteRebasePlanResult::Succeeded { rewritten_oids } => {
+            let amended_commit = repo.find_commit_or_fail(amended_commit_oid)?;
+            writeln!(
+                effects.get_output_stream(),
+                "Recorded changes into: {}",
+                effects
+                    .get_glyphs()
+                    .render(amended_commit.friendly_describe(effects.get_glyphs())?)?,
+            )?;
+            let num_restacked = rewritten_oids
+                .map(|rewritten_oids| {
+                    rewritten_oids
+                        .keys()
+                        .filter(|oid| **oid != target_oid)
+                        .count()
+                })
+                .unwrap_or_default();
+            if num_restacked > 0 {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Restacked {}.",
+                    Pluralize {
+                        determiner: None,
+                        amount: num_restacked,
+                        unit: ("commit", "commits")
+                    }
+                )?;
+            }
+            Ok(Ok(()))
+        }
+        ExecuteRebasePlanResult::DeclinedToMerge { failed_merge_info } => {
+            failed_merge_info.describe(effects, repo, MergeConflictRemediation::Restack)?;
+            Ok(Err(ExitCode(1)))
+        }
+        ExecuteRebasePlanResult::Failed { exit_code } => Ok(Err(exit_code)),
+    }
+}
+
+/// Open the recorder UI on the changes between the two trees, limited to the
+/// paths matching the pathspecs, if any, and return the user's selections.
+pub fn select_changes_interactively(
+    effects: &Effects,
+    repo: &Repo,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    pathspecs: &[String],
+) -> EyreExitOr<Vec<File<'static>>> {
+    let keymap = {
+        let bindings = repo
+            .get_readonly_config()?
+            .list(format!("{KEY_BINDINGS_CONFIG_PREFIX}.*"))?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(KEY_BINDINGS_CONFIG_PREFIX)
+                    .and_then(|action| action.strip_prefix('.'))
+                    .map(|action| (action.to_owned(), value))
+            });
+        match Keymap::from_bindings(bindings) {
+            Ok(keymap) => keymap,
+            Err(err) => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Invalid key bindings in {KEY_BINDINGS_CONFIG_PREFIX}: {err}"
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+        }
     };
+
+    let files = get_files_to_record(effects, repo, old_tree, new_tree, pathspecs)?;
     let record_state = RecordState { files };
 
-    let recorder = Recorder::new(record_state, EventSource::Crossterm);
+    let recorder = Recorder::new(record_state, EventSource::Crossterm).with_keymap(keymap);
     let result = recorder.run();
-    let RecordState { files: result } = match result {
+    let RecordState { files } = match result {
         Ok(result) => result,
         Err(RecordError::Cancelled) => {
             println!("Aborted.");
@@ -259,14 +614,248 @@ fn record_interactive(
             | RecordError::ReadInput(_)
             | RecordError::RenderFrame(_)
             | RecordError::SerializeJson(_)
-            | RecordError::WriteFile(_)),
+            | RecordError::WriteFile(_)
+            | RecordError::ReadFile(_)
+            | RecordError::RunEditor(_)),
         ) => {
             println!("Error: {err}");
             return Ok(Err(ExitCode(1)));
         }
     };
 
-    let update_index_script: Vec<UpdateIndexCommand> = result
+    Ok(Ok(files))
+}
+
+/// Make the tree which results from applying only the selected changes between
+/// `old_tree` and `new_tree` to `old_tree`.
+pub fn make_selected_tree<'repo>(
+    repo: &'repo Repo,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    files: Vec<File>,
+) -> eyre::Result<Tree<'repo>> {
+    let update_index_script = make_update_index_script(repo, old_tree, new_tree, files)?;
+    let entries = update_index_script
+        .into_iter()
+        .map(|command| match command {
+            UpdateIndexCommand::Delete { path } => (path, None),
+            UpdateIndexCommand::Update {
+                path,
+                stage: _,
+                mode,
+                oid,
+            } => (path, Some((oid, mode))),
+        })
+        .collect();
+    let selected_tree_oid = hydrate_tree(repo, Some(old_tree), entries)?;
+    let selected_tree = repo.find_tree_or_fail(selected_tree_oid)?;
+    Ok(selected_tree)
+}
+
+/// Commit the changes to the files matching the pathspecs (or all files, if
+/// there are none), keeping only the changed lines which match
+/// `hunks_matching`, if provided.
+#[instrument]
+fn record_matching(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    snapshot: &WorkingCopySnapshot,
+    event_tx_id: EventTransactionId,
+    message: Option<&str>,
+    pathspecs: &[String],
+    hunks_matching: Option<&Regex>,
+) -> EyreExitOr<()> {
+    let old_tree = snapshot.commit_stage0.get_tree()?;
+    let new_tree = snapshot.commit_unstaged.get_tree()?;
+    let mut files = get_files_to_record(effects, repo, &old_tree, &new_tree, pathspecs)?;
+    let mut has_selected_changes = false;
+    for file in files.iter_mut() {
+        has_selected_changes |= select_matching_changes(file, hunks_matching);
+    }
+    if !has_selected_changes {
+        writeln!(
+            effects.get_output_stream(),
+            "There are no changes to tracked files in the working copy which match the given paths or pattern."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    commit_selected_changes(
+        git_run_info,
+        repo,
+        &old_tree,
+        &new_tree,
+        event_tx_id,
+        message,
+        files,
+    )
+}
+
+/// Make the pathspecs, which are relative to the current directory, relative
+/// to the root of the repository instead. If any pathspec refers to the
+/// entire repository, then returns no pathspecs at all.
+fn resolve_pathspecs(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    pathspecs: &[String],
+) -> eyre::Result<Vec<String>> {
+    if pathspecs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let working_copy_path = match repo.get_working_copy_path() {
+        Some(working_copy_path) => working_copy_path.canonicalize()?,
+        None => eyre::bail!("Cannot record changes in a bare repository"),
+    };
+    let current_dir = git_run_info.working_directory.canonicalize()?;
+
+    let mut result = Vec::new();
+    for pathspec in pathspecs {
+        let full_pathspec = current_dir.join(pathspec);
+        let relative_pathspec = match full_pathspec.strip_prefix(&working_copy_path) {
+            Ok(relative_pathspec) => relative_pathspec,
+            Err(_) => eyre::bail!("Pathspec is outside of the repository: {pathspec}"),
+        };
+        let mut path = PathBuf::new();
+        for component in relative_pathspec.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !path.pop() {
+                        eyre::bail!("Pathspec is outside of the repository: {pathspec}");
+                    }
+                }
+                Component::Normal(component) => path.push(component),
+                Component::Prefix(_) | Component::RootDir => {
+                    eyre::bail!("BUG: Unexpected component in relative path: {component:?}")
+                }
+            }
+        }
+        if path.as_os_str().is_empty() {
+            return Ok(Vec::new());
+        }
+        result.push(path.to_string_lossy().into_owned());
+    }
+    Ok(result)
+}
+
+/// Get the changes between the two trees as `scm-record` files, limited to the
+/// paths matching the pathspecs, if any.
+fn get_files_to_record(
+    effects: &Effects,
+    repo: &Repo,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    pathspecs: &[String],
+) -> eyre::Result<Vec<File<'static>>> {
+    let (effects, _progress) = effects.start_operation(OperationType::CalculateDiff);
+    let diff = repo.get_diff_between_trees_for_paths(
+        &effects,
+        Some(old_tree),
+        new_tree,
+        // We manually add context to the git-record output, so suppress the context lines here.
+        0,
+        pathspecs,
+    )?;
+    let files = process_diff_for_record(repo, &diff)?;
+    Ok(files)
+}
+
+/// Select the changes in the file which should be committed: all of them if
+/// `hunks_matching` is not provided, or only the changed lines which match it
+/// otherwise. Returns whether any change was selected.
+fn select_matching_changes(file: &mut File, hunks_matching: Option<&Regex>) -> bool {
+    let mut num_lines = 0;
+    let mut num_selected_lines = 0;
+    for section in file.sections.iter_mut() {
+        match section {
+            Section::Unchanged { .. } => {}
+            Section::Changed { lines } => {
+                for line in lines {
+                    line.is_toggled = match hunks_matching {
+                        Some(hunks_matching) => hunks_matching.is_match(&line.line),
+                        None => true,
+                    };
+                    num_lines += 1;
+                    if line.is_toggled {
+                        num_selected_lines += 1;
+                    }
+                }
+            }
+            Section::FileMode { .. } | Section::Binary { .. } => {}
+        }
+    }
+
+    let mut has_selected_changes = num_selected_lines > 0;
+    for section in file.sections.iter_mut() {
+        match section {
+            Section::Unchanged { .. } | Section::Changed { .. } => {}
+            Section::FileMode {
+                is_toggled,
+                before,
+                after,
+            } => {
+                *is_toggled = match hunks_matching {
+                    None => true,
+                    // The file has to be created in order to add any of its
+                    // lines, but should only be deleted if all of its lines
+                    // were removed.
+                    Some(_) => {
+                        (*before == scm_record::FileMode::absent() && num_selected_lines > 0)
+                            || (*after == scm_record::FileMode::absent()
+                                && num_selected_lines == num_lines)
+                    }
+                };
+                has_selected_changes |= *is_toggled;
+            }
+            Section::Binary { is_toggled, .. } => {
+                *is_toggled = hunks_matching.is_none();
+                has_selected_changes |= *is_toggled;
+            }
+        }
+    }
+    has_selected_changes
+}
+
+/// Stage the selected changes to the files and commit them.
+fn commit_selected_changes(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    event_tx_id: EventTransactionId,
+    message: Option<&str>,
+    files: Vec<File>,
+) -> EyreExitOr<()> {
+    let update_index_script = make_update_index_script(repo, old_tree, new_tree, files)?;
+    let index = repo.get_index()?;
+    update_index(
+        git_run_info,
+        repo,
+        &index,
+        event_tx_id,
+        &update_index_script,
+    )?;
+
+    let args = {
+        let mut args = vec!["commit"];
+        if let Some(message) = message {
+            args.extend(["--message", message]);
+        }
+        args
+    };
+    git_run_info.run_direct_no_wrapping(Some(event_tx_id), &args)
+}
+
+/// Calculate the updates to the index which would stage the selected changes
+/// to the files.
+fn make_update_index_script(
+    repo: &Repo,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    files: Vec<File>,
+) -> eyre::Result<Vec<UpdateIndexCommand>> {
+    let update_index_script: Vec<UpdateIndexCommand> = files
         .into_iter()
         .map(|file| -> eyre::Result<UpdateIndexCommand> {
             let mode = {
@@ -323,23 +912,7 @@ fn record_interactive(
             Ok(command)
         })
         .try_collect()?;
-    let index = repo.get_index()?;
-    update_index(
-        git_run_info,
-        repo,
-        &index,
-        event_tx_id,
-        &update_index_script,
-    )?;
-
-    let args = {
-        let mut args = vec!["commit"];
-        if let Some(message) = message {
-            args.extend(["--message", message]);
-        }
-        args
-    };
-    git_run_info.run_direct_no_wrapping(Some(event_tx_id), &args)
+    Ok(update_index_script)
 }
 
 #[instrument]
@@ -216,6 +216,60 @@ fn test_record_staged_changes_interactive() -> eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_record_interactive_key_bindings() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.commit_file("test1", 1)?;
+    git.write_file_txt("test1", "contents1\n")?;
+
+    git.run(&["config", "branchless.record.keys.accept", "q"])?;
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "record",
+            &["-i", "-m", "foo"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Invalid key bindings in branchless.record.keys: key q is bound to both accept and quit
+        "###);
+    }
+
+    git.run(&["config", "branchless.record.keys.accept", "y"])?;
+    git.run(&["config", "branchless.record.keys.toggleItem", "x"])?;
+    {
+        run_in_pty(
+            &git,
+            "record",
+            &["-i", "-m", "foo"],
+            &[
+                PtyAction::WaitUntilContains("contents1"),
+                PtyAction::Write("x"),
+                PtyAction::WaitUntilContains("(×)"),
+                PtyAction::Write("y"),
+            ],
+        )?;
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["show", "--name-only", "--oneline"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        914812a foo
+        test1.txt
+        "###);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_record_detach() -> eyre::Result<()> {
     let git = make_git()?;
@@ -630,3 +684,337 @@ fn test_record_binary_contents() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_record_pathspecs() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.write_file("dir/test3.txt", "test3 contents\n")?;
+    git.run(&["add", "dir/test3.txt"])?;
+    git.run(&["commit", "-m", "create dir/test3.txt"])?;
+
+    git.write_file_txt("test1", "new contents1\n")?;
+    git.write_file_txt("test2", "new contents2\n")?;
+    git.write_file("dir/test3.txt", "new contents3\n")?;
+    {
+        let (stdout, _stderr) =
+            git.branchless("record", &["-m", "foo", "--", "test1.txt", "dir"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        [master 1643192] foo
+         2 files changed, 2 insertions(+), 2 deletions(-)
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["show", "--stat", "--format=%s"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        foo
+
+         dir/test3.txt | 2 +-
+         test1.txt     | 2 +-
+         2 files changed, 2 insertions(+), 2 deletions(-)
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["diff", "--name-only"])?;
+        insta::assert_snapshot!(stdout, @"test2.txt");
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "record",
+            &["-m", "bar", "--", "nonexistent.txt"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        There are no changes to tracked files in the working copy which match the given paths or pattern.
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_record_hunks_matching() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.write_file_txt("test1", "foo\nbar\nbaz\nqux\n")?;
+    git.run(&["add", "test1.txt"])?;
+    git.run(&["commit", "-m", "create test1.txt"])?;
+
+    git.write_file_txt("test1", "foo\nbar changed\nbaz\nqux changed\nnew bar\n")?;
+    {
+        let (stdout, _stderr) =
+            git.branchless("record", &["-m", "update bar", "--hunks-matching", "bar"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        [master 2ac2a66] update bar
+         1 file changed, 2 insertions(+), 1 deletion(-)
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["show", "--format=%s"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        update bar
+
+        diff --git a/test1.txt b/test1.txt
+        index ab9b661..11168df 100644
+        --- a/test1.txt
+        +++ b/test1.txt
+        @@ -1,4 +1,5 @@
+         foo
+        -bar
+        +bar changed
+         baz
+         qux
+        +new bar
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["diff"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        diff --git a/test1.txt b/test1.txt
+        index 11168df..e22380f 100644
+        --- a/test1.txt
+        +++ b/test1.txt
+        @@ -1,5 +1,5 @@
+         foo
+         bar changed
+         baz
+        -qux
+        +qux changed
+         new bar
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "record",
+            &["-m", "bad", "--hunks-matching", "("],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Invalid regular expression for --hunks-matching: regex parse error:
+            (
+            ^
+        error: unclosed group
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_record_into() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+    git.write_file_txt("test1", "new test1 contents\n")?;
+    git.write_file_txt("test3", "new test3 contents\n")?;
+
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "record",
+            &["-i", "--into", "draft()"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @r###"
+        Expected revset to expand to exactly 1 commit (got 3): draft()
+        "###);
+        insta::assert_snapshot!(stdout, @"");
+    }
+
+    {
+        run_in_pty(
+            &git,
+            "record",
+            &["-i", "--into", "HEAD~2"],
+            &[
+                PtyAction::WaitUntilContains("new test1 contents"),
+                PtyAction::Write(" "),
+                PtyAction::WaitUntilContains("(×)"),
+                PtyAction::Write("c"),
+            ],
+        )?;
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o c2a0325 create test1.txt
+        |
+        o 053cc26 create test2.txt
+        |
+        @ 153b20a create test3.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["show", "HEAD~2"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        commit c2a0325d5fd1e648c4a71119fc22417620290fed
+        Author: Testy McTestface <test@example.com>
+        Date:   Thu Oct 29 12:34:56 2020 -0100
+
+            create test1.txt
+
+        diff --git a/test1.txt b/test1.txt
+        new file mode 100644
+        index 0000000..2121042
+        --- /dev/null
+        +++ b/test1.txt
+        @@ -0,0 +1 @@
+        +new test1 contents
+        "###);
+    }
+
+    {
+        // Only the unselected changes should remain in the working copy.
+        let (stdout, _stderr) = git.run(&["diff", "HEAD"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        diff --git a/test3.txt b/test3.txt
+        index a474f4e..84b4305 100644
+        --- a/test3.txt
+        +++ b/test3.txt
+        @@ -1 +1 @@
+        -test3 contents
+        +new test3 contents
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_record_into_head() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.run(&["checkout", "-b", "feature"])?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.write_file_txt("test1", "new test1 contents\n")?;
+    git.write_file_txt("test2", "new test2 contents\n")?;
+
+    {
+        run_in_pty(
+            &git,
+            "record",
+            &["-i", "--into", "HEAD"],
+            &[
+                PtyAction::WaitUntilContains("new test1 contents"),
+                PtyAction::Write(" "),
+                PtyAction::WaitUntilContains("(×)"),
+                PtyAction::Write("c"),
+            ],
+        )?;
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d create test1.txt
+        |
+        @ 1b3ef05 (feature) create test2.txt
+        "###);
+    }
+
+    {
+        // Only the unselected changes should remain in the working copy.
+        let (stdout, _stderr) = git.run(&["diff", "HEAD"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        diff --git a/test2.txt b/test2.txt
+        index 4e512d2..60a7dee 100644
+        --- a/test2.txt
+        +++ b/test2.txt
+        @@ -1 +1 @@
+        -test2 contents
+        +new test2 contents
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_record_into_conflict() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.write_file_txt("test1", "updated test1 contents\n")?;
+    git.run(&["commit", "-am", "update test1.txt"])?;
+    git.write_file_txt("test1", "new test1 contents\n")?;
+
+    {
+        run_in_pty(
+            &git,
+            "record",
+            &["-i", "--into", "HEAD~"],
+            &[
+                PtyAction::WaitUntilContains("new test1 contents"),
+                PtyAction::Write(" "),
+                PtyAction::WaitUntilContains("(×)"),
+                PtyAction::Write("c"),
+            ],
+        )?;
+    }
+
+    {
+        // Nothing should have been rewritten.
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d create test1.txt
+        |
+        @ 77bd569 update test1.txt
+        "###);
+    }
+
+    Ok(())
+}
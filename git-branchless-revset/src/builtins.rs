@@ -64,6 +64,8 @@ lazy_static! {
             ("tests.passed", &fn_tests_passed),
             ("tests.failed", &fn_tests_failed),
             ("tests.fixable", &fn_tests_fixable),
+            ("tests.timedout", &fn_tests_timedout),
+            ("tests.flaky", &fn_tests_flaky),
         ];
         functions.iter().cloned().collect()
     };
@@ -575,6 +577,10 @@ fn fn_tests_passed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
                         head_commit_oid: _,
                         snapshot_tree_oid: _,
                         interactive: _,
+                        timed_out: _,
+                        flaky: _,
+                        sparse_checkout: _,
+                        duration_ms: _,
                     } = test_result;
                     exit_code == TEST_SUCCESS_EXIT_CODE
                         && pattern.matches_text(&command.to_string())
@@ -602,6 +608,10 @@ fn fn_tests_failed(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
                         head_commit_oid: _,
                         snapshot_tree_oid: _,
                         interactive: _,
+                        timed_out: _,
+                        flaky: _,
+                        sparse_checkout: _,
+                        duration_ms: _,
                     } = test_result;
                     exit_code != TEST_SUCCESS_EXIT_CODE
                         && exit_code != TEST_INDETERMINATE_EXIT_CODE
@@ -631,6 +641,10 @@ fn fn_tests_fixable(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult
                         head_commit_oid: _,
                         snapshot_tree_oid,
                         interactive: _,
+                        timed_out: _,
+                        flaky: _,
+                        sparse_checkout: _,
+                        duration_ms: _,
                     } = test_result;
                     exit_code == TEST_SUCCESS_EXIT_CODE
                         && pattern.matches_text(&command.to_string())
@@ -646,3 +660,65 @@ fn fn_tests_fixable(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult
         }),
     )
 }
+
+#[instrument]
+fn fn_tests_timedout(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let pattern = eval_test_command_pattern(ctx, name, args)?;
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let result = read_all_test_results(repo, commit)
+                .unwrap_or_default()
+                .into_iter()
+                .any(|test_result| {
+                    let SerializedTestResult {
+                        command,
+                        exit_code: _,
+                        head_commit_oid: _,
+                        snapshot_tree_oid: _,
+                        interactive: _,
+                        timed_out,
+                        flaky: _,
+                        sparse_checkout: _,
+                        duration_ms: _,
+                    } = test_result;
+                    timed_out && pattern.matches_text(&command.to_string())
+                });
+            Ok(result)
+        }),
+    )
+}
+
+#[instrument]
+fn fn_tests_flaky(ctx: &mut Context, name: &str, args: &[Expr]) -> EvalResult {
+    let pattern = eval_test_command_pattern(ctx, name, args)?;
+    make_pattern_matcher(
+        ctx,
+        name,
+        args,
+        Box::new(move |repo: &Repo, commit: &Commit| {
+            let result = read_all_test_results(repo, commit)
+                .unwrap_or_default()
+                .into_iter()
+                .any(|test_result| {
+                    let SerializedTestResult {
+                        command,
+                        exit_code,
+                        head_commit_oid: _,
+                        snapshot_tree_oid: _,
+                        interactive: _,
+                        timed_out: _,
+                        flaky,
+                        sparse_checkout: _,
+                        duration_ms: _,
+                    } = test_result;
+                    flaky
+                        && exit_code == TEST_SUCCESS_EXIT_CODE
+                        && pattern.matches_text(&command.to_string())
+                });
+            Ok(result)
+        }),
+    )
+}
@@ -47,7 +47,7 @@ mod graph {
     use std::collections::HashMap;
     use std::convert::TryFrom;
 
-    use lib::core::gc::mark_commit_reachable;
+    use lib::core::gc::mark_commits_reachable;
     use tracing::instrument;
 
     use lib::core::dag::{CommitSet, CommitVertex, Dag};
@@ -285,7 +285,8 @@ mod graph {
 
         for (oid, node) in graph.iter_mut() {
             let oid_set = CommitSet::from(*oid);
-            let is_main_head = !dag.set_is_empty(&dag.main_branch_commit.intersection(&oid_set))?;
+            let is_main_head =
+                !dag.set_is_empty(&dag.public_branch_commits.intersection(&oid_set))?;
             let ancestor_of_main = node.is_main && !is_main_head;
             let has_descendants_in_graph =
                 !node.children.is_empty() || !node.descendants.is_empty();
@@ -352,9 +353,7 @@ mod graph {
                 .union(&dag.head_commit)
                 .union(&dag.main_branch_commit);
 
-            for oid in dag.commit_set_to_vec(&commits)? {
-                mark_commit_reachable(repo, oid)?;
-            }
+            mark_commits_reachable(repo, &dag.commit_set_to_vec(&commits)?)?;
 
             build_graph(&effects, repo, dag, &commits)?
         };
@@ -762,7 +761,11 @@ pub fn smartlog(
     let head_info = repo.get_head_info()?;
     let conn = repo.get_db_conn()?;
     let event_log_db = EventLogDb::new(&conn)?;
-    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_replayer = match event_id {
+        // Only the full history lets us show the state at an arbitrary event.
+        Some(_) => EventReplayer::from_event_log_db_with_history(effects, &repo, &event_log_db)?,
+        None => EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?,
+    };
     let (references_snapshot, event_cursor) = {
         let default_cursor = event_replayer.make_default_cursor();
         match event_id {
@@ -221,19 +221,27 @@ fn submit(
         no_cache: true,
         interactive: false,
         jobs: None,
+        timeout: None,
+        retries: 0,
         verbosity: Verbosity::None,
         apply_fixes: false,
+        confirm_fixes: false,
     };
     let ResolvedTestOptions {
         command: _,
         execution_strategy,
         search_strategy: _,
+        probabilistic_search_options: _,
         is_dry_run: _,
+        should_confirm_fixes: _,
         use_cache: _,
         is_interactive: _,
         num_jobs,
+        timeout: _,
+        num_retries: _,
         verbosity: _,
         fix_options: _,
+        remote_command: _,
     } = {
         let now = SystemTime::now();
         let event_tx_id =
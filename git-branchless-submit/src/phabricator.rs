@@ -396,12 +396,17 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
                 command,
                 execution_strategy: *execution_strategy,
                 search_strategy: None,
+                probabilistic_search_options: None,
                 is_dry_run: false,
+                should_confirm_fixes: false,
                 use_cache: false,
                 is_interactive: false,
                 num_jobs: *num_jobs,
+                timeout: None,
+                num_retries: 0,
                 verbosity: Verbosity::None,
                 fix_options: Some((execute_options.clone(), permissions.clone())),
+                remote_command: None,
             },
         ) {
             Ok(Ok(test_results)) => test_results,
@@ -411,6 +416,7 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
 
         let TestResults {
             search_bounds: _,
+            search_confidence: _,
             test_outputs,
             testing_aborted_error,
         } = test_results;
@@ -441,6 +447,7 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
                     | TestStatus::ReadCacheFailed(_)
                     | TestStatus::Indeterminate { .. }
                     | TestStatus::Abort { .. }
+                    | TestStatus::TimedOut { .. }
                     | TestStatus::Failed { .. } => {
                         self.render_failed_test(commit_oid, &test_output)?;
                         return Ok(Err(ExitCode(1)));
@@ -453,6 +460,14 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
                                 snapshot_tree_oid: _,
                             },
                         interactive: _,
+                    }
+                    | TestStatus::Flaky {
+                        cached: _,
+                        fix_info:
+                            FixInfo {
+                                head_commit_oid,
+                                snapshot_tree_oid: _,
+                            },
                     } => head_commit_oid,
                 };
 
@@ -626,15 +641,21 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
             },
             execution_strategy: *execution_strategy,
             search_strategy: None,
+            probabilistic_search_options: None,
             is_dry_run: false,
+            should_confirm_fixes: false,
             use_cache: false,
             is_interactive: false,
             num_jobs: *num_jobs,
+            timeout: None,
+            num_retries: 0,
             verbosity: Verbosity::None,
             fix_options: Some((execute_options, permissions)),
+            remote_command: None,
         };
         let TestResults {
             search_bounds: _,
+            search_confidence: _,
             test_outputs,
             testing_aborted_error,
         } = try_exit_code!(run_tests(
@@ -668,7 +689,7 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
         let (success_commits, failure_commits): (Vec<_>, Vec<_>) = test_outputs
             .into_iter()
             .partition(|(_commit_oid, test_output)| match test_output.test_status {
-                TestStatus::Passed { .. } => true,
+                TestStatus::Passed { .. } | TestStatus::Flaky { .. } => true,
                 TestStatus::CheckoutFailed
                 | TestStatus::SpawnTestFailed(_)
                 | TestStatus::TerminatedBySignal
@@ -676,6 +697,7 @@ Differential Revision: https://phabricator.example.com/D000$(git rev-list --coun
                 | TestStatus::ReadCacheFailed(_)
                 | TestStatus::Indeterminate { .. }
                 | TestStatus::Abort { .. }
+                | TestStatus::TimedOut { .. }
                 | TestStatus::Failed { .. } => false,
             });
         if !failure_commits.is_empty() {
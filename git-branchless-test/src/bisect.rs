@@ -0,0 +1,822 @@
+//! Search for the commit which introduced a bug by marking commits as good or
+//! bad by hand, similarly to `git bisect`. Unlike `git bisect`, the set of
+//! commits to search can be described by any revset, and doesn't need to form
+//! a linear history.
+//!
+//! The state of the search is persisted between invocations, so that the user
+//! can test each suggested commit however they like. At any point, the rest of
+//! the search can be handed off to `git test run --bisect` (or equivalently,
+//! `git branchless bisect run`), which continues from the persisted state.
+
+use std::fmt::Write;
+use std::time::SystemTime;
+
+use cursive::theme::Effect;
+use git_branchless_invoke::CommandContext;
+use git_branchless_opts::{BisectArgs, BisectSubcommand, ResolveRevsetOptions, Revset};
+use git_branchless_revset::resolve_commits;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use lib::core::check_out::{check_out_commit, CheckOutCommitOptions, CheckoutTarget};
+use lib::core::dag::{sorted_commit_set, CommitSet, Dag};
+use lib::core::effects::Effects;
+use lib::core::eventlog::{EventLogDb, EventReplayer, EventTransactionId};
+use lib::core::formatting::{Pluralize, StyledStringBuilder};
+use lib::core::repo_ext::RepoExt;
+use lib::git::{
+    get_bisect_state_path, GitRunInfo, NonZeroOid, ReferenceName, Repo, SerializedNonZeroOid,
+};
+use lib::try_exit_code;
+use lib::util::{ExitCode, EyreExitOr};
+use scm_bisect::search;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::{
+    run_tests, RawTestOptions, ResolvedTestOptions, SearchGraph, TestingAbortedError, Verbosity,
+};
+
+/// The status of a commit in a bisection, as persisted to disk.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum BisectStatus {
+    Untested,
+    Good,
+    Bad,
+    Skipped,
+}
+
+impl From<search::Status> for BisectStatus {
+    fn from(status: search::Status) -> Self {
+        match status {
+            search::Status::Untested => BisectStatus::Untested,
+            search::Status::Success => BisectStatus::Good,
+            search::Status::Failure => BisectStatus::Bad,
+            search::Status::Indeterminate => BisectStatus::Skipped,
+        }
+    }
+}
+
+impl From<BisectStatus> for search::Status {
+    fn from(status: BisectStatus) -> Self {
+        match status {
+            BisectStatus::Untested => search::Status::Untested,
+            BisectStatus::Good => search::Status::Success,
+            BisectStatus::Bad => search::Status::Failure,
+            BisectStatus::Skipped => search::Status::Indeterminate,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SerializedBisectCommit {
+    oid: SerializedNonZeroOid,
+    status: BisectStatus,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SerializedBisectState {
+    revset: String,
+    original_head_oid: Option<SerializedNonZeroOid>,
+    original_head_reference_name: Option<String>,
+    commits: Vec<SerializedBisectCommit>,
+}
+
+/// The state of the current bisection.
+#[derive(Debug)]
+struct BisectState {
+    /// The revset which the bisection was started with.
+    revset: Revset,
+
+    /// The commit which was checked out when the bisection was started.
+    original_head_oid: Option<NonZeroOid>,
+
+    /// The branch which was checked out when the bisection was started, if any.
+    original_head_reference_name: Option<ReferenceName>,
+
+    /// The commits being searched, in topological order, along with their
+    /// statuses. Commits which haven't been marked are `Untested`, even if
+    /// their status is implied by the other commits.
+    commits: IndexMap<NonZeroOid, search::Status>,
+}
+
+impl BisectState {
+    #[instrument]
+    fn load(repo: &Repo) -> eyre::Result<Option<Self>> {
+        let path = get_bisect_state_path(repo);
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let SerializedBisectState {
+            revset,
+            original_head_oid,
+            original_head_reference_name,
+            commits,
+        } = serde_json::from_str(&contents)?;
+        Ok(Some(Self {
+            revset: Revset(revset),
+            original_head_oid: original_head_oid.map(|SerializedNonZeroOid(oid)| oid),
+            original_head_reference_name: original_head_reference_name.map(ReferenceName::from),
+            commits: commits
+                .into_iter()
+                .map(
+                    |SerializedBisectCommit {
+                         oid: SerializedNonZeroOid(oid),
+                         status,
+                     }| (oid, search::Status::from(status)),
+                )
+                .collect(),
+        }))
+    }
+
+    #[instrument]
+    fn save(&self, repo: &Repo) -> eyre::Result<()> {
+        let Self {
+            revset,
+            original_head_oid,
+            original_head_reference_name,
+            commits,
+        } = self;
+        let serialized = SerializedBisectState {
+            revset: revset.to_string(),
+            original_head_oid: original_head_oid.map(SerializedNonZeroOid),
+            original_head_reference_name: original_head_reference_name
+                .as_ref()
+                .map(|reference_name| reference_name.as_str().to_owned()),
+            commits: commits
+                .iter()
+                .map(|(oid, status)| SerializedBisectCommit {
+                    oid: SerializedNonZeroOid(*oid),
+                    status: BisectStatus::from(*status),
+                })
+                .collect(),
+        };
+        let path = get_bisect_state_path(repo);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&serialized)?)?;
+        Ok(())
+    }
+
+    /// Reconstruct the search from the statuses provided so far.
+    #[instrument]
+    fn make_search<'a>(&self, dag: &'a Dag) -> eyre::Result<search::Search<SearchGraph<'a>>> {
+        let graph = SearchGraph {
+            dag,
+            commit_set: self.commits.keys().copied().collect(),
+        };
+        let mut search = search::Search::new(graph, self.commits.keys().copied());
+        for (oid, status) in &self.commits {
+            match status {
+                search::Status::Untested => {}
+                search::Status::Success
+                | search::Status::Failure
+                | search::Status::Indeterminate => search.notify(*oid, *status)?,
+            }
+        }
+        Ok(search)
+    }
+}
+
+/// `bisect` command.
+#[instrument]
+pub fn bisect_command_main(ctx: CommandContext, args: BisectArgs) -> EyreExitOr<()> {
+    let CommandContext {
+        effects,
+        git_run_info,
+    } = ctx;
+    let BisectArgs { subcommand } = args;
+
+    let now = SystemTime::now();
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_tx_id = event_log_db.make_transaction_id(now, "bisect")?;
+    let event_replayer = EventReplayer::from_event_log_db(&effects, &repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        &effects,
+        &repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+
+    if let BisectSubcommand::Start {
+        revset,
+        resolve_revset_options,
+    } = subcommand
+    {
+        return subcommand_start(
+            &effects,
+            &git_run_info,
+            &mut dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            revset,
+            &resolve_revset_options,
+        );
+    }
+
+    let mut state = match BisectState::load(&repo)? {
+        Some(state) => state,
+        None => {
+            writeln!(
+                effects.get_output_stream(),
+                "No bisection is in progress. To start one, run: git branchless bisect start <revset>"
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+    match subcommand {
+        BisectSubcommand::Start { .. } => unreachable!("Handled above"),
+
+        BisectSubcommand::Good {
+            revset,
+            resolve_revset_options,
+        } => subcommand_mark(
+            &effects,
+            &git_run_info,
+            &mut dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            &mut state,
+            search::Status::Success,
+            revset,
+            &resolve_revset_options,
+        ),
+
+        BisectSubcommand::Bad {
+            revset,
+            resolve_revset_options,
+        } => subcommand_mark(
+            &effects,
+            &git_run_info,
+            &mut dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            &mut state,
+            search::Status::Failure,
+            revset,
+            &resolve_revset_options,
+        ),
+
+        BisectSubcommand::Skip {
+            revset,
+            resolve_revset_options,
+        } => subcommand_mark(
+            &effects,
+            &git_run_info,
+            &mut dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            &mut state,
+            search::Status::Indeterminate,
+            revset,
+            &resolve_revset_options,
+        ),
+
+        BisectSubcommand::Status => {
+            print_status(&effects, &dag, &repo, &state)?;
+            Ok(Ok(()))
+        }
+
+        BisectSubcommand::Run {
+            exec,
+            command,
+            verbosity,
+            strategy,
+            no_cache,
+            jobs,
+            timeout,
+            retries,
+        } => subcommand_run(
+            now,
+            &effects,
+            &git_run_info,
+            &dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            &mut state,
+            &RawTestOptions {
+                exec,
+                command,
+                dry_run: false,
+                strategy,
+                search: None,
+                bisect: true,
+                no_cache,
+                interactive: false,
+                jobs,
+                timeout,
+                retries,
+                verbosity: Verbosity::from(verbosity),
+                apply_fixes: false,
+                confirm_fixes: false,
+            },
+        ),
+
+        BisectSubcommand::Reset => subcommand_reset(
+            &effects,
+            &git_run_info,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            &state,
+        ),
+    }
+}
+
+/// Whether a bisection started with `git branchless bisect start` is in
+/// progress.
+#[instrument]
+pub(crate) fn is_bisection_in_progress(repo: &Repo) -> eyre::Result<bool> {
+    Ok(BisectState::load(repo)?.is_some())
+}
+
+/// Continue the bisection in progress by running the command in `options` on
+/// the remaining commits. This is how `git test run --bisect` picks up a
+/// bisection which was started by hand.
+#[instrument]
+pub(crate) fn continue_bisection(
+    now: SystemTime,
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    options: &RawTestOptions,
+) -> EyreExitOr<()> {
+    let mut state = match BisectState::load(repo)? {
+        Some(state) => state,
+        None => eyre::bail!("No bisection is in progress"),
+    };
+    writeln!(
+        effects.get_output_stream(),
+        "Continuing the bisection in progress for: {}",
+        state.revset
+    )?;
+    subcommand_run(
+        now,
+        effects,
+        git_run_info,
+        dag,
+        repo,
+        event_log_db,
+        event_tx_id,
+        &mut state,
+        options,
+    )
+}
+
+#[instrument]
+fn subcommand_start(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &mut Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    revset: Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    if BisectState::load(repo)?.is_some() {
+        writeln!(
+            effects.get_output_stream(),
+            "A bisection is already in progress. To abandon it, run: git branchless bisect reset"
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let commit_set = try_exit_code!(resolve_revset(
+        effects,
+        dag,
+        repo,
+        &revset,
+        resolve_revset_options
+    )?);
+    let commits = sorted_commit_set(repo, dag, &commit_set)?;
+    if commits.is_empty() {
+        writeln!(
+            effects.get_output_stream(),
+            "There are no commits to bisect in: {revset}"
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let head_info = repo.get_head_info()?;
+    let state = BisectState {
+        revset,
+        original_head_oid: head_info.oid,
+        original_head_reference_name: head_info.reference_name,
+        commits: commits
+            .iter()
+            .map(|commit| (commit.get_oid(), search::Status::Untested))
+            .collect(),
+    };
+    state.save(repo)?;
+
+    advance(
+        effects,
+        git_run_info,
+        dag,
+        repo,
+        event_log_db,
+        event_tx_id,
+        &state,
+    )
+}
+
+#[instrument]
+fn subcommand_mark(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &mut Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    state: &mut BisectState,
+    status: search::Status,
+    revset: Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    let commit_set = try_exit_code!(resolve_revset(
+        effects,
+        dag,
+        repo,
+        &revset,
+        resolve_revset_options
+    )?);
+    let commits = sorted_commit_set(repo, dag, &commit_set)?;
+
+    let mut search = state.make_search(dag)?;
+    for commit in commits {
+        let commit_oid = commit.get_oid();
+        if !state.commits.contains_key(&commit_oid) {
+            writeln!(
+                effects.get_output_stream(),
+                "Commit is not part of the current bisection: {}",
+                effects
+                    .get_glyphs()
+                    .render(commit.friendly_describe(effects.get_glyphs())?)?
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+        try_exit_code!(notify(
+            effects,
+            repo,
+            &mut search,
+            state,
+            commit_oid,
+            status
+        )?);
+    }
+    state.save(repo)?;
+
+    advance(
+        effects,
+        git_run_info,
+        dag,
+        repo,
+        event_log_db,
+        event_tx_id,
+        state,
+    )
+}
+
+#[instrument]
+fn subcommand_run(
+    now: SystemTime,
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    state: &mut BisectState,
+    options: &RawTestOptions,
+) -> EyreExitOr<()> {
+    let mut search = state.make_search(dag)?;
+    let commit_set: CommitSet = search
+        .search(search::Strategy::Linear)?
+        .next_to_search
+        .collect();
+    let commits = sorted_commit_set(repo, dag, &commit_set)?;
+    if !commits.is_empty() {
+        let options = try_exit_code!(ResolvedTestOptions::resolve(
+            now,
+            effects,
+            dag,
+            repo,
+            event_tx_id,
+            &commit_set,
+            None,
+            options,
+        )?);
+        let test_results = try_exit_code!(run_tests(
+            now,
+            effects,
+            git_run_info,
+            dag,
+            repo,
+            event_log_db,
+            event_tx_id,
+            &state.revset,
+            &commits,
+            &options,
+        )?);
+
+        for (commit_oid, test_output) in &test_results.test_outputs {
+            let commit = repo.find_commit_or_fail(*commit_oid)?;
+            write!(
+                effects.get_output_stream(),
+                "{}",
+                effects.get_glyphs().render(test_output.describe(
+                    effects,
+                    &commit,
+                    false,
+                    options.verbosity,
+                )?)?
+            )?;
+            try_exit_code!(notify(
+                effects,
+                repo,
+                &mut search,
+                state,
+                *commit_oid,
+                test_output.test_status.get_search_status(),
+            )?);
+        }
+        state.save(repo)?;
+
+        if let Some(TestingAbortedError {
+            commit_oid,
+            exit_code,
+        }) = &test_results.testing_aborted_error
+        {
+            let commit = repo.find_commit_or_fail(*commit_oid)?;
+            writeln!(
+                effects.get_output_stream(),
+                "Aborted running commands with exit code {} at commit: {}",
+                exit_code,
+                effects
+                    .get_glyphs()
+                    .render(commit.friendly_describe(effects.get_glyphs())?)?
+            )?;
+            return Ok(Err(ExitCode(1)));
+        }
+    }
+
+    // Running the tests in the working copy restores `HEAD` afterwards, so
+    // check out the next commit to test again, if there is one.
+    advance(
+        effects,
+        git_run_info,
+        dag,
+        repo,
+        event_log_db,
+        event_tx_id,
+        state,
+    )
+}
+
+#[instrument]
+fn subcommand_reset(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    state: &BisectState,
+) -> EyreExitOr<()> {
+    std::fs::remove_file(get_bisect_state_path(repo))?;
+    writeln!(
+        effects.get_output_stream(),
+        "Stopped bisecting: {}",
+        state.revset
+    )?;
+
+    let target = match (&state.original_head_reference_name, state.original_head_oid) {
+        (Some(reference_name), _) => CheckoutTarget::Reference(reference_name.clone()),
+        (None, Some(oid)) => CheckoutTarget::Oid(oid),
+        (None, None) => return Ok(Ok(())),
+    };
+    check_out_commit(
+        effects,
+        git_run_info,
+        repo,
+        event_log_db,
+        event_tx_id,
+        Some(target),
+        &CheckOutCommitOptions {
+            render_smartlog: false,
+            ..Default::default()
+        },
+    )
+}
+
+#[instrument]
+fn resolve_revset(
+    effects: &Effects,
+    dag: &mut Dag,
+    repo: &Repo,
+    revset: &Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<CommitSet> {
+    match resolve_commits(
+        effects,
+        repo,
+        dag,
+        &[revset.clone()],
+        resolve_revset_options,
+    ) {
+        Ok(mut commit_sets) => Ok(Ok(commit_sets.pop().unwrap())),
+        Err(err) => {
+            err.describe(effects)?;
+            Ok(Err(ExitCode(1)))
+        }
+    }
+}
+
+/// Record the status of the given commit in both the search and the persisted
+/// state. Fails if the status contradicts the statuses provided so far.
+#[instrument(skip(search))]
+fn notify(
+    effects: &Effects,
+    repo: &Repo,
+    search: &mut search::Search<SearchGraph>,
+    state: &mut BisectState,
+    commit_oid: NonZeroOid,
+    status: search::Status,
+) -> EyreExitOr<()> {
+    match search.notify(commit_oid, status) {
+        Ok(()) => {
+            state.commits.insert(commit_oid, status);
+            Ok(Ok(()))
+        }
+        Err(search::Error2::Underlying(err)) => Err(err.into()),
+        Err(
+            search::Error2::IllegalStateTransition { .. }
+            | search::Error2::InconsistentStateTransition { .. }
+            | search::Error2::ImpossibleObservation { .. },
+        ) => {
+            let commit = repo.find_commit_or_fail(commit_oid)?;
+            writeln!(
+                effects.get_output_stream(),
+                "Cannot mark commit as {} because it contradicts the commits marked so far: {}",
+                describe_status(status),
+                effects
+                    .get_glyphs()
+                    .render(commit.friendly_describe(effects.get_glyphs())?)?
+            )?;
+            Ok(Err(ExitCode(1)))
+        }
+    }
+}
+
+fn describe_status(status: search::Status) -> &'static str {
+    match status {
+        search::Status::Untested => "untested",
+        search::Status::Success => "good",
+        search::Status::Failure => "bad",
+        search::Status::Indeterminate => "skipped",
+    }
+}
+
+/// Print the progress of the bisection, and check out the next commit to test,
+/// if any.
+#[instrument]
+fn advance(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    state: &BisectState,
+) -> EyreExitOr<()> {
+    let next_commit_oid = match print_status(effects, dag, repo, state)? {
+        Some(next_commit_oid) => next_commit_oid,
+        None => return Ok(Ok(())),
+    };
+    if repo.get_head_info()?.oid == Some(next_commit_oid) {
+        return Ok(Ok(()));
+    }
+    check_out_commit(
+        effects,
+        git_run_info,
+        repo,
+        event_log_db,
+        event_tx_id,
+        Some(CheckoutTarget::Oid(next_commit_oid)),
+        &CheckOutCommitOptions {
+            render_smartlog: false,
+            ..Default::default()
+        },
+    )
+}
+
+/// Print the progress of the bisection. Returns the next commit to test, if
+/// the bisection isn't complete.
+#[instrument]
+fn print_status(
+    effects: &Effects,
+    dag: &Dag,
+    repo: &Repo,
+    state: &BisectState,
+) -> eyre::Result<Option<NonZeroOid>> {
+    let search = state.make_search(dag)?;
+    let search::LazySolution {
+        bounds,
+        next_to_search,
+    } = search.search(search::Strategy::InformationGain)?;
+    let next_to_search = next_to_search.collect_vec();
+
+    writeln!(
+        effects.get_output_stream(),
+        "Bisecting {}: {}",
+        Pluralize {
+            determiner: None,
+            amount: state.commits.len(),
+            unit: ("commit", "commits"),
+        },
+        effects.get_glyphs().render(
+            StyledStringBuilder::new()
+                .append_styled(state.revset.to_string(), Effect::Bold)
+                .build()
+        )?,
+    )?;
+
+    for (commits, singular, plural, empty_message) in [
+        (
+            &bounds.success,
+            "Last good commit:",
+            "Last good commits:",
+            "There are no good commits yet.",
+        ),
+        (
+            &bounds.failure,
+            "First bad commit:",
+            "First bad commits:",
+            "There are no bad commits yet.",
+        ),
+    ] {
+        let commits: CommitSet = commits.iter().copied().collect();
+        let commits = sorted_commit_set(repo, dag, &commits)?;
+        if commits.is_empty() {
+            writeln!(effects.get_output_stream(), "{empty_message}")?;
+            continue;
+        }
+        writeln!(
+            effects.get_output_stream(),
+            "{}",
+            if commits.len() == 1 { singular } else { plural }
+        )?;
+        for commit in commits {
+            writeln!(
+                effects.get_output_stream(),
+                "{} {}",
+                effects.get_glyphs().bullet_point,
+                effects
+                    .get_glyphs()
+                    .render(commit.friendly_describe(effects.get_glyphs())?)?
+            )?;
+        }
+    }
+
+    match next_to_search.first() {
+        None => {
+            writeln!(
+                effects.get_output_stream(),
+                "Bisection complete. To return to where you started, run: git branchless bisect reset"
+            )?;
+            Ok(None)
+        }
+        Some(next_commit_oid) => {
+            let next_commit = repo.find_commit_or_fail(*next_commit_oid)?;
+            writeln!(
+                effects.get_output_stream(),
+                "{} left to test. Next commit to test: {}",
+                Pluralize {
+                    determiner: None,
+                    amount: next_to_search.len(),
+                    unit: ("commit", "commits"),
+                },
+                effects
+                    .get_glyphs()
+                    .render(next_commit.friendly_describe(effects.get_glyphs())?)?
+            )?;
+            Ok(Some(*next_commit_oid))
+        }
+    }
+}
@@ -11,16 +11,20 @@
 )]
 #![allow(clippy::too_many_arguments, clippy::blocks_in_if_conditions)]
 
+mod bisect;
+mod report;
 mod worker;
 
-use std::collections::{HashMap, HashSet};
+pub use bisect::bisect_command_main;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::Write as _;
 use std::fs::File;
-use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use bstr::ByteSlice;
 use clap::ValueEnum;
@@ -49,23 +53,28 @@ use lib::core::rewrite::{
     RebaseCommand, RebasePlan, RebasePlanBuilder, RebasePlanPermissions, RepoResource,
 };
 use lib::git::{
-    get_latest_test_command_path, get_test_locks_dir, get_test_tree_dir, get_test_worktrees_dir,
-    make_test_command_slug, Commit, ConfigRead, GitRunInfo, GitRunResult, MaybeZeroOid, NonZeroOid,
+    get_latest_test_command_path, get_test_locks_dir, get_test_tree_dir,
+    get_test_tree_dir_for_tree_oid, get_test_worktrees_dir, make_test_command_slug, Commit,
+    ConfigRead, GitRunInfo, GitRunOpts, GitRunResult, MaybeZeroOid, NonZeroOid, ReferenceName,
     Repo, SerializedNonZeroOid, SerializedTestResult, TestCommand, WorkingCopyChangesType,
     TEST_ABORT_EXIT_CODE, TEST_INDETERMINATE_EXIT_CODE, TEST_SUCCESS_EXIT_CODE,
+    TEST_TIMED_OUT_EXIT_CODE,
 };
 use lib::try_exit_code;
 use lib::util::{get_sh, ExitCode, EyreExitOr};
 use rayon::ThreadPoolBuilder;
+use scm_bisect::probabilistic::{ProbabilisticOptions, ProbabilisticSearch};
 use scm_bisect::search;
+use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
 use thiserror::Error;
 use tracing::{debug, info, instrument, warn};
 
 use git_branchless_opts::{
-    MoveOptions, ResolveRevsetOptions, Revset, TestArgs, TestExecutionStrategy, TestSearchStrategy,
-    TestSubcommand,
+    MoveOptions, ResolveRevsetOptions, Revset, TestArgs, TestExecutionStrategy, TestReport,
+    TestSearchStrategy, TestSubcommand,
 };
+use git_branchless_record::{make_selected_tree, select_changes_interactively};
 use git_branchless_revset::resolve_commits;
 
 use crate::worker::{worker, JobResult, WorkQueue, WorkerId};
@@ -77,6 +86,8 @@ lazy_static! {
         Style::merge(&[BaseColor::Red.light().into(), Effect::Bold.into()]);
     static ref STYLE_SKIPPED: Style =
         Style::merge(&[BaseColor::Yellow.light().into(), Effect::Bold.into()]);
+    static ref STYLE_FLAKY: Style =
+        Style::merge(&[BaseColor::Magenta.light().into(), Effect::Bold.into()]);
 }
 
 /// How verbose of output to produce.
@@ -104,7 +115,7 @@ impl From<u8> for Verbosity {
 
 /// The options for testing before they've assumed default values or been
 /// validated.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct RawTestOptions {
     /// The command to execute, if any.
     pub exec: Option<String>,
@@ -135,12 +146,89 @@ pub struct RawTestOptions {
     /// The number of jobs to run in parallel.
     pub jobs: Option<usize>,
 
+    /// The maximum amount of time to let the test command run on each commit
+    /// before killing it.
+    pub timeout: Option<Duration>,
+
+    /// The number of times to re-run the test command on a commit if it fails.
+    pub retries: usize,
+
     /// The requested verbosity of the test output.
     pub verbosity: Verbosity,
 
     /// Whether to amend commits with the changes produced by the executed
     /// command.
     pub apply_fixes: bool,
+
+    /// Whether to ask before applying each fix, after showing its changes.
+    pub confirm_fixes: bool,
+}
+
+/// Read the parameters for `--search probabilistic` from the config.
+#[instrument(skip(config))]
+fn resolve_probabilistic_search_options(
+    effects: &Effects,
+    config: &impl ConfigRead,
+) -> EyreExitOr<ProbabilisticOptions> {
+    let read_probability =
+        |config_key: &str, default: f64, is_exclusive: bool| -> EyreExitOr<f64> {
+            let value: Option<String> = config.get(config_key)?;
+            let value = match value {
+                None => return Ok(Ok(default)),
+                Some(value) => value,
+            };
+            match value.parse::<f64>() {
+                Ok(probability) if is_exclusive && probability > 0.0 && probability < 1.0 => {
+                    Ok(Ok(probability))
+                }
+                Ok(probability) if !is_exclusive && (0.0..=1.0).contains(&probability) => {
+                    Ok(Ok(probability))
+                }
+                _ => {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "Invalid value for config value {config_key}: {value}"
+                    )?;
+                    if is_exclusive {
+                        writeln!(
+                            effects.get_output_stream(),
+                            "Expected a number greater than 0 and less than 1."
+                        )?;
+                    } else {
+                        writeln!(
+                            effects.get_output_stream(),
+                            "Expected a number between 0 and 1."
+                        )?;
+                    }
+                    Ok(Err(ExitCode(1)))
+                }
+            }
+        };
+
+    let failure_rate_config_key = "branchless.test.search.failureRate";
+    let false_failure_rate_config_key = "branchless.test.search.falseFailureRate";
+    let failure_rate = try_exit_code!(read_probability(failure_rate_config_key, 0.5, false)?);
+    let false_failure_rate =
+        try_exit_code!(read_probability(false_failure_rate_config_key, 0.0, false)?);
+    // A confidence of 0 would end the search immediately, and one of 1 could
+    // never be reached if the test is flaky.
+    let confidence = try_exit_code!(read_probability(
+        "branchless.test.search.confidence",
+        0.95,
+        true
+    )?);
+    if false_failure_rate >= failure_rate {
+        writeln!(
+            effects.get_output_stream(),
+            "The value of {failure_rate_config_key} ({failure_rate}) must be greater than the value of {false_failure_rate_config_key} ({false_failure_rate})."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+    Ok(Ok(ProbabilisticOptions {
+        failure_rate,
+        false_failure_rate,
+        confidence,
+    }))
 }
 
 fn resolve_test_command_alias(
@@ -206,12 +294,17 @@ pub struct ResolvedTestOptions {
     pub command: TestCommand,
     pub execution_strategy: TestExecutionStrategy,
     pub search_strategy: Option<TestSearchStrategy>,
+    pub probabilistic_search_options: Option<ProbabilisticOptions>,
     pub is_dry_run: bool,
+    pub should_confirm_fixes: bool,
     pub use_cache: bool,
     pub is_interactive: bool,
     pub num_jobs: usize,
+    pub timeout: Option<Duration>,
+    pub num_retries: usize,
     pub verbosity: Verbosity,
     pub fix_options: Option<(ExecuteRebasePlanOptions, RebasePlanPermissions)>,
+    pub remote_command: Option<String>,
 }
 
 impl ResolvedTestOptions {
@@ -237,8 +330,11 @@ impl ResolvedTestOptions {
             no_cache,
             interactive,
             jobs,
+            timeout,
+            retries,
             verbosity,
             apply_fixes,
+            confirm_fixes,
         } = options;
         let resolved_command = match (command, command_alias) {
             (Some(command), None) => command.to_owned(),
@@ -321,6 +417,11 @@ impl ResolvedTestOptions {
                     TestExecutionStrategy::Worktree,
                     false,
                 ),
+                (Some(TestExecutionStrategy::Remote), interactive) => (
+                    configured_jobs.unwrap_or(1),
+                    TestExecutionStrategy::Remote,
+                    interactive,
+                ),
                 (None, true) => (1, configured_execution_strategy, true),
                 (None, false) => (
                     configured_jobs.unwrap_or(1),
@@ -339,11 +440,15 @@ The --jobs option cannot be used with the --interactive option."
                     return Ok(Err(ExitCode(1)));
                 }
                 // NB: match on the strategy passed on the command-line here, not the resolved strategy.
-                match strategy {
-                    None | Some(TestExecutionStrategy::Worktree) => {
+                match (strategy, configured_execution_strategy) {
+                    (Some(TestExecutionStrategy::Remote), _)
+                    | (None, TestExecutionStrategy::Remote) => {
+                        (*jobs, TestExecutionStrategy::Remote, false)
+                    }
+                    (None | Some(TestExecutionStrategy::Worktree), _) => {
                         (*jobs, TestExecutionStrategy::Worktree, false)
                     }
-                    Some(TestExecutionStrategy::WorkingCopy) => {
+                    (Some(TestExecutionStrategy::WorkingCopy), _) => {
                         writeln!(
                             effects.get_output_stream(),
                             "\
@@ -363,6 +468,43 @@ BUG: Expected resolved_interactive ({resolved_interactive:?}) to match interacti
             return Ok(Err(ExitCode(1)));
         }
 
+        let remote_command = match resolved_execution_strategy {
+            TestExecutionStrategy::WorkingCopy | TestExecutionStrategy::Worktree => None,
+            TestExecutionStrategy::Remote => {
+                if resolved_interactive {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "The --interactive option cannot be used with --strategy remote."
+                    )?;
+                    return Ok(Err(ExitCode(1)));
+                }
+                if *apply_fixes {
+                    writeln!(
+                        effects.get_output_stream(),
+                        "Fixes cannot be applied with --strategy remote, since the test command is not run in a local working copy."
+                    )?;
+                    return Ok(Err(ExitCode(1)));
+                }
+
+                let remote_command_config_key = "branchless.test.remoteCommand";
+                let remote_command: Option<String> = config.get(remote_command_config_key)?;
+                match remote_command {
+                    Some(remote_command) => Some(remote_command),
+                    None => {
+                        writeln!(
+                            effects.get_output_stream(),
+                            "\
+The remote execution strategy requires a command to hand each commit off to,
+but none was configured.
+
+To configure one, run: git config {remote_command_config_key} <command>"
+                        )?;
+                        return Ok(Err(ExitCode(1)));
+                    }
+                }
+            }
+        };
+
         let resolved_num_jobs = if resolved_num_jobs == 0 {
             num_cpus::get_physical()
         } else {
@@ -433,17 +575,35 @@ BUG: Expected resolved_interactive ({resolved_interactive:?}) to match interacti
         } else {
             *search
         };
+        let probabilistic_search_options = match resolved_search_strategy {
+            Some(TestSearchStrategy::Probabilistic) => Some(try_exit_code!(
+                resolve_probabilistic_search_options(effects, &config)?
+            )),
+            None
+            | Some(
+                TestSearchStrategy::Linear
+                | TestSearchStrategy::Reverse
+                | TestSearchStrategy::Binary,
+            ) => None,
+        };
 
         let resolved_test_options = ResolvedTestOptions {
             command: TestCommand::String(resolved_command),
             execution_strategy: resolved_execution_strategy,
             search_strategy: resolved_search_strategy,
-            use_cache: !no_cache,
+            probabilistic_search_options,
+            // Re-testing a commit has to actually re-run the test command for
+            // a probabilistic search, so cached results can't be used.
+            use_cache: !no_cache && probabilistic_search_options.is_none(),
             is_dry_run: *dry_run,
+            should_confirm_fixes: *confirm_fixes,
             is_interactive: resolved_interactive,
             num_jobs: resolved_num_jobs,
+            timeout: *timeout,
+            num_retries: *retries,
             verbosity: *verbosity,
             fix_options,
+            remote_command,
         };
         debug!(?resolved_test_options, "Resolved test options");
         Ok(Ok(resolved_test_options))
@@ -470,7 +630,7 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
 
         TestSubcommand::Run {
             exec: command,
-            command: command_alias,
+            command: command_aliases,
             revset,
             resolve_revset_options,
             verbosity,
@@ -480,12 +640,13 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
             no_cache,
             interactive,
             jobs,
-        } => subcommand_run(
-            &effects,
-            &git_run_info,
-            &RawTestOptions {
+            timeout,
+            retries,
+            reports,
+        } => {
+            let options = RawTestOptions {
                 exec: command,
-                command: command_alias,
+                command: None,
                 dry_run: false,
                 strategy,
                 search,
@@ -493,13 +654,33 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
                 no_cache,
                 interactive,
                 jobs,
+                timeout,
+                retries,
                 verbosity: Verbosity::from(verbosity),
                 apply_fixes: false,
-            },
-            revset,
-            &resolve_revset_options,
-            None,
-        ),
+                confirm_fixes: false,
+            };
+            let options = if command_aliases.is_empty() {
+                vec![options]
+            } else {
+                command_aliases
+                    .into_iter()
+                    .map(|command_alias| RawTestOptions {
+                        command: Some(command_alias),
+                        ..options.clone()
+                    })
+                    .collect()
+            };
+            subcommand_run(
+                &effects,
+                &git_run_info,
+                &options,
+                revset,
+                &resolve_revset_options,
+                None,
+                &reports,
+            )
+        }
 
         TestSubcommand::Show {
             exec: command,
@@ -519,28 +700,73 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
                 no_cache: false,
                 interactive: false,
                 jobs: None,
+                timeout: None,
+                retries: 0,
+                verbosity: Verbosity::from(verbosity),
+                apply_fixes: false,
+                confirm_fixes: false,
+            },
+            revset,
+            &resolve_revset_options,
+        ),
+
+        TestSubcommand::Watch {
+            exec: command,
+            command: command_alias,
+            revset,
+            resolve_revset_options,
+            verbosity,
+            strategy,
+            jobs,
+            timeout,
+            retries,
+            interval,
+            iterations,
+        } => subcommand_watch(
+            &effects,
+            &git_run_info,
+            &RawTestOptions {
+                exec: command,
+                command: command_alias,
+                dry_run: false,
+                // Testing in the working copy would check out each commit over the
+                // changes being made while watching.
+                strategy: Some(strategy.unwrap_or(TestExecutionStrategy::Worktree)),
+                search: None,
+                bisect: false,
+                no_cache: false,
+                interactive: false,
+                jobs,
+                timeout,
+                retries,
                 verbosity: Verbosity::from(verbosity),
                 apply_fixes: false,
+                confirm_fixes: false,
             },
             revset,
             &resolve_revset_options,
+            interval,
+            iterations,
         ),
 
         TestSubcommand::Fix {
             exec: command,
             command: command_alias,
             dry_run,
+            confirm,
             revset,
             resolve_revset_options,
             verbosity,
             strategy,
             no_cache,
             jobs,
+            timeout,
+            retries,
             move_options,
         } => subcommand_run(
             &effects,
             &git_run_info,
-            &RawTestOptions {
+            &[RawTestOptions {
                 exec: command,
                 command: command_alias,
                 dry_run,
@@ -550,25 +776,48 @@ pub fn command_main(ctx: CommandContext, args: TestArgs) -> EyreExitOr<()> {
                 no_cache,
                 interactive: false,
                 jobs,
+                timeout,
+                retries,
                 verbosity: Verbosity::from(verbosity),
                 apply_fixes: true,
-            },
+                confirm_fixes: confirm,
+            }],
             revset,
             &resolve_revset_options,
             Some(&move_options),
+            &[],
+        ),
+
+        TestSubcommand::PushResults {
+            remote,
+            revset,
+            resolve_revset_options,
+        } => subcommand_push_results(
+            &effects,
+            &git_run_info,
+            remote,
+            revset,
+            &resolve_revset_options,
         ),
+
+        TestSubcommand::FetchResults { remote } => {
+            subcommand_fetch_results(&effects, &git_run_info, remote)
+        }
     }
 }
 
 /// Run the command provided in `options` on each of the commits in `revset`.
+/// If more than one set of options is provided, then each of their commands
+/// is run on each commit.
 #[instrument]
 fn subcommand_run(
     effects: &Effects,
     git_run_info: &GitRunInfo,
-    options: &RawTestOptions,
+    options: &[RawTestOptions],
     revset: Revset,
     resolve_revset_options: &ResolveRevsetOptions,
     move_options: Option<&MoveOptions>,
+    reports: &[TestReport],
 ) -> EyreExitOr<()> {
     let now = SystemTime::now();
     let repo = Repo::from_current_dir()?;
@@ -600,6 +849,39 @@ fn subcommand_run(
         }
     };
 
+    let options = expand_test_suites(&repo, options)?;
+    let options = match options.as_slice() {
+        [options] => options,
+        options => {
+            return subcommand_run_matrix(
+                now,
+                effects,
+                git_run_info,
+                &dag,
+                &repo,
+                &event_log_db,
+                event_tx_id,
+                &revset,
+                &commit_set,
+                options,
+                reports,
+            )
+        }
+    };
+
+    if options.bisect && move_options.is_none() && bisect::is_bisection_in_progress(&repo)? {
+        return bisect::continue_bisection(
+            now,
+            effects,
+            git_run_info,
+            &dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            options,
+        );
+    }
+
     let options = try_exit_code!(ResolvedTestOptions::resolve(
         now,
         effects,
@@ -624,8 +906,7 @@ fn subcommand_run(
         &commits,
         &options,
     )?);
-
-    try_exit_code!(print_summary(
+    let summary_result = print_summary(
         effects,
         &dag,
         &repo,
@@ -635,7 +916,9 @@ fn subcommand_run(
         options.search_strategy.is_some(),
         options.fix_options.is_some(),
         &options.verbosity,
-    )?);
+    )?;
+    report::write_reports(&commits, &[(&options.command, &test_results)], reports)?;
+    try_exit_code!(summary_result);
 
     if let Some((execute_options, permissions)) = &options.fix_options {
         try_exit_code!(apply_fixes(
@@ -647,6 +930,7 @@ fn subcommand_run(
             execute_options,
             permissions.clone(),
             options.is_dry_run,
+            options.should_confirm_fixes,
             &options.command,
             &test_results,
         )?);
@@ -655,6 +939,143 @@ fn subcommand_run(
     Ok(Ok(()))
 }
 
+/// Replace each of the provided options whose command alias names a suite of
+/// aliases (configured with `branchless.test.suite.<name>`) with one set of
+/// options per alias in that suite. Aliases take precedence over suites with
+/// the same name.
+#[instrument]
+fn expand_test_suites(
+    repo: &Repo,
+    options: &[RawTestOptions],
+) -> eyre::Result<Vec<RawTestOptions>> {
+    let config = repo.get_readonly_config()?;
+    let mut result = Vec::new();
+    for options in options {
+        let command_alias = match &options.command {
+            Some(command_alias) => command_alias,
+            None => {
+                result.push(options.clone());
+                continue;
+            }
+        };
+        let alias: Option<String> = config
+            .get(format!("branchless.test.alias.{command_alias}"))
+            .unwrap_or_default();
+        let suite: Option<String> = config
+            .get(format!("branchless.test.suite.{command_alias}"))
+            .unwrap_or_default();
+        match (alias, suite) {
+            (None, Some(suite)) => {
+                result.extend(
+                    suite
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .filter(|alias| !alias.is_empty())
+                        .map(|alias| RawTestOptions {
+                            command: Some(alias.to_owned()),
+                            ..options.clone()
+                        }),
+                );
+            }
+            (Some(_), _) | (None, None) => result.push(options.clone()),
+        }
+    }
+    Ok(result)
+}
+
+/// Run each of the commands provided in `options` on each of the commits in
+/// `commit_set`, and present the results as a table.
+#[instrument]
+fn subcommand_run_matrix(
+    now: SystemTime,
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    revset: &Revset,
+    commit_set: &CommitSet,
+    options: &[RawTestOptions],
+    reports: &[TestReport],
+) -> EyreExitOr<()> {
+    if options
+        .iter()
+        .any(|options| options.search.is_some() || options.bisect || options.interactive)
+    {
+        writeln!(
+            effects.get_output_stream(),
+            "\
+Only one test command can be run at a time when searching (--search/--bisect)
+or running interactively (--interactive)."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+    if options.iter().any(|options| options.apply_fixes) {
+        writeln!(
+            effects.get_output_stream(),
+            "\
+Only one test command can be run at a time when applying fixes (git test fix).
+Run git test fix separately for each command in the suite."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let mut resolved_options = Vec::new();
+    for options in options {
+        resolved_options.push(try_exit_code!(ResolvedTestOptions::resolve(
+            now,
+            effects,
+            dag,
+            repo,
+            event_tx_id,
+            commit_set,
+            None,
+            options,
+        )?));
+    }
+    let labels = options
+        .iter()
+        .zip(resolved_options.iter())
+        .map(|(options, resolved_options)| match &options.command {
+            Some(command_alias) => command_alias.clone(),
+            None => resolved_options.command.to_string(),
+        })
+        .collect_vec();
+
+    let commits = sorted_commit_set(repo, dag, commit_set)?;
+    let test_results = try_exit_code!(run_test_matrix(
+        now,
+        effects,
+        git_run_info,
+        dag,
+        repo,
+        event_log_db,
+        event_tx_id,
+        revset,
+        &commits,
+        &resolved_options,
+    )?);
+    let summary_result = print_matrix_summary(
+        effects,
+        repo,
+        revset,
+        &labels,
+        &commits,
+        &test_results,
+        &resolved_options[0].verbosity,
+    )?;
+    report::write_reports(
+        &commits,
+        &resolved_options
+            .iter()
+            .map(|options| &options.command)
+            .zip(test_results.iter())
+            .collect_vec(),
+        reports,
+    )?;
+    Ok(summary_result)
+}
+
 #[must_use]
 #[derive(Debug)]
 struct AbortTrap {
@@ -677,7 +1098,9 @@ fn set_abort_trap(
     strategy: TestExecutionStrategy,
 ) -> EyreExitOr<AbortTrap> {
     match strategy {
-        TestExecutionStrategy::Worktree => return Ok(Ok(AbortTrap { is_active: false })),
+        TestExecutionStrategy::Worktree | TestExecutionStrategy::Remote => {
+            return Ok(Ok(AbortTrap { is_active: false }))
+        }
         TestExecutionStrategy::WorkingCopy => {}
     }
 
@@ -783,6 +1206,11 @@ pub struct TestOutput {
 
     /// The resulting status of the test.
     pub test_status: TestStatus,
+
+    /// How long the test command took to run. For cached results, this is how
+    /// long it took when the result was recorded. This is zero if the command
+    /// wasn't run to completion (such as if it couldn't be spawned).
+    pub duration: Duration,
 }
 
 /// The possible results of attempting to run a test.
@@ -817,6 +1245,13 @@ pub enum TestStatus {
         exit_code: i32,
     },
 
+    /// The test command didn't finish within the timeout, so it was killed.
+    TimedOut {
+        /// Whether or not the result was cached (indicating that we didn't
+        /// actually re-run the test).
+        cached: bool,
+    },
+
     /// The test failed and returned the provided (non-zero) exit code.
     Failed {
         /// Whether or not the result was cached (indicating that we didn't
@@ -844,6 +1279,17 @@ pub enum TestStatus {
         /// command via `--interactive`).
         interactive: bool,
     },
+
+    /// The test failed at first, but passed when it was retried (with
+    /// `--retries`).
+    Flaky {
+        /// Whether or not the result was cached (indicating that we didn't
+        /// actually re-run the test).
+        cached: bool,
+
+        /// Information about the working copy state after running the test command.
+        fix_info: FixInfo,
+    },
 }
 
 /// Information about the working copy state after running the test command.
@@ -861,6 +1307,38 @@ pub struct FixInfo {
 }
 
 impl TestStatus {
+    /// The status of the commit for the purposes of searching for the first
+    /// failing commit.
+    #[instrument]
+    fn get_search_status(&self) -> search::Status {
+        match self {
+            TestStatus::CheckoutFailed
+            | TestStatus::SpawnTestFailed(_)
+            | TestStatus::TerminatedBySignal
+            | TestStatus::AlreadyInProgress
+            | TestStatus::ReadCacheFailed(_)
+            | TestStatus::Indeterminate { .. }
+            | TestStatus::Abort { .. } => search::Status::Indeterminate,
+
+            TestStatus::TimedOut { cached: _ }
+            | TestStatus::Failed {
+                cached: _,
+                interactive: _,
+                exit_code: _,
+            } => search::Status::Failure,
+
+            TestStatus::Passed {
+                cached: _,
+                fix_info: _,
+                interactive: _,
+            }
+            | TestStatus::Flaky {
+                cached: _,
+                fix_info: _,
+            } => search::Status::Success,
+        }
+    }
+
     #[instrument]
     fn get_icon(&self) -> &'static str {
         match self {
@@ -870,7 +1348,10 @@ impl TestStatus {
             | TestStatus::ReadCacheFailed(_)
             | TestStatus::TerminatedBySignal
             | TestStatus::Indeterminate { .. } => icons::EXCLAMATION,
-            TestStatus::Failed { .. } | TestStatus::Abort { .. } => icons::CROSS,
+            TestStatus::TimedOut { .. } | TestStatus::Failed { .. } | TestStatus::Abort { .. } => {
+                icons::CROSS
+            }
+            TestStatus::Flaky { .. } => icons::TILDE,
             TestStatus::Passed { .. } => icons::CHECKMARK,
         }
     }
@@ -884,7 +1365,10 @@ impl TestStatus {
             | TestStatus::ReadCacheFailed(_)
             | TestStatus::TerminatedBySignal
             | TestStatus::Indeterminate { .. } => *STYLE_SKIPPED,
-            TestStatus::Failed { .. } | TestStatus::Abort { .. } => *STYLE_FAILURE,
+            TestStatus::TimedOut { .. } | TestStatus::Failed { .. } | TestStatus::Abort { .. } => {
+                *STYLE_FAILURE
+            }
+            TestStatus::Flaky { .. } => *STYLE_FLAKY,
             TestStatus::Passed { .. } => *STYLE_SUCCESS,
         }
     }
@@ -897,6 +1381,21 @@ impl TestStatus {
         commit: &Commit,
         apply_fixes: bool,
     ) -> eyre::Result<StyledString> {
+        let get_fix_descriptor = |snapshot_tree_oid: &Option<NonZeroOid>| -> Option<String> {
+            match (snapshot_tree_oid, commit.get_tree_oid()) {
+                (Some(snapshot_tree_oid), MaybeZeroOid::NonZero(original_tree_oid))
+                    if *snapshot_tree_oid != original_tree_oid =>
+                {
+                    Some(if apply_fixes {
+                        "fixed".to_string()
+                    } else {
+                        "fixable".to_string()
+                    })
+                }
+                (Some(_), _) | (None, _) => None,
+            }
+        };
+
         let description = match self {
             TestStatus::CheckoutFailed => StyledStringBuilder::new()
                 .append_styled("Failed to check out: ", self.get_style())
@@ -942,9 +1441,17 @@ impl TestStatus {
                 .append(commit.friendly_describe(glyphs)?)
                 .build(),
 
-            TestStatus::Failed {
-                cached,
-                interactive,
+            TestStatus::TimedOut { cached } => {
+                let descriptors = if *cached { " (cached)" } else { "" };
+                StyledStringBuilder::new()
+                    .append_styled(format!("Timed out{descriptors}: "), self.get_style())
+                    .append(commit.friendly_describe(glyphs)?)
+                    .build()
+            }
+
+            TestStatus::Failed {
+                cached,
+                interactive,
                 exit_code,
             } => {
                 let mut descriptors = Vec::new();
@@ -975,18 +1482,7 @@ impl TestStatus {
                 if *cached {
                     descriptors.push("cached".to_string());
                 }
-                match (snapshot_tree_oid, commit.get_tree_oid()) {
-                    (Some(snapshot_tree_oid), MaybeZeroOid::NonZero(original_tree_oid)) => {
-                        if *snapshot_tree_oid != original_tree_oid {
-                            descriptors.push(if apply_fixes {
-                                "fixed".to_string()
-                            } else {
-                                "fixable".to_string()
-                            });
-                        }
-                    }
-                    (None, _) | (_, MaybeZeroOid::Zero) => {}
-                }
+                descriptors.extend(get_fix_descriptor(snapshot_tree_oid));
                 if *interactive {
                     descriptors.push("interactive".to_string());
                 }
@@ -1000,13 +1496,34 @@ impl TestStatus {
                     .append(commit.friendly_describe(glyphs)?)
                     .build()
             }
+
+            TestStatus::Flaky {
+                cached,
+                fix_info:
+                    FixInfo {
+                        head_commit_oid: _,
+                        snapshot_tree_oid,
+                    },
+            } => {
+                let mut descriptors = Vec::new();
+                if *cached {
+                    descriptors.push("cached".to_string());
+                }
+                descriptors.push("flaky".to_string());
+                descriptors.extend(get_fix_descriptor(snapshot_tree_oid));
+                let descriptors = descriptors.join(", ");
+                StyledStringBuilder::new()
+                    .append_styled(format!("Passed ({descriptors}): "), self.get_style())
+                    .append(commit.friendly_describe(glyphs)?)
+                    .build()
+            }
         };
         Ok(description)
     }
 }
 
 /// An error produced when testing is aborted due to a certain commit.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct TestingAbortedError {
     /// The commit which aborted testing.
     pub commit_oid: NonZeroOid,
@@ -1083,7 +1600,9 @@ impl TestOutput {
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
             | TestStatus::Indeterminate { .. }
-            | TestStatus::Abort { .. } => false,
+            | TestStatus::Abort { .. }
+            | TestStatus::TimedOut { .. }
+            | TestStatus::Flaky { .. } => false,
             TestStatus::Failed { interactive, .. } | TestStatus::Passed { interactive, .. } => {
                 interactive
             }
@@ -1146,6 +1665,12 @@ fn shell_escape(s: impl AsRef<str>) -> String {
 struct TestJob {
     commit_oid: NonZeroOid,
     operation_type: OperationType,
+
+    /// The number of times that the commit has already been tested during
+    /// this run. The work queue never accepts the same job twice, so this
+    /// distinguishes re-tests (as in a probabilistic search) from the original
+    /// test.
+    attempt: usize,
 }
 
 #[derive(Debug, Error)]
@@ -1191,7 +1716,12 @@ pub struct TestResults {
     /// commit set.
     pub search_bounds: search::Bounds<NonZeroOid>,
 
-    /// The test output for each commit.
+    /// If a probabilistic search strategy was provided, the probability that
+    /// `search_bounds` is correct.
+    pub search_confidence: Option<f64>,
+
+    /// The test output for each commit. If a commit was tested more than once,
+    /// then this is the output of the last test.
     pub test_outputs: IndexMap<NonZeroOid, TestOutput>,
 
     /// If testing was aborted, the corresponding error.
@@ -1212,6 +1742,44 @@ pub fn run_tests<'a>(
     commits: &[Commit],
     options: &ResolvedTestOptions,
 ) -> EyreExitOr<TestResults> {
+    let mut test_results = try_exit_code!(run_test_matrix(
+        now,
+        effects,
+        git_run_info,
+        dag,
+        repo,
+        event_log_db,
+        event_tx_id,
+        revset,
+        commits,
+        std::slice::from_ref(options),
+    )?);
+    Ok(Ok(test_results.pop().unwrap()))
+}
+
+/// Run each of the test commands in `options` on the provided set of commits.
+/// All of the commands for a given commit are run in the same worker, one
+/// after the other, so that they can share the same checkout. Returns the
+/// results for each of the commands, in the same order as `options`.
+///
+/// All of the `options` must be the same, except for their commands.
+#[instrument]
+fn run_test_matrix(
+    now: SystemTime,
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    event_tx_id: EventTransactionId,
+    revset: &Revset,
+    commits: &[Commit],
+    options: &[ResolvedTestOptions],
+) -> EyreExitOr<Vec<TestResults>> {
+    let primary_options = match options.first() {
+        Some(primary_options) => primary_options,
+        None => return Ok(Ok(Vec::new())),
+    };
     let abort_trap = match set_abort_trap(
         now,
         effects,
@@ -1219,13 +1787,13 @@ pub fn run_tests<'a>(
         repo,
         event_log_db,
         event_tx_id,
-        options.execution_strategy,
+        primary_options.execution_strategy,
     )? {
         Ok(abort_trap) => abort_trap,
         Err(exit_code) => return Ok(Err(exit_code)),
     };
     let test_results: Result<_, _> = {
-        let effects = if options.is_interactive {
+        let effects = if primary_options.is_interactive {
             effects.suppress()
         } else {
             effects.clone()
@@ -1249,6 +1817,12 @@ pub fn run_tests<'a>(
         event_tx_id,
         abort_trap
     )?);
+    if interrupt::is_interrupted() {
+        // Exit with the conventional status for `SIGINT`, now that the test
+        // commands have been interrupted and the repository has been cleaned
+        // up.
+        return Ok(Err(ExitCode(130)));
+    }
     test_results
 }
 
@@ -1262,19 +1836,24 @@ fn run_tests_inner<'a>(
     event_tx_id: EventTransactionId,
     revset: &Revset,
     commits: &[Commit],
-    options: &ResolvedTestOptions,
-) -> EyreExitOr<TestResults> {
+    options: &[ResolvedTestOptions],
+) -> EyreExitOr<Vec<TestResults>> {
     let ResolvedTestOptions {
         command,
         execution_strategy,
         search_strategy,
-        use_cache: _,      // Used only in `make_test_files`.
-        is_dry_run: _,     // Used only in `apply_fixes`.
-        is_interactive: _, // Used in `test_commit`.
+        probabilistic_search_options,
+        use_cache: _,            // Used only in `make_test_files`.
+        is_dry_run: _,           // Used only in `apply_fixes`.
+        should_confirm_fixes: _, // Used only in `apply_fixes`.
+        is_interactive: _,       // Used in `test_commit`.
         num_jobs,
-        verbosity: _,   // Verbosity used by caller to print results.
+        timeout: _,        // Used in `test_commit`.
+        num_retries: _,    // Used in `test_commit`.
+        verbosity: _,      // Verbosity used by caller to print results.
         fix_options: _, // Whether to apply fixes is checked by `test_commit`, after the working directory is set up.
-    } = &options;
+        remote_command: _, // Used in `test_commit_remote`.
+    } = &options[0];
 
     let shell_path = match get_sh() {
         Some(shell_path) => shell_path,
@@ -1320,10 +1899,21 @@ fn run_tests_inner<'a>(
     }
     let search_strategy = match search_strategy {
         None => None,
-        Some(TestSearchStrategy::Linear) => Some(search::Strategy::Linear),
-        Some(TestSearchStrategy::Reverse) => Some(search::Strategy::LinearReverse),
-        Some(TestSearchStrategy::Binary) => Some(search::Strategy::Binary),
+        Some(TestSearchStrategy::Linear) => Some(Ok(search::Strategy::Linear)),
+        Some(TestSearchStrategy::Reverse) => Some(Ok(search::Strategy::LinearReverse)),
+        Some(TestSearchStrategy::Binary) => Some(Ok(search::Strategy::Binary)),
+        Some(TestSearchStrategy::Probabilistic) => match probabilistic_search_options {
+            Some(probabilistic_search_options) => Some(Err(*probabilistic_search_options)),
+            None => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "BUG: a probabilistic search was requested, but no `ProbabilisticOptions` were provided."
+                )?;
+                return Ok(Err(ExitCode(1)));
+            }
+        },
     };
+    let is_search = search_strategy.is_some();
 
     let latest_test_command_path = get_latest_test_command_path(repo);
     if let Some(parent) = latest_test_command_path.parent() {
@@ -1348,8 +1938,12 @@ fn run_tests_inner<'a>(
         test_outputs: test_outputs_unordered,
         testing_aborted_error,
     } = {
-        let (effects, progress) =
-            effects.start_operation(OperationType::RunTests(Arc::new(command.to_string())));
+        let (effects, progress) = effects.start_operation(OperationType::RunTests(Arc::new(
+            options
+                .iter()
+                .map(|options| options.command.to_string())
+                .join(", "),
+        )));
         progress.notify_progress(0, commits.len());
         let commit_jobs = {
             let mut results = IndexMap::new();
@@ -1371,6 +1965,7 @@ fn run_tests_inner<'a>(
                     TestJob {
                         commit_oid: commit.get_oid(),
                         operation_type,
+                        attempt: 0,
                     },
                 );
             }
@@ -1381,7 +1976,16 @@ fn run_tests_inner<'a>(
             dag,
             commit_set: commits.iter().map(|c| c.get_oid()).collect(),
         };
-        let search = search::Search::new(graph, commits.iter().map(|c| c.get_oid()));
+        let commit_oids = commits.iter().map(|c| c.get_oid());
+        let search = search_strategy.map(|search_strategy| match search_strategy {
+            Ok(strategy) => TestSearch::Deterministic {
+                search: search::Search::new(graph, commit_oids),
+                strategy,
+            },
+            Err(options) => {
+                TestSearch::Probabilistic(ProbabilisticSearch::new(graph, commit_oids, options))
+            }
+        });
 
         let work_queue = WorkQueue::new();
         let repo_dir = repo.get_path();
@@ -1399,23 +2003,29 @@ fn run_tests_inner<'a>(
                         let repo = Repo::from_dir(repo_dir)?;
                         Ok(repo)
                     };
-                    let f = move |job: TestJob, repo: &Repo| -> eyre::Result<TestOutput> {
+                    let f = move |job: TestJob, repo: &Repo| -> eyre::Result<Vec<TestOutput>> {
                         let TestJob {
                             commit_oid,
                             operation_type,
+                            attempt: _,
                         } = job;
                         let commit = repo.find_commit_or_fail(commit_oid)?;
-                        run_test(
-                            effects,
-                            operation_type,
-                            git_run_info,
-                            shell_path,
-                            repo,
-                            event_tx_id,
-                            options,
-                            worker_id,
-                            &commit,
-                        )
+                        options
+                            .iter()
+                            .map(|options| {
+                                run_test(
+                                    effects,
+                                    operation_type.clone(),
+                                    git_run_info,
+                                    shell_path,
+                                    repo,
+                                    event_tx_id,
+                                    options,
+                                    worker_id,
+                                    &commit,
+                                )
+                            })
+                            .collect()
                     };
                     result.insert(
                         worker_id,
@@ -1437,7 +2047,6 @@ fn run_tests_inner<'a>(
             let test_results = event_loop(
                 commit_jobs,
                 search,
-                search_strategy,
                 *num_jobs,
                 work_queue.clone(),
                 result_rx,
@@ -1445,7 +2054,7 @@ fn run_tests_inner<'a>(
             work_queue.close();
             let test_results = test_results?;
 
-            if test_results.testing_aborted_error.is_none() && search_strategy.is_none() {
+            if test_results.testing_aborted_error.is_none() && !is_search {
                 debug!("Waiting for workers");
                 progress.notify_status(OperationIcon::InProgress, "Waiting for workers");
                 for (worker_id, worker) in workers {
@@ -1465,16 +2074,19 @@ fn run_tests_inner<'a>(
 
     // The results may be returned in an arbitrary order if they were produced
     // in parallel, so recover the input order to produce deterministic output.
-    let test_outputs_ordered: IndexMap<NonZeroOid, TestOutput> = {
+    let mut test_outputs_ordered: Vec<IndexMap<NonZeroOid, TestOutput>> =
+        options.iter().map(|_| IndexMap::new()).collect();
+    {
         let mut test_outputs_unordered = test_outputs_unordered;
-        let mut test_outputs_ordered = IndexMap::new();
         for commit_oid in commits.iter().map(|commit| commit.get_oid()) {
             match test_outputs_unordered.remove(&commit_oid) {
-                Some(result) => {
-                    test_outputs_ordered.insert(commit_oid, result);
+                Some(results) => {
+                    for (test_outputs, result) in test_outputs_ordered.iter_mut().zip(results) {
+                        test_outputs.insert(commit_oid, result);
+                    }
                 }
                 None => {
-                    if search_strategy.is_none() && testing_aborted_error.is_none() {
+                    if !is_search && testing_aborted_error.is_none() {
                         warn!(?commit_oid, "No result was returned for commit");
                     }
                 }
@@ -1487,42 +2099,110 @@ fn run_tests_inner<'a>(
                 "There were extra results for commits not appearing in the input list"
             );
         }
-        test_outputs_ordered
+    }
+
+    let (search_bounds, search_confidence) = match search {
+        None => Default::default(),
+        Some(search) => search.solution()?,
     };
+    Ok(Ok(test_outputs_ordered
+        .into_iter()
+        .map(|test_outputs| TestResults {
+            search_bounds: search_bounds.clone(),
+            search_confidence,
+            test_outputs,
+            testing_aborted_error: testing_aborted_error.clone(),
+        })
+        .collect()))
+}
 
-    Ok(Ok(TestResults {
-        search_bounds: match search_strategy {
-            None => Default::default(),
-            Some(search_strategy) => search.search(search_strategy)?.bounds,
-        },
-        test_outputs: test_outputs_ordered,
-        testing_aborted_error,
-    }))
+/// The search being conducted over the commits to test.
+#[derive(Debug)]
+enum TestSearch<'a> {
+    /// Each commit is tested at most once, and its result is assumed to be
+    /// correct.
+    Deterministic {
+        search: search::Search<SearchGraph<'a>>,
+        strategy: search::Strategy,
+    },
+
+    /// Commits may be tested more than once, and their results may be noisy.
+    Probabilistic(ProbabilisticSearch<SearchGraph<'a>>),
+}
+
+impl TestSearch<'_> {
+    /// Get up to `limit` commits to test next, given the predicate
+    /// `is_complete` to determine which commits have already been tested.
+    fn next_to_search(
+        &self,
+        limit: usize,
+        is_complete: impl Fn(&NonZeroOid) -> bool,
+    ) -> eyre::Result<Vec<NonZeroOid>> {
+        let next_to_search = match self {
+            TestSearch::Deterministic { search, strategy } => search
+                .search(*strategy)?
+                .next_to_search
+                .filter(|commit_oid| !is_complete(commit_oid))
+                .take(limit)
+                .collect(),
+            TestSearch::Probabilistic(search) => {
+                let mut next_to_search = search.search()?.next_to_search;
+                next_to_search.truncate(limit);
+                next_to_search
+            }
+        };
+        Ok(next_to_search)
+    }
+
+    fn notify(&mut self, commit_oid: NonZeroOid, status: search::Status) -> eyre::Result<()> {
+        match self {
+            TestSearch::Deterministic {
+                search,
+                strategy: _,
+            } => search.notify(commit_oid, status)?,
+            TestSearch::Probabilistic(search) => search.notify(commit_oid, status)?,
+        }
+        Ok(())
+    }
+
+    /// Get the bounds of the search so far, along with the confidence in them,
+    /// if the search is probabilistic.
+    fn solution(&self) -> eyre::Result<(search::Bounds<NonZeroOid>, Option<f64>)> {
+        match self {
+            TestSearch::Deterministic { search, strategy } => {
+                Ok((search.search(*strategy)?.bounds, None))
+            }
+            TestSearch::Probabilistic(search) => {
+                let solution = search.search()?;
+                Ok((solution.bounds, Some(solution.confidence)))
+            }
+        }
+    }
 }
 
 struct EventLoopOutput<'a> {
-    search: search::Search<SearchGraph<'a>>,
-    test_outputs: HashMap<NonZeroOid, TestOutput>,
+    search: Option<TestSearch<'a>>,
+    test_outputs: HashMap<NonZeroOid, Vec<TestOutput>>,
     testing_aborted_error: Option<TestingAbortedError>,
 }
 
 fn event_loop(
     commit_jobs: IndexMap<NonZeroOid, TestJob>,
-    mut search: search::Search<SearchGraph>,
-    search_strategy: Option<search::Strategy>,
+    mut search: Option<TestSearch>,
     num_jobs: usize,
     work_queue: WorkQueue<TestJob>,
-    result_rx: Receiver<JobResult<TestJob, TestOutput>>,
+    result_rx: Receiver<JobResult<TestJob, Vec<TestOutput>>>,
 ) -> eyre::Result<EventLoopOutput> {
     #[derive(Debug)]
     enum ScheduledJob {
         Scheduled(TestJob),
-        Complete(TestOutput),
+        Complete(Vec<TestOutput>),
     }
     let mut scheduled_jobs: HashMap<NonZeroOid, ScheduledJob> = Default::default();
+    let mut num_attempts: HashMap<NonZeroOid, usize> = Default::default();
     let mut testing_aborted_error = None;
 
-    if search_strategy.is_none() {
+    if search.is_none() {
         let jobs_to_schedule = commit_jobs
             .keys()
             .map(|commit_oid| commit_jobs[commit_oid].clone())
@@ -1542,8 +2222,12 @@ fn event_loop(
             debug!(?err, "Testing aborted");
             break;
         }
+        if interrupt::is_interrupted() {
+            debug!("Testing interrupted");
+            break;
+        }
 
-        if let Some(search_strategy) = search_strategy {
+        if let Some(search) = &search {
             scheduled_jobs = scheduled_jobs
                 .into_iter()
                 .filter_map(|(commit_oid, scheduled_job)| match scheduled_job {
@@ -1552,37 +2236,36 @@ fn event_loop(
                 })
                 .collect();
 
-            let solution = search.search(search_strategy)?;
-            let next_to_search = solution
-                .next_to_search
-                .filter(|commit_oid| {
-                    // At this point, `scheduled_jobs` should only contain completed jobs.
-                    match scheduled_jobs.get(commit_oid) {
-                        Some(ScheduledJob::Complete(_)) => false,
-                        Some(ScheduledJob::Scheduled(_)) => {
-                            warn!(?commit_oid, "Left-over scheduled job; this should have already been filtered out.");
-                            true
-                        }
-                        None => true
+            let next_to_search = search.next_to_search(num_jobs, |commit_oid| {
+                // At this point, `scheduled_jobs` should only contain completed jobs.
+                match scheduled_jobs.get(commit_oid) {
+                    Some(ScheduledJob::Complete(_)) => true,
+                    Some(ScheduledJob::Scheduled(_)) => {
+                        warn!(
+                            ?commit_oid,
+                            "Left-over scheduled job; this should have already been filtered out."
+                        );
+                        false
                     }
-                })
-                .take(num_jobs)
-                .collect_vec();
+                    None => false,
+                }
+            })?;
             if next_to_search.is_empty() {
                 debug!("Search completed, exiting.");
                 break;
             }
             let jobs_to_schedule = next_to_search
                 .into_iter()
-                .map(|commit_oid| commit_jobs[&commit_oid].clone())
+                .map(|commit_oid| TestJob {
+                    attempt: num_attempts.get(&commit_oid).copied().unwrap_or_default(),
+                    ..commit_jobs[&commit_oid].clone()
+                })
                 .collect_vec();
-            debug!(
-                ?search_strategy,
-                ?jobs_to_schedule,
-                "Jobs to schedule for search"
-            );
+            debug!(?jobs_to_schedule, "Jobs to schedule for search");
             for job in &jobs_to_schedule {
-                if let Some(previous_job) =
+                // A probabilistic search may re-test a commit which has already
+                // completed, in which case its previous result is replaced.
+                if let Some(previous_job @ ScheduledJob::Scheduled(_)) =
                     scheduled_jobs.insert(job.commit_oid, ScheduledJob::Scheduled(job.clone()))
                 {
                     warn!(?job, ?previous_job, "Overwriting previously-scheduled job");
@@ -1614,7 +2297,7 @@ fn event_loop(
             debug!(?result, "Event loop got new job result");
             result
         };
-        let (job, test_output) = match message {
+        let (job, test_outputs) = match message {
             Err(RecvError) => {
                 debug!("No more job results could be received because result_rx closed");
                 break;
@@ -1624,50 +2307,67 @@ fn event_loop(
                 let TestJob {
                     commit_oid,
                     operation_type: _,
+                    attempt,
                 } = job;
-                eyre::bail!("Worker {worker_id} failed when processing commit {commit_oid}: {error_message}");
+                eyre::bail!("Worker {worker_id} failed when processing commit {commit_oid} (attempt {attempt}): {error_message}");
             }
 
-            Ok(JobResult::Done(job, test_output)) => (job, test_output),
+            Ok(JobResult::Done(job, test_outputs)) => (job, test_outputs),
         };
 
         let TestJob {
             commit_oid,
             operation_type: _,
+            attempt,
         } = job;
-        let (maybe_testing_aborted_error, search_status) = match &test_output.test_status {
-            TestStatus::CheckoutFailed
-            | TestStatus::SpawnTestFailed(_)
-            | TestStatus::TerminatedBySignal
-            | TestStatus::AlreadyInProgress
-            | TestStatus::ReadCacheFailed(_)
-            | TestStatus::Indeterminate { .. } => (None, search::Status::Indeterminate),
-
-            TestStatus::Abort { exit_code } => (
-                Some(TestingAbortedError {
-                    commit_oid,
-                    exit_code: *exit_code,
-                }),
-                search::Status::Indeterminate,
-            ),
-
-            TestStatus::Failed {
-                cached: _,
-                interactive: _,
-                exit_code: _,
-            } => (None, search::Status::Failure),
-
-            TestStatus::Passed {
-                cached: _,
-                fix_info: _,
-                interactive: _,
-            } => (None, search::Status::Success),
-        };
-        if search_strategy.is_some() {
+        debug!(?commit_oid, ?attempt, "Processing job result");
+        // When running several commands, a commit is only considered to have
+        // passed if all of the commands passed.
+        let (maybe_testing_aborted_error, search_status) = test_outputs
+            .iter()
+            .map(|test_output| {
+                let testing_aborted_error = match &test_output.test_status {
+                    TestStatus::Abort { exit_code } => Some(TestingAbortedError {
+                        commit_oid,
+                        exit_code: *exit_code,
+                    }),
+                    TestStatus::CheckoutFailed
+                    | TestStatus::SpawnTestFailed(_)
+                    | TestStatus::TerminatedBySignal
+                    | TestStatus::AlreadyInProgress
+                    | TestStatus::ReadCacheFailed(_)
+                    | TestStatus::Indeterminate { .. }
+                    | TestStatus::TimedOut { .. }
+                    | TestStatus::Failed { .. }
+                    | TestStatus::Passed { .. }
+                    | TestStatus::Flaky { .. } => None,
+                };
+                (
+                    testing_aborted_error,
+                    test_output.test_status.get_search_status(),
+                )
+            })
+            .fold(
+                (None, search::Status::Success),
+                |(acc_error, acc_status), (error, status)| {
+                    let status = match (acc_status, status) {
+                        (search::Status::Failure, _) | (_, search::Status::Failure) => {
+                            search::Status::Failure
+                        }
+                        (search::Status::Indeterminate, _) | (_, search::Status::Indeterminate) => {
+                            search::Status::Indeterminate
+                        }
+                        (status, _) => status,
+                    };
+                    (acc_error.or(error), status)
+                },
+            );
+        *num_attempts.entry(commit_oid).or_default() += 1;
+        if let Some(search) = &mut search {
             search.notify(commit_oid, search_status)?;
         }
         if scheduled_jobs
-            .insert(commit_oid, ScheduledJob::Complete(test_output))
+            .insert(commit_oid, ScheduledJob::Complete(test_outputs))
             .is_none()
         {
             warn!(
@@ -1685,7 +2385,7 @@ fn event_loop(
         .into_iter()
         .filter_map(|(commit_oid, scheduled_job)| match scheduled_job {
             ScheduledJob::Scheduled(_) => None,
-            ScheduledJob::Complete(test_output) => Some((commit_oid, test_output)),
+            ScheduledJob::Complete(test_outputs) => Some((commit_oid, test_outputs)),
         })
         .collect();
     Ok(EventLoopOutput {
@@ -1734,7 +2434,8 @@ fn print_summary(
             TestStatus::Abort { .. } => {
                 num_failed += 1;
             }
-            TestStatus::Failed {
+            TestStatus::TimedOut { cached }
+            | TestStatus::Failed {
                 cached,
                 exit_code: _,
                 interactive: _,
@@ -1748,6 +2449,10 @@ fn print_summary(
                 cached,
                 fix_info: _,
                 interactive: _,
+            }
+            | TestStatus::Flaky {
+                cached,
+                fix_info: _,
             } => {
                 num_passed += 1;
                 if cached {
@@ -1849,6 +2554,14 @@ fn print_summary(
                 )?;
             }
         }
+
+        if let Some(search_confidence) = test_results.search_confidence {
+            writeln!(
+                effects.get_output_stream(),
+                "Confidence: {:.1}%",
+                search_confidence * 100.0
+            )?;
+        }
     }
 
     if num_cached_results > 0 && get_hint_enabled(repo, Hint::CleanCachedTestResults)? {
@@ -1897,99 +2610,313 @@ fn print_summary(
     }
 }
 
-#[instrument(skip(permissions))]
-fn apply_fixes(
+/// Print the results of running several commands as a table, with one column
+/// per command and one row per commit.
+#[instrument]
+fn print_matrix_summary(
     effects: &Effects,
-    git_run_info: &GitRunInfo,
-    dag: &mut Dag,
     repo: &Repo,
-    event_log_db: &EventLogDb,
-    execute_options: &ExecuteRebasePlanOptions,
-    permissions: RebasePlanPermissions,
-    dry_run: bool,
-    command: &TestCommand,
-    test_results: &TestResults,
+    revset: &Revset,
+    labels: &[String],
+    commits: &[Commit],
+    test_results: &[TestResults],
+    verbosity: &Verbosity,
 ) -> EyreExitOr<()> {
-    let fixed_tree_oids: Vec<(NonZeroOid, NonZeroOid)> = test_results
-        .test_outputs
-        .iter()
-        .filter_map(|(commit_oid, test_output)| match test_output.test_status {
-            TestStatus::Passed {
-                cached: _,
-                fix_info:
-                    FixInfo {
-                        head_commit_oid: _,
-                        snapshot_tree_oid: Some(snapshot_tree_oid),
-                    },
-                interactive: _,
-            } => Some((*commit_oid, snapshot_tree_oid)),
-
-            TestStatus::Passed {
-                cached: _,
-                fix_info:
-                    FixInfo {
-                        head_commit_oid: _,
-                        snapshot_tree_oid: None,
-                    },
-                interactive: _,
-            }
-            | TestStatus::CheckoutFailed
-            | TestStatus::SpawnTestFailed(_)
-            | TestStatus::TerminatedBySignal
-            | TestStatus::AlreadyInProgress
-            | TestStatus::ReadCacheFailed(_)
-            | TestStatus::Indeterminate { .. }
-            | TestStatus::Failed { .. }
-            | TestStatus::Abort { .. } => None,
-        })
-        .collect();
-
-    #[derive(Debug)]
-    struct Fix {
-        original_commit_oid: NonZeroOid,
-        original_commit_parent_oids: Vec<NonZeroOid>,
-        fixed_commit_oid: NonZeroOid,
-    }
-    let fixes: Vec<Fix> = {
-        let mut fixes = Vec::new();
-        for (original_commit_oid, fixed_tree_oid) in fixed_tree_oids {
-            let original_commit = repo.find_commit_or_fail(original_commit_oid)?;
-            let original_tree_oid = original_commit.get_tree_oid();
-            let commit_message = original_commit.get_message_raw();
-            let commit_message = commit_message.to_str().with_context(|| {
-                eyre::eyre!(
-                    "Could not decode commit message for commit: {:?}",
-                    original_commit_oid
-                )
-            })?;
-            let parents: Vec<Commit> = original_commit
-                .get_parent_oids()
-                .into_iter()
-                .map(|parent_oid| repo.find_commit_or_fail(parent_oid))
-                .try_collect()?;
-            let fixed_tree = repo.find_tree_or_fail(fixed_tree_oid)?;
-            let fixed_commit_oid = repo.create_commit(
-                None,
-                &original_commit.get_author(),
-                &original_commit.get_committer(),
-                commit_message,
-                &fixed_tree,
-                parents.iter().collect(),
+    if *verbosity != Verbosity::None {
+        for (label, test_results) in labels.iter().zip(test_results) {
+            writeln!(
+                effects.get_output_stream(),
+                "{}:",
+                effects.get_glyphs().render(
+                    StyledStringBuilder::new()
+                        .append_styled(label.as_str(), Effect::Bold)
+                        .build()
+                )?
             )?;
-            if original_commit_oid == fixed_commit_oid {
-                continue;
+            for (commit_oid, test_output) in &test_results.test_outputs {
+                let commit = repo.find_commit_or_fail(*commit_oid)?;
+                write!(
+                    effects.get_output_stream(),
+                    "{}",
+                    effects
+                        .get_glyphs()
+                        .render(test_output.describe(effects, &commit, false, *verbosity,)?)?
+                )?;
             }
+        }
+    }
 
-            let fix = Fix {
-                original_commit_oid,
-                original_commit_parent_oids: original_commit.get_parent_oids(),
-                fixed_commit_oid,
-            };
-            debug!(
-                ?fix,
-                ?original_tree_oid,
-                ?fixed_tree_oid,
-                "Generated fix to apply"
+    writeln!(
+        effects.get_output_stream(),
+        "{}",
+        effects.get_glyphs().render(
+            StyledStringBuilder::new()
+                .append_styled(labels.join("  "), Effect::Bold)
+                .build()
+        )?
+    )?;
+    for commit in commits {
+        let mut row = StyledStringBuilder::new();
+        for (label, test_results) in labels.iter().zip(test_results) {
+            let width = label.chars().count();
+            match test_results.test_outputs.get(&commit.get_oid()) {
+                Some(test_output) => {
+                    row = row.append_styled(
+                        test_output.test_status.get_icon(),
+                        test_output.test_status.get_style(),
+                    );
+                }
+                None => {
+                    row = row.append_plain("-");
+                }
+            }
+            row = row.append_plain(" ".repeat(width.saturating_sub(1) + 2));
+        }
+        let row = row
+            .append(commit.friendly_describe(effects.get_glyphs())?)
+            .build();
+        writeln!(
+            effects.get_output_stream(),
+            "{}",
+            effects.get_glyphs().render(row)?
+        )?;
+    }
+
+    writeln!(
+        effects.get_output_stream(),
+        "Ran {} on {}",
+        Pluralize {
+            determiner: None,
+            amount: labels.len(),
+            unit: ("command", "commands")
+        },
+        Pluralize {
+            determiner: None,
+            amount: commits.len(),
+            unit: ("commit", "commits")
+        },
+    )?;
+
+    let mut any_failed = false;
+    let mut num_cached_results = 0;
+    for (label, test_results) in labels.iter().zip(test_results) {
+        let mut num_passed = 0;
+        let mut num_failed = 0;
+        let mut num_skipped = 0;
+        for test_output in test_results.test_outputs.values() {
+            match test_output.test_status {
+                TestStatus::CheckoutFailed
+                | TestStatus::SpawnTestFailed(_)
+                | TestStatus::AlreadyInProgress
+                | TestStatus::ReadCacheFailed(_)
+                | TestStatus::TerminatedBySignal
+                | TestStatus::Indeterminate { .. } => num_skipped += 1,
+                TestStatus::Abort { .. } => num_failed += 1,
+                TestStatus::TimedOut { cached } | TestStatus::Failed { cached, .. } => {
+                    num_failed += 1;
+                    if cached {
+                        num_cached_results += 1;
+                    }
+                }
+                TestStatus::Passed { cached, .. } | TestStatus::Flaky { cached, .. } => {
+                    num_passed += 1;
+                    if cached {
+                        num_cached_results += 1;
+                    }
+                }
+            }
+        }
+        any_failed = any_failed || num_failed > 0;
+
+        let summary = StyledStringBuilder::new()
+            .append_styled(label.as_str(), Effect::Bold)
+            .append_plain(": ")
+            .append_styled(format!("{num_passed} passed"), *STYLE_SUCCESS)
+            .append_plain(", ")
+            .append_styled(format!("{num_failed} failed"), *STYLE_FAILURE)
+            .append_plain(", ")
+            .append_styled(format!("{num_skipped} skipped"), *STYLE_SKIPPED)
+            .build();
+        writeln!(
+            effects.get_output_stream(),
+            "{}",
+            effects.get_glyphs().render(summary)?
+        )?;
+    }
+
+    if num_cached_results > 0 && get_hint_enabled(repo, Hint::CleanCachedTestResults)? {
+        writeln!(
+            effects.get_output_stream(),
+            "{}: there {}",
+            effects.get_glyphs().render(get_hint_string())?,
+            Pluralize {
+                determiner: Some(("was", "were")),
+                amount: num_cached_results,
+                unit: ("cached test result", "cached test results")
+            }
+        )?;
+        writeln!(
+            effects.get_output_stream(),
+            "{}: to clear these cached results, run: git test clean {}",
+            effects.get_glyphs().render(get_hint_string())?,
+            shell_escape(revset.to_string()),
+        )?;
+        print_hint_suppression_notice(effects, Hint::CleanCachedTestResults)?;
+    }
+
+    if let Some(TestingAbortedError {
+        commit_oid,
+        exit_code,
+    }) = test_results
+        .iter()
+        .find_map(|test_results| test_results.testing_aborted_error.as_ref())
+    {
+        let commit = repo.find_commit_or_fail(*commit_oid)?;
+        writeln!(
+            effects.get_output_stream(),
+            "Aborted running commands with exit code {} at commit: {}",
+            exit_code,
+            effects
+                .get_glyphs()
+                .render(commit.friendly_describe(effects.get_glyphs())?)?
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    if any_failed {
+        Ok(Err(ExitCode(1)))
+    } else {
+        Ok(Ok(()))
+    }
+}
+
+#[instrument(skip(permissions))]
+fn apply_fixes(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    dag: &mut Dag,
+    repo: &Repo,
+    event_log_db: &EventLogDb,
+    execute_options: &ExecuteRebasePlanOptions,
+    permissions: RebasePlanPermissions,
+    dry_run: bool,
+    confirm: bool,
+    command: &TestCommand,
+    test_results: &TestResults,
+) -> EyreExitOr<()> {
+    let fixed_tree_oids: Vec<(NonZeroOid, NonZeroOid)> = test_results
+        .test_outputs
+        .iter()
+        .filter_map(|(commit_oid, test_output)| match test_output.test_status {
+            TestStatus::Passed {
+                cached: _,
+                fix_info:
+                    FixInfo {
+                        head_commit_oid: _,
+                        snapshot_tree_oid: Some(snapshot_tree_oid),
+                    },
+                interactive: _,
+            }
+            | TestStatus::Flaky {
+                cached: _,
+                fix_info:
+                    FixInfo {
+                        head_commit_oid: _,
+                        snapshot_tree_oid: Some(snapshot_tree_oid),
+                    },
+            } => Some((*commit_oid, snapshot_tree_oid)),
+
+            TestStatus::Passed {
+                cached: _,
+                fix_info:
+                    FixInfo {
+                        head_commit_oid: _,
+                        snapshot_tree_oid: None,
+                    },
+                interactive: _,
+            }
+            | TestStatus::Flaky {
+                cached: _,
+                fix_info:
+                    FixInfo {
+                        head_commit_oid: _,
+                        snapshot_tree_oid: None,
+                    },
+            }
+            | TestStatus::CheckoutFailed
+            | TestStatus::SpawnTestFailed(_)
+            | TestStatus::TerminatedBySignal
+            | TestStatus::AlreadyInProgress
+            | TestStatus::ReadCacheFailed(_)
+            | TestStatus::Indeterminate { .. }
+            | TestStatus::TimedOut { .. }
+            | TestStatus::Failed { .. }
+            | TestStatus::Abort { .. } => None,
+        })
+        .collect();
+
+    #[derive(Debug)]
+    struct Fix {
+        original_commit_oid: NonZeroOid,
+        original_commit_parent_oids: Vec<NonZeroOid>,
+        fixed_commit_oid: NonZeroOid,
+    }
+    let fixes: Vec<Fix> = {
+        let mut fixes = Vec::new();
+        for (original_commit_oid, fixed_tree_oid) in fixed_tree_oids {
+            let original_commit = repo.find_commit_or_fail(original_commit_oid)?;
+            let original_tree_oid = original_commit.get_tree_oid();
+            let fixed_tree_oid =
+                if confirm && original_tree_oid != MaybeZeroOid::NonZero(fixed_tree_oid) {
+                    match try_exit_code!(prompt_fix(
+                        effects,
+                        git_run_info,
+                        repo,
+                        &original_commit,
+                        fixed_tree_oid
+                    )?) {
+                        FixDecision::Apply(fixed_tree_oid) => fixed_tree_oid,
+                        FixDecision::Skip => continue,
+                        FixDecision::Quit => break,
+                    }
+                } else {
+                    fixed_tree_oid
+                };
+            let commit_message = original_commit.get_message_raw();
+            let commit_message = commit_message.to_str().with_context(|| {
+                eyre::eyre!(
+                    "Could not decode commit message for commit: {:?}",
+                    original_commit_oid
+                )
+            })?;
+            let parents: Vec<Commit> = original_commit
+                .get_parent_oids()
+                .into_iter()
+                .map(|parent_oid| repo.find_commit_or_fail(parent_oid))
+                .try_collect()?;
+            let fixed_tree = repo.find_tree_or_fail(fixed_tree_oid)?;
+            let fixed_commit_oid = repo.create_commit(
+                None,
+                &original_commit.get_author(),
+                &original_commit.get_committer(),
+                commit_message,
+                &fixed_tree,
+                parents.iter().collect(),
+            )?;
+            if original_commit_oid == fixed_commit_oid {
+                continue;
+            }
+
+            let fix = Fix {
+                original_commit_oid,
+                original_commit_parent_oids: original_commit.get_parent_oids(),
+                fixed_commit_oid,
+            };
+            debug!(
+                ?fix,
+                ?original_tree_oid,
+                ?fixed_tree_oid,
+                "Generated fix to apply"
             );
             fixes.push(fix);
         }
@@ -2167,6 +3094,107 @@ fn apply_fixes(
     Ok(Ok(()))
 }
 
+/// What to do with a fix to a commit, as chosen by the user.
+#[derive(Debug)]
+enum FixDecision {
+    /// Rewrite the commit to have the given tree, which may only contain some
+    /// of the changes from the fix.
+    Apply(NonZeroOid),
+
+    /// Leave the commit as-is.
+    Skip,
+
+    /// Leave this commit and all of the remaining commits as-is.
+    Quit,
+}
+
+/// Show the changes which the fix would make to `original_commit`, and ask the
+/// user whether to apply them, skip them, or select which of them to apply.
+#[instrument]
+fn prompt_fix(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    original_commit: &Commit,
+    fixed_tree_oid: NonZeroOid,
+) -> EyreExitOr<FixDecision> {
+    let original_tree = original_commit.get_tree()?;
+    let fixed_tree = repo.find_tree_or_fail(fixed_tree_oid)?;
+
+    writeln!(
+        effects.get_output_stream(),
+        "Fix for {}:",
+        effects
+            .get_glyphs()
+            .render(original_commit.friendly_describe(effects.get_glyphs())?)?
+    )?;
+    let color_arg = if effects.get_glyphs().should_write_ansi_escape_codes {
+        "--color=always"
+    } else {
+        "--no-color"
+    };
+    let GitRunResult {
+        exit_code: _,
+        stdout,
+        stderr: _,
+    } = git_run_info.run_silent(
+        repo,
+        None,
+        &[
+            "diff",
+            "--no-ext-diff",
+            color_arg,
+            &original_tree.get_oid().to_string(),
+            &fixed_tree_oid.to_string(),
+        ],
+        GitRunOpts::default(),
+    )?;
+    write!(
+        effects.get_output_stream(),
+        "{}",
+        String::from_utf8_lossy(&stdout)
+    )?;
+
+    loop {
+        write!(
+            effects.get_output_stream(),
+            "Apply this fix? [y]es, [n]o, [e]dit, [q]uit: "
+        )?;
+        let mut user_input = String::new();
+        if std::io::stdin().read_line(&mut user_input)? == 0 {
+            writeln!(effects.get_output_stream())?;
+            return Ok(Ok(FixDecision::Quit));
+        }
+        match user_input.trim() {
+            "y" | "Y" => return Ok(Ok(FixDecision::Apply(fixed_tree_oid))),
+            "n" | "N" => return Ok(Ok(FixDecision::Skip)),
+            "q" | "Q" => return Ok(Ok(FixDecision::Quit)),
+            "e" | "E" => {
+                let files = try_exit_code!(select_changes_interactively(
+                    effects,
+                    repo,
+                    &original_tree,
+                    &fixed_tree,
+                    &[]
+                )?);
+                let selected_tree = make_selected_tree(repo, &original_tree, &fixed_tree, files)?;
+                let decision = if selected_tree.get_oid() == original_tree.get_oid() {
+                    FixDecision::Skip
+                } else {
+                    FixDecision::Apply(selected_tree.get_oid())
+                };
+                return Ok(Ok(decision));
+            }
+            _ => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "Please enter one of: y, n, e, q"
+                )?;
+            }
+        }
+    }
+}
+
 #[instrument]
 fn run_test(
     effects: &Effects,
@@ -2183,12 +3211,17 @@ fn run_test(
         command: _, // Used in `test_commit`.
         execution_strategy,
         search_strategy: _, // Caller handles which commits to test.
-        use_cache: _,       // Used only in `make_test_files`.
-        is_dry_run: _,      // Used only in `apply_fixes`.
-        is_interactive: _,  // Used in `test_commit`.
-        num_jobs: _,        // Caller handles job management.
+        probabilistic_search_options: _,
+        use_cache: _,            // Used only in `make_test_files`.
+        is_dry_run: _,           // Used only in `apply_fixes`.
+        should_confirm_fixes: _, // Used only in `apply_fixes`.
+        is_interactive: _,       // Used in `test_commit`.
+        num_jobs: _,             // Caller handles job management.
+        timeout: _,              // Used in `test_commit`.
+        num_retries: _,          // Used in `test_commit`.
         verbosity: _,
         fix_options,
+        remote_command,
     } = options;
     let (effects, progress) = effects.start_operation(operation_type);
     progress.notify_status(
@@ -2203,8 +3236,30 @@ fn run_test(
 
     let test_output = match make_test_files(repo, commit, options)? {
         TestFilesResult::Cached(test_output) => test_output,
-        TestFilesResult::NotCached(test_files) => {
-            match prepare_working_directory(
+        TestFilesResult::NotCached(test_files) => match (execution_strategy, remote_command) {
+            (TestExecutionStrategy::Remote, Some(remote_command)) => {
+                progress.notify_status(
+                    OperationIcon::InProgress,
+                    format!(
+                        "Running remotely on {}",
+                        effects
+                            .get_glyphs()
+                            .render(commit.friendly_describe(effects.get_glyphs())?)?
+                    ),
+                );
+                test_commit_remote(
+                    git_run_info,
+                    repo,
+                    event_tx_id,
+                    test_files,
+                    shell_path,
+                    remote_command,
+                    options,
+                    commit,
+                )?
+            }
+
+            (_, _) => match prepare_working_directory(
                 git_run_info,
                 repo,
                 event_tx_id,
@@ -2217,6 +3272,7 @@ fn run_test(
                     let TestFiles {
                         temp_dir,
                         lock_file: _, // Drop lock.
+                        sparse_checkout: _,
                         result_path,
                         result_file: _,
                         stdout_path,
@@ -2230,6 +3286,7 @@ fn run_test(
                         stdout_path,
                         stderr_path,
                         test_status: TestStatus::CheckoutFailed,
+                        duration: Duration::ZERO,
                     }
                 }
                 Ok(PreparedWorkingDirectory {
@@ -2263,8 +3320,8 @@ fn run_test(
                     drop(working_directory_lock_file);
                     result
                 }
-            }
-        }
+            },
+        },
     };
 
     let description = StyledStringBuilder::new()
@@ -2280,9 +3337,11 @@ fn run_test(
             | TestStatus::SpawnTestFailed(_)
             | TestStatus::AlreadyInProgress
             | TestStatus::ReadCacheFailed(_)
-            | TestStatus::Indeterminate { .. } => OperationIcon::Warning,
+            | TestStatus::Indeterminate { .. }
+            | TestStatus::Flaky { .. } => OperationIcon::Warning,
 
             TestStatus::TerminatedBySignal
+            | TestStatus::TimedOut { .. }
             | TestStatus::Failed { .. }
             | TestStatus::Abort { .. } => OperationIcon::Failure,
 
@@ -2297,6 +3356,7 @@ fn run_test(
 struct TestFiles {
     temp_dir: Option<TempDir>,
     lock_file: LockFile,
+    sparse_checkout: Option<Vec<String>>,
     result_path: PathBuf,
     result_file: File,
     stdout_path: PathBuf,
@@ -2317,7 +3377,16 @@ fn make_test_files(
     commit: &Commit,
     options: &ResolvedTestOptions,
 ) -> eyre::Result<TestFilesResult> {
-    if !options.use_cache {
+    // The local strategies run the test in a working copy with the same sparse
+    // checkout patterns as the current one (see `prepare_working_directory`).
+    let sparse_checkout = match options.execution_strategy {
+        TestExecutionStrategy::WorkingCopy | TestExecutionStrategy::Worktree => repo
+            .get_sparse_checkout()?
+            .map(|sparse_checkout| sparse_checkout.get_set_args()),
+        TestExecutionStrategy::Remote => None,
+    };
+
+    if !options.use_cache {
         let temp_dir = tempfile::tempdir().context("Creating temporary directory")?;
         let lock_path = temp_dir.path().join("pid.lock");
         let mut lock_file = LockFile::open(&lock_path)
@@ -2342,6 +3411,7 @@ fn make_test_files(
         return Ok(TestFilesResult::NotCached(TestFiles {
             temp_dir: Some(temp_dir),
             lock_file,
+            sparse_checkout,
             result_path,
             result_file,
             stdout_path,
@@ -2376,6 +3446,7 @@ fn make_test_files(
             stdout_path,
             stderr_path,
             test_status: TestStatus::AlreadyInProgress,
+            duration: Duration::ZERO,
         }));
     }
 
@@ -2385,16 +3456,65 @@ fn make_test_files(
         // the lock, so it should be the case that we are the exclusive writers
         // to the contents of this directory (i.e. the previous attempt is not
         // still running), so it's safe to proceed and overwrite these files.
-        if !contents.is_empty() {
-            let serialized_result: Result<SerializedTestResult, _> =
-                serde_json::from_str(&contents);
+        let serialized_result: Result<SerializedTestResult, _> = serde_json::from_str(&contents);
+
+        // A result from a working copy with different sparse checkout patterns
+        // may not reflect the files which would be present now, so run the
+        // test again rather than reusing it.
+        let is_sparse_checkout_changed = matches!(
+            &serialized_result,
+            Ok(serialized_result) if serialized_result.sparse_checkout != sparse_checkout
+        );
+
+        if !contents.is_empty() && !is_sparse_checkout_changed {
+            let duration = match &serialized_result {
+                Ok(serialized_result) => serialized_result
+                    .duration_ms
+                    .map(Duration::from_millis)
+                    .unwrap_or_default(),
+                Err(_) => Duration::ZERO,
+            };
             let test_status = match serialized_result {
+                Ok(SerializedTestResult {
+                    command: _,
+                    exit_code: _,
+                    head_commit_oid: _,
+                    snapshot_tree_oid: _,
+                    interactive: _,
+                    timed_out: true,
+                    flaky: _,
+                    sparse_checkout: _,
+                    duration_ms: _,
+                }) => TestStatus::TimedOut { cached: true },
+
+                Ok(SerializedTestResult {
+                    command: _,
+                    exit_code: 0,
+                    head_commit_oid,
+                    snapshot_tree_oid,
+                    interactive: _,
+                    timed_out: _,
+                    flaky: true,
+                    sparse_checkout: _,
+                    duration_ms: _,
+                }) => TestStatus::Flaky {
+                    cached: true,
+                    fix_info: FixInfo {
+                        head_commit_oid: head_commit_oid.map(|SerializedNonZeroOid(oid)| oid),
+                        snapshot_tree_oid: snapshot_tree_oid.map(|SerializedNonZeroOid(oid)| oid),
+                    },
+                },
+
                 Ok(SerializedTestResult {
                     command: _,
                     exit_code: 0,
                     head_commit_oid,
                     snapshot_tree_oid,
                     interactive,
+                    timed_out: _,
+                    flaky: _,
+                    sparse_checkout: _,
+                    duration_ms: _,
                 }) => TestStatus::Passed {
                     cached: true,
                     fix_info: FixInfo {
@@ -2411,6 +3531,10 @@ fn make_test_files(
                     head_commit_oid: _,
                     snapshot_tree_oid: _,
                     interactive: _,
+                    timed_out: _,
+                    flaky: _,
+                    sparse_checkout: _,
+                    duration_ms: _,
                 }) if exit_code == TEST_INDETERMINATE_EXIT_CODE => {
                     TestStatus::Indeterminate { exit_code }
                 }
@@ -2421,6 +3545,10 @@ fn make_test_files(
                     head_commit_oid: _,
                     snapshot_tree_oid: _,
                     interactive: _,
+                    timed_out: _,
+                    flaky: _,
+                    sparse_checkout: _,
+                    duration_ms: _,
                 }) if exit_code == TEST_ABORT_EXIT_CODE => TestStatus::Abort { exit_code },
 
                 Ok(SerializedTestResult {
@@ -2429,6 +3557,10 @@ fn make_test_files(
                     head_commit_oid: _,
                     snapshot_tree_oid: _,
                     interactive,
+                    timed_out: _,
+                    flaky: _,
+                    sparse_checkout: _,
+                    duration_ms: _,
                 }) => TestStatus::Failed {
                     cached: true,
                     exit_code,
@@ -2442,6 +3574,7 @@ fn make_test_files(
                 stdout_path,
                 stderr_path,
                 test_status,
+                duration,
             }));
         }
     }
@@ -2455,6 +3588,7 @@ fn make_test_files(
     Ok(TestFilesResult::NotCached(TestFiles {
         temp_dir: None,
         lock_file,
+        sparse_checkout,
         result_path,
         result_file,
         stdout_path,
@@ -2476,6 +3610,7 @@ enum PrepareWorkingDirectoryError {
     NoWorkingCopy,
     CheckoutFailed(NonZeroOid),
     CreateWorktreeFailed(PathBuf),
+    SparseCheckoutFailed(PathBuf),
 }
 
 #[instrument]
@@ -2496,6 +3631,11 @@ fn prepare_working_directory(
         TestExecutionStrategy::Worktree => {
             format!("worktree-{worker_id}.lock")
         }
+        TestExecutionStrategy::Remote => {
+            // Remote tests aren't run in a local working directory; see
+            // `test_commit_remote`.
+            return Ok(Err(PrepareWorkingDirectoryError::NoWorkingCopy));
+        }
     };
     let lock_path = test_lock_dir_path.join(lock_file_name);
     let mut lock_file = LockFile::open(&lock_path)
@@ -2559,7 +3699,16 @@ fn prepare_working_directory(
                 } = git_run_info.run_silent(
                     repo,
                     Some(event_tx_id),
-                    &["worktree", "add", worktree_dir_str, "--force", "--detach"],
+                    &[
+                        "worktree",
+                        "add",
+                        worktree_dir_str,
+                        "--force",
+                        "--detach",
+                        // The commit is checked out below, after setting up
+                        // any sparse checkout.
+                        "--no-checkout",
+                    ],
                     Default::default(),
                 )?;
                 if !exit_code.is_success() {
@@ -2569,6 +3718,43 @@ fn prepare_working_directory(
                 }
             }
 
+            // Use the same sparse checkout patterns as the current worktree,
+            // so that only the paths in them are checked out.
+            let sparse_checkout = repo.get_sparse_checkout()?;
+            let worktree_repo = Repo::from_dir(&worktree_dir)?;
+            if worktree_repo.get_sparse_checkout()? != sparse_checkout {
+                let mut args = vec![
+                    "-C".to_owned(),
+                    worktree_dir_str.to_owned(),
+                    "sparse-checkout".to_owned(),
+                ];
+                match &sparse_checkout {
+                    Some(sparse_checkout) => {
+                        args.push("set".to_owned());
+                        args.extend(sparse_checkout.get_set_args());
+                    }
+                    None => args.push("disable".to_owned()),
+                }
+                let GitRunResult {
+                    exit_code,
+                    stdout: _,
+                    stderr: _,
+                } = git_run_info.run_silent(
+                    repo,
+                    Some(event_tx_id),
+                    &args.iter().map(String::as_str).collect_vec(),
+                    GitRunOpts {
+                        treat_git_failure_as_error: false,
+                        ..Default::default()
+                    },
+                )?;
+                if !exit_code.is_success() {
+                    return Ok(Err(PrepareWorkingDirectoryError::SparseCheckoutFailed(
+                        worktree_dir,
+                    )));
+                }
+            }
+
             let GitRunResult {
                 exit_code,
                 stdout: _,
@@ -2595,6 +3781,8 @@ fn prepare_working_directory(
                 path: worktree_dir,
             }))
         }
+
+        TestExecutionStrategy::Remote => unreachable!("Handled above"),
     }
 }
 
@@ -2613,6 +3801,7 @@ fn test_commit(
     let TestFiles {
         temp_dir,
         lock_file: _lock_file, // Make sure not to drop lock.
+        sparse_checkout,
         result_path,
         result_file,
         stdout_path,
@@ -2661,7 +3850,7 @@ To mark this commit as {skipped}, run: {exit125}
 To abort testing entirely, run:      {exit127}",
         );
         match options.execution_strategy {
-            TestExecutionStrategy::WorkingCopy => {}
+            TestExecutionStrategy::WorkingCopy | TestExecutionStrategy::Remote => {}
             TestExecutionStrategy::Worktree => {
                 let warning = effects
                     .get_glyphs()
@@ -2674,79 +3863,445 @@ To abort testing entirely, run:      {exit127}",
                 println!("To incorporate the changes from the main repository, switch to the main repository's current commit or branch.");
             }
         }
-    } else {
-        command
-            .stdin(Stdio::null())
-            .stdout(stdout_file)
-            .stderr(stderr_file);
     }
+    let output_files = if options.is_interactive {
+        None
+    } else {
+        command.stdin(Stdio::null());
+        Some((stdout_file, stderr_file))
+    };
 
-    let exit_code = match command.status() {
-        Ok(status) => status.code(),
-        Err(err) => {
+    let start_time = Instant::now();
+    let outcome = run_test_command(
+        &mut command,
+        options,
+        output_files,
+        &stdout_path,
+        &stderr_path,
+    )?;
+    let duration = start_time.elapsed();
+    let (exit_code, num_attempts) = match outcome {
+        TestCommandOutcome::Exited {
+            exit_code,
+            num_attempts,
+        } => (exit_code, num_attempts),
+        outcome => {
+            let test_status = match outcome {
+                TestCommandOutcome::Exited { .. } => unreachable!("Handled above"),
+                TestCommandOutcome::SpawnFailed(err) => TestStatus::SpawnTestFailed(err),
+                TestCommandOutcome::TerminatedBySignal => TestStatus::TerminatedBySignal,
+                TestCommandOutcome::TimedOut => {
+                    let test_status = TestStatus::TimedOut { cached: false };
+                    write_test_result(
+                        result_file,
+                        &result_path,
+                        options,
+                        sparse_checkout,
+                        TEST_TIMED_OUT_EXIT_CODE,
+                        &test_status,
+                        duration,
+                    )?;
+                    test_status
+                }
+            };
             return Ok(TestOutput {
                 temp_dir,
                 result_path,
                 stdout_path,
                 stderr_path,
-                test_status: TestStatus::SpawnTestFailed(err.to_string()),
+                test_status,
+                duration,
             });
         }
     };
-    let exit_code = match exit_code {
-        Some(exit_code) => exit_code,
-        None => {
-            return Ok(TestOutput {
-                temp_dir,
-                result_path,
-                stdout_path,
-                stderr_path,
-                test_status: TestStatus::TerminatedBySignal,
-            });
+    let test_status = make_test_status(exit_code, num_attempts, options.is_interactive, || {
+        let repo = Repo::from_dir(working_directory)?;
+        let (head_commit_oid, snapshot) = {
+            let index = repo.get_index()?;
+            let head_info = repo.get_head_info()?;
+            let (snapshot, _status) = repo.get_status(
+                &effects.suppress(),
+                git_run_info,
+                &index,
+                &head_info,
+                Some(event_tx_id),
+            )?;
+            (head_info.oid, snapshot)
+        };
+        let snapshot_tree_oid = match snapshot.get_working_copy_changes_type()? {
+            WorkingCopyChangesType::None | WorkingCopyChangesType::Unstaged => {
+                let fixed_tree_oid: MaybeZeroOid = snapshot.commit_unstaged.get_tree_oid();
+                fixed_tree_oid.into()
+            }
+            changes_type @ (WorkingCopyChangesType::Staged | WorkingCopyChangesType::Conflicts) => {
+                // FIXME: surface information about the fix that failed to be applied.
+                warn!(
+                    ?changes_type,
+                    "There were staged changes or conflicts in the resulting working copy"
+                );
+                None
+            }
+        };
+        Ok(FixInfo {
+            head_commit_oid,
+            snapshot_tree_oid,
+        })
+    })?;
+    write_test_result(
+        result_file,
+        &result_path,
+        options,
+        sparse_checkout,
+        exit_code,
+        &test_status,
+        duration,
+    )?;
+
+    Ok(TestOutput {
+        temp_dir,
+        result_path,
+        stdout_path,
+        stderr_path,
+        test_status,
+        duration,
+    })
+}
+
+/// How often to check whether a test command with a timeout has finished.
+const TEST_COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The result of running the test command, including any retries.
+#[derive(Debug)]
+enum TestCommandOutcome {
+    /// The command exited normally. If it was retried, then this is the exit
+    /// code of the last attempt.
+    Exited { exit_code: i32, num_attempts: usize },
+
+    /// The command could not be started.
+    SpawnFailed(String),
+
+    /// The command was terminated by a signal.
+    TerminatedBySignal,
+
+    /// The command ran for longer than the timeout, and was killed.
+    TimedOut,
+}
+
+/// Run the test command, retrying it (up to the number of retries in
+/// `options`) if it fails or times out. If `output_files` is provided, then
+/// the command's stdout and stderr are written to them, and they're recreated
+/// at `stdout_path` and `stderr_path` for each retry, so that only the output
+/// of the last attempt is kept.
+#[instrument]
+fn run_test_command(
+    command: &mut Command,
+    options: &ResolvedTestOptions,
+    output_files: Option<(File, File)>,
+    stdout_path: &Path,
+    stderr_path: &Path,
+) -> eyre::Result<TestCommandOutcome> {
+    // Interactive commands are controlled by the user, so don't kill or re-run
+    // them.
+    let (timeout, num_retries) = if options.is_interactive {
+        (None, 0)
+    } else {
+        (options.timeout, options.num_retries)
+    };
+
+    let mut output_files = output_files.map(Some);
+    let mut num_attempts = 0;
+    loop {
+        num_attempts += 1;
+        if let Some(output_files) = output_files.as_mut() {
+            let (stdout_file, stderr_file) = match output_files.take() {
+                Some(output_files) => output_files,
+                None => (
+                    File::create(stdout_path)
+                        .wrap_err_with(|| format!("Opening stdout file {stdout_path:?}"))?,
+                    File::create(stderr_path)
+                        .wrap_err_with(|| format!("Opening stderr file {stderr_path:?}"))?,
+                ),
+            };
+            command.stdout(stdout_file).stderr(stderr_file);
+        }
+
+        let outcome = match wait_for_test_command(command, timeout) {
+            Err(err) => TestCommandOutcome::SpawnFailed(err.to_string()),
+            Ok(None) => TestCommandOutcome::TimedOut,
+            Ok(Some(status)) => match status.code() {
+                Some(exit_code) => TestCommandOutcome::Exited {
+                    exit_code,
+                    num_attempts,
+                },
+                None => TestCommandOutcome::TerminatedBySignal,
+            },
+        };
+        let should_retry = match outcome {
+            TestCommandOutcome::TimedOut => true,
+            TestCommandOutcome::Exited { exit_code, .. } => {
+                exit_code != TEST_SUCCESS_EXIT_CODE
+                    && exit_code != TEST_INDETERMINATE_EXIT_CODE
+                    && exit_code != TEST_ABORT_EXIT_CODE
+            }
+            TestCommandOutcome::SpawnFailed(_) | TestCommandOutcome::TerminatedBySignal => false,
+        };
+        if !should_retry || num_attempts > num_retries {
+            return Ok(outcome);
         }
+        debug!(?outcome, ?num_attempts, "Retrying test command");
+    }
+}
+
+/// Run the command to completion. If it runs for longer than `timeout`, then
+/// kill it (along with any processes that it spawned) and return `None`.
+fn wait_for_test_command(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<ExitStatus>> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return command.status().map(Some),
     };
-    let test_status = match exit_code {
-        TEST_SUCCESS_EXIT_CODE => {
-            let fix_info = {
-                let repo = Repo::from_dir(working_directory)?;
-                let (head_commit_oid, snapshot) = {
-                    let index = repo.get_index()?;
-                    let head_info = repo.get_head_info()?;
-                    let (snapshot, _status) = repo.get_status(
-                        &effects.suppress(),
-                        git_run_info,
-                        &index,
-                        &head_info,
-                        Some(event_tx_id),
-                    )?;
-                    (head_info.oid, snapshot)
-                };
-                let snapshot_tree_oid = match snapshot.get_working_copy_changes_type()? {
-                    WorkingCopyChangesType::None | WorkingCopyChangesType::Unstaged => {
-                        let fixed_tree_oid: MaybeZeroOid = snapshot.commit_unstaged.get_tree_oid();
-                        fixed_tree_oid.into()
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        // Run the command in its own process group, so that the entire group
+        // can be killed if it times out. Otherwise, any subprocesses that it
+        // spawned might keep running (and holding onto the output files).
+        command.process_group(0);
+    }
+    let _handler = interrupt::HandlerGuard::install();
+    let mut child = command.spawn()?;
+    let process_group = interrupt::ProcessGroupGuard::new(&child);
+    let deadline = Instant::now() + timeout;
+    let mut is_interrupt_forwarded = false;
+    loop {
+        if let Some(status) = process_group.try_wait(&mut child)? {
+            return Ok(Some(status));
+        }
+        if !is_interrupt_forwarded && interrupt::is_interrupted() {
+            // Since the command is in its own process group, it won't receive
+            // the interrupt from the terminal, so pass it along to all running
+            // test commands. The main thread stops testing once the workers
+            // have returned their results.
+            interrupt::forward_interrupt();
+            is_interrupt_forwarded = true;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        std::thread::sleep(TEST_COMMAND_POLL_INTERVAL.min(deadline - now));
+    }
+
+    drop(process_group);
+    kill_process_group(&mut child)?;
+    child.wait()?;
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    let pid = libc::pid_t::try_from(child.id())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    // SAFETY: `kill` has no memory-safety preconditions. The child was spawned
+    // as the leader of its own process group, and hasn't been reaped yet, so
+    // the process group ID can't have been reused.
+    let result = unsafe { libc::kill(-pid, libc::SIGKILL) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    child.kill()
+}
+
+/// Handling for interrupts (Ctrl-C) while test commands are running in their
+/// own process groups.
+#[cfg(unix)]
+mod interrupt {
+    use std::collections::HashSet;
+    use std::process::{Child, ExitStatus};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    use lazy_static::lazy_static;
+
+    static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+    lazy_static! {
+        /// The process groups of the test commands which are currently running.
+        static ref PROCESS_GROUPS: Mutex<HashSet<libc::pid_t>> = Default::default();
+
+        /// The number of installed `HandlerGuard`s, and the `SIGINT` action
+        /// which was in place before the first of them was installed.
+        static ref HANDLER_STATE: Mutex<(usize, Option<libc::sigaction>)> = Default::default();
+    }
+
+    extern "C" fn handle_sigint(_signal: libc::c_int) {
+        // Only async-signal-safe operations are allowed here, so the interrupt
+        // is actually forwarded by whichever thread is waiting on a test
+        // command.
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    /// Records interrupts instead of exiting immediately while any test
+    /// command is running in its own process group. The previous `SIGINT`
+    /// action is restored once the last guard is dropped, so that interrupts
+    /// at other times behave as usual.
+    pub struct HandlerGuard;
+
+    impl HandlerGuard {
+        #[allow(clippy::as_conversions)] // `libc` represents handlers as integers.
+        pub fn install() -> Self {
+            let mut state = HANDLER_STATE.lock().unwrap();
+            let (num_guards, previous_action) = &mut *state;
+            if *num_guards == 0 {
+                let handler = handle_sigint as extern "C" fn(libc::c_int);
+                // SAFETY: the handler only stores to an atomic, which is
+                // async-signal-safe, and the actions are valid for the
+                // duration of the call.
+                unsafe {
+                    let mut action: libc::sigaction = std::mem::zeroed();
+                    action.sa_sigaction = handler as libc::sighandler_t;
+                    action.sa_flags = libc::SA_RESTART;
+                    libc::sigemptyset(&mut action.sa_mask);
+                    let mut old_action: libc::sigaction = std::mem::zeroed();
+                    if libc::sigaction(libc::SIGINT, &action, &mut old_action) == 0 {
+                        *previous_action = Some(old_action);
                     }
-                    changes_type @ (WorkingCopyChangesType::Staged
-                    | WorkingCopyChangesType::Conflicts) => {
-                        // FIXME: surface information about the fix that failed to be applied.
-                        warn!(
-                            ?changes_type,
-                            "There were staged changes or conflicts in the resulting working copy"
-                        );
-                        None
+                }
+            }
+            *num_guards += 1;
+            Self
+        }
+    }
+
+    impl Drop for HandlerGuard {
+        fn drop(&mut self) {
+            let mut state = HANDLER_STATE.lock().unwrap();
+            let (num_guards, previous_action) = &mut *state;
+            *num_guards -= 1;
+            if *num_guards == 0 {
+                if let Some(previous_action) = previous_action.take() {
+                    // SAFETY: the action was returned by `sigaction` above.
+                    unsafe {
+                        libc::sigaction(libc::SIGINT, &previous_action, std::ptr::null_mut());
                     }
-                };
-                FixInfo {
-                    head_commit_oid,
-                    snapshot_tree_oid,
                 }
-            };
-            TestStatus::Passed {
-                cached: false,
-                fix_info,
-                interactive: options.is_interactive,
             }
         }
+    }
+
+    /// Whether an interrupt was received while running test commands.
+    pub fn is_interrupted() -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+
+    /// Send `SIGINT` to the process groups of all running test commands.
+    pub fn forward_interrupt() {
+        let process_groups = PROCESS_GROUPS.lock().unwrap();
+        for pid in process_groups.iter() {
+            // SAFETY: `kill` has no memory-safety preconditions. Process
+            // groups are removed from the set (under the same lock) when
+            // their leaders are reaped, so the IDs can't have been reused.
+            unsafe {
+                libc::kill(-pid, libc::SIGINT);
+            }
+        }
+    }
+
+    /// Tracks the process group of a running test command, so that interrupts
+    /// can be forwarded to it.
+    pub struct ProcessGroupGuard {
+        pid: Option<libc::pid_t>,
+    }
+
+    impl ProcessGroupGuard {
+        pub fn new(child: &Child) -> Self {
+            let pid = libc::pid_t::try_from(child.id()).ok();
+            if let Some(pid) = pid {
+                PROCESS_GROUPS.lock().unwrap().insert(pid);
+            }
+            Self { pid }
+        }
+
+        /// Check whether the command has exited, and stop tracking its process
+        /// group if so.
+        pub fn try_wait(&self, child: &mut Child) -> std::io::Result<Option<ExitStatus>> {
+            let mut process_groups = PROCESS_GROUPS.lock().unwrap();
+            let status = child.try_wait()?;
+            if let (Some(_), Some(pid)) = (status, self.pid) {
+                process_groups.remove(&pid);
+            }
+            Ok(status)
+        }
+    }
+
+    impl Drop for ProcessGroupGuard {
+        fn drop(&mut self) {
+            if let Some(pid) = self.pid {
+                PROCESS_GROUPS.lock().unwrap().remove(&pid);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod interrupt {
+    use std::process::{Child, ExitStatus};
+
+    pub struct HandlerGuard;
+
+    impl HandlerGuard {
+        pub fn install() -> Self {
+            Self
+        }
+    }
+
+    pub fn is_interrupted() -> bool {
+        false
+    }
+
+    pub fn forward_interrupt() {}
+
+    pub struct ProcessGroupGuard;
+
+    impl ProcessGroupGuard {
+        pub fn new(_child: &Child) -> Self {
+            Self
+        }
+
+        pub fn try_wait(&self, child: &mut Child) -> std::io::Result<Option<ExitStatus>> {
+            child.try_wait()
+        }
+    }
+}
+
+/// Determine the test status corresponding to the given exit code of the test
+/// command. `make_fix_info` is only called if the test passed.
+fn make_test_status(
+    exit_code: i32,
+    num_attempts: usize,
+    interactive: bool,
+    make_fix_info: impl FnOnce() -> eyre::Result<FixInfo>,
+) -> eyre::Result<TestStatus> {
+    let test_status = match exit_code {
+        TEST_SUCCESS_EXIT_CODE if num_attempts > 1 => TestStatus::Flaky {
+            cached: false,
+            fix_info: make_fix_info()?,
+        },
+        TEST_SUCCESS_EXIT_CODE => TestStatus::Passed {
+            cached: false,
+            fix_info: make_fix_info()?,
+            interactive,
+        },
 
         exit_code @ TEST_INDETERMINATE_EXIT_CODE => TestStatus::Indeterminate { exit_code },
         exit_code @ TEST_ABORT_EXIT_CODE => TestStatus::Abort { exit_code },
@@ -2754,21 +4309,39 @@ To abort testing entirely, run:      {exit127}",
         exit_code => TestStatus::Failed {
             cached: false,
             exit_code,
-            interactive: options.is_interactive,
+            interactive,
         },
     };
+    Ok(test_status)
+}
 
-    let fix_info = match &test_status {
+/// Write the result of running the test command to the cache.
+#[instrument]
+fn write_test_result(
+    result_file: File,
+    result_path: &Path,
+    options: &ResolvedTestOptions,
+    sparse_checkout: Option<Vec<String>>,
+    exit_code: i32,
+    test_status: &TestStatus,
+    duration: Duration,
+) -> eyre::Result<()> {
+    let fix_info = match test_status {
         TestStatus::Passed {
             cached: _,
             fix_info,
             interactive: _,
+        }
+        | TestStatus::Flaky {
+            cached: _,
+            fix_info,
         } => Some(fix_info),
         TestStatus::CheckoutFailed
         | TestStatus::SpawnTestFailed(_)
         | TestStatus::TerminatedBySignal
         | TestStatus::AlreadyInProgress
         | TestStatus::ReadCacheFailed(_)
+        | TestStatus::TimedOut { .. }
         | TestStatus::Failed { .. }
         | TestStatus::Abort { .. }
         | TestStatus::Indeterminate { .. } => None,
@@ -2781,43 +4354,238 @@ To abort testing entirely, run:      {exit127}",
         snapshot_tree_oid: fix_info
             .and_then(|fix_info| fix_info.snapshot_tree_oid.map(SerializedNonZeroOid)),
         interactive: options.is_interactive,
+        timed_out: matches!(test_status, TestStatus::TimedOut { .. }),
+        flaky: matches!(test_status, TestStatus::Flaky { .. }),
+        sparse_checkout,
+        duration_ms: Some(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)),
     };
     serde_json::to_writer_pretty(result_file, &serialized_test_result)
         .wrap_err_with(|| format!("Writing test status {test_status:?} to {result_path:?}"))?;
+    Ok(())
+}
 
-    Ok(TestOutput {
+/// Run the test command for the given commit by handing it off to the
+/// configured remote command, rather than running it in a local working
+/// directory.
+#[instrument]
+fn test_commit_remote(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_tx_id: EventTransactionId,
+    test_files: TestFiles,
+    shell_path: &Path,
+    remote_command: &str,
+    options: &ResolvedTestOptions,
+    commit: &Commit,
+) -> eyre::Result<TestOutput> {
+    let TestFiles {
         temp_dir,
+        lock_file: _lock_file, // Make sure not to drop lock.
+        sparse_checkout,
         result_path,
+        result_file,
         stdout_path,
+        stdout_file,
         stderr_path,
-        test_status,
-    })
-}
+        stderr_file,
+    } = test_files;
 
-/// Show test output for the command provided in `options` for each of the
-/// commits in `revset`.
-#[instrument]
-fn subcommand_show(
-    effects: &Effects,
-    options: &RawTestOptions,
-    revset: Revset,
-    resolve_revset_options: &ResolveRevsetOptions,
-) -> EyreExitOr<()> {
-    let now = SystemTime::now();
-    let repo = Repo::from_current_dir()?;
-    let conn = repo.get_db_conn()?;
-    let event_log_db = EventLogDb::new(&conn)?;
-    let event_tx_id = event_log_db.make_transaction_id(now, "test show")?;
-    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
-    let event_cursor = event_replayer.make_default_cursor();
-    let references_snapshot = repo.get_references_snapshot()?;
-    let mut dag = Dag::open_and_sync(
-        effects,
-        &repo,
-        &event_replayer,
-        event_cursor,
-        &references_snapshot,
-    )?;
+    let bundle_dir = tempfile::tempdir().context("Creating temporary directory for bundle")?;
+    let bundle_path = bundle_dir.path().join("commit.bundle");
+    if !create_commit_bundle(git_run_info, repo, event_tx_id, commit, &bundle_path)? {
+        return Ok(TestOutput {
+            temp_dir,
+            result_path,
+            stdout_path,
+            stderr_path,
+            test_status: TestStatus::CheckoutFailed,
+            duration: Duration::ZERO,
+        });
+    }
+
+    let working_directory = repo
+        .get_working_copy_path()
+        .unwrap_or_else(|| repo.get_path());
+    let mut command = Command::new(shell_path);
+    command
+        .arg("-c")
+        .arg(remote_command)
+        .current_dir(working_directory)
+        .env("BRANCHLESS_TEST_COMMIT", commit.get_oid().to_string())
+        .env("BRANCHLESS_TEST_COMMAND", options.command.to_string())
+        .env("BRANCHLESS_TEST_BUNDLE", &bundle_path)
+        .stdin(Stdio::null());
+
+    let start_time = Instant::now();
+    let outcome = run_test_command(
+        &mut command,
+        options,
+        Some((stdout_file, stderr_file)),
+        &stdout_path,
+        &stderr_path,
+    )?;
+    let duration = start_time.elapsed();
+    let (exit_code, num_attempts) = match outcome {
+        TestCommandOutcome::Exited {
+            exit_code,
+            num_attempts,
+        } => (exit_code, num_attempts),
+        outcome => {
+            let test_status = match outcome {
+                TestCommandOutcome::Exited { .. } => unreachable!("Handled above"),
+                TestCommandOutcome::SpawnFailed(err) => TestStatus::SpawnTestFailed(err),
+                TestCommandOutcome::TerminatedBySignal => TestStatus::TerminatedBySignal,
+                TestCommandOutcome::TimedOut => {
+                    let test_status = TestStatus::TimedOut { cached: false };
+                    write_test_result(
+                        result_file,
+                        &result_path,
+                        options,
+                        sparse_checkout,
+                        TEST_TIMED_OUT_EXIT_CODE,
+                        &test_status,
+                        duration,
+                    )?;
+                    test_status
+                }
+            };
+            return Ok(TestOutput {
+                temp_dir,
+                result_path,
+                stdout_path,
+                stderr_path,
+                test_status,
+                duration,
+            });
+        }
+    };
+
+    // The remote command doesn't run in a local working copy, so there's no
+    // resulting working copy state to snapshot.
+    let test_status = make_test_status(exit_code, num_attempts, false, || {
+        Ok(FixInfo {
+            head_commit_oid: Some(commit.get_oid()),
+            snapshot_tree_oid: None,
+        })
+    })?;
+    write_test_result(
+        result_file,
+        &result_path,
+        options,
+        sparse_checkout,
+        exit_code,
+        &test_status,
+        duration,
+    )?;
+
+    Ok(TestOutput {
+        temp_dir,
+        result_path,
+        stdout_path,
+        stderr_path,
+        test_status,
+        duration,
+    })
+}
+
+/// Write a Git bundle containing the given commit to `bundle_path`. Commits on
+/// the main branch are left out, since the remote is expected to have them
+/// already, so the bundle only contains the commit's draft ancestors. Returns
+/// `false` if the bundle could not be created.
+#[instrument]
+fn create_commit_bundle(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_tx_id: EventTransactionId,
+    commit: &Commit,
+    bundle_path: &Path,
+) -> eyre::Result<bool> {
+    let bundle_path = match bundle_path.to_str() {
+        Some(bundle_path) => bundle_path,
+        None => {
+            warn!(?bundle_path, "Bundle path was not valid UTF-8");
+            return Ok(false);
+        }
+    };
+
+    // `git bundle` can only bundle commits which are pointed to by a
+    // reference, so create a temporary one for the duration of the operation.
+    // It's created via libgit2 rather than Git so that no reference-transaction
+    // hook (and hence no event) is triggered.
+    let reference_name =
+        ReferenceName::from(format!("refs/branchless-test/bundle/{}", commit.get_oid()));
+    let mut reference = repo.create_reference(
+        &reference_name,
+        commit.get_oid(),
+        true,
+        "branchless: bundling commit for remote testing",
+    )?;
+    // If the commit is itself on the main branch, then excluding the main
+    // branch would leave nothing to bundle, so only exclude its parents.
+    let main_branch_oid = repo.get_main_branch_oid()?;
+    let excluded_oids =
+        if repo.find_merge_base(commit.get_oid(), main_branch_oid)? == Some(commit.get_oid()) {
+            commit.get_parent_oids()
+        } else {
+            vec![main_branch_oid]
+        };
+    let mut args = vec![
+        "bundle".to_string(),
+        "create".to_string(),
+        bundle_path.to_string(),
+        reference_name.as_str().to_string(),
+    ];
+    args.extend(excluded_oids.into_iter().map(|oid| format!("^{oid}")));
+    let result = git_run_info.run_silent(
+        repo,
+        Some(event_tx_id),
+        &args.iter().map(String::as_str).collect_vec(),
+        GitRunOpts {
+            treat_git_failure_as_error: false,
+            stdin: None,
+        },
+    );
+    reference.delete()?;
+
+    let GitRunResult {
+        exit_code,
+        stdout: _,
+        stderr,
+    } = result?;
+    if !exit_code.is_success() {
+        warn!(
+            ?exit_code,
+            stderr = ?String::from_utf8_lossy(&stderr),
+            "Failed to create bundle"
+        );
+    }
+    Ok(exit_code.is_success())
+}
+
+/// Show test output for the command provided in `options` for each of the
+/// commits in `revset`.
+#[instrument]
+fn subcommand_show(
+    effects: &Effects,
+    options: &RawTestOptions,
+    revset: Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    let now = SystemTime::now();
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_tx_id = event_log_db.make_transaction_id(now, "test show")?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        effects,
+        &repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
 
     let commit_set =
         match resolve_commits(effects, &repo, &mut dag, &[revset], resolve_revset_options) {
@@ -2892,6 +4660,203 @@ fn subcommand_show(
     Ok(Ok(()))
 }
 
+/// Watch for new transactions in the event log, which indicate that the
+/// commits in `revset` may have changed, and run the command in `options` on
+/// each commit whose tree doesn't have a cached result yet. The results are
+/// written to the usual cache, so that they can be queried with `tests.failed()`
+/// and friends while watching.
+#[instrument]
+fn subcommand_watch(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    options: &RawTestOptions,
+    revset: Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+    interval: Duration,
+    iterations: Option<usize>,
+) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+
+    let mut last_event_tx_id = None;
+    let mut iteration = 0;
+    while iterations != Some(iteration) {
+        if iteration > 0 {
+            std::thread::sleep(interval);
+        }
+        iteration += 1;
+
+        let latest_event_tx_id = event_log_db.get_latest_transaction_id()?;
+        if iteration > 1 && latest_event_tx_id == last_event_tx_id {
+            continue;
+        }
+
+        match watch_once(
+            effects,
+            git_run_info,
+            options,
+            &revset,
+            resolve_revset_options,
+        )? {
+            Ok(()) => {}
+            Err(ExitCode(exit_code)) => {
+                // The cause has already been printed. It may be resolved by
+                // later changes (such as cleaning up the working copy), so
+                // keep watching.
+                writeln!(
+                    effects.get_output_stream(),
+                    "{}",
+                    effects.get_glyphs().render(StyledString::styled(
+                        format!("Could not update the results (exit code {exit_code})."),
+                        *STYLE_FAILURE,
+                    ))?
+                )?;
+            }
+        }
+
+        let is_last_iteration = iterations == Some(iteration);
+        if !is_last_iteration {
+            writeln!(
+                effects.get_output_stream(),
+                "Watching for changes to commits (press Ctrl-C to stop)..."
+            )?;
+        }
+
+        // Running the tests creates transactions of its own, so don't treat
+        // those as changes to the commits.
+        last_event_tx_id = event_log_db.get_latest_transaction_id()?;
+    }
+
+    Ok(Ok(()))
+}
+
+/// Run the command in `options` on the commits in `revset` which don't have a
+/// cached result, and then redraw the table of results for all of the commits.
+#[instrument]
+fn watch_once(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    options: &RawTestOptions,
+    revset: &Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    let now = SystemTime::now();
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_tx_id = event_log_db.make_transaction_id(now, "test watch")?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        effects,
+        &repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+
+    let commit_set = match resolve_commits(
+        effects,
+        &repo,
+        &mut dag,
+        &[revset.clone()],
+        resolve_revset_options,
+    ) {
+        Ok(mut commit_sets) => commit_sets.pop().unwrap(),
+        Err(err) => {
+            err.describe(effects)?;
+            return Ok(Err(ExitCode(1)));
+        }
+    };
+
+    let options = try_exit_code!(ResolvedTestOptions::resolve(
+        now,
+        effects,
+        &dag,
+        &repo,
+        event_tx_id,
+        &commit_set,
+        None,
+        options,
+    )?);
+
+    let commits = sorted_commit_set(&repo, &dag, &commit_set)?;
+    let mut test_outputs = HashMap::new();
+    let mut uncached_commits = Vec::new();
+    for commit in &commits {
+        match make_test_files(&repo, commit, &options)? {
+            TestFilesResult::Cached(test_output) => {
+                test_outputs.insert(commit.get_oid(), test_output);
+            }
+            TestFilesResult::NotCached(_) => uncached_commits.push(commit.clone()),
+        }
+    }
+    let mut run_tests_result = Ok(());
+    if !uncached_commits.is_empty() {
+        match run_tests(
+            now,
+            effects,
+            git_run_info,
+            &dag,
+            &repo,
+            &event_log_db,
+            event_tx_id,
+            revset,
+            &uncached_commits,
+            &options,
+        )? {
+            Ok(test_results) => test_outputs.extend(test_results.test_outputs),
+            Err(exit_code) => run_tests_result = Err(exit_code),
+        }
+    }
+
+    // Clear the screen so that the table is refreshed in place, rather than
+    // scrolling the previous results off of the terminal. If the tests
+    // couldn't be run, keep the reason on the screen instead.
+    if run_tests_result.is_ok() && effects.get_glyphs().should_write_ansi_escape_codes {
+        write!(effects.get_output_stream(), "\x1b[2J\x1b[H")?;
+    }
+    writeln!(
+        effects.get_output_stream(),
+        "Results for {} on {}:",
+        effects.get_glyphs().render(
+            StyledStringBuilder::new()
+                .append_styled(options.command.to_string(), Effect::Bold)
+                .build()
+        )?,
+        revset,
+    )?;
+    for commit in &commits {
+        match test_outputs.get(&commit.get_oid()) {
+            Some(test_output) => {
+                write!(
+                    effects.get_output_stream(),
+                    "{}",
+                    effects.get_glyphs().render(test_output.describe(
+                        effects,
+                        commit,
+                        false,
+                        options.verbosity
+                    )?)?,
+                )?;
+            }
+            None => {
+                writeln!(
+                    effects.get_output_stream(),
+                    "No cached test data for {}",
+                    effects
+                        .get_glyphs()
+                        .render(commit.friendly_describe(effects.get_glyphs())?)?
+                )?;
+            }
+        }
+    }
+
+    Ok(run_tests_result)
+}
+
 /// Delete cached test output for the commits in `revset`.
 #[instrument]
 pub fn subcommand_clean(
@@ -2958,6 +4923,380 @@ pub fn subcommand_clean(
     Ok(Ok(()))
 }
 
+/// The notes reference under which shared test results are stored. Notes are
+/// attached to tree OIDs (rather than commit OIDs), for the same reason that
+/// the local test results are keyed by tree OID.
+const TEST_RESULTS_NOTES_REF: &str = "refs/notes/branchless-test";
+
+/// The reference into which the remote's shared test results are fetched
+/// before being merged into `TEST_RESULTS_NOTES_REF`.
+const TEST_RESULTS_FETCHED_NOTES_REF: &str = "refs/notes/branchless-test-fetched";
+
+/// The contents of a note in `TEST_RESULTS_NOTES_REF`, containing all shared
+/// test results for a given tree, keyed by command slug.
+type SharedTestResults = BTreeMap<String, SharedTestResult>;
+
+/// A single cached test result, along with its output.
+#[derive(Debug, Deserialize, Serialize)]
+struct SharedTestResult {
+    result: SerializedTestResult,
+    stdout: String,
+    stderr: String,
+}
+
+/// Determine the remote to share test results with.
+fn resolve_results_remote(
+    effects: &Effects,
+    repo: &Repo,
+    remote: Option<String>,
+) -> EyreExitOr<String> {
+    if let Some(remote) = remote {
+        return Ok(Ok(remote));
+    }
+    match repo.get_default_push_remote()? {
+        Some(remote) => Ok(Ok(remote)),
+        None => {
+            writeln!(
+                effects.get_output_stream(),
+                "\
+No remote was specified, and the main branch has no default push remote.
+Specify which remote to use with: --remote <remote>"
+            )?;
+            Ok(Err(ExitCode(1)))
+        }
+    }
+}
+
+/// Fetch the shared test results from the given remote and merge them into
+/// `TEST_RESULTS_NOTES_REF`. Where both sides have a note for the same tree,
+/// the remote's note wins. (The local test results themselves are stored
+/// separately, so nothing is lost.) Returns `false` if the remote doesn't have
+/// any shared test results.
+#[instrument]
+fn fetch_results_notes(
+    git_run_info: &GitRunInfo,
+    repo: &Repo,
+    event_tx_id: EventTransactionId,
+    remote: &str,
+) -> eyre::Result<bool> {
+    let GitRunResult {
+        exit_code: _,
+        stdout,
+        stderr: _,
+    } = git_run_info.run_silent(
+        repo,
+        Some(event_tx_id),
+        &["ls-remote", remote, TEST_RESULTS_NOTES_REF],
+        GitRunOpts {
+            treat_git_failure_as_error: true,
+            stdin: None,
+        },
+    )?;
+    if stdout.trim().is_empty() {
+        return Ok(false);
+    }
+
+    for args in [
+        vec![
+            "fetch",
+            remote,
+            &format!("+{TEST_RESULTS_NOTES_REF}:{TEST_RESULTS_FETCHED_NOTES_REF}"),
+        ],
+        vec![
+            "notes",
+            &format!("--ref={TEST_RESULTS_NOTES_REF}"),
+            "merge",
+            "--quiet",
+            "--strategy=theirs",
+            TEST_RESULTS_FETCHED_NOTES_REF,
+        ],
+    ] {
+        let GitRunResult {
+            exit_code,
+            stdout: _,
+            stderr,
+        } = git_run_info.run_silent(
+            repo,
+            Some(event_tx_id),
+            &args,
+            GitRunOpts {
+                treat_git_failure_as_error: false,
+                stdin: None,
+            },
+        )?;
+        if !exit_code.is_success() {
+            eyre::bail!(
+                "Could not fetch test results from remote {remote:?}: {}",
+                String::from_utf8_lossy(&stderr)
+            );
+        }
+    }
+    Ok(true)
+}
+
+/// Read all the cached test results for the given commit from disk.
+#[instrument]
+fn read_local_test_results(repo: &Repo, commit: &Commit) -> eyre::Result<SharedTestResults> {
+    let tree_dir = get_test_tree_dir(repo, commit);
+    let mut results = SharedTestResults::new();
+    let entries = match std::fs::read_dir(&tree_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(results),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("Reading test directory {tree_dir:?}"))
+        }
+    };
+    for entry in entries {
+        let entry = entry.wrap_err_with(|| format!("Reading test directory {tree_dir:?}"))?;
+        let command_dir = entry.path();
+        let contents = match std::fs::read_to_string(command_dir.join("result")) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        let result: SerializedTestResult = match serde_json::from_str(&contents) {
+            Ok(result) => result,
+            Err(err) => {
+                // Possibly a test which is still running.
+                debug!(?command_dir, ?err, "Skipping unreadable test result");
+                continue;
+            }
+        };
+        let read_output = |name: &str| -> String {
+            std::fs::read(command_dir.join(name))
+                .map(|contents| String::from_utf8_lossy(&contents).into_owned())
+                .unwrap_or_default()
+        };
+        let shared_result = SharedTestResult {
+            result: SerializedTestResult {
+                // The fix info refers to objects which only exist in this
+                // repository, so don't share it.
+                head_commit_oid: None,
+                snapshot_tree_oid: None,
+                ..result
+            },
+            stdout: read_output("stdout"),
+            stderr: read_output("stderr"),
+        };
+        let slug = make_test_command_slug(shared_result.result.command.to_string());
+        results.insert(slug, shared_result);
+    }
+    Ok(results)
+}
+
+/// Push the cached test results for the commits in `revset` to a remote.
+#[instrument]
+fn subcommand_push_results(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    remote: Option<String>,
+    revset: Revset,
+    resolve_revset_options: &ResolveRevsetOptions,
+) -> EyreExitOr<()> {
+    let now = SystemTime::now();
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_tx_id = event_log_db.make_transaction_id(now, "test push-results")?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    let references_snapshot = repo.get_references_snapshot()?;
+    let mut dag = Dag::open_and_sync(
+        effects,
+        &repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    )?;
+
+    let commit_set =
+        match resolve_commits(effects, &repo, &mut dag, &[revset], resolve_revset_options) {
+            Ok(mut commit_sets) => commit_sets.pop().unwrap(),
+            Err(err) => {
+                err.describe(effects)?;
+                return Ok(Err(ExitCode(1)));
+            }
+        };
+    let remote = try_exit_code!(resolve_results_remote(effects, &repo, remote)?);
+
+    // Build on top of the remote's shared results, so that the push is a
+    // fast-forward and doesn't clobber anyone else's results.
+    fetch_results_notes(git_run_info, &repo, event_tx_id, &remote)?;
+
+    let notes_ref = ReferenceName::from(TEST_RESULTS_NOTES_REF);
+    let mut num_shared_results = 0;
+    for commit in sorted_commit_set(&repo, &dag, &commit_set)? {
+        let local_results = read_local_test_results(&repo, &commit)?;
+        if local_results.is_empty() {
+            continue;
+        }
+
+        let tree_oid = commit.get_tree()?.get_oid();
+        let mut shared_results: SharedTestResults = match repo.find_note(&notes_ref, tree_oid)? {
+            Some(message) => serde_json::from_str(&message).unwrap_or_else(|err| {
+                warn!(?tree_oid, ?err, "Discarding unreadable shared test results");
+                Default::default()
+            }),
+            None => Default::default(),
+        };
+        writeln!(
+            effects.get_output_stream(),
+            "Sharing {} for {}",
+            Pluralize {
+                determiner: None,
+                amount: local_results.len(),
+                unit: ("test result", "test results"),
+            },
+            effects
+                .get_glyphs()
+                .render(commit.friendly_describe(effects.get_glyphs())?)?,
+        )?;
+        num_shared_results += local_results.len();
+        shared_results.extend(local_results);
+        let message = serde_json::to_string_pretty(&shared_results)
+            .wrap_err("Serializing shared test results")?;
+        repo.create_note(&notes_ref, tree_oid, &message, now)?;
+    }
+
+    if num_shared_results == 0 {
+        writeln!(effects.get_output_stream(), "No test results to push.")?;
+        return Ok(Ok(()));
+    }
+    try_exit_code!(git_run_info.run(
+        effects,
+        Some(event_tx_id),
+        &["push", &remote, TEST_RESULTS_NOTES_REF],
+    )?);
+    writeln!(
+        effects.get_output_stream(),
+        "Pushed {} to {remote}.",
+        Pluralize {
+            determiner: None,
+            amount: num_shared_results,
+            unit: ("test result", "test results"),
+        },
+    )?;
+    Ok(Ok(()))
+}
+
+/// Fetch the test results shared on a remote and add them to the local cache
+/// of test results. Local test results take precedence over shared ones.
+#[instrument]
+fn subcommand_fetch_results(
+    effects: &Effects,
+    git_run_info: &GitRunInfo,
+    remote: Option<String>,
+) -> EyreExitOr<()> {
+    let now = SystemTime::now();
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_tx_id = event_log_db.make_transaction_id(now, "test fetch-results")?;
+
+    let remote = try_exit_code!(resolve_results_remote(effects, &repo, remote)?);
+    if !fetch_results_notes(git_run_info, &repo, event_tx_id, &remote)? {
+        writeln!(
+            effects.get_output_stream(),
+            "No test results have been pushed to {remote}."
+        )?;
+        return Ok(Ok(()));
+    }
+
+    let notes_ref = ReferenceName::from(TEST_RESULTS_NOTES_REF);
+    let mut num_fetched_results = 0;
+    for tree_oid in repo.get_all_noted_objects(&notes_ref)? {
+        let message = match repo.find_note(&notes_ref, tree_oid)? {
+            Some(message) => message,
+            None => continue,
+        };
+        let shared_results: SharedTestResults = match serde_json::from_str(&message) {
+            Ok(shared_results) => shared_results,
+            Err(err) => {
+                warn!(?tree_oid, ?err, "Skipping unreadable shared test results");
+                continue;
+            }
+        };
+
+        let tree_dir = get_test_tree_dir_for_tree_oid(&repo, tree_oid.into());
+        for (slug, shared_result) in shared_results {
+            // Only trust slugs which could have been produced by us, so that a
+            // malicious note can't write outside of the test directory.
+            let is_single_component = matches!(
+                Path::new(&slug).components().collect_vec().as_slice(),
+                [Component::Normal(_)]
+            );
+            if !is_single_component
+                || slug != make_test_command_slug(shared_result.result.command.to_string())
+            {
+                warn!(
+                    ?tree_oid,
+                    ?slug,
+                    "Skipping shared test result with invalid slug"
+                );
+                continue;
+            }
+            if import_shared_test_result(&tree_dir.join(slug), shared_result)? {
+                num_fetched_results += 1;
+            }
+        }
+    }
+
+    writeln!(
+        effects.get_output_stream(),
+        "Fetched {} from {remote}.",
+        Pluralize {
+            determiner: None,
+            amount: num_fetched_results,
+            unit: ("new test result", "new test results"),
+        },
+    )?;
+    Ok(Ok(()))
+}
+
+/// Write a shared test result into the local cache at `command_dir`, unless
+/// there's already a result there. Returns whether the result was written.
+#[instrument]
+fn import_shared_test_result(
+    command_dir: &Path,
+    shared_result: SharedTestResult,
+) -> eyre::Result<bool> {
+    let SharedTestResult {
+        result,
+        stdout,
+        stderr,
+    } = shared_result;
+
+    std::fs::create_dir_all(command_dir)
+        .wrap_err_with(|| format!("Creating command directory {command_dir:?}"))?;
+    let lock_path = command_dir.join("pid.lock");
+    let mut lock_file =
+        LockFile::open(&lock_path).wrap_err_with(|| format!("Opening lock file {lock_path:?}"))?;
+    if !lock_file
+        .try_lock_with_pid()
+        .wrap_err_with(|| format!("Locking file {lock_path:?}"))?
+    {
+        // The test is currently being run locally.
+        return Ok(false);
+    }
+
+    let result_path = command_dir.join("result");
+    match std::fs::read_to_string(&result_path) {
+        Ok(contents) if !contents.is_empty() => return Ok(false),
+        Ok(_) | Err(_) => {}
+    }
+
+    let stdout_path = command_dir.join("stdout");
+    std::fs::write(&stdout_path, stdout)
+        .wrap_err_with(|| format!("Writing stdout file {stdout_path:?}"))?;
+    let stderr_path = command_dir.join("stderr");
+    std::fs::write(&stderr_path, stderr)
+        .wrap_err_with(|| format!("Writing stderr file {stderr_path:?}"))?;
+    let result_file = File::create(&result_path)
+        .wrap_err_with(|| format!("Opening result file {result_path:?}"))?;
+    serde_json::to_writer_pretty(result_file, &result)
+        .wrap_err_with(|| format!("Writing test result to {result_path:?}"))?;
+    Ok(true)
+}
+
 #[cfg(test)]
 mod tests {
     use git_branchless_testing::make_git;
@@ -0,0 +1,397 @@
+//! Machine-readable reports of test results, such as for consumption by CI
+//! systems.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use eyre::WrapErr;
+use git_branchless_opts::{TestReport, TestReportFormat};
+use itertools::Itertools;
+use lib::git::{Commit, TestCommand};
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::{TestResults, TestStatus};
+
+/// The result of testing a single commit, as it appears in a report.
+#[derive(Debug, Serialize)]
+struct ReportEntry {
+    command: String,
+    commit_oid: String,
+    short_commit_oid: String,
+    summary: String,
+    status: &'static str,
+    message: String,
+    exit_code: Option<i32>,
+    cached: bool,
+    duration_secs: f64,
+
+    /// The paths to the command's output, or `None` if the output was written
+    /// to a temporary directory (such as for `--no-cache`), which has already
+    /// been deleted by the time the report is written.
+    stdout_path: Option<PathBuf>,
+    stderr_path: Option<PathBuf>,
+
+    #[serde(skip)]
+    junit_outcome: JunitOutcome,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    results: &'a [ReportEntry],
+}
+
+/// How a test status is categorized in a JUnit report.
+#[derive(Clone, Copy, Debug)]
+enum JunitOutcome {
+    Passed,
+    Failure,
+    Error,
+    Skipped,
+}
+
+fn get_status_name(test_status: &TestStatus) -> &'static str {
+    match test_status {
+        TestStatus::CheckoutFailed => "checkout-failed",
+        TestStatus::SpawnTestFailed(_) => "spawn-test-failed",
+        TestStatus::TerminatedBySignal => "terminated-by-signal",
+        TestStatus::AlreadyInProgress => "already-in-progress",
+        TestStatus::ReadCacheFailed(_) => "read-cache-failed",
+        TestStatus::Indeterminate { .. } => "indeterminate",
+        TestStatus::Abort { .. } => "abort",
+        TestStatus::TimedOut { .. } => "timed-out",
+        TestStatus::Failed { .. } => "failed",
+        TestStatus::Passed { .. } => "passed",
+        TestStatus::Flaky { .. } => "flaky",
+    }
+}
+
+fn get_exit_code(test_status: &TestStatus) -> Option<i32> {
+    match test_status {
+        TestStatus::CheckoutFailed
+        | TestStatus::SpawnTestFailed(_)
+        | TestStatus::TerminatedBySignal
+        | TestStatus::AlreadyInProgress
+        | TestStatus::ReadCacheFailed(_)
+        | TestStatus::TimedOut { .. } => None,
+        TestStatus::Indeterminate { exit_code }
+        | TestStatus::Abort { exit_code }
+        | TestStatus::Failed { exit_code, .. } => Some(*exit_code),
+        TestStatus::Passed { .. } | TestStatus::Flaky { .. } => Some(0),
+    }
+}
+
+fn is_cached(test_status: &TestStatus) -> bool {
+    match test_status {
+        TestStatus::CheckoutFailed
+        | TestStatus::SpawnTestFailed(_)
+        | TestStatus::TerminatedBySignal
+        | TestStatus::AlreadyInProgress
+        | TestStatus::ReadCacheFailed(_)
+        | TestStatus::Indeterminate { .. }
+        | TestStatus::Abort { .. } => false,
+        TestStatus::TimedOut { cached }
+        | TestStatus::Failed { cached, .. }
+        | TestStatus::Passed { cached, .. }
+        | TestStatus::Flaky { cached, .. } => *cached,
+    }
+}
+
+/// Categorize the test status for a JUnit report, along with a description.
+fn get_junit_outcome(test_status: &TestStatus) -> (JunitOutcome, String) {
+    match test_status {
+        TestStatus::CheckoutFailed => (JunitOutcome::Error, "Failed to check out".to_string()),
+        TestStatus::SpawnTestFailed(err) => (
+            JunitOutcome::Error,
+            format!("Failed to spawn command: {err}"),
+        ),
+        TestStatus::TerminatedBySignal => (
+            JunitOutcome::Error,
+            "Command terminated by signal".to_string(),
+        ),
+        TestStatus::AlreadyInProgress => (
+            JunitOutcome::Error,
+            "Command already in progress".to_string(),
+        ),
+        TestStatus::ReadCacheFailed(err) => (
+            JunitOutcome::Error,
+            format!("Could not read cached command result: {err}"),
+        ),
+        TestStatus::Indeterminate { exit_code } => (
+            JunitOutcome::Skipped,
+            format!("Exit code indicated to skip this commit (exit code {exit_code})"),
+        ),
+        TestStatus::Abort { exit_code } => (
+            JunitOutcome::Failure,
+            format!("Exit code indicated to abort testing (exit code {exit_code})"),
+        ),
+        TestStatus::TimedOut { .. } => (JunitOutcome::Failure, "Timed out".to_string()),
+        TestStatus::Failed { exit_code, .. } => (
+            JunitOutcome::Failure,
+            format!("Failed (exit code {exit_code})"),
+        ),
+        TestStatus::Passed { .. } => (JunitOutcome::Passed, "Passed".to_string()),
+        TestStatus::Flaky { .. } => (
+            JunitOutcome::Passed,
+            "Passed when retried (flaky)".to_string(),
+        ),
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters (such as the escape character in colored
+            // output) aren't allowed in XML 1.0, even when escaped.
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => escaped.push('\u{FFFD}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_json(entries: &[ReportEntry]) -> eyre::Result<String> {
+    let report = JsonReport { results: entries };
+    let mut contents =
+        serde_json::to_string_pretty(&report).wrap_err("Serializing JSON test report")?;
+    contents.push('\n');
+    Ok(contents)
+}
+
+/// The counts and rendered test cases for a single `<testsuite>` element.
+#[derive(Debug, Default)]
+struct JunitSuite {
+    num_tests: usize,
+    num_failures: usize,
+    num_errors: usize,
+    num_skipped: usize,
+    duration_secs: f64,
+    test_cases: String,
+}
+
+impl JunitSuite {
+    fn get_counts(&self) -> String {
+        let Self {
+            num_tests,
+            num_failures,
+            num_errors,
+            num_skipped,
+            duration_secs,
+            test_cases: _,
+        } = self;
+        format!(
+            r#"tests="{num_tests}" failures="{num_failures}" errors="{num_errors}" skipped="{num_skipped}" time="{duration_secs:.3}""#
+        )
+    }
+}
+
+fn render_junit_suite(command: &str, entries: &[&ReportEntry]) -> eyre::Result<JunitSuite> {
+    let command = escape_xml(command);
+    let mut num_failures = 0;
+    let mut num_errors = 0;
+    let mut num_skipped = 0;
+    let mut test_cases = String::new();
+    for entry in entries.iter().copied() {
+        let ReportEntry {
+            command: _,
+            commit_oid,
+            short_commit_oid,
+            summary,
+            status,
+            message,
+            exit_code,
+            cached,
+            duration_secs,
+            stdout_path,
+            stderr_path,
+            junit_outcome,
+        } = entry;
+        writeln!(
+            test_cases,
+            r#"    <testcase classname="{command}" name="{}" time="{duration_secs:.3}">"#,
+            escape_xml(&format!("{short_commit_oid} {summary}")),
+        )?;
+        writeln!(test_cases, "      <properties>")?;
+        writeln!(
+            test_cases,
+            r#"        <property name="commit" value="{commit_oid}"/>"#
+        )?;
+        writeln!(
+            test_cases,
+            r#"        <property name="status" value="{status}"/>"#
+        )?;
+        if let Some(exit_code) = exit_code {
+            writeln!(
+                test_cases,
+                r#"        <property name="exit_code" value="{exit_code}"/>"#
+            )?;
+        }
+        writeln!(
+            test_cases,
+            r#"        <property name="cached" value="{cached}"/>"#
+        )?;
+        writeln!(test_cases, "      </properties>")?;
+
+        let message = escape_xml(message);
+        match junit_outcome {
+            JunitOutcome::Passed => {}
+            JunitOutcome::Failure => {
+                num_failures += 1;
+                writeln!(test_cases, r#"      <failure message="{message}"/>"#)?;
+            }
+            JunitOutcome::Error => {
+                num_errors += 1;
+                writeln!(test_cases, r#"      <error message="{message}"/>"#)?;
+            }
+            JunitOutcome::Skipped => {
+                num_skipped += 1;
+                writeln!(test_cases, r#"      <skipped message="{message}"/>"#)?;
+            }
+        }
+
+        // The `[[ATTACHMENT|...]]` syntax is understood by several CI systems
+        // as a link to a file, rather than inline output.
+        if let Some(stdout_path) = stdout_path {
+            writeln!(
+                test_cases,
+                "      <system-out>[[ATTACHMENT|{}]]</system-out>",
+                escape_xml(&stdout_path.to_string_lossy()),
+            )?;
+        }
+        if let Some(stderr_path) = stderr_path {
+            writeln!(
+                test_cases,
+                "      <system-err>[[ATTACHMENT|{}]]</system-err>",
+                escape_xml(&stderr_path.to_string_lossy()),
+            )?;
+        }
+        writeln!(test_cases, "    </testcase>")?;
+    }
+
+    Ok(JunitSuite {
+        num_tests: entries.len(),
+        num_failures,
+        num_errors,
+        num_skipped,
+        duration_secs: entries.iter().map(|entry| entry.duration_secs).sum(),
+        test_cases,
+    })
+}
+
+/// Render a JUnit report with one `<testsuite>` per command.
+fn render_junit(commands: &[&TestCommand], entries: &[ReportEntry]) -> eyre::Result<String> {
+    let mut suites = Vec::new();
+    for command in commands {
+        let command = command.to_string();
+        let command_entries = entries
+            .iter()
+            .filter(|entry| entry.command == command)
+            .collect_vec();
+        let suite = render_junit_suite(&command, &command_entries)?;
+        suites.push((command, suite));
+    }
+
+    let total = suites
+        .iter()
+        .fold(JunitSuite::default(), |acc, (_, suite)| JunitSuite {
+            num_tests: acc.num_tests + suite.num_tests,
+            num_failures: acc.num_failures + suite.num_failures,
+            num_errors: acc.num_errors + suite.num_errors,
+            num_skipped: acc.num_skipped + suite.num_skipped,
+            duration_secs: acc.duration_secs + suite.duration_secs,
+            test_cases: String::new(),
+        });
+    let mut contents = String::new();
+    writeln!(contents, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(contents, "<testsuites {}>", total.get_counts())?;
+    for (command, suite) in suites {
+        writeln!(
+            contents,
+            r#"  <testsuite name="{}" {}>"#,
+            escape_xml(&command),
+            suite.get_counts()
+        )?;
+        contents.push_str(&suite.test_cases);
+        writeln!(contents, "  </testsuite>")?;
+    }
+    writeln!(contents, "</testsuites>")?;
+    Ok(contents)
+}
+
+/// Write each of the requested reports for the given test results, which are
+/// paired with the command that produced them. Commits which weren't tested
+/// (such as due to a search) are omitted from the reports.
+#[instrument]
+pub(crate) fn write_reports(
+    commits: &[Commit],
+    test_results: &[(&TestCommand, &TestResults)],
+    reports: &[TestReport],
+) -> eyre::Result<()> {
+    if reports.is_empty() {
+        return Ok(());
+    }
+
+    let mut entries = Vec::new();
+    for (command, test_results) in test_results {
+        for commit in commits {
+            let test_output = match test_results.test_outputs.get(&commit.get_oid()) {
+                Some(test_output) => test_output,
+                None => continue,
+            };
+            let (junit_outcome, message) = get_junit_outcome(&test_output.test_status);
+            let is_output_kept = test_output.temp_dir.is_none();
+            entries.push(ReportEntry {
+                command: command.to_string(),
+                commit_oid: commit.get_oid().to_string(),
+                short_commit_oid: commit.get_short_oid()?,
+                summary: commit.get_summary()?.to_string(),
+                status: get_status_name(&test_output.test_status),
+                message,
+                exit_code: get_exit_code(&test_output.test_status),
+                cached: is_cached(&test_output.test_status),
+                duration_secs: test_output.duration.as_secs_f64(),
+                stdout_path: is_output_kept.then(|| test_output.stdout_path.clone()),
+                stderr_path: is_output_kept.then(|| test_output.stderr_path.clone()),
+                junit_outcome,
+            });
+        }
+    }
+
+    let commands = test_results
+        .iter()
+        .map(|(command, _)| *command)
+        .collect_vec();
+    for TestReport { format, path } in reports {
+        let contents = match format {
+            TestReportFormat::Junit => render_junit(&commands, &entries)?,
+            TestReportFormat::Json => render_json(&entries)?,
+        };
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("Writing test report to {path:?}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("<a href=\"x\">it's & more</a>"),
+            "&lt;a href=&quot;x&quot;&gt;it&apos;s &amp; more&lt;/a&gt;"
+        );
+        assert_eq!(
+            escape_xml("\x1b[31mred\x1b[0m\tand\nnot\x00"),
+            "\u{FFFD}[31mred\u{FFFD}[0m\tand\nnot\u{FFFD}"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use git_branchless_testing::{make_git, GitRunOptions};
+
+#[test]
+fn test_bisect_manual() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+    git.commit_file("test4", 4)?;
+    git.commit_file("test5", 5)?;
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "bisect",
+            &["status"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        No bisection is in progress. To start one, run: git branchless bisect start <revset>
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["start", "stack()"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 5 commits: stack()
+        There are no good commits yet.
+        There are no bad commits yet.
+        5 commits left to test. Next commit to test: 70deb1e create test3.txt
+        branchless: running command: <git-executable> checkout 70deb1e28791d8e7dd5a1f0c871a51b91282562f
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "bisect",
+            &["start"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        A bisection is already in progress. To abandon it, run: git branchless bisect reset
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["good"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 5 commits: stack()
+        Last good commit:
+        - 70deb1e create test3.txt
+        There are no bad commits yet.
+        2 commits left to test. Next commit to test: 355e173 create test4.txt
+        branchless: running command: <git-executable> checkout 355e173bf9c5d2efac2e451da0cdad3fb82b869a
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "bisect",
+            &["good", "master"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Commit is not part of the current bisection: f777ecc create initial.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["bad"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 5 commits: stack()
+        Last good commit:
+        - 70deb1e create test3.txt
+        First bad commit:
+        - 355e173 create test4.txt
+        Bisection complete. To return to where you started, run: git branchless bisect reset
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "bisect",
+            &["good", "children(@)"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Cannot mark commit as good because it contradicts the commits marked so far: f81d55c create test5.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["status"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 5 commits: stack()
+        Last good commit:
+        - 70deb1e create test3.txt
+        First bad commit:
+        - 355e173 create test4.txt
+        Bisection complete. To return to where you started, run: git branchless bisect reset
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["reset"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Stopped bisecting: stack()
+        branchless: running command: <git-executable> checkout f81d55c0d520ff8d02ef9294d95156dcb78a5255
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d create test1.txt
+        |
+        o 96d1c37 create test2.txt
+        |
+        o 70deb1e create test3.txt
+        |
+        o 355e173 create test4.txt
+        |
+        @ f81d55c create test5.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_bisect_non_linear_run() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.run(&["checkout", "-b", "feature", "HEAD~"])?;
+    git.commit_file("test3", 3)?;
+    git.commit_file("test4", 4)?;
+    git.commit_file("test5", 5)?;
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["start", "draft() | master"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 4 commits: draft() | master
+        There are no good commits yet.
+        There are no bad commits yet.
+        4 commits left to test. Next commit to test: a248207 create test4.txt
+        branchless: running command: <git-executable> checkout a248207402822b7396cabe0f1011d8a7ce7daf1b
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["skip"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 4 commits: draft() | master
+        There are no good commits yet.
+        There are no bad commits yet.
+        3 commits left to test. Next commit to test: 96d1c37 create test2.txt
+        branchless: running command: <git-executable> checkout master
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) =
+            git.branchless("bisect", &["run", "--exec", "! git grep -q 'test4'"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        Using test search strategy: binary
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 96d1c37 create test2.txt
+        ✓ Passed: 4838e49 create test3.txt
+        X Failed (exit code 1): 566e434 create test5.txt
+        Bisecting 4 commits: draft() | master
+        Last good commits:
+        - 96d1c37 create test2.txt
+        - 4838e49 create test3.txt
+        First bad commit:
+        - 566e434 create test5.txt
+        Bisection complete. To return to where you started, run: git branchless bisect reset
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["reset"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Stopped bisecting: draft() | master
+        branchless: running command: <git-executable> checkout feature
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        O 62fc20d create test1.txt
+        |\
+        | o 4838e49 create test3.txt
+        | |
+        | o a248207 create test4.txt
+        | |
+        | @ 566e434 (> feature) create test5.txt
+        |
+        O 96d1c37 (master) create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_bisect_hand_off_to_test_run() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.run(&["checkout", "-b", "feature", "HEAD~"])?;
+    git.commit_file("test3", 3)?;
+    git.commit_file("test4", 4)?;
+    git.commit_file("test5", 5)?;
+
+    git.branchless("bisect", &["start", "draft() | master"])?;
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["skip"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 4 commits: draft() | master
+        There are no good commits yet.
+        There are no bad commits yet.
+        3 commits left to test. Next commit to test: 96d1c37 create test2.txt
+        branchless: running command: <git-executable> checkout master
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless(
+            "test",
+            &["run", "--bisect", "--exec", "! git grep -q 'test4'"],
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Continuing the bisection in progress for: draft() | master
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        Using test search strategy: binary
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 96d1c37 create test2.txt
+        ✓ Passed: 4838e49 create test3.txt
+        X Failed (exit code 1): 566e434 create test5.txt
+        Bisecting 4 commits: draft() | master
+        Last good commits:
+        - 96d1c37 create test2.txt
+        - 4838e49 create test3.txt
+        First bad commit:
+        - 566e434 create test5.txt
+        Bisection complete. To return to where you started, run: git branchless bisect reset
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("bisect", &["status"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Bisecting 4 commits: draft() | master
+        Last good commits:
+        - 96d1c37 create test2.txt
+        - 4838e49 create test3.txt
+        First bad commit:
+        - 566e434 create test5.txt
+        Bisection complete. To return to where you started, run: git branchless bisect reset
+        "###);
+    }
+
+    Ok(())
+}
@@ -1,4 +1,7 @@
-use git_branchless_testing::{make_git, GitRunOptions, GitWrapper};
+use git_branchless_testing::{
+    make_git, make_git_with_remote_repo, GitInitOptions, GitRunOptions, GitWrapper,
+    GitWrapperWithRemoteRepo,
+};
 use maplit::hashmap;
 
 fn write_test_script(git: &GitWrapper) -> eyre::Result<()> {
@@ -593,6 +596,118 @@ fn test_test_worktree_strategy() -> eyre::Result<()> {
     Ok(())
 }
 
+#[cfg(unix)] // Paths don't match on Windows.
+#[test]
+fn test_test_remote_strategy() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.commit_file("test1", 1)?;
+    git.run(&["checkout", "--detach"])?;
+    git.commit_file("test2", 2)?;
+
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "test",
+            &["run", "--strategy", "remote", "-x", "echo hello", "@"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        The remote execution strategy requires a command to hand each commit off to,
+        but none was configured.
+
+        To configure one, run: git config branchless.test.remoteCommand <command>
+        "###);
+    }
+
+    git.run(&[
+        "config",
+        "branchless.test.remoteCommand",
+        r#"dir=$(mktemp -d) && git clone -q --single-branch --branch master . "$dir" && cd "$dir" && git fetch -q "$BRANCHLESS_TEST_BUNDLE" "$BRANCHLESS_TEST_COMMIT" && git checkout -q FETCH_HEAD && sh -c "$BRANCHLESS_TEST_COMMAND""#,
+    ])?;
+
+    {
+        let (stdout, stderr) = git.branchless(
+            "test",
+            &[
+                "run",
+                "--strategy",
+                "remote",
+                "-x",
+                "cat test1.txt test2.txt",
+                "-vv",
+                "@",
+            ],
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: remote
+        ✓ Passed: 96d1c37 create test2.txt
+        Stdout: <repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/cat__test1.txt__test2.txt/stdout
+        test1 contents
+        test2 contents
+        Stderr: <repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/cat__test1.txt__test2.txt/stderr
+        <no output>
+        Ran command on 1 commit: cat test1.txt test2.txt
+        1 passed, 0 failed, 0 skipped
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.branchless(
+            "test",
+            &[
+                "run",
+                "--strategy",
+                "remote",
+                "-x",
+                "cat test1.txt test2.txt",
+                "@",
+            ],
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: remote
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        Ran command on 1 commit: cat test1.txt test2.txt
+        1 passed, 0 failed, 0 skipped
+        hint: there was 1 cached test result
+        hint: to clear these cached results, run: git test clean "@"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "test",
+            &["run", "--strategy", "remote", "-x", "exit 1", "@"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: remote
+        X Failed (exit code 1): 96d1c37 create test2.txt
+        Ran command on 1 commit: exit 1
+        0 passed, 1 failed, 0 skipped
+        "###);
+    }
+
+    // Make sure the temporary bundle reference was cleaned up.
+    {
+        let (stdout, _stderr) = git.run(&["for-each-ref", "refs/branchless-test/"])?;
+        insta::assert_snapshot!(stdout, @"");
+    }
+
+    Ok(())
+}
+
 #[cfg(unix)] // Paths don't match on Windows.
 #[test]
 fn test_test_config_strategy() -> eyre::Result<()> {
@@ -660,7 +775,7 @@ echo hello
         insta::assert_snapshot!(stderr, @"");
         insta::assert_snapshot!(stdout, @r###"
         Invalid value for config value branchless.test.strategy: invalid-value
-        Expected one of: working-copy, worktree
+        Expected one of: working-copy, worktree, remote
         "###);
     }
 
@@ -1421,6 +1536,89 @@ fi
     Ok(())
 }
 
+#[test]
+fn test_test_search_probabilistic() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+    git.commit_file("test4", 4)?;
+    git.commit_file("test5", 5)?;
+
+    // Commits with the bug only fail every other time that they're tested.
+    let test_command = "if git grep -q test4; then n=$(cat .git/flaky-count 2>/dev/null || echo 0); echo $((n + 1)) > .git/flaky-count; exit $((n % 2)); fi";
+
+    {
+        git.run(&["config", "branchless.test.search.failureRate", "2"])?;
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "--search", "probabilistic", "--exec", test_command],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Invalid value for config value branchless.test.search.failureRate: 2
+        Expected a number between 0 and 1.
+        "###);
+    }
+
+    {
+        git.run(&["config", "branchless.test.search.failureRate", "0.5"])?;
+        git.run(&["config", "branchless.test.search.confidence", "0"])?;
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "--search", "probabilistic", "--exec", test_command],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Invalid value for config value branchless.test.search.confidence: 0
+        Expected a number greater than 0 and less than 1.
+        "###);
+    }
+
+    {
+        git.run(&["config", "branchless.test.search.failureRate", "0.5"])?;
+        git.run(&["config", "branchless.test.search.confidence", "0.9"])?;
+        let (stdout, _stderr) = git.branchless(
+            "test",
+            &["run", "--search", "probabilistic", "--exec", test_command],
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        Using test search strategy: probabilistic
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 96d1c37 create test2.txt
+        ✓ Passed: 70deb1e create test3.txt
+        X Failed (exit code 1): 355e173 create test4.txt
+        Ran command on 3 commits: if git grep -q test4; then n=$(cat .git/flaky-count 2>/dev/null || echo 0); echo $((n + 1)) > .git/flaky-count; exit $((n % 2)); fi
+        2 passed, 1 failed, 0 skipped
+        Last passing commit:
+        - 70deb1e create test3.txt
+        First failing commit:
+        - 355e173 create test4.txt
+        Confidence: 94.1%
+        "###);
+    }
+
+    // The commit with the bug passed the first time that it was tested, so it
+    // had to be re-tested.
+    let num_tests = std::fs::read_to_string(git.repo_path.join(".git").join("flaky-count"))?;
+    assert_eq!(num_tests.trim(), "2");
+
+    Ok(())
+}
+
 #[test]
 fn test_test_interactive() -> eyre::Result<()> {
     let git = make_git()?;
@@ -1897,3 +2095,950 @@ fn test_test_no_cache() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_test_push_and_fetch_results() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    original_repo.init_repo()?;
+    original_repo.commit_file("test1", 1)?;
+    original_repo.commit_file("test2", 2)?;
+    original_repo.clone_repo_into(&cloned_repo, &[])?;
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+
+    {
+        let (stdout, stderr) = cloned_repo.branchless("test", &["push-results"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        No test results to push.
+        "###);
+    }
+
+    cloned_repo.branchless("test", &["run", "-x", "echo hello", "@"])?;
+    cloned_repo.branchless_with_options(
+        "test",
+        &["run", "-x", "echo goodbye && exit 1", "@"],
+        &GitRunOptions {
+            expected_exit_code: 1,
+            ..Default::default()
+        },
+    )?;
+    {
+        let (stdout, _stderr) = cloned_repo.branchless("test", &["push-results"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Sharing 2 test results for 96d1c37 create test2.txt
+        branchless: running command: <git-executable> push origin refs/notes/branchless-test
+        Pushed 2 test results to origin.
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = original_repo.branchless_with_options(
+            "test",
+            &["fetch-results"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        No remote was specified, and the main branch has no default push remote.
+        Specify which remote to use with: --remote <remote>
+        "###);
+    }
+
+    {
+        let (stdout, stderr) =
+            original_repo.branchless("test", &["fetch-results", "--remote", "../cloned"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        Fetched 2 new test results from ../cloned.
+        "###);
+    }
+
+    {
+        let (stdout, stderr) =
+            original_repo.branchless("test", &["fetch-results", "--remote", "../cloned"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        Fetched 0 new test results from ../cloned.
+        "###);
+    }
+
+    {
+        let (stdout, stderr) =
+            original_repo.branchless("test", &["show", "-x", "echo goodbye && exit 1", "-v"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        X Failed (cached, exit code 1): 96d1c37 create test2.txt
+        Stdout: <repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/echo__goodbye__&&__exit__1/stdout
+        goodbye
+        Stderr: <repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/echo__goodbye__&&__exit__1/stderr
+        <no output>
+        hint: to see more detailed output, re-run with -vv/--verbose --verbose
+        hint: disable this hint by running: git config --global branchless.hint.testShowVerbose false
+        "###);
+    }
+
+    {
+        let (stdout, stderr) =
+            original_repo.branchless("query", &["tests.passed('echo hello')"])?;
+        insta::assert_snapshot!(stderr, @"");
+        insta::assert_snapshot!(stdout, @r###"
+        96d1c37 create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_fetch_results_merges_notes() -> eyre::Result<()> {
+    let GitWrapperWithRemoteRepo {
+        temp_dir: _guard,
+        original_repo,
+        cloned_repo,
+    } = make_git_with_remote_repo()?;
+
+    original_repo.init_repo()?;
+    original_repo.commit_file("test1", 1)?;
+    original_repo.commit_file("test2", 2)?;
+    original_repo.clone_repo_into(&cloned_repo, &[])?;
+    cloned_repo.init_repo_with_options(&GitInitOptions {
+        make_initial_commit: false,
+        ..Default::default()
+    })?;
+
+    // A note whose slug would escape the test directory.
+    let (tree_oid, _stderr) = cloned_repo.run(&["rev-parse", "HEAD^{tree}"])?;
+    let tree_oid = tree_oid.trim();
+    cloned_repo.run(&[
+        "notes",
+        "--ref=branchless-test",
+        "add",
+        "-m",
+        r#"{"..": {"result": {"command": {"String": ".."}, "exit_code": 0, "head_commit_oid": null, "snapshot_tree_oid": null}, "stdout": "", "stderr": ""}}"#,
+        tree_oid,
+    ])?;
+    cloned_repo.run(&["push", "origin", "refs/notes/branchless-test"])?;
+
+    // A local note which isn't on the remote shouldn't be overwritten.
+    let (local_tree_oid, _stderr) = original_repo.run(&["rev-parse", "HEAD^^{tree}"])?;
+    let local_tree_oid = local_tree_oid.trim();
+    original_repo.run(&[
+        "notes",
+        "--ref=branchless-test",
+        "add",
+        "-m",
+        "{}",
+        local_tree_oid,
+    ])?;
+
+    {
+        // The invalid slug is reported as a warning in the logs.
+        let (stdout, _stderr) =
+            original_repo.branchless("test", &["fetch-results", "--remote", "../cloned"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Fetched 0 new test results from ../cloned.
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) =
+            original_repo.run(&["notes", "--ref=branchless-test", "show", local_tree_oid])?;
+        insta::assert_snapshot!(stdout, @r###"
+        {}
+        "###);
+    }
+    assert!(!original_repo
+        .repo_path
+        .join(".git")
+        .join("branchless")
+        .join("test")
+        .join("result")
+        .exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_test_reports() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+
+    let command = "! test -f test2.txt";
+    git.branchless_with_options(
+        "test",
+        &["run", "-x", command],
+        &GitRunOptions {
+            expected_exit_code: 1,
+            ..Default::default()
+        },
+    )?;
+
+    // Re-run to read the results from the cache, which report the durations
+    // which were recorded with them. Overwrite the recorded durations so that
+    // the reports are deterministic.
+    for tree_dir in std::fs::read_dir(git.repo_path.join(".git/branchless/test"))? {
+        let result_path = tree_dir?.path().join("!__test__-f__test2.txt/result");
+        if !result_path.exists() {
+            continue;
+        }
+        let mut result: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&result_path)?)?;
+        assert!(result["duration_ms"].is_u64());
+        result["duration_ms"] = serde_json::json!(1500);
+        std::fs::write(&result_path, serde_json::to_string(&result)?)?;
+    }
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "test",
+            &[
+                "run",
+                "-x",
+                command,
+                "--report",
+                "junit=report.xml",
+                "--report",
+                "json=report.json",
+            ],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @r###"
+        Stopped at 70deb1e (create test3.txt)
+        branchless: processing 1 update: ref HEAD
+        "###);
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (cached): 62fc20d create test1.txt
+        X Failed (cached, exit code 1): 96d1c37 create test2.txt
+        X Failed (cached, exit code 1): 70deb1e create test3.txt
+        Ran command on 3 commits: ! test -f test2.txt
+        1 passed, 2 failed, 0 skipped
+        hint: there were 3 cached test results
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+    }
+
+    {
+        let report = std::fs::read_to_string(git.repo_path.join("report.xml"))?;
+        let report = git.preprocess_output(report)?;
+        insta::assert_snapshot!(report, @r###"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <testsuites tests="3" failures="2" errors="0" skipped="0" time="4.500">
+          <testsuite name="! test -f test2.txt" tests="3" failures="2" errors="0" skipped="0" time="4.500">
+            <testcase classname="! test -f test2.txt" name="62fc20d create test1.txt" time="1.500">
+              <properties>
+                <property name="commit" value="62fc20d2a290daea0d52bdc2ed2ad4be6491010e"/>
+                <property name="status" value="passed"/>
+                <property name="exit_code" value="0"/>
+                <property name="cached" value="true"/>
+              </properties>
+              <system-out>[[ATTACHMENT|<repo-path>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stdout]]</system-out>
+              <system-err>[[ATTACHMENT|<repo-path>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stderr]]</system-err>
+            </testcase>
+            <testcase classname="! test -f test2.txt" name="96d1c37 create test2.txt" time="1.500">
+              <properties>
+                <property name="commit" value="96d1c37a3d4363611c49f7e52186e189a04c531f"/>
+                <property name="status" value="failed"/>
+                <property name="exit_code" value="1"/>
+                <property name="cached" value="true"/>
+              </properties>
+              <failure message="Failed (exit code 1)"/>
+              <system-out>[[ATTACHMENT|<repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stdout]]</system-out>
+              <system-err>[[ATTACHMENT|<repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stderr]]</system-err>
+            </testcase>
+            <testcase classname="! test -f test2.txt" name="70deb1e create test3.txt" time="1.500">
+              <properties>
+                <property name="commit" value="70deb1e28791d8e7dd5a1f0c871a51b91282562f"/>
+                <property name="status" value="failed"/>
+                <property name="exit_code" value="1"/>
+                <property name="cached" value="true"/>
+              </properties>
+              <failure message="Failed (exit code 1)"/>
+              <system-out>[[ATTACHMENT|<repo-path>/.git/branchless/test/aedff3aac8216d25422b52e8a198b802c0e94db3/!__test__-f__test2.txt/stdout]]</system-out>
+              <system-err>[[ATTACHMENT|<repo-path>/.git/branchless/test/aedff3aac8216d25422b52e8a198b802c0e94db3/!__test__-f__test2.txt/stderr]]</system-err>
+            </testcase>
+          </testsuite>
+        </testsuites>
+        "###);
+    }
+
+    {
+        let report = std::fs::read_to_string(git.repo_path.join("report.json"))?;
+        let report = git.preprocess_output(report)?;
+        insta::assert_snapshot!(report, @r###"
+        {
+          "results": [
+            {
+              "command": "! test -f test2.txt",
+              "commit_oid": "62fc20d2a290daea0d52bdc2ed2ad4be6491010e",
+              "short_commit_oid": "62fc20d",
+              "summary": "create test1.txt",
+              "status": "passed",
+              "message": "Passed",
+              "exit_code": 0,
+              "cached": true,
+              "duration_secs": 1.5,
+              "stdout_path": "<repo-path>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stdout",
+              "stderr_path": "<repo-path>/.git/branchless/test/8108c01b1930423879f106c1ebf725fcbfedccda/!__test__-f__test2.txt/stderr"
+            },
+            {
+              "command": "! test -f test2.txt",
+              "commit_oid": "96d1c37a3d4363611c49f7e52186e189a04c531f",
+              "short_commit_oid": "96d1c37",
+              "summary": "create test2.txt",
+              "status": "failed",
+              "message": "Failed (exit code 1)",
+              "exit_code": 1,
+              "cached": true,
+              "duration_secs": 1.5,
+              "stdout_path": "<repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stdout",
+              "stderr_path": "<repo-path>/.git/branchless/test/a6e5ab06462160856f1be3370fd22772c1beaa56/!__test__-f__test2.txt/stderr"
+            },
+            {
+              "command": "! test -f test2.txt",
+              "commit_oid": "70deb1e28791d8e7dd5a1f0c871a51b91282562f",
+              "short_commit_oid": "70deb1e",
+              "summary": "create test3.txt",
+              "status": "failed",
+              "message": "Failed (exit code 1)",
+              "exit_code": 1,
+              "cached": true,
+              "duration_secs": 1.5,
+              "stdout_path": "<repo-path>/.git/branchless/test/aedff3aac8216d25422b52e8a198b802c0e94db3/!__test__-f__test2.txt/stdout",
+              "stderr_path": "<repo-path>/.git/branchless/test/aedff3aac8216d25422b52e8a198b802c0e94db3/!__test__-f__test2.txt/stderr"
+            }
+          ]
+        }
+        "###);
+    }
+
+    // Results which aren't cached are written to a temporary directory, so
+    // their output paths aren't included.
+    {
+        git.branchless_with_options(
+            "test",
+            &[
+                "run",
+                "-x",
+                command,
+                "--no-cache",
+                "--report",
+                "json=report.json",
+            ],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(git.repo_path.join("report.json"))?)?;
+        let results = report["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        for result in results {
+            assert_eq!(result["cached"], serde_json::json!(false));
+            assert!(result["stdout_path"].is_null());
+            assert!(result["stderr_path"].is_null());
+        }
+    }
+
+    {
+        let (stdout, stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", command, "--report", "xml=report.xml"],
+            &GitRunOptions {
+                expected_exit_code: 2,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stderr, @r###"
+        error: invalid value 'xml=report.xml' for '--report <REPORTS>': invalid variant: xml
+
+        For more information, try '--help'.
+        "###);
+        insta::assert_snapshot!(stdout, @"");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_command_matrix() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+
+    git.run(&["config", "branchless.test.alias.lint", "true"])?;
+    git.run(&[
+        "config",
+        "branchless.test.alias.unit",
+        "! test -f test2.txt",
+    ])?;
+    git.run(&["config", "branchless.test.suite.all", "lint unit"])?;
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-c", "lint", "-c", "unit"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        lint  unit
+        ✓     ✓     62fc20d create test1.txt
+        ✓     X     96d1c37 create test2.txt
+        ✓     X     70deb1e create test3.txt
+        Ran 2 commands on 3 commits
+        lint: 3 passed, 0 failed, 0 skipped
+        unit: 1 passed, 2 failed, 0 skipped
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-c", "all"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        lint  unit
+        ✓     ✓     62fc20d create test1.txt
+        ✓     X     96d1c37 create test2.txt
+        ✓     X     70deb1e create test3.txt
+        Ran 2 commands on 3 commits
+        lint: 3 passed, 0 failed, 0 skipped
+        unit: 1 passed, 2 failed, 0 skipped
+        hint: there were 6 cached test results
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-c", "all", "--bisect"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Only one test command can be run at a time when searching (--search/--bisect)
+        or running interactively (--interactive).
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["fix", "-c", "all"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Only one test command can be run at a time when applying fixes (git test fix).
+        Run git test fix separately for each command in the suite.
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_timeout_and_retries() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+
+    // Only hangs on commits containing `test2.txt`.
+    let hanging_command = "if test -f test2.txt; then sleep 60; fi";
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", hanging_command, "--timeout", "1s"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 62fc20d create test1.txt
+        X Timed out: 96d1c37 create test2.txt
+        X Timed out: 70deb1e create test3.txt
+        Ran command on 3 commits: if test -f test2.txt; then sleep 60; fi
+        1 passed, 2 failed, 0 skipped
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", hanging_command, "--timeout", "1s"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (cached): 62fc20d create test1.txt
+        X Timed out (cached): 96d1c37 create test2.txt
+        X Timed out (cached): 70deb1e create test3.txt
+        Ran command on 3 commits: if test -f test2.txt; then sleep 60; fi
+        1 passed, 2 failed, 0 skipped
+        hint: there were 3 cached test results
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+    }
+
+    // Fails the first time that it's run on each commit, and always fails on
+    // commits containing `test2.txt`.
+    let flaky_command = r#"attempt="$(git rev-parse --git-common-dir)/attempt-$BRANCHLESS_TEST_COMMIT"; if test -f "$attempt" && ! test -f test2.txt; then exit 0; fi; touch "$attempt"; exit 1"#;
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", flaky_command, "--retries", "2"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(git.preprocess_output(stdout)?, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ~ Passed (flaky): 62fc20d create test1.txt
+        X Failed (exit code 1): 96d1c37 create test2.txt
+        X Failed (exit code 1): 70deb1e create test3.txt
+        Ran command on 3 commits: attempt="$(git rev-parse --git-common-dir)/attempt-$BRANCHLESS_TEST_COMMIT"; if test -f "$attempt" && ! test -f test2.txt; then exit 0; fi; touch "$attempt"; exit 1
+        1 passed, 2 failed, 0 skipped
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["run", "-x", flaky_command],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(git.preprocess_output(stdout)?, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ~ Passed (cached, flaky): 62fc20d create test1.txt
+        X Failed (cached, exit code 1): 96d1c37 create test2.txt
+        X Failed (cached, exit code 1): 70deb1e create test3.txt
+        Ran command on 3 commits: attempt="$(git rev-parse --git-common-dir)/attempt-$BRANCHLESS_TEST_COMMIT"; if test -f "$attempt" && ! test -f test2.txt; then exit 0; fi; touch "$attempt"; exit 1
+        1 passed, 2 failed, 0 skipped
+        hint: there were 3 cached test results
+        hint: to clear these cached results, run: git test clean "stack() | @"
+        hint: disable this hint by running: git config --global branchless.hint.cleanCachedTestResults false
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) =
+            git.branchless("query", &[&format!("tests.timedout('{hanging_command}')")])?;
+        insta::assert_snapshot!(stdout, @r###"
+        96d1c37 create test2.txt
+        70deb1e create test3.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("query", &["tests.flaky()"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        62fc20d create test1.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_worktree_sparse_checkout() -> eyre::Result<()> {
+    let git = make_git()?;
+    if !git.supports_sparse_checkout_set_cone()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+
+    git.write_file_txt("included/foo", "foo")?;
+    git.write_file_txt("excluded/bar", "bar")?;
+    git.run(&["add", "."])?;
+    git.run(&["commit", "-m", "add files"])?;
+    git.run(&["sparse-checkout", "set", "--cone", "included"])?;
+
+    {
+        let (stdout, _stderr) = git.branchless(
+            "test",
+            &[
+                "run",
+                "--strategy",
+                "worktree",
+                "-x",
+                "find . -name '*.txt' | sort",
+                "-vv",
+                "@",
+            ],
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ✓ Passed: 83fad24 add files
+        Stdout: <repo-path>/.git/branchless/test/4525535543aca5ea6f2d4bcdab762b2c6926f866/find__.__-name__'*.txt'__|__sort/stdout
+        ./included/foo.txt
+        ./initial.txt
+        Stderr: <repo-path>/.git/branchless/test/4525535543aca5ea6f2d4bcdab762b2c6926f866/find__.__-name__'*.txt'__|__sort/stderr
+        <no output>
+        Ran command on 1 commit: find . -name '*.txt' | sort
+        1 passed, 0 failed, 0 skipped
+        "###);
+    }
+
+    // Changing the sparse checkout patterns also changes the ones for the
+    // test worktree, and the cached result from the old patterns isn't reused.
+    git.run(&["sparse-checkout", "disable"])?;
+    {
+        let (stdout, _stderr) = git.branchless(
+            "test",
+            &[
+                "run",
+                "--strategy",
+                "worktree",
+                "-x",
+                "find . -name '*.txt' | sort",
+                "-vv",
+                "@",
+            ],
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        ✓ Passed: 83fad24 add files
+        Stdout: <repo-path>/.git/branchless/test/4525535543aca5ea6f2d4bcdab762b2c6926f866/find__.__-name__'*.txt'__|__sort/stdout
+        ./excluded/bar.txt
+        ./included/foo.txt
+        ./initial.txt
+        Stderr: <repo-path>/.git/branchless/test/4525535543aca5ea6f2d4bcdab762b2c6926f866/find__.__-name__'*.txt'__|__sort/stderr
+        <no output>
+        Ran command on 1 commit: find . -name '*.txt' | sort
+        1 passed, 0 failed, 0 skipped
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_watch() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    {
+        let (stdout, _stderr) = git.branchless("test", &["run", "-x", "exit 0", "HEAD^"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed: 62fc20d create test1.txt
+        Ran command on 1 commit: exit 0
+        1 passed, 0 failed, 0 skipped
+        "###);
+    }
+
+    // Only the commit without a cached result should be tested.
+    {
+        let (stdout, _stderr) =
+            git.branchless("test", &["watch", "-x", "exit 0", "--iterations", "1"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        Results for exit 0 on stack() | @:
+        ✓ Passed (cached): 62fc20d create test1.txt
+        ✓ Passed: 96d1c37 create test2.txt
+        "###);
+    }
+
+    // The new commit is tested, and since nothing changes between the
+    // iterations, the results aren't redrawn.
+    git.commit_file("test3", 3)?;
+    {
+        let (stdout, _stderr) = git.branchless(
+            "test",
+            &[
+                "watch",
+                "-x",
+                "exit 0",
+                "--iterations",
+                "2",
+                "--interval",
+                "10ms",
+            ],
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        Results for exit 0 on stack() | @:
+        ✓ Passed (cached): 62fc20d create test1.txt
+        ✓ Passed (cached): 96d1c37 create test2.txt
+        ✓ Passed: 70deb1e create test3.txt
+        Watching for changes to commits (press Ctrl-C to stop)...
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_watch_dirty_working_copy() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.write_file_txt("test1", "uncommitted changes")?;
+
+    // By default, the tests are run in a worktree, so the changes in the
+    // working copy don't get in the way.
+    {
+        let (stdout, _stderr) =
+            git.branchless("test", &["watch", "-x", "exit 0", "--iterations", "1"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Using command execution strategy: worktree
+        Results for exit 0 on stack() | @:
+        ✓ Passed: 62fc20d create test1.txt
+        "###);
+    }
+
+    // When the tests can't be run, the problem is reported, but watching
+    // continues.
+    {
+        let (stdout, _stderr) = git.branchless(
+            "test",
+            &[
+                "watch",
+                "--strategy",
+                "working-copy",
+                "-x",
+                "true",
+                "--iterations",
+                "1",
+            ],
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        This operation would modify the working copy, but you have uncommitted changes
+        in your working copy which might be overwritten as a result.
+        Commit your changes and then try again.
+        Results for true on stack() | @:
+        No cached test data for 62fc20d create test1.txt
+        Could not update the results (exit code 1).
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_test_fix_confirm() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.commit_file("test3", 3)?;
+
+    git.write_file(
+        "test.sh",
+        r#"#!/bin/sh
+for i in *.txt; do
+    echo "Updated contents for file $i" >"$i"
+done
+"#,
+    )?;
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "test",
+            &["fix", "--confirm", "-x", "bash test.sh"],
+            &GitRunOptions {
+                input: Some("n\nx\ny\nq\n".to_string()),
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: running command: <git-executable> diff --quiet
+        Calling Git for on-disk rebase...
+        branchless: running command: <git-executable> rebase --continue
+        Using command execution strategy: working-copy
+        branchless: running command: <git-executable> rebase --abort
+        ✓ Passed (fixed): 62fc20d create test1.txt
+        ✓ Passed (fixed): 96d1c37 create test2.txt
+        ✓ Passed (fixed): 70deb1e create test3.txt
+        Ran command on 3 commits: bash test.sh
+        3 passed, 0 failed, 0 skipped
+        Fix for 62fc20d create test1.txt:
+        diff --git a/initial.txt b/initial.txt
+        index 63af228..a48ef19 100644
+        --- a/initial.txt
+        +++ b/initial.txt
+        @@ -1 +1 @@
+        -initial contents
+        +Updated contents for file initial.txt
+        diff --git a/test1.txt b/test1.txt
+        index 7432a8f..4d62cad 100644
+        --- a/test1.txt
+        +++ b/test1.txt
+        @@ -1 +1 @@
+        -test1 contents
+        +Updated contents for file test1.txt
+        Apply this fix? [y]es, [n]o, [e]dit, [q]uit: Fix for 96d1c37 create test2.txt:
+        diff --git a/initial.txt b/initial.txt
+        index 63af228..a48ef19 100644
+        --- a/initial.txt
+        +++ b/initial.txt
+        @@ -1 +1 @@
+        -initial contents
+        +Updated contents for file initial.txt
+        diff --git a/test1.txt b/test1.txt
+        index 7432a8f..4d62cad 100644
+        --- a/test1.txt
+        +++ b/test1.txt
+        @@ -1 +1 @@
+        -test1 contents
+        +Updated contents for file test1.txt
+        diff --git a/test2.txt b/test2.txt
+        index 4e512d2..dce8610 100644
+        --- a/test2.txt
+        +++ b/test2.txt
+        @@ -1 +1 @@
+        -test2 contents
+        +Updated contents for file test2.txt
+        Apply this fix? [y]es, [n]o, [e]dit, [q]uit: Please enter one of: y, n, e, q
+        Apply this fix? [y]es, [n]o, [e]dit, [q]uit: Fix for 70deb1e create test3.txt:
+        diff --git a/initial.txt b/initial.txt
+        index 63af228..a48ef19 100644
+        --- a/initial.txt
+        +++ b/initial.txt
+        @@ -1 +1 @@
+        -initial contents
+        +Updated contents for file initial.txt
+        diff --git a/test1.txt b/test1.txt
+        index 7432a8f..4d62cad 100644
+        --- a/test1.txt
+        +++ b/test1.txt
+        @@ -1 +1 @@
+        -test1 contents
+        +Updated contents for file test1.txt
+        diff --git a/test2.txt b/test2.txt
+        index 4e512d2..dce8610 100644
+        --- a/test2.txt
+        +++ b/test2.txt
+        @@ -1 +1 @@
+        -test2 contents
+        +Updated contents for file test2.txt
+        diff --git a/test3.txt b/test3.txt
+        index a474f4e..95c32b2 100644
+        --- a/test3.txt
+        +++ b/test3.txt
+        @@ -1 +1 @@
+        -test3 contents
+        +Updated contents for file test3.txt
+        Apply this fix? [y]es, [n]o, [e]dit, [q]uit: Attempting rebase in-memory...
+        [1/2] Committed as: a5a8f25 create test2.txt
+        [2/2] Committed as: b8b3110 create test3.txt
+        branchless: processing 2 rewritten commits
+        branchless: running command: <git-executable> checkout b8b31107f0cfc3df312d72e772f5c9ee3d2a57b7
+        In-memory rebase succeeded.
+        Fixed 1 commit with bash test.sh:
+        96d1c37 -> a5a8f25 create test2.txt
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d create test1.txt
+        |
+        o a5a8f25 create test2.txt
+        |
+        @ b8b3110 create test3.txt
+        "###);
+    }
+
+    Ok(())
+}
@@ -593,6 +593,13 @@ then you can only run tests in the main `git-branchless` and \
         Ok(version >= GitVersion(2, 27, 0))
     }
 
+    /// The `--cone` option to `git sparse-checkout set` was introduced in Git
+    /// v2.35.
+    pub fn supports_sparse_checkout_set_cone(&self) -> eyre::Result<bool> {
+        let version = self.get_version()?;
+        Ok(version >= GitVersion(2, 35, 0))
+    }
+
     /// Resolve a file during a merge or rebase conflict with the provided
     /// contents.
     #[instrument]
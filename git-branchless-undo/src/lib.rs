@@ -945,7 +945,8 @@ pub fn undo(
     let references_snapshot = repo.get_references_snapshot()?;
     let conn = repo.get_db_conn()?;
     let mut event_log_db = EventLogDb::new(&conn)?;
-    let mut event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let mut event_replayer =
+        EventReplayer::from_event_log_db_with_history(effects, &repo, &event_log_db)?;
     let dag = {
         // Don't let `event_cursor` leak from this scope, since we intend to
         // determine a new event cursor below.
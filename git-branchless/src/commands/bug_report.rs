@@ -25,7 +25,7 @@ use lib::core::node_descriptors::{
 };
 use lib::git::{GitRunInfo, Repo, ResolvedReferenceInfo};
 
-use git_branchless_init::{determine_hook_path, Hook, ALL_HOOKS};
+use git_branchless_init::{determine_hook_path, ALL_HOOKS};
 
 fn redact_event(redactor: &Redactor, event: &Event) -> String {
     let event = match event.clone() {
@@ -170,7 +170,8 @@ fn collect_events(effects: &Effects, git_run_info: &GitRunInfo) -> eyre::Result<
     let references_snapshot = repo.get_references_snapshot()?;
     let conn = repo.get_db_conn()?;
     let event_log_db = EventLogDb::new(&conn)?;
-    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let event_replayer =
+        EventReplayer::from_event_log_db_with_history(effects, &repo, &event_log_db)?;
     let event_cursor = event_replayer.make_default_cursor();
     let mut dag = Dag::open_and_sync(
         effects,
@@ -246,10 +247,8 @@ fn collect_hooks(git_run_info: &GitRunInfo) -> eyre::Result<ReportEntry> {
     let hook_contents = {
         let mut result = Vec::new();
         for (hook_type, _content) in ALL_HOOKS {
-            let hook_path = match determine_hook_path(&repo, &hooks_dir, hook_type)? {
-                Hook::RegularHook { path } | Hook::MultiHook { path } => path,
-            };
-            let hook_contents = match std::fs::read_to_string(hook_path) {
+            let hook = determine_hook_path(&repo, &hooks_dir, hook_type)?;
+            let hook_contents = match std::fs::read_to_string(hook.get_path()) {
                 Ok(hook_contents) => hook_contents,
                 Err(err) if err.kind() == std::io::ErrorKind::NotFound => "<not found>".to_string(),
                 Err(err) => return Err(err.into()),
@@ -0,0 +1,444 @@
+//! Check for common problems with the git-branchless setup of a repository.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use eyre::Context;
+use itertools::Itertools;
+use lib::core::config::{get_hooks_dir, get_main_branch_name};
+use lib::core::dag::Dag;
+use lib::core::effects::Effects;
+use lib::core::eventlog::{EventLogDb, EventReplayer};
+use lib::core::formatting::Pluralize;
+use lib::core::repo_ext::RepoExt;
+use lib::git::{
+    BranchType, CategorizedReferenceName, Config, ConfigRead, ConfigWrite, GitRunInfo, GitRunOpts,
+    GitRunResult, Repo,
+};
+use lib::util::{ExitCode, EyreExitOr};
+use tracing::instrument;
+
+use git_branchless_init::{
+    detect_hook_frameworks, detect_main_branch_name, determine_hook_path, get_alias_value,
+    get_git_version, install_hook, is_hook_installed, Hook, ALL_ALIASES, ALL_HOOKS,
+    MIN_REFERENCE_TRANSACTION_GIT_VERSION,
+};
+
+use super::repair::{find_broken_objects, make_repair_events, BrokenObjects};
+
+/// A way to automatically fix a [`Problem`].
+enum Fix {
+    InstallHook {
+        hooks_dir: PathBuf,
+        hook_type: &'static str,
+        hook_script: &'static str,
+    },
+    SetConfig {
+        key: String,
+        value: String,
+    },
+    RemoveDir {
+        path: PathBuf,
+    },
+    RepairEventLog {
+        broken_objects: BrokenObjects,
+    },
+    PruneWorktrees,
+}
+
+impl Fix {
+    fn describe(&self) -> String {
+        match self {
+            Fix::InstallHook { .. } => "reinstall the hook".to_string(),
+            Fix::SetConfig { key, value } => format!("set {key} to {value:?}"),
+            Fix::RemoveDir { path } => format!("delete {}", path.display()),
+            Fix::RepairEventLog { .. } => "update the event log".to_string(),
+            Fix::PruneWorktrees => "run `git worktree prune`".to_string(),
+        }
+    }
+
+    fn apply(&self, git_run_info: &GitRunInfo, repo: &Repo, main_repo: &Repo) -> eyre::Result<()> {
+        match self {
+            Fix::InstallHook {
+                hooks_dir,
+                hook_type,
+                hook_script,
+            } => {
+                install_hook(main_repo, hooks_dir, hook_type, hook_script)?;
+            }
+
+            Fix::SetConfig { key, value } => {
+                let mut config = Config::open(&main_repo.get_config_path())?;
+                config.set(key, value.as_str())?;
+            }
+
+            Fix::RemoveDir { path } => {
+                std::fs::remove_dir_all(path)
+                    .wrap_err_with(|| format!("Deleting directory: {path:?}"))?;
+            }
+
+            Fix::RepairEventLog { broken_objects } => {
+                let conn = repo.get_db_conn()?;
+                let event_log_db = EventLogDb::new(&conn)?;
+                let now = SystemTime::now();
+                let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs_f64();
+                let event_tx_id = event_log_db.make_transaction_id(now, "doctor")?;
+                let events = make_repair_events(event_tx_id, timestamp, broken_objects);
+                event_log_db.add_events(events)?;
+            }
+
+            Fix::PruneWorktrees => {
+                let GitRunResult { .. } = git_run_info.run_silent(
+                    repo,
+                    None,
+                    &["worktree", "prune"],
+                    Default::default(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A problem found by `git branchless doctor`.
+struct Problem {
+    description: String,
+    fix: Option<Fix>,
+}
+
+#[instrument]
+fn is_initialized(repo: &Repo) -> eyre::Result<bool> {
+    if !repo.get_config_path().exists() {
+        return Ok(false);
+    }
+    let include_paths = repo.get_readonly_config()?.list(r"^include\.path$")?;
+    let result = include_paths
+        .into_iter()
+        .any(|(_key, include_path)| include_path.starts_with("branchless/"));
+    Ok(result)
+}
+
+#[instrument]
+fn check_hooks(git_run_info: &GitRunInfo, main_repo: &Repo) -> eyre::Result<Vec<Problem>> {
+    let hooks_dir = get_hooks_dir(git_run_info, main_repo, None)?;
+    let hook_frameworks = detect_hook_frameworks(main_repo, &hooks_dir)?;
+    let mut problems = Vec::new();
+    for (hook_type, hook_script) in ALL_HOOKS {
+        let hook = determine_hook_path(main_repo, &hooks_dir, hook_type)?;
+        if !is_hook_installed(&hook, hook_script)? {
+            let mut description = format!("The {hook_type} hook does not run git-branchless.");
+            if !hook_frameworks.is_empty() {
+                write!(
+                    description,
+                    " It may have been overwritten by {}.",
+                    hook_frameworks.iter().join(" or ")
+                )?;
+                if !matches!(hook, Hook::DispatchedHook { .. }) {
+                    description.push_str(" Run `git branchless init --hook-dispatcher` to run the hooks from all tools.");
+                }
+            }
+            problems.push(Problem {
+                description,
+                fix: Some(Fix::InstallHook {
+                    hooks_dir: hooks_dir.clone(),
+                    hook_type,
+                    hook_script,
+                }),
+            });
+        }
+    }
+    Ok(problems)
+}
+
+#[instrument]
+fn check_git_version(git_run_info: &GitRunInfo, repo: &Repo) -> eyre::Result<Vec<Problem>> {
+    let (version, version_str) = get_git_version(git_run_info, repo)?;
+    let mut problems = Vec::new();
+    if version < MIN_REFERENCE_TRANSACTION_GIT_VERSION {
+        problems.push(Problem {
+            description: format!(
+                "Your Git version ({version_str}) does not support the reference-transaction hook, so branch updates can't be undone. Upgrade to Git v2.29 or later."
+            ),
+            fix: None,
+        });
+    }
+    Ok(problems)
+}
+
+#[instrument]
+fn check_config(main_repo: &Repo) -> eyre::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+
+    let main_branch_name = get_main_branch_name(main_repo)?;
+    if main_repo
+        .find_branch(&main_branch_name, BranchType::Local)?
+        .is_none()
+    {
+        let fix = detect_main_branch_name(main_repo)?
+            .filter(|detected_branch_name| detected_branch_name != &main_branch_name)
+            .map(|detected_branch_name| Fix::SetConfig {
+                key: "branchless.core.mainBranch".to_string(),
+                value: detected_branch_name,
+            });
+        let description = match fix {
+            Some(_) => format!("The main branch {main_branch_name:?} does not exist."),
+            None => format!("The main branch {main_branch_name:?} does not exist. Run `git branchless init --main-branch <branch>` to set it."),
+        };
+        problems.push(Problem { description, fix });
+    }
+
+    // Aliases set by the user take precedence over the ones installed by
+    // `git branchless init`, so only the latter can be fixed automatically.
+    let config = main_repo.get_readonly_config()?;
+    let branchless_config = Config::open(&main_repo.get_config_path())?;
+    for (from, to) in ALL_ALIASES {
+        let alias_key = format!("alias.{from}");
+        let expected_value = get_alias_value(to);
+        let alias_value: Option<String> = config.get(&alias_key)?;
+        let branchless_alias_value: Option<String> = branchless_config.get(&alias_key)?;
+        let problem = match alias_value {
+            Some(alias_value)
+                if alias_value == expected_value || alias_value == format!("branchless-{to}") =>
+            {
+                continue
+            }
+            Some(alias_value) if branchless_alias_value.as_ref() != Some(&alias_value) => {
+                Problem {
+                    description: format!("The alias `git {from}` runs `git {alias_value}` instead of `git {expected_value}`. Run `git config --unset {alias_key}` to remove it."),
+                    fix: None,
+                }
+            }
+            _ => Problem {
+                description: format!("The alias `git {from}` does not run `git {expected_value}`."),
+                fix: Some(Fix::SetConfig {
+                    key: alias_key,
+                    value: expected_value,
+                }),
+            },
+        };
+        problems.push(problem);
+    }
+
+    Ok(problems)
+}
+
+#[instrument]
+fn check_dag(effects: &Effects, repo: &Repo) -> eyre::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+
+    // The DAG was stored in a different directory before the upgrade to
+    // `esl01-dag==0.3.0`.
+    let legacy_dag_dir = repo.get_path().join("branchless").join("dag");
+    if legacy_dag_dir.exists() {
+        problems.push(Problem {
+            description: "The commit graph directory of an older version of git-branchless is no longer used.".to_string(),
+            fix: Some(Fix::RemoveDir {
+                path: legacy_dag_dir,
+            }),
+        });
+    }
+
+    let references_snapshot = match repo.get_references_snapshot() {
+        Ok(references_snapshot) => references_snapshot,
+        // The main branch doesn't exist, which is reported by `check_config`.
+        Err(_) => return Ok(problems),
+    };
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, repo, &event_log_db)?;
+    let event_cursor = event_replayer.make_default_cursor();
+    if let Err(err) = Dag::open_and_sync(
+        effects,
+        repo,
+        &event_replayer,
+        event_cursor,
+        &references_snapshot,
+    ) {
+        problems.push(Problem {
+            description: format!("The commit graph could not be loaded: {err}"),
+            fix: Some(Fix::RemoveDir {
+                path: repo.get_dag_dir(),
+            }),
+        });
+    }
+
+    Ok(problems)
+}
+
+#[instrument]
+fn check_event_log(effects: &Effects, repo: &Repo) -> eyre::Result<Vec<Problem>> {
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, repo, &event_log_db)?;
+    let BrokenObjects { commits, branches } = find_broken_objects(effects, repo, &event_replayer)?;
+
+    let mut problems = Vec::new();
+    if !commits.is_empty() {
+        problems.push(Problem {
+            description: format!(
+                "The event log refers to missing commits: {}",
+                commits.iter().sorted().join(", "),
+            ),
+            fix: Some(Fix::RepairEventLog {
+                broken_objects: BrokenObjects {
+                    commits,
+                    branches: HashSet::new(),
+                },
+            }),
+        });
+    }
+    if !branches.is_empty() {
+        problems.push(Problem {
+            description: format!(
+                "The event log refers to missing branches: {}",
+                branches
+                    .iter()
+                    .map(
+                        |(_oid, reference_name)| CategorizedReferenceName::new(reference_name)
+                            .render_suffix()
+                    )
+                    .sorted()
+                    .join(", "),
+            ),
+            fix: Some(Fix::RepairEventLog {
+                broken_objects: BrokenObjects {
+                    commits: HashSet::new(),
+                    branches,
+                },
+            }),
+        });
+    }
+    Ok(problems)
+}
+
+#[instrument]
+fn check_worktrees(git_run_info: &GitRunInfo, repo: &Repo) -> eyre::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+
+    if let Some(operation_type) = repo.get_current_operation_type() {
+        problems.push(Problem {
+            description: format!("A {operation_type} is in progress. Run `git {operation_type} --continue` or `git {operation_type} --abort` to finish it."),
+            fix: None,
+        });
+    }
+
+    // Each worktree is listed as a block of attributes separated by blank
+    // lines. Worktrees whose directories no longer exist are marked with a
+    // `prunable` attribute.
+    let GitRunResult { stdout, .. } = git_run_info.run_silent(
+        repo,
+        None,
+        &["worktree", "list", "--porcelain"],
+        GitRunOpts {
+            treat_git_failure_as_error: true,
+            ..Default::default()
+        },
+    )?;
+    let output = String::from_utf8_lossy(&stdout);
+    let mut stale_worktrees = Vec::new();
+    let mut current_worktree = None;
+    for line in output.lines() {
+        if let Some(worktree) = line.strip_prefix("worktree ") {
+            current_worktree = Some(worktree);
+        } else if line == "prunable" || line.starts_with("prunable ") {
+            stale_worktrees.extend(current_worktree.take());
+        }
+    }
+    if !stale_worktrees.is_empty() {
+        problems.push(Problem {
+            description: format!(
+                "Git has {} whose directories no longer exist: {}",
+                Pluralize {
+                    determiner: None,
+                    amount: stale_worktrees.len(),
+                    unit: ("worktree", "worktrees"),
+                },
+                stale_worktrees.join(", ")
+            ),
+            fix: Some(Fix::PruneWorktrees),
+        });
+    }
+
+    Ok(problems)
+}
+
+/// Check the repository for problems and fix them if requested.
+#[instrument]
+pub fn doctor(effects: &Effects, git_run_info: &GitRunInfo, fix: bool) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    let main_repo = repo.open_worktree_parent_repo()?;
+    let main_repo = main_repo.as_ref().unwrap_or(&repo);
+
+    if !is_initialized(main_repo)? {
+        writeln!(
+            effects.get_output_stream(),
+            "git-branchless is not initialized in this repository. Run `git branchless init` to initialize it."
+        )?;
+        return Ok(Err(ExitCode(1)));
+    }
+
+    let problems = vec![
+        check_hooks(git_run_info, main_repo)?,
+        check_git_version(git_run_info, &repo)?,
+        check_config(main_repo)?,
+        check_dag(effects, &repo)?,
+        check_event_log(effects, &repo)?,
+        check_worktrees(git_run_info, &repo)?,
+    ]
+    .into_iter()
+    .flatten()
+    .collect_vec();
+
+    if problems.is_empty() {
+        writeln!(effects.get_output_stream(), "No problems found.")?;
+        return Ok(Ok(()));
+    }
+
+    let num_problems = problems.len();
+    let mut num_fixable_problems = 0;
+    for Problem {
+        description,
+        fix: problem_fix,
+    } in problems
+    {
+        writeln!(effects.get_output_stream(), "Problem: {description}")?;
+        if let Some(problem_fix) = problem_fix {
+            num_fixable_problems += 1;
+            writeln!(
+                effects.get_output_stream(),
+                "    Fix: {}",
+                problem_fix.describe()
+            )?;
+            if fix {
+                problem_fix.apply(git_run_info, &repo, main_repo)?;
+            }
+        }
+    }
+
+    let problems_description = Pluralize {
+        determiner: None,
+        amount: num_problems,
+        unit: ("problem", "problems"),
+    };
+    if fix {
+        writeln!(
+            effects.get_output_stream(),
+            "Found {problems_description} and fixed {num_fixable_problems}."
+        )?;
+    } else if num_fixable_problems > 0 {
+        writeln!(
+            effects.get_output_stream(),
+            "Found {problems_description}. Run `git branchless doctor --fix` to fix {num_fixable_problems} of them."
+        )?;
+    } else {
+        writeln!(effects.get_output_stream(), "Found {problems_description}.")?;
+    }
+
+    if fix && num_fixable_problems == num_problems {
+        Ok(Ok(()))
+    } else {
+        Ok(Err(ExitCode(1)))
+    }
+}
@@ -2,7 +2,7 @@
 
 mod amend;
 mod bug_report;
-mod hide;
+mod doctor;
 mod repair;
 mod restack;
 mod snapshot;
@@ -41,14 +41,22 @@ fn command_main(ctx: CommandContext, opts: Opts) -> EyreExitOr<()> {
             reparent,
         )?,
 
+        Command::Bisect(args) => git_branchless_test::bisect_command_main(ctx, args)?,
+
         Command::BugReport => bug_report::bug_report(&effects, &git_run_info)?,
 
+        Command::Doctor { fix } => doctor::doctor(&effects, &git_run_info, fix)?,
+
         Command::Switch { switch_options } => {
             git_branchless_navigation::switch(&effects, &git_run_info, &switch_options)?
         }
 
-        Command::Gc => {
-            gc::gc(&effects)?;
+        Command::Gc {
+            prune_events,
+            older_than,
+        } => {
+            let prune_events_older_than = if prune_events { older_than } else { None };
+            gc::gc(&effects, prune_events_older_than)?;
             Ok(())
         }
 
@@ -59,7 +67,7 @@ fn command_main(ctx: CommandContext, opts: Opts) -> EyreExitOr<()> {
             resolve_revset_options,
             delete_branches,
             recursive,
-        } => hide::hide(
+        } => git_branchless_hide::hide(
             &effects,
             &git_run_info,
             revsets,
@@ -191,7 +199,7 @@ fn command_main(ctx: CommandContext, opts: Opts) -> EyreExitOr<()> {
             revsets,
             resolve_revset_options,
             recursive,
-        } => hide::unhide(&effects, revsets, &resolve_revset_options, recursive)?,
+        } => git_branchless_hide::unhide(&effects, revsets, &resolve_revset_options, recursive)?,
 
         Command::Wrap {
             git_executable: explicit_git_executable,
@@ -2,7 +2,8 @@ use std::fmt::Write;
 use std::{collections::HashSet, time::SystemTime};
 
 use itertools::Itertools;
-use lib::git::{CategorizedReferenceName, MaybeZeroOid};
+use lib::core::eventlog::{CommitActivityStatus, EventTransactionId};
+use lib::git::{CategorizedReferenceName, MaybeZeroOid, NonZeroOid, ReferenceName};
 use lib::util::EyreExitOr;
 use lib::{
     core::{
@@ -13,11 +14,19 @@ use lib::{
     git::Repo,
 };
 
-pub fn repair(effects: &Effects, dry_run: bool) -> EyreExitOr<()> {
-    let repo = Repo::from_current_dir()?;
-    let conn = repo.get_db_conn()?;
-    let event_log_db = EventLogDb::new(&conn)?;
-    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+/// Commits and branches which the event log refers to, but which no longer
+/// exist in the repository.
+pub(crate) struct BrokenObjects {
+    pub commits: HashSet<NonZeroOid>,
+    pub branches: HashSet<(NonZeroOid, ReferenceName)>,
+}
+
+/// Find the commits and branches in the event log which no longer exist.
+pub(crate) fn find_broken_objects(
+    effects: &Effects,
+    repo: &Repo,
+    event_replayer: &EventReplayer,
+) -> eyre::Result<BrokenObjects> {
     let event_cursor = event_replayer.make_default_cursor();
 
     let broken_commits = {
@@ -27,7 +36,13 @@ pub fn repair(effects: &Effects, dry_run: bool) -> EyreExitOr<()> {
         progress.notify_progress(0, cursor_oids.len());
         let mut result = HashSet::new();
         for oid in cursor_oids {
-            if repo.find_commit(oid)?.is_none() {
+            // Missing commits which are already obsolete (such as those
+            // hidden by an earlier repair) have nothing left to fix.
+            let is_obsolete = matches!(
+                event_replayer.get_cursor_commit_activity_status(event_cursor, oid),
+                CommitActivityStatus::Obsolete
+            );
+            if !is_obsolete && repo.find_commit(oid)?.is_none() {
                 result.insert(oid);
             }
             progress.notify_progress_inc(1);
@@ -38,7 +53,7 @@ pub fn repair(effects: &Effects, dry_run: bool) -> EyreExitOr<()> {
     let broken_branches = {
         let (effects, progress) = effects.start_operation(OperationType::RepairBranches);
         let _effects = effects;
-        let references_snapshot = event_replayer.get_references_snapshot(&repo, event_cursor)?;
+        let references_snapshot = event_replayer.get_references_snapshot(repo, event_cursor)?;
         let branch_names = references_snapshot
             .branch_oid_to_names
             .into_iter()
@@ -59,37 +74,61 @@ pub fn repair(effects: &Effects, dry_run: bool) -> EyreExitOr<()> {
         result
     };
 
-    let now = SystemTime::now();
-    let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs_f64();
-    let event_tx_id = event_log_db.make_transaction_id(now, "repair")?;
+    Ok(BrokenObjects {
+        commits: broken_commits,
+        branches: broken_branches,
+    })
+}
 
-    let num_broken_commits = broken_commits.len();
-    let commit_events = broken_commits
+/// Make the events which mark the given broken commits as obsolete and the
+/// given broken branches as deleted.
+pub(crate) fn make_repair_events(
+    event_tx_id: EventTransactionId,
+    timestamp: f64,
+    broken_objects: &BrokenObjects,
+) -> Vec<Event> {
+    let BrokenObjects { commits, branches } = broken_objects;
+    let commit_events = commits.iter().map(|commit_oid| Event::ObsoleteEvent {
+        timestamp,
+        event_tx_id,
+        commit_oid: *commit_oid,
+    });
+    let branch_events = branches
         .iter()
-        .map(|commit_oid| Event::ObsoleteEvent {
+        .map(|(old_oid, reference_name)| Event::RefUpdateEvent {
             timestamp,
             event_tx_id,
-            commit_oid: *commit_oid,
-        })
-        .collect_vec();
-    let num_broken_branches = broken_branches.len();
-    let branch_events =
-        broken_branches
-            .iter()
-            .map(|(old_oid, reference_name)| Event::RefUpdateEvent {
-                timestamp,
-                event_tx_id,
-                ref_name: reference_name.to_owned(),
-                old_oid: MaybeZeroOid::NonZero(*old_oid),
-                new_oid: MaybeZeroOid::Zero,
-                message: None,
-            });
+            ref_name: reference_name.to_owned(),
+            old_oid: MaybeZeroOid::NonZero(*old_oid),
+            new_oid: MaybeZeroOid::Zero,
+            message: None,
+        });
+    commit_events.chain(branch_events).collect_vec()
+}
+
+pub fn repair(effects: &Effects, dry_run: bool) -> EyreExitOr<()> {
+    let repo = Repo::from_current_dir()?;
+    let conn = repo.get_db_conn()?;
+    let event_log_db = EventLogDb::new(&conn)?;
+    let event_replayer = EventReplayer::from_event_log_db(effects, &repo, &event_log_db)?;
+    let broken_objects = find_broken_objects(effects, &repo, &event_replayer)?;
+
+    let now = SystemTime::now();
+    let timestamp = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs_f64();
+    let event_tx_id = event_log_db.make_transaction_id(now, "repair")?;
 
     if !dry_run {
-        let events = commit_events.into_iter().chain(branch_events).collect_vec();
+        let events = make_repair_events(event_tx_id, timestamp, &broken_objects);
         event_log_db.add_events(events)?;
     }
 
+    let BrokenObjects {
+        commits: broken_commits,
+        branches: broken_branches,
+    } = broken_objects;
+    let num_broken_commits = broken_commits.len();
+    let num_broken_branches = broken_branches.len();
+
     if num_broken_commits > 0 {
         writeln!(
             effects.get_output_stream(),
@@ -36,7 +36,28 @@ fn get_stack_roots(dag: &Dag) -> eyre::Result<CommitSet> {
     Ok(draft_roots)
 }
 
-/// Move all commit stacks on top of the main branch.
+/// Find the public branch which the stack rooted at `root_commit` forked from,
+/// i.e. the first of `public_branch_oids` which contains a parent of the root.
+/// The main branch should be first in `public_branch_oids`, so that it's
+/// preferred when the stack forked from a commit shared by several public
+/// branches.
+fn get_stack_destination(
+    dag: &Dag,
+    public_branch_oids: &[NonZeroOid],
+    root_commit: &Commit,
+) -> eyre::Result<Option<NonZeroOid>> {
+    for public_branch_oid in public_branch_oids.iter().copied() {
+        for parent_oid in root_commit.get_parent_oids() {
+            if dag.query_is_ancestor(parent_oid, public_branch_oid)? {
+                return Ok(Some(public_branch_oid));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Move all commit stacks on top of the public branch they forked from, which
+/// is usually the main branch.
 pub fn sync(
     effects: &Effects,
     git_run_info: &GitRunInfo,
@@ -296,6 +317,7 @@ fn execute_sync_plans(
             }
         };
     let main_branch_oid = repo.get_main_branch_oid()?;
+    let public_branch_oids = references_snapshot.get_public_branch_oids(repo)?;
     let root_commit_oids = if commit_sets.is_empty() {
         get_stack_roots(&dag)?
     } else {
@@ -331,13 +353,15 @@ fn execute_sync_plans(
                     let repo = repo_pool.try_create()?;
                     let root_commit = repo.find_commit_or_fail(root_commit_oid)?;
 
+                    let dest_oid = get_stack_destination(&dag, &public_branch_oids, &root_commit)?
+                        .unwrap_or(main_branch_oid);
                     let only_parent_id =
                         root_commit.get_only_parent().map(|parent| parent.get_oid());
-                    if only_parent_id == Some(main_branch_oid) {
+                    if only_parent_id == Some(dest_oid) {
                         return Ok(Ok((root_commit_oid, None)));
                     }
 
-                    builder.move_subtree(root_commit.get_oid(), vec![main_branch_oid])?;
+                    builder.move_subtree(root_commit.get_oid(), vec![dest_oid])?;
                     let rebase_plan = builder.build(effects, thread_pool, repo_pool)?;
                     Ok(rebase_plan.map(|rebase_plan| (root_commit_oid, rebase_plan)))
                 },
@@ -99,32 +99,56 @@ fn test_sparse_checkout() -> eyre::Result<()> {
         "###);
     }
 
-    if let Ok(stdout) = git.smartlog() {
-        insta::assert_snapshot!(stdout, @"@ f777ecc (> master) create initial.txt
-");
-    } else {
-        let (stdout, _stderr) = git.branchless_with_options(
-            "smartlog",
-            &[],
-            &GitRunOptions {
-                expected_exit_code: 1,
-                ..Default::default()
-            },
-        )?;
-        insta::assert_snapshot!(stdout, @r###"
-        Error: the Git configuration setting `extensions.worktreeConfig` is enabled in
-        this repository. Due to upstream libgit2 limitations, git-branchless does not
-        support repositories with this configuration option enabled.
+    // Older versions of Git also upgrade the repository format version when
+    // initializing a sparse checkout, which makes libgit2 check the
+    // extensions in use.
+    git.run(&["config", "core.repositoryformatversion", "1"])?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.run(&["checkout", "HEAD^"])?;
 
-        Usually, this configuration setting is enabled when initializing a sparse
-        checkout. See https://github.com/arxanas/git-branchless/issues/278 for more
-        information.
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        @ 62fc20d create test1.txt
+        |
+        o 96d1c37 create test2.txt
+        "###);
+    }
 
-        Here are some options:
+    git.run(&[
+        "config",
+        "--worktree",
+        "branchless.smartlog.defaultRevset",
+        "@",
+    ])?;
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        @ 62fc20d create test1.txt
+        :
+        # 1 omitted descendant commit
+        "###);
+    }
 
-        - To unset the configuration option, run: git config --unset extensions.worktreeConfig
-          - This is safe unless you created another worktree also using a sparse checkout.
-        - Try upgrading to Git v2.36+ and reinitializing your sparse checkout.
+    // Configuration written by git-branchless goes to the repository's
+    // configuration file, not the worktree's.
+    git.branchless("init", &[])?;
+    {
+        let config_worktree =
+            std::fs::read_to_string(git.repo_path.join(".git").join("config.worktree"))?;
+        insta::assert_snapshot!(config_worktree, @r###"
+        [core]
+        	sparseCheckout = true
+        	sparseCheckoutCone = true
+        [branchless "smartlog"]
+        	defaultRevset = @
         "###);
     }
 
@@ -0,0 +1,148 @@
+use git_branchless_testing::{make_git, GitRunOptions};
+
+#[test]
+fn test_doctor_no_problems() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        No problems found.
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_doctor_fix_hooks_and_aliases() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    std::fs::write(
+        git.repo_path.join(".git").join("hooks").join("post-commit"),
+        "#!/bin/sh\necho 'some other tool'\n",
+    )?;
+    git.run(&[
+        "config",
+        "--file",
+        ".git/branchless/config",
+        "--unset",
+        "alias.sw",
+    ])?;
+    git.run(&["config", "alias.sl", "log --oneline"])?;
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "doctor",
+            &[],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Problem: The post-commit hook does not run git-branchless.
+            Fix: reinstall the hook
+        Problem: The alias `git sl` runs `git log --oneline` instead of `git branchless smartlog`. Run `git config --unset alias.sl` to remove it.
+        Problem: The alias `git sw` does not run `git branchless switch`.
+            Fix: set alias.sw to "branchless switch"
+        Found 3 problems. Run `git branchless doctor --fix` to fix 2 of them.
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "doctor",
+            &["--fix"],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Problem: The post-commit hook does not run git-branchless.
+            Fix: reinstall the hook
+        Problem: The alias `git sl` runs `git log --oneline` instead of `git branchless smartlog`. Run `git config --unset alias.sl` to remove it.
+        Problem: The alias `git sw` does not run `git branchless switch`.
+            Fix: set alias.sw to "branchless switch"
+        Found 3 problems and fixed 2.
+        "###);
+    }
+
+    git.run(&["config", "--unset", "alias.sl"])?;
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        No problems found.
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_doctor_broken_commit() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+    git.run(&["checkout", "HEAD^"])?;
+
+    git.run(&["update-ref", "-d", "refs/branchless/keep-alive"])?;
+    git.run(&["gc", "--prune=now"])?;
+
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &["--fix"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Problem: The event log refers to missing commits: 96d1c37a3d4363611c49f7e52186e189a04c531f
+            Fix: update the event log
+        Found 1 problem and fixed 1.
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        No problems found.
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_doctor_stale_worktree() -> eyre::Result<()> {
+    let git = make_git()?;
+    git.init_repo()?;
+
+    let worktree_path = git.repo_path.join("stale-worktree");
+    git.run(&[
+        "worktree",
+        "add",
+        "--detach",
+        worktree_path.to_str().unwrap(),
+    ])?;
+    std::fs::remove_dir_all(&worktree_path)?;
+
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &["--fix"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Problem: Git has 1 worktree whose directories no longer exist: <repo-path>/stale-worktree
+            Fix: run `git worktree prune`
+        Found 1 problem and fixed 1.
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        No problems found.
+        "###);
+    }
+
+    Ok(())
+}
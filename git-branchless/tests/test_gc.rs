@@ -1,4 +1,4 @@
-use git_branchless_testing::{make_git, GitInitOptions};
+use git_branchless_testing::{make_git, trim_lines, GitInitOptions};
 use itertools::Itertools;
 use lib::core::eventlog::testing::redact_event_timestamp;
 use lib::core::eventlog::EventLogDb;
@@ -289,3 +289,201 @@ fn test_gc_no_init() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_gc_prune_events() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    let test2_oid = git.commit_file("test2", 2)?;
+    git.run(&["checkout", "HEAD^"])?;
+    git.branchless("hide", &[&test2_oid.to_string()])?;
+
+    {
+        let (stdout, _stderr) = git.branchless("gc", &["--prune-events", "--older-than", "1d"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: collecting garbage
+        branchless: 0 events pruned
+        branchless: 1 dangling reference deleted
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("gc", &["--prune-events", "--older-than", "0s"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: collecting garbage
+        branchless: 9 events pruned
+        branchless: 0 dangling references deleted
+        "###);
+    }
+
+    {
+        let repo = git.get_repo()?;
+        let conn = repo.get_db_conn()?;
+        let event_log_db = EventLogDb::new(&conn)?;
+        assert_eq!(event_log_db.get_events()?.len(), 0);
+    }
+
+    // The obsolete commit stays hidden, even though its events were pruned.
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        @ 62fc20d create test1.txt
+        "###);
+    }
+
+    git.run(&["gc", "--prune=now"])?;
+    {
+        let repo = git.get_repo()?;
+        assert!(repo.find_commit(test2_oid)?.is_none());
+    }
+
+    // Operations after the pruned events can still be undone.
+    git.commit_file("test3", 3)?;
+    {
+        let (stdout, _stderr) = git.branchless("undo", &["-y"])?;
+        let stdout = trim_lines(stdout);
+        insta::assert_snapshot!(stdout, @r###"
+        Will apply these actions:
+        1. Hide commit 4838e49 create test3.txt
+
+        Applied 1 inverse event.
+        "###);
+    }
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d create test1.txt
+        |
+        % 4838e49 (manually hidden) create test3.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_migrate_keep_alive_references() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    let test1_oid = git.commit_file("test1", 1)?;
+    let test2_oid = git.commit_file("test2", 2)?;
+    git.run(&["checkout", "master"])?;
+
+    {
+        let (stdout, _stderr) = git.run(&["for-each-ref", "refs/branchless/"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        278d96b8940a06378d882cdd8b355a2238ac1614 commit	refs/branchless/keep-alive
+        "###);
+    }
+
+    // Simulate a repository which was set up by an earlier version, which
+    // used one reference per commit.
+    git.run(&["update-ref", "-d", "refs/branchless/keep-alive"])?;
+    for oid in [test1_oid, test2_oid] {
+        git.run(&[
+            "update-ref",
+            &format!("refs/branchless/{oid}"),
+            &oid.to_string(),
+        ])?;
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("gc", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        branchless: collecting garbage
+        branchless: 2 keep-alive references migrated to refs/branchless/keep-alive
+        branchless: 0 dangling references deleted
+        "###);
+    }
+    {
+        let (stdout, _stderr) = git.run(&["for-each-ref", "refs/branchless/"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        278d96b8940a06378d882cdd8b355a2238ac1614 commit	refs/branchless/keep-alive
+        "###);
+    }
+
+    git.run(&["gc", "--prune=now"])?;
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        @ f777ecc (> master) create initial.txt
+        |
+        o 62fc20d create test1.txt
+        |
+        o 96d1c37 create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_keep_alive_chain_does_not_grow_per_commit() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    for i in 1..=5 {
+        git.commit_file(&format!("test{i}"), i)?;
+    }
+
+    let count_keep_alive_commits = || -> eyre::Result<usize> {
+        let (stdout, _stderr) = git.run(&[
+            "log",
+            "--first-parent",
+            "--format=%s",
+            "refs/branchless/keep-alive",
+        ])?;
+        Ok(stdout
+            .lines()
+            .filter(|line| *line == "branchless: keep-alive")
+            .count())
+    };
+    assert_eq!(count_keep_alive_commits()?, 1);
+
+    // Marking commits which are already kept alive shouldn't grow the chain.
+    git.run(&["commit", "--amend", "--no-edit", "-m", "amended"])?;
+    git.run(&["checkout", "HEAD@{1}"])?;
+    assert_eq!(count_keep_alive_commits()?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_smartlog_does_not_update_keep_alive_chain() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    // Keep enough commits alive that they span several keep-alive commits.
+    for i in 0..120 {
+        git.run(&["commit", "--allow-empty", "-m", &format!("commit {i}")])?;
+    }
+
+    let get_keep_alive_oid = || -> eyre::Result<String> {
+        let (stdout, _stderr) = git.run(&["rev-parse", "refs/branchless/keep-alive"])?;
+        Ok(stdout)
+    };
+    // The first smartlog may keep the main branch commit alive, but later
+    // ones have nothing new to keep alive.
+    git.smartlog()?;
+    let keep_alive_oid = get_keep_alive_oid()?;
+    git.smartlog()?;
+    assert_eq!(get_keep_alive_oid()?, keep_alive_oid);
+    git.smartlog()?;
+    assert_eq!(get_keep_alive_oid()?, keep_alive_oid);
+
+    Ok(())
+}
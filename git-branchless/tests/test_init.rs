@@ -60,6 +60,154 @@ echo Hello, world
     Ok(())
 }
 
+#[cfg(unix)]
+#[test]
+fn test_init_hook_dispatcher() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo_with_options(&GitInitOptions {
+        run_branchless_init: false,
+        ..Default::default()
+    })?;
+
+    let hooks_dir = git.repo_path.join(".git").join("hooks");
+    git.write_file(".git/hooks/helper.sh", "echo Hello, world\n")?;
+    // Like husky, refer to other files relative to the hook. Also include a
+    // comment which isn't valid UTF-8.
+    std::fs::write(
+        hooks_dir.join("post-commit"),
+        b"#!/bin/sh\n# \xff\n. \"$(dirname \"$0\")/helper.sh\"\n",
+    )?;
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let hook_path = hooks_dir.join("post-commit");
+        let mut permissions = std::fs::metadata(&hook_path)?.permissions();
+        permissions.set_mode(0o755);
+        std::fs::set_permissions(&hook_path, permissions)?;
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("init", &["--hook-dispatcher"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Created config file at <repo-path>/.git/branchless/config
+        Auto-detected your main branch as: master
+        If this is incorrect, run: git branchless init --main-branch <branch>
+        Installing hooks: post-applypatch, post-checkout, post-commit, post-merge, post-rewrite, pre-auto-gc, reference-transaction
+        Successfully installed git-branchless.
+        To uninstall, run: git branchless init --uninstall
+        "###);
+    }
+
+    {
+        let mut hook_file_names = std::fs::read_dir(hooks_dir.join("post-commit.d"))?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        hook_file_names.sort();
+        insta::assert_debug_snapshot!(hook_file_names, @r###"
+        [
+            "branchless",
+        ]
+        "###);
+        let previous_hook = std::fs::read(hooks_dir.join("post-commit.branchless-previous"))?;
+        assert!(previous_hook.contains(&0xff));
+    }
+
+    {
+        let (stdout, stderr) = git.run(&["commit", "--allow-empty", "-m", "test"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        [master 4cd1a9b] test
+        "###);
+        insta::assert_snapshot!(stderr, @r###"
+        branchless: processing 2 updates: branch master, ref HEAD
+        Hello, world
+        branchless: processed commit: 4cd1a9b test
+        "###);
+    }
+
+    // Simulate another tool overwriting the dispatcher.
+    git.write_file(
+        ".git/hooks/post-commit",
+        "#!/bin/sh
+# lefthook
+echo Hello, world
+",
+    )?;
+    {
+        let (stdout, _stderr) = git.branchless_with_options(
+            "doctor",
+            &[],
+            &GitRunOptions {
+                expected_exit_code: 1,
+                ..Default::default()
+            },
+        )?;
+        insta::assert_snapshot!(stdout, @r###"
+        Problem: The post-commit hook does not run git-branchless. It may have been overwritten by lefthook.
+            Fix: reinstall the hook
+        Found 1 problem. Run `git branchless doctor --fix` to fix 1 of them.
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.branchless("doctor", &["--fix"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Problem: The post-commit hook does not run git-branchless. It may have been overwritten by lefthook.
+            Fix: reinstall the hook
+        Found 1 problem and fixed 1.
+        "###);
+    }
+
+    {
+        let (stdout, stderr) = git.run(&["commit", "--allow-empty", "-m", "test2"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        [master a24c28a] test2
+        "###);
+        insta::assert_snapshot!(stderr, @r###"
+        branchless: processing 2 updates: branch master, ref HEAD
+        Hello, world
+        Hello, world
+        branchless: processed commit: a24c28a test2
+        "###);
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_init_detect_hook_frameworks() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo_with_options(&GitInitOptions {
+        run_branchless_init: false,
+        ..Default::default()
+    })?;
+    git.write_file("lefthook.yml", "post-commit:\n")?;
+
+    {
+        let (stdout, _stderr) = git.branchless("init", &[])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Created config file at <repo-path>/.git/branchless/config
+        Auto-detected your main branch as: master
+        If this is incorrect, run: git branchless init --main-branch <branch>
+        Installing hooks: post-applypatch, post-checkout, post-commit, post-merge, post-rewrite, pre-auto-gc, reference-transaction
+        Detected other tools which manage Git hooks: lefthook
+        They may overwrite the hooks above. To run the hooks from all tools, run:
+        git branchless init --hook-dispatcher
+        Successfully installed git-branchless.
+        To uninstall, run: git branchless init --uninstall
+        "###);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_alias_installed() -> eyre::Result<()> {
     let git = make_git()?;
@@ -690,9 +838,15 @@ fn test_install_man_pages() -> eyre::Result<()> {
     git\-branchless\-amend(1)
     Amend the current HEAD commit
     .TP
+    git\-branchless\-bisect(1)
+    Search for the commit which introduced a bug, marking commits as good or bad by hand
+    .TP
     git\-branchless\-bug\-report(1)
     Gather information about recent operations to upload as part of a bug report
     .TP
+    git\-branchless\-doctor(1)
+    Check for common problems with the git\-branchless setup of this repository, such as hooks which were overwritten by other tools, and optionally fix them
+    .TP
     git\-branchless\-gc(1)
     Run internal garbage collection
     .TP
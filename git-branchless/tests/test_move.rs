@@ -4334,12 +4334,26 @@ fn test_move_standalone_create_gc_refs() -> eyre::Result<()> {
     {
         let (stdout, _stderr) = git.run(&["show-ref"])?;
         insta::assert_snapshot!(stdout, @r###"
-        62fc20d2a290daea0d52bdc2ed2ad4be6491010e refs/branchless/62fc20d2a290daea0d52bdc2ed2ad4be6491010e
-        96d1c37a3d4363611c49f7e52186e189a04c531f refs/branchless/96d1c37a3d4363611c49f7e52186e189a04c531f
+        de1296ae81057233ffc09e1301e6be1f70bd87ee refs/branchless/keep-alive
         62fc20d2a290daea0d52bdc2ed2ad4be6491010e refs/heads/master
         "###);
     }
 
+    {
+        let (stdout, _stderr) = git.run(&[
+            "log",
+            "--format=%h %s",
+            "--topo-order",
+            "refs/branchless/keep-alive",
+        ])?;
+        insta::assert_snapshot!(stdout, @r###"
+        de1296a branchless: keep-alive
+        96d1c37 create test2.txt
+        62fc20d create test1.txt
+        f777ecc create initial.txt
+        "###);
+    }
+
     Ok(())
 }
 
@@ -3,6 +3,8 @@ use git_branchless_testing::{make_git, GitRunOptions};
 
 const CARRIAGE_RETURN: &str = "\r";
 const END_OF_TEXT: &str = "\x03";
+const CTRL_B: &str = "\x02";
+const CTRL_X: &str = "\x18";
 
 #[test]
 fn test_prev() -> eyre::Result<()> {
@@ -649,6 +651,143 @@ fn test_switch_pty_initial_query() -> eyre::Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg(unix)]
+fn test_switch_pty_revset() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    // Only `test1` is offered, so accepting the empty query selects it.
+    run_in_pty(
+        &git,
+        "switch",
+        &["-i", "-r", "draft() - @"],
+        &[
+            PtyAction::WaitUntilContains("> 62fc20d"),
+            PtyAction::Write(CARRIAGE_RETURN),
+        ],
+    )?;
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        @ 62fc20d create test1.txt
+        |
+        o 96d1c37 create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_switch_pty_create_branch() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    run_in_pty(
+        &git,
+        "switch",
+        &["-i", "-c", "foo", "test1"],
+        &[
+            PtyAction::WaitUntilContains("> test1"),
+            PtyAction::Write(CTRL_B),
+        ],
+    )?;
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d (foo) create test1.txt
+        |
+        @ 96d1c37 create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_switch_pty_create_branch_prompt() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.commit_file("test2", 2)?;
+
+    run_in_pty(
+        &git,
+        "switch",
+        &["-i", "test1"],
+        &[
+            PtyAction::WaitUntilContains("> test1"),
+            PtyAction::Write(CTRL_B),
+            PtyAction::WaitUntilContains("Enter a name for the new branch:"),
+            PtyAction::Write("bar"),
+            PtyAction::WaitUntilContains("> bar"),
+            PtyAction::Write(CARRIAGE_RETURN),
+        ],
+    )?;
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        o 62fc20d (bar) create test1.txt
+        |
+        @ 96d1c37 create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_switch_pty_hide() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    git.init_repo()?;
+    git.detach_head()?;
+    git.commit_file("test1", 1)?;
+    git.run(&["checkout", "master"])?;
+    git.detach_head()?;
+    git.commit_file("test2", 2)?;
+
+    run_in_pty(
+        &git,
+        "switch",
+        &["-i", "test1"],
+        &[
+            PtyAction::WaitUntilContains("> test1"),
+            PtyAction::Write(CTRL_X),
+        ],
+    )?;
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        O f777ecc (master) create initial.txt
+        |
+        @ fe65c1f create test2.txt
+        "###);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_navigation_merge() -> eyre::Result<()> {
     let git = make_git()?;
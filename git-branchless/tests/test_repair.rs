@@ -1,5 +1,5 @@
 use git_branchless_testing::make_git;
-use lib::git::{BranchType, ReferenceName};
+use lib::git::BranchType;
 
 #[test]
 fn test_repair_broken_commit() -> eyre::Result<()> {
@@ -9,13 +9,10 @@ fn test_repair_broken_commit() -> eyre::Result<()> {
     git.detach_head()?;
     git.commit_file("test1", 1)?;
     git.commit_file("test2", 2)?;
-    let test3_oid = git.commit_file("test3", 3)?;
+    git.commit_file("test3", 3)?;
     git.run(&["checkout", "HEAD^"])?;
 
-    let repo = git.get_repo()?;
-    repo.find_reference(&ReferenceName::from(format!("refs/branchless/{test3_oid}")))?
-        .unwrap()
-        .delete()?;
+    git.run(&["update-ref", "-d", "refs/branchless/keep-alive"])?;
     git.run(&["gc", "--prune=now"])?;
 
     {
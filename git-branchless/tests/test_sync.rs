@@ -452,3 +452,92 @@ fn test_sync_merge_commit() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_sync_public_branches() -> eyre::Result<()> {
+    let git = make_git()?;
+
+    if !git.supports_reference_transactions()? {
+        return Ok(());
+    }
+    git.init_repo()?;
+    git.run(&["config", "branchless.core.publicBranches", "release/*"])?;
+
+    git.commit_file("test1", 1)?;
+    git.run(&["checkout", "-b", "release/1"])?;
+    git.commit_file("test2", 2)?;
+    git.detach_head()?;
+    git.commit_file("test3", 3)?;
+    git.run(&["checkout", "release/1"])?;
+    git.commit_file("test4", 4)?;
+
+    git.run(&["checkout", "master"])?;
+    git.detach_head()?;
+    git.commit_file("test5", 5)?;
+    git.run(&["checkout", "master"])?;
+    git.commit_file("test6", 6)?;
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        O 62fc20d create test1.txt
+        |\
+        | o ea7aa06 create test5.txt
+        |
+        O 96d1c37 create test2.txt
+        |\
+        | o 70deb1e create test3.txt
+        |
+        O f57e36f (release/1) create test4.txt
+        :
+        @ d25afe6 (> master) create test6.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["sync"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        Attempting rebase in-memory...
+        [1/1] Committed as: 4b8e07b create test3.txt
+        branchless: processing 1 rewritten commit
+        branchless: running command: <git-executable> checkout master
+        In-memory rebase succeeded.
+        Attempting rebase in-memory...
+        [1/1] Committed as: eb32c51 create test5.txt
+        branchless: processing 1 rewritten commit
+        branchless: running command: <git-executable> checkout master
+        In-memory rebase succeeded.
+        Synced 70deb1e create test3.txt
+        Synced ea7aa06 create test5.txt
+        "###);
+    }
+
+    {
+        let stdout = git.smartlog()?;
+        insta::assert_snapshot!(stdout, @r###"
+        :
+        O 62fc20d create test1.txt
+        |
+        O 96d1c37 create test2.txt
+        |
+        O f57e36f (release/1) create test4.txt
+        |\
+        : o 4b8e07b create test3.txt
+        :
+        @ d25afe6 (> master) create test6.txt
+        |
+        o eb32c51 create test5.txt
+        "###);
+    }
+
+    {
+        let (stdout, _stderr) = git.run(&["query", "draft()"])?;
+        insta::assert_snapshot!(stdout, @r###"
+        4b8e07b create test3.txt
+        eb32c51 create test5.txt
+        "###);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,439 @@
+//! A search algorithm for directed acyclic graphs which tolerates noisy
+//! observations, such as the results of a flaky test.
+//!
+//! Unlike `search::Search`, which assumes that each observation is certain,
+//! this search keeps a probability distribution over which node is the first
+//! failing node, and updates it with Bayes' rule after each observation.
+//! Conflicting observations are allowed, and the same node may be suggested
+//! for testing more than once. The search is complete once a single
+//! hypothesis reaches the configured confidence.
+//!
+//! The model assumes that there is at most one first failing node `F`, and
+//! that a node fails the predicate if and only if `F` is one of its ancestors
+//! (or `F` is the node itself). The next node to test is the one whose result
+//! is expected to reduce the uncertainty about `F` the most.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use tracing::instrument;
+
+use crate::search::{Bounds, Error, SearchGraph, Status};
+
+/// The parameters for a probabilistic search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbabilisticOptions {
+    /// The probability that testing a node which doesn't satisfy the
+    /// predicate reports `Status::Failure`. For a bug which only shows up one
+    /// time in five, this would be `0.2`. Must be in `(0, 1]`.
+    pub failure_rate: f64,
+
+    /// The probability that testing a node which satisfies the predicate
+    /// reports `Status::Failure` anyways. Must be in `[0, failure_rate)`.
+    pub false_failure_rate: f64,
+
+    /// The probability which the most likely hypothesis has to reach before
+    /// the search is considered complete. Must be in `(0, 1]`.
+    pub confidence: f64,
+}
+
+/// The results of the search so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProbabilisticSolution<Node: Debug + Eq + Hash> {
+    /// The bounds implied by the most likely hypothesis. These are only a
+    /// best guess until `next_to_search` is empty.
+    pub bounds: Bounds<Node>,
+
+    /// The most likely first failing node, or `None` if it's most likely that
+    /// none of the nodes fail.
+    pub most_likely_failure: Option<Node>,
+
+    /// The probability of the most likely hypothesis.
+    pub confidence: f64,
+
+    /// The next nodes to search in a suggested order. This may include nodes
+    /// which have already been tested.
+    ///
+    /// This will be empty when the configured confidence has been reached, or
+    /// when no further observation could change the result.
+    pub next_to_search: Vec<Node>,
+}
+
+/// The search algorithm.
+#[derive(Clone, Debug)]
+pub struct ProbabilisticSearch<G: SearchGraph> {
+    graph: G,
+    options: ProbabilisticOptions,
+
+    /// For each node, the probability that it's the first failing node.
+    probabilities: IndexMap<G::Node, f64>,
+
+    /// The probability that none of the nodes fail.
+    no_failure_probability: f64,
+}
+
+impl<G: SearchGraph> ProbabilisticSearch<G> {
+    /// Construct a new search. Initially, each node (and the possibility that
+    /// no node fails) is considered equally likely.
+    pub fn new(
+        graph: G,
+        nodes: impl IntoIterator<Item = G::Node>,
+        options: ProbabilisticOptions,
+    ) -> Self {
+        let mut search = Self {
+            graph,
+            options,
+            probabilities: nodes.into_iter().map(|node| (node, 1.0)).collect(),
+            no_failure_probability: 1.0,
+        };
+        search.normalize();
+        search
+    }
+
+    /// Get the probability that `node` is the first failing node, or `None`
+    /// if `node` isn't part of the search.
+    pub fn probability(&self, node: &G::Node) -> Option<f64> {
+        self.probabilities.get(node).copied()
+    }
+
+    /// Get the probability that none of the nodes fail.
+    pub fn no_failure_probability(&self) -> f64 {
+        self.no_failure_probability
+    }
+
+    fn normalize(&mut self) {
+        let total = self.no_failure_probability + self.probabilities.values().sum::<f64>();
+        for probability in self.probabilities.values_mut() {
+            *probability /= total;
+        }
+        self.no_failure_probability /= total;
+    }
+
+    /// Summarize the current search progress and suggest the next node(s) to
+    /// search. The caller is responsible for calling `notify` with the result.
+    #[instrument]
+    pub fn search(&self) -> Result<ProbabilisticSolution<G::Node>, Error<G>> {
+        let ProbabilisticOptions {
+            failure_rate,
+            false_failure_rate,
+            confidence: target_confidence,
+        } = self.options;
+
+        let (most_likely_failure, confidence) = self.probabilities.iter().fold(
+            (None, self.no_failure_probability),
+            |(acc_node, acc_probability), (node, probability)| {
+                if *probability > acc_probability {
+                    (Some(node.clone()), *probability)
+                } else {
+                    (acc_node, acc_probability)
+                }
+            },
+        );
+
+        let bounds = match &most_likely_failure {
+            Some(node) => {
+                let mut ancestors = self.graph.ancestors(node.clone())?;
+                ancestors.remove(node);
+                ancestors.retain(|ancestor| self.probabilities.contains_key(ancestor));
+                Bounds {
+                    success: self.graph.ancestors_heads(ancestors)?,
+                    failure: [node.clone()].into_iter().collect(),
+                }
+            }
+            None => Bounds {
+                success: self
+                    .graph
+                    .ancestors_heads(self.probabilities.keys().cloned().collect())?,
+                failure: Default::default(),
+            },
+        };
+
+        let next_to_search = if confidence >= target_confidence {
+            Vec::new()
+        } else {
+            let mut scored_nodes = Vec::with_capacity(self.probabilities.len());
+            for (index, node) in self.probabilities.keys().enumerate() {
+                let failing_probability = self.failing_probability(node.clone())?;
+                let gain = information_gain(failing_probability, failure_rate, false_failure_rate);
+                if gain > 0.0 {
+                    scored_nodes.push((gain, index, node.clone()));
+                }
+            }
+            scored_nodes.sort_by(|(lhs_gain, lhs_index, _), (rhs_gain, rhs_index, _)| {
+                rhs_gain
+                    .partial_cmp(lhs_gain)
+                    .unwrap_or(Ordering::Equal)
+                    .then(lhs_index.cmp(rhs_index))
+            });
+            scored_nodes
+                .into_iter()
+                .map(|(_gain, _index, node)| node)
+                .collect()
+        };
+
+        Ok(ProbabilisticSolution {
+            bounds,
+            most_likely_failure,
+            confidence,
+            next_to_search,
+        })
+    }
+
+    /// Get the probability that `node` fails the predicate, i.e. that the
+    /// first failing node is one of its ancestors.
+    fn failing_probability(&self, node: G::Node) -> Result<f64, Error<G>> {
+        let ancestors: HashSet<G::Node> = self.graph.ancestors(node)?;
+        // Sum in a consistent order so that ties between nodes are broken
+        // deterministically.
+        Ok(self
+            .probabilities
+            .iter()
+            .filter_map(|(hypothesis, probability)| {
+                if ancestors.contains(hypothesis) {
+                    Some(probability)
+                } else {
+                    None
+                }
+            })
+            .sum())
+    }
+
+    /// Update the search state with the result of testing a node. Unlike
+    /// `Search::notify`, the same node can be reported several times, with
+    /// different results.
+    #[instrument]
+    pub fn notify(&mut self, node: G::Node, status: Status) -> Result<(), Error<G>> {
+        let ProbabilisticOptions {
+            failure_rate,
+            false_failure_rate,
+            confidence: _,
+        } = self.options;
+        let (likelihood_if_failing, likelihood_if_passing) = match status {
+            Status::Untested | Status::Indeterminate => return Ok(()),
+            Status::Success => (1.0 - failure_rate, 1.0 - false_failure_rate),
+            Status::Failure => (failure_rate, false_failure_rate),
+        };
+
+        let ancestors = self.graph.ancestors(node.clone())?;
+        let probabilities: IndexMap<G::Node, f64> = self
+            .probabilities
+            .iter()
+            .map(|(hypothesis, probability)| {
+                let likelihood = if ancestors.contains(hypothesis) {
+                    likelihood_if_failing
+                } else {
+                    likelihood_if_passing
+                };
+                (hypothesis.clone(), probability * likelihood)
+            })
+            .collect();
+        let no_failure_probability = self.no_failure_probability * likelihood_if_passing;
+
+        let total = no_failure_probability + probabilities.values().sum::<f64>();
+        if total <= 0.0 {
+            return Err(Error::<G>::ImpossibleObservation { node, status });
+        }
+        self.probabilities = probabilities;
+        self.no_failure_probability = no_failure_probability;
+        self.normalize();
+        Ok(())
+    }
+}
+
+/// The binary entropy function, in nats.
+fn entropy(probability: f64) -> f64 {
+    if probability <= 0.0 || probability >= 1.0 {
+        0.0
+    } else {
+        -probability * probability.ln() - (1.0 - probability) * (1.0 - probability).ln()
+    }
+}
+
+/// The expected reduction in entropy of the search from testing a node which
+/// fails the predicate with probability `failing_probability`.
+fn information_gain(failing_probability: f64, failure_rate: f64, false_failure_rate: f64) -> f64 {
+    let observed_failure_probability =
+        failing_probability * failure_rate + (1.0 - failing_probability) * false_failure_rate;
+    entropy(observed_failure_probability)
+        - failing_probability * entropy(failure_rate)
+        - (1.0 - failing_probability) * entropy(false_failure_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use maplit::hashset;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct UsizeGraph {
+        max: usize,
+    }
+
+    impl SearchGraph for UsizeGraph {
+        type Node = usize;
+        type Error = Infallible;
+
+        fn ancestors(&self, node: Self::Node) -> Result<HashSet<Self::Node>, Infallible> {
+            assert!(node < self.max);
+            Ok((0..=node).collect())
+        }
+
+        fn descendants(&self, node: Self::Node) -> Result<HashSet<Self::Node>, Infallible> {
+            assert!(node < self.max);
+            Ok((node..self.max).collect())
+        }
+    }
+
+    /// A deterministic pseudo-random number generator, so that the tests
+    /// don't depend on a particular random number generator crate.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next_f64(&mut self) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let high_bits = u32::try_from(self.0 >> 32).unwrap();
+            f64::from(high_bits) / f64::from(u32::MAX)
+        }
+    }
+
+    /// Run the search to completion, reporting each node at or after
+    /// `first_failure` as failing with probability `failure_rate`. Returns the
+    /// solution and the number of tests which were run.
+    fn run_search(
+        search: &mut ProbabilisticSearch<UsizeGraph>,
+        first_failure: Option<usize>,
+        failure_rate: f64,
+        rng: &mut Lcg,
+    ) -> Result<(ProbabilisticSolution<usize>, usize), Error<UsizeGraph>> {
+        let mut num_tests = 0;
+        loop {
+            let solution = search.search()?;
+            let node = match solution.next_to_search.first() {
+                Some(node) => *node,
+                None => return Ok((solution, num_tests)),
+            };
+            assert!(num_tests < 1000, "Search did not converge");
+            num_tests += 1;
+            let is_failing = matches!(first_failure, Some(first_failure) if node >= first_failure);
+            let status = if is_failing && rng.next_f64() < failure_rate {
+                Status::Failure
+            } else {
+                Status::Success
+            };
+            search.notify(node, status)?;
+        }
+    }
+
+    #[test]
+    fn test_probabilistic_search_noiseless() -> Result<(), Error<UsizeGraph>> {
+        let options = ProbabilisticOptions {
+            failure_rate: 1.0,
+            false_failure_rate: 0.0,
+            confidence: 0.99,
+        };
+        let mut search = ProbabilisticSearch::new(UsizeGraph { max: 8 }, 0..8, options);
+        assert_eq!(search.probability(&0), Some(1.0 / 9.0));
+        assert_eq!(search.no_failure_probability(), 1.0 / 9.0);
+        assert_eq!(
+            search.search()?.next_to_search,
+            vec![3, 4, 2, 5, 1, 6, 0, 7]
+        );
+
+        let (solution, num_tests) = run_search(&mut search, Some(5), 1.0, &mut Lcg(0))?;
+        assert_eq!(
+            solution,
+            ProbabilisticSolution {
+                bounds: Bounds {
+                    success: hashset! {4},
+                    failure: hashset! {5},
+                },
+                most_likely_failure: Some(5),
+                confidence: 1.0,
+                next_to_search: vec![],
+            }
+        );
+        // There are 9 hypotheses, so an optimal noiseless search needs at most
+        // `ceil(log2(9))` tests.
+        assert!(num_tests <= 4);
+
+        let mut search = ProbabilisticSearch::new(UsizeGraph { max: 8 }, 0..8, options);
+        let (solution, _num_tests) = run_search(&mut search, None, 1.0, &mut Lcg(0))?;
+        assert_eq!(
+            solution,
+            ProbabilisticSolution {
+                bounds: Bounds {
+                    success: hashset! {7},
+                    failure: hashset! {},
+                },
+                most_likely_failure: None,
+                confidence: 1.0,
+                next_to_search: vec![],
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_probabilistic_search_flaky() -> Result<(), Error<UsizeGraph>> {
+        let options = ProbabilisticOptions {
+            failure_rate: 0.2,
+            false_failure_rate: 0.0,
+            confidence: 0.95,
+        };
+        let mut search = ProbabilisticSearch::new(UsizeGraph { max: 16 }, 0..16, options);
+        let (solution, num_tests) = run_search(&mut search, Some(11), 0.2, &mut Lcg(42))?;
+        assert_eq!(solution.most_likely_failure, Some(11));
+        assert!(solution.confidence >= 0.95);
+        assert!(solution.next_to_search.is_empty());
+        // Each node can only be tested once in a deterministic search, so
+        // reaching the configured confidence must have required re-testing
+        // some nodes.
+        assert!(num_tests > 16);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_probabilistic_search_conflicting_observations() -> Result<(), Error<UsizeGraph>> {
+        let options = ProbabilisticOptions {
+            failure_rate: 0.9,
+            false_failure_rate: 0.1,
+            confidence: 0.95,
+        };
+        let mut search = ProbabilisticSearch::new(UsizeGraph { max: 4 }, 0..4, options);
+        search.notify(2, Status::Success)?;
+        search.notify(2, Status::Failure)?;
+        search.notify(2, Status::Indeterminate)?;
+        let solution = search.search()?;
+        assert!(solution.confidence < 0.95);
+        assert!(!solution.next_to_search.is_empty());
+
+        let options = ProbabilisticOptions {
+            failure_rate: 1.0,
+            false_failure_rate: 0.0,
+            confidence: 0.95,
+        };
+        let mut search = ProbabilisticSearch::new(UsizeGraph { max: 4 }, 0..4, options);
+        search.notify(2, Status::Success)?;
+        assert!(matches!(
+            search.notify(2, Status::Failure),
+            Err(Error::<UsizeGraph>::ImpossibleObservation {
+                node: 2,
+                status: Status::Failure
+            })
+        ));
+        assert_eq!(search.probability(&3), Some(0.5));
+
+        Ok(())
+    }
+}
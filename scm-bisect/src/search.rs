@@ -1,6 +1,7 @@
 //! A search algorithm for directed acyclic graphs to find the nodes which
 //! "flip" from passing to failing a predicate.
 
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -144,7 +145,7 @@ pub enum Status {
 }
 
 /// The upper and lower bounds of the search.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Bounds<Node: Debug + Eq + Hash> {
     /// The upper bounds of the search. The ancestors of this set have (or are
     /// assumed to have) `Status::Success`.
@@ -219,12 +220,10 @@ pub enum Strategy {
     /// Conduct a binary search on the nodes by partitioning the nodes into two
     /// groups of approximately equal size.
     ///
-    /// TODO: Partitioning into groups of approximately equal size isn't
-    /// actually optimal for the DAG case. Really, we want to maximize the
-    /// information that we gain from each test. The `git bisect` algorithm at
-    /// https://git-scm.com/docs/git-bisect-lk2009#_bisection_algorithm_discussed
-    /// discusses a metric to find the best partition for the subgraph which
-    /// remains to be tested.
+    /// Partitioning into groups of approximately equal size isn't actually
+    /// optimal for the DAG case, since it doesn't take the shape of the graph
+    /// into account. See `Strategy::InformationGain` for a strategy which
+    /// does.
     ///
     /// See also `git-bisect`'s skip algorithm:
     /// https://git-scm.com/docs/git-bisect-lk2009#_skip_algorithm. This does
@@ -245,6 +244,22 @@ pub enum Strategy {
     /// - https://byorgey.wordpress.com/2023/01/01/competitive-programming-in-haskell-better-binary-search/
     /// - https://julesjacobs.com/notes/binarysearch/binarysearch.pdf
     Binary,
+
+    /// Search the node which maximizes the information gained from testing
+    /// it, taking the shape of the remaining graph into account.
+    ///
+    /// If a node succeeds, then all of its ancestors are eliminated from the
+    /// search, and if it fails, then all of its descendants are eliminated.
+    /// Each node is scored by the number of remaining nodes that would be
+    /// eliminated in the worse of those two cases, and nodes are suggested in
+    /// order of decreasing score (ties are broken by the order that the nodes
+    /// were provided in). This is the same metric as the `git bisect`
+    /// algorithm described at
+    /// https://git-scm.com/docs/git-bisect-lk2009#_bisection_algorithm_discussed.
+    ///
+    /// For a linear graph, this picks the same first node as
+    /// `Strategy::Binary`.
+    InformationGain,
 }
 
 /// The error type for the search.
@@ -266,6 +281,9 @@ pub enum Error2<Node, Error> {
         to: Status,
     },
 
+    #[error("observed {status:?} for {node}, which is impossible given the previous observations")]
+    ImpossibleObservation { node: Node, status: Status },
+
     /// Returned when the caller's trait implementation returns an error.
     #[error(transparent)]
     Underlying(#[from] Error),
@@ -349,6 +367,10 @@ impl<G: SearchGraph> Search<G> {
             Strategy::Linear => Box::new(nodes_to_search.into_iter()),
             Strategy::LinearReverse => Box::new(nodes_to_search.into_iter().rev()),
             Strategy::Binary => Box::new(make_binary_search_iter(&nodes_to_search)),
+            Strategy::InformationGain => Box::new(
+                self.make_information_gain_search_iter(&nodes_to_search)?
+                    .into_iter(),
+            ),
         };
 
         Ok(LazySolution {
@@ -409,6 +431,39 @@ impl<G: SearchGraph> Search<G> {
         self.nodes.insert(node, status);
         Ok(())
     }
+
+    /// Order `nodes` (the nodes remaining to be searched) by the number of
+    /// them that would be eliminated from the search in the worst case, if
+    /// each node were tested. See `Strategy::InformationGain`.
+    ///
+    /// FIXME: O(n^2) complexity.
+    #[instrument]
+    fn make_information_gain_search_iter(
+        &self,
+        nodes: &[G::Node],
+    ) -> Result<Vec<G::Node>, Error<G>> {
+        let remaining_nodes: HashSet<G::Node> = nodes.iter().cloned().collect();
+        let mut scored_nodes = Vec::with_capacity(nodes.len());
+        for (index, node) in nodes.iter().enumerate() {
+            let num_eliminated_if_success = self
+                .graph
+                .ancestors(node.clone())?
+                .intersection(&remaining_nodes)
+                .count();
+            let num_eliminated_if_failure = self
+                .graph
+                .descendants(node.clone())?
+                .intersection(&remaining_nodes)
+                .count();
+            let score = num_eliminated_if_success.min(num_eliminated_if_failure);
+            scored_nodes.push((Reverse(score), index, node.clone()));
+        }
+        scored_nodes.sort_by_key(|(score, index, _node)| (*score, *index));
+        Ok(scored_nodes
+            .into_iter()
+            .map(|(_score, _index, node)| node)
+            .collect())
+    }
 }
 
 fn make_binary_search_iter<T: Clone>(nodes: &[T]) -> impl Iterator<Item = T> {
@@ -494,6 +549,13 @@ mod tests {
                 next_to_search: vec![3, 1, 5, 0, 4, 2, 6],
             }
         );
+        assert_eq!(
+            search.search(Strategy::InformationGain)?.into_eager(),
+            EagerSolution {
+                bounds: Default::default(),
+                next_to_search: vec![3, 2, 4, 1, 5, 0, 6],
+            }
+        );
 
         search.notify(2, Status::Success)?;
         assert_eq!(
@@ -516,6 +578,16 @@ mod tests {
                 next_to_search: vec![5, 4, 6, 3],
             }
         );
+        assert_eq!(
+            search.search(Strategy::InformationGain)?.into_eager(),
+            EagerSolution {
+                bounds: Bounds {
+                    success: hashset! {2},
+                    failure: hashset! {},
+                },
+                next_to_search: vec![4, 5, 3, 6],
+            }
+        );
 
         search.notify(5, Status::Failure)?;
         assert_eq!(
@@ -538,6 +610,16 @@ mod tests {
                 next_to_search: vec![4, 3],
             }
         );
+        assert_eq!(
+            search.search(Strategy::InformationGain)?.into_eager(),
+            EagerSolution {
+                bounds: Bounds {
+                    success: hashset! {2},
+                    failure: hashset! {5},
+                },
+                next_to_search: vec![3, 4],
+            }
+        );
 
         search.notify(3, Status::Indeterminate)?;
         assert_eq!(
@@ -650,6 +732,13 @@ mod tests {
                 next_to_search: vec!['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'],
             }
         );
+        assert_eq!(
+            search.search(Strategy::InformationGain)?.into_eager(),
+            EagerSolution {
+                bounds: Default::default(),
+                next_to_search: vec!['e', 'b', 'd', 'f', 'a', 'c', 'g', 'h'],
+            }
+        );
 
         search.notify('b', Status::Success)?;
         search.notify('g', Status::Failure)?;
@@ -708,9 +797,34 @@ mod tests {
             Just(Strategy::Linear),
             Just(Strategy::LinearReverse),
             Just(Strategy::Binary),
+            Just(Strategy::InformationGain),
         ]
     }
 
+    /// Compute the transitive closure of the graph by brute force, as a map
+    /// from each node to the set of its descendants (including itself).
+    fn brute_force_descendants(graph: &TestGraph) -> HashMap<char, HashSet<char>> {
+        let nodes = graph.nodes.keys().copied().collect_vec();
+        let mut reachable: HashMap<char, HashSet<char>> = nodes
+            .iter()
+            .map(|node| {
+                let mut reachable = graph.nodes[node].clone();
+                reachable.insert(*node);
+                (*node, reachable)
+            })
+            .collect();
+        for k in &nodes {
+            for i in &nodes {
+                for j in &nodes {
+                    if reachable[i].contains(k) && reachable[k].contains(j) {
+                        reachable.get_mut(i).unwrap().insert(*j);
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
     fn arb_test_graph_and_nodes() -> impl ProptestStrategy<Value = (TestGraph, Vec<char>)> {
         let nodes = prop::collection::hash_set(
             prop::sample::select(vec!['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h']),
@@ -787,5 +901,53 @@ mod tests {
                 "all_success_nodes: {all_success_nodes:?}, all_failure_nodes: {all_failure_nodes:?}, nodes: {nodes:?}",
             );
         }
+
+        #[test]
+        fn test_search_information_gain_proptest(num_steps in 0..8_usize, (graph, failure_nodes) in arb_test_graph_and_nodes()) {
+            let nodes = graph.nodes.keys().sorted().copied().collect::<Vec<_>>();
+            let mut search = Search::new(graph.clone(), nodes);
+            let failure_nodes = graph.descendants_all(failure_nodes.into_iter().collect()).unwrap();
+            for _ in 0..num_steps {
+                let solution = search.search(Strategy::Binary).unwrap().into_eager();
+                match solution.next_to_search.first() {
+                    Some(node) => {
+                        search.notify(*node, if failure_nodes.contains(node) {
+                            Status::Failure
+                        } else {
+                            Status::Success
+                        }).unwrap();
+                    }
+                    None => break,
+                }
+            }
+
+            // The nodes to search should be the same as for any other
+            // strategy, just in a different order.
+            let remaining_nodes = search.search(Strategy::Linear).unwrap().into_eager().next_to_search;
+            let solution = search.search(Strategy::InformationGain).unwrap().into_eager();
+            assert_eq!(
+                solution.next_to_search.iter().copied().collect::<HashSet<_>>(),
+                remaining_nodes.iter().copied().collect::<HashSet<_>>(),
+            );
+
+            // Score each remaining node by brute force.
+            let descendants = brute_force_descendants(&graph);
+            let score = |node: char| -> usize {
+                let num_ancestors = remaining_nodes
+                    .iter()
+                    .filter(|other| descendants[other].contains(&node))
+                    .count();
+                let num_descendants = remaining_nodes
+                    .iter()
+                    .filter(|other| descendants[&node].contains(other))
+                    .count();
+                num_ancestors.min(num_descendants)
+            };
+            let best_score = remaining_nodes.iter().map(|node| score(*node)).max();
+            assert_eq!(solution.next_to_search.first().map(|node| score(*node)), best_score);
+            for (lhs, rhs) in solution.next_to_search.iter().tuple_windows() {
+                assert!(score(*lhs) >= score(*rhs), "{lhs:?} should not be ordered before {rhs:?}: {solution:?}");
+            }
+        }
     }
 }
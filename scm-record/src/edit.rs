@@ -0,0 +1,200 @@
+//! Editing the changed lines of a section as text.
+//!
+//! The section is rendered in a format similar to `git add --edit`: removed
+//! lines are prefixed with `-`, added lines are prefixed with `+`, and removed
+//! lines which are not selected are prefixed with a space (i.e. they are kept
+//! as context). Added lines which are not selected are omitted. Once the user
+//! has edited the text, it's parsed back into a new set of
+//! [`SectionChangedLine`]s.
+
+use std::borrow::Cow;
+
+use crate::types::{ChangeType, SectionChangedLine};
+
+/// The help text written at the top of a section being edited in an external
+/// editor.
+pub(crate) const EDIT_HELP_TEXT: &str = "\
+# To keep a removed line, change its leading '-' to ' '.
+# To skip an added line, delete it.
+# The text of added lines may be edited. The edited line will be recorded in
+# place of the original line, and the original line will be left unrecorded.
+# Lines starting with '#' will be ignored.
+";
+
+/// Render the given changed lines into text to be edited by the user.
+pub(crate) fn render_section_text(lines: &[SectionChangedLine]) -> String {
+    let mut result = String::new();
+    for line in lines {
+        let SectionChangedLine {
+            is_toggled,
+            change_type,
+            line,
+        } = line;
+        let prefix = match (change_type, is_toggled) {
+            (ChangeType::Removed, true) => '-',
+            (ChangeType::Removed, false) => ' ',
+            (ChangeType::Added, true) => '+',
+            (ChangeType::Added, false) => continue,
+        };
+        result.push(prefix);
+        result.push_str(line.strip_suffix('\n').unwrap_or(line));
+        result.push('\n');
+    }
+    result
+}
+
+/// Parse the text edited by the user back into changed lines, using the
+/// original changed lines of the section to determine which lines were
+/// selected, and which added lines were edited.
+///
+/// Returns an error message suitable for displaying to the user if the text
+/// couldn't be parsed.
+pub(crate) fn parse_section_text(
+    original_lines: &[SectionChangedLine],
+    text: &str,
+) -> Result<Vec<SectionChangedLine<'static>>, String> {
+    let mut removed_toggles: Vec<Option<bool>> = vec![None; original_lines.len()];
+    let mut added_toggles: Vec<Option<bool>> = vec![None; original_lines.len()];
+    // New lines to insert before the original line at the given index (or at
+    // the end, if the index is equal to the number of original lines).
+    let mut inserted_lines: Vec<(usize, String)> = Vec::new();
+
+    let mut next_removed_idx = 0;
+    let mut next_added_idx = 0;
+    for (line_num, edited_line) in text.lines().enumerate() {
+        let line_num = line_num + 1;
+        let (prefix, contents) = match edited_line.chars().next() {
+            Some('#') => continue,
+            Some(c) => edited_line.split_at(c.len_utf8()),
+            None => {
+                // The editor may have stripped the trailing whitespace from an
+                // empty context line, so treat it as one if there's a removed
+                // empty line for it to match. Otherwise, ignore it.
+                let is_empty_context =
+                    original_lines
+                        .iter()
+                        .skip(next_removed_idx)
+                        .any(|original_line| match original_line.change_type {
+                            ChangeType::Removed => strip_newline(&original_line.line).is_empty(),
+                            ChangeType::Added => false,
+                        });
+                if !is_empty_context {
+                    continue;
+                }
+                (" ", "")
+            }
+        };
+        match prefix {
+            "-" | " " => {
+                let found_idx = original_lines
+                    .iter()
+                    .enumerate()
+                    .skip(next_removed_idx)
+                    .find_map(|(idx, original_line)| match original_line.change_type {
+                        ChangeType::Removed if strip_newline(&original_line.line) == contents => {
+                            Some(idx)
+                        }
+                        ChangeType::Removed | ChangeType::Added => None,
+                    });
+                match found_idx {
+                    Some(idx) => {
+                        removed_toggles[idx] = Some(prefix == "-");
+                        next_removed_idx = idx + 1;
+                    }
+                    None => {
+                        return Err(format!(
+                            "Line {line_num}: removed lines can't be edited or reordered"
+                        ));
+                    }
+                }
+            }
+
+            "+" => {
+                let found_idx = original_lines
+                    .iter()
+                    .enumerate()
+                    .skip(next_added_idx)
+                    .find_map(|(idx, original_line)| match original_line.change_type {
+                        ChangeType::Added if strip_newline(&original_line.line) == contents => {
+                            Some(idx)
+                        }
+                        ChangeType::Removed | ChangeType::Added => None,
+                    });
+                match found_idx {
+                    Some(idx) => {
+                        added_toggles[idx] = Some(true);
+                        next_added_idx = idx + 1;
+                    }
+                    None => {
+                        let insert_idx = original_lines
+                            .iter()
+                            .enumerate()
+                            .skip(next_added_idx)
+                            .find_map(|(idx, original_line)| match original_line.change_type {
+                                ChangeType::Added => Some(idx),
+                                ChangeType::Removed => None,
+                            })
+                            .unwrap_or(original_lines.len());
+                        inserted_lines.push((insert_idx, contents.to_owned()));
+                    }
+                }
+            }
+
+            _ => {
+                return Err(format!(
+                    "Line {line_num} must start with '+', '-', or ' ': {edited_line}"
+                ));
+            }
+        }
+    }
+
+    // Preserve the lack of a trailing newline if the user edited the last line
+    // of the section.
+    let last_line_has_newline = original_lines
+        .last()
+        .map(|line| line.line.ends_with('\n'))
+        .unwrap_or(true);
+
+    let mut result = Vec::new();
+    let mut inserted_lines = inserted_lines.into_iter().peekable();
+    for (idx, original_line) in original_lines.iter().enumerate() {
+        while let Some((_, contents)) = inserted_lines.next_if(|(insert_idx, _)| *insert_idx == idx)
+        {
+            result.push(SectionChangedLine {
+                is_toggled: true,
+                change_type: ChangeType::Added,
+                line: Cow::Owned(format!("{contents}\n")),
+            });
+        }
+
+        let is_toggled = match original_line.change_type {
+            // A removed line which was deleted from the text is kept.
+            ChangeType::Removed => removed_toggles[idx].unwrap_or(false),
+            ChangeType::Added => added_toggles[idx].unwrap_or(false),
+        };
+        result.push(SectionChangedLine {
+            is_toggled,
+            change_type: original_line.change_type,
+            line: Cow::Owned(original_line.line.clone().into_owned()),
+        });
+    }
+    let num_remaining = inserted_lines.len();
+    for (i, (_, contents)) in inserted_lines.enumerate() {
+        let is_last = i + 1 == num_remaining;
+        result.push(SectionChangedLine {
+            is_toggled: true,
+            change_type: ChangeType::Added,
+            line: Cow::Owned(if is_last && !last_line_has_newline {
+                contents
+            } else {
+                format!("{contents}\n")
+            }),
+        });
+    }
+    Ok(result)
+}
+
+fn strip_newline(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
@@ -0,0 +1,470 @@
+//! Configurable key bindings.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use thiserror::Error;
+
+use crate::Event;
+
+/// An error which occurred when loading key bindings.
+#[derive(Debug, Error)]
+pub enum KeymapError {
+    /// The action name was not recognized.
+    #[error("unknown action: {0}")]
+    UnknownAction(String),
+
+    /// The key chord could not be parsed.
+    #[error("invalid key chord for action {action}: {chord}")]
+    InvalidKeyChord {
+        /// The name of the action being bound.
+        action: String,
+        /// The key chord which could not be parsed.
+        chord: String,
+    },
+
+    /// The same key chord was bound to multiple actions.
+    #[error("key {chord} is bound to both {first_action} and {second_action}")]
+    Conflict {
+        /// The key chord which was bound multiple times.
+        chord: KeyChord,
+        /// The name of the first action bound to the chord.
+        first_action: &'static str,
+        /// The name of the second action bound to the chord.
+        second_action: &'static str,
+    },
+
+    /// A line in a key bindings file could not be parsed.
+    #[error("invalid line {line_num} in key bindings file: {line}")]
+    InvalidLine {
+        /// The line number (1-indexed).
+        line_num: usize,
+        /// The contents of the line.
+        line: String,
+    },
+
+    /// The key bindings file could not be read.
+    #[error("failed to read key bindings file: {0}")]
+    ReadFile(#[source] std::io::Error),
+}
+
+/// A key together with its modifiers, such as `ctrl-e`.
+///
+/// Character keys are stored without the `shift` modifier, since the shifted
+/// character (e.g. `N` instead of `n`) is reported instead.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse a key chord like `q`, `N`, `space`, `pagedown`, or `ctrl-e`.
+    pub fn parse(chord: &str) -> Option<Self> {
+        let chord = chord.trim();
+        let (modifier_names, key_name) = match chord.rsplit_once('-') {
+            // Support binding the `-` key itself, e.g. `-` or `ctrl--`.
+            Some((modifiers, "")) => (modifiers.strip_suffix('-').unwrap_or(modifiers), "-"),
+            Some((modifiers, key_name)) => (modifiers, key_name),
+            None => ("", chord),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        if !modifier_names.is_empty() {
+            for modifier_name in modifier_names.split('-') {
+                modifiers |= match modifier_name.to_ascii_lowercase().as_str() {
+                    "ctrl" | "c" => KeyModifiers::CONTROL,
+                    "alt" | "meta" | "m" => KeyModifiers::ALT,
+                    "shift" => KeyModifiers::SHIFT,
+                    _ => return None,
+                };
+            }
+        }
+
+        let mut chars = key_name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (None, _) => return None,
+            (Some(c), None) => {
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            (Some(_), Some(_)) => match key_name.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => KeyCode::F(n),
+                    None => return None,
+                },
+            },
+        };
+        Some(Self::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { code, modifiers } = self;
+        if modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// A bindable action: its name (as used in configuration), a description for
+/// the help overlay, and the event it produces.
+struct Action {
+    name: &'static str,
+    description: &'static str,
+    event: Event,
+    default_chords: &'static [&'static str],
+}
+
+fn actions() -> Vec<Action> {
+    vec![
+        Action {
+            name: "accept",
+            description: "Confirm the selected changes",
+            event: Event::QuitAccept,
+            default_chords: &["c"],
+        },
+        Action {
+            name: "quit",
+            description: "Quit without confirming changes",
+            event: Event::QuitCancel,
+            default_chords: &["q"],
+        },
+        Action {
+            name: "interrupt",
+            description: "Quit immediately",
+            event: Event::QuitInterrupt,
+            default_chords: &["ctrl-c"],
+        },
+        Action {
+            name: "help",
+            description: "Show the key bindings",
+            event: Event::Help,
+            default_chords: &["?"],
+        },
+        Action {
+            name: "focusPrev",
+            description: "Select the previous item",
+            event: Event::FocusPrev,
+            default_chords: &["up"],
+        },
+        Action {
+            name: "focusNext",
+            description: "Select the next item",
+            event: Event::FocusNext,
+            default_chords: &["down"],
+        },
+        Action {
+            name: "focusOuter",
+            description: "Select the outer item",
+            event: Event::FocusOuter,
+            default_chords: &["left"],
+        },
+        Action {
+            name: "focusInner",
+            description: "Select the inner item",
+            event: Event::FocusInner,
+            default_chords: &["right"],
+        },
+        Action {
+            name: "focusPrevPage",
+            description: "Select an item half a page up",
+            event: Event::FocusPrevPage,
+            default_chords: &["ctrl-u"],
+        },
+        Action {
+            name: "focusNextPage",
+            description: "Select an item half a page down",
+            event: Event::FocusNextPage,
+            default_chords: &["ctrl-d"],
+        },
+        Action {
+            name: "scrollUp",
+            description: "Scroll up one line",
+            event: Event::ScrollUp,
+            default_chords: &["ctrl-y"],
+        },
+        Action {
+            name: "scrollDown",
+            description: "Scroll down one line",
+            event: Event::ScrollDown,
+            default_chords: &["ctrl-e"],
+        },
+        Action {
+            name: "pageUp",
+            description: "Scroll up one page",
+            event: Event::PageUp,
+            default_chords: &["pageup", "ctrl-b"],
+        },
+        Action {
+            name: "pageDown",
+            description: "Scroll down one page",
+            event: Event::PageDown,
+            default_chords: &["pagedown", "ctrl-f"],
+        },
+        Action {
+            name: "toggleItem",
+            description: "Toggle the selected item",
+            event: Event::ToggleItem,
+            default_chords: &["space"],
+        },
+        Action {
+            name: "toggleItemAndAdvance",
+            description: "Toggle the selected item and select the next one",
+            event: Event::ToggleItemAndAdvance,
+            default_chords: &["enter"],
+        },
+        Action {
+            name: "editItem",
+            description: "Edit the selected section as text",
+            event: Event::EditItem,
+            default_chords: &["e"],
+        },
+        Action {
+            name: "search",
+            description: "Search files and changed lines",
+            event: Event::StartSearch,
+            default_chords: &["/"],
+        },
+        Action {
+            name: "searchNext",
+            description: "Select the next search match",
+            event: Event::SearchNext,
+            default_chords: &["n"],
+        },
+        Action {
+            name: "searchPrev",
+            description: "Select the previous search match",
+            event: Event::SearchPrev,
+            default_chords: &["N"],
+        },
+        Action {
+            name: "filter",
+            description: "Filter files by path glob",
+            event: Event::StartFilter,
+            default_chords: &["f"],
+        },
+        Action {
+            name: "toggleMatches",
+            description: "Toggle all search matches or filtered files",
+            event: Event::ToggleMatches,
+            default_chords: &["a"],
+        },
+    ]
+}
+
+/// A mapping from key chords to the [`Event`]s that they produce.
+///
+/// Actions are named in camelCase (e.g. `toggleItem`), but names are matched
+/// case-insensitively, so that they can be read from Git configuration keys
+/// like `branchless.record.keys.toggleItem`.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(&'static str, Vec<KeyChord>)>,
+    chords: HashMap<KeyChord, Event>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_bindings(Vec::<(String, String)>::new())
+            .expect("default key bindings should not conflict")
+    }
+}
+
+impl Keymap {
+    /// Construct a keymap from the default key bindings, overridden by the
+    /// provided `(action, chords)` pairs, where `chords` is a comma-separated
+    /// list of key chords. Binding an action replaces all of its default key
+    /// chords. Returns an error if any key chord would be bound to more than
+    /// one action.
+    pub fn from_bindings(
+        bindings: impl IntoIterator<Item = (impl AsRef<str>, impl AsRef<str>)>,
+    ) -> Result<Self, KeymapError> {
+        let actions = actions();
+        let mut overrides: HashMap<&'static str, Vec<KeyChord>> = HashMap::new();
+        for (action_name, chords) in bindings {
+            let action_name = action_name.as_ref();
+            let action = actions
+                .iter()
+                .find(|action| action.name.eq_ignore_ascii_case(action_name))
+                .ok_or_else(|| KeymapError::UnknownAction(action_name.to_owned()))?;
+            let chords = chords
+                .as_ref()
+                .split(',')
+                .filter(|chord| !chord.trim().is_empty())
+                .map(|chord| {
+                    KeyChord::parse(chord).ok_or_else(|| KeymapError::InvalidKeyChord {
+                        action: action.name.to_owned(),
+                        chord: chord.trim().to_owned(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            overrides.insert(action.name, chords);
+        }
+
+        let mut bindings = Vec::new();
+        let mut chords: HashMap<KeyChord, Event> = HashMap::new();
+        let mut chord_actions: HashMap<KeyChord, &'static str> = HashMap::new();
+        for action in actions {
+            let Action {
+                name,
+                description,
+                event,
+                default_chords,
+            } = action;
+            let action_chords = match overrides.remove(name) {
+                Some(action_chords) => action_chords,
+                None => default_chords
+                    .iter()
+                    .map(|chord| KeyChord::parse(chord).unwrap())
+                    .collect(),
+            };
+            for chord in action_chords.iter() {
+                if let Some(first_action) = chord_actions.insert(*chord, name) {
+                    return Err(KeymapError::Conflict {
+                        chord: *chord,
+                        first_action,
+                        second_action: name,
+                    });
+                }
+                chords.insert(*chord, event.clone());
+            }
+            bindings.push((description, action_chords));
+        }
+        Ok(Self { bindings, chords })
+    }
+
+    /// Load key bindings from a file. Each line should be of the form `action
+    /// = chord, chord, ...`. Blank lines and lines starting with `#` are
+    /// ignored.
+    pub fn from_file(path: &Path) -> Result<Self, KeymapError> {
+        let contents = fs::read_to_string(path).map_err(KeymapError::ReadFile)?;
+        let mut bindings = Vec::new();
+        for (line_num, line) in contents.lines().enumerate() {
+            let trimmed_line = line.trim();
+            if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+                continue;
+            }
+            match trimmed_line.split_once('=') {
+                Some((action, chords)) => bindings.push((action.trim(), chords)),
+                None => {
+                    return Err(KeymapError::InvalidLine {
+                        line_num: line_num + 1,
+                        line: line.to_owned(),
+                    })
+                }
+            }
+        }
+        Self::from_bindings(bindings)
+    }
+
+    /// The active key bindings, as `(description, chords)` pairs, in the
+    /// order they should be displayed.
+    pub(crate) fn help_lines(&self) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .map(|(description, chords)| {
+                let chords: Vec<String> = chords.iter().map(|chord| chord.to_string()).collect();
+                (chords.join(", "), *description)
+            })
+            .collect()
+    }
+
+    /// Convert the `crossterm` event into the [`Event`] bound to it, if any.
+    /// Mouse events are not configurable.
+    pub fn event_for(&self, event: crossterm::event::Event) -> Event {
+        use crossterm::event::Event;
+        match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                state: _,
+            }) => self
+                .chords
+                .get(&KeyChord::new(code, modifiers))
+                .cloned()
+                .unwrap_or(crate::Event::None),
+
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollUp,
+                column: _,
+                row: _,
+                modifiers: _,
+            }) => crate::Event::ScrollUp,
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                column: _,
+                row: _,
+                modifiers: _,
+            }) => crate::Event::ScrollDown,
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                modifiers: _,
+            }) => crate::Event::Click {
+                row: row.into(),
+                column: column.into(),
+            },
+
+            _event => crate::Event::None,
+        }
+    }
+}
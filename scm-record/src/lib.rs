@@ -9,13 +9,17 @@
 )]
 #![allow(clippy::too_many_arguments, clippy::blocks_in_if_conditions)]
 
+mod edit;
+mod keymap;
 mod render;
 mod types;
 mod ui;
 mod util;
+mod word_diff;
 
 pub mod consts;
 pub mod helpers;
+pub use keymap::{KeyChord, Keymap, KeymapError};
 pub use types::{
     ChangeType, File, FileMode, RecordError, RecordState, Section, SectionChangedLine,
     SelectedContents,
@@ -44,6 +44,12 @@ pub enum RecordError {
     #[error("failed to wrote file: {0}")]
     WriteFile(#[source] io::Error),
 
+    #[error("failed to read file: {0}")]
+    ReadFile(#[source] io::Error),
+
+    #[error("failed to run editor: {0}")]
+    RunEditor(#[source] io::Error),
+
     #[error("bug: {0}")]
     Bug(String),
 }
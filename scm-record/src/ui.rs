@@ -8,18 +8,19 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::ops::Range;
 use std::path::Path;
 use std::rc::Rc;
-use std::{fs, io, panic};
+use std::{fs, io, panic, process};
 
 use crossterm::event::{
     DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
-    MouseButton, MouseEvent, MouseEventKind,
 };
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, EnterAlternateScreen,
     LeaveAlternateScreen,
 };
+use regex::Regex;
 use tui::backend::{Backend, TestBackend};
 use tui::buffer::Buffer;
 use tui::style::{Color, Modifier, Style};
@@ -29,9 +30,12 @@ use tui::{backend::CrosstermBackend, Terminal};
 use unicode_width::UnicodeWidthStr;
 
 use crate::consts::{DUMP_UI_STATE_FILENAME, ENV_VAR_DEBUG_UI, ENV_VAR_DUMP_UI_STATE};
+use crate::edit::{parse_section_text, render_section_text, EDIT_HELP_TEXT};
+use crate::keymap::Keymap;
 use crate::render::{centered_rect, Component, Rect, RectSize, Viewport};
 use crate::types::{ChangeType, RecordError, RecordState};
-use crate::util::UsizeExt;
+use crate::util::{get_sh, UsizeExt};
+use crate::word_diff::{section_emphasis, ChangedRanges};
 use crate::{File, Section, SectionChangedLine};
 
 const NUM_CONTEXT_LINES: usize = 3;
@@ -78,11 +82,25 @@ impl Default for SelectionKey {
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct TestingScreenshot {
     contents: Rc<RefCell<Option<String>>>,
+    show_emphasis: bool,
 }
 
 impl TestingScreenshot {
+    /// Construct a screenshot which additionally marks the cells drawn with a
+    /// background color (such as the emphasized parts of changed lines) with
+    /// `^` on the line underneath.
+    pub fn with_emphasis() -> Self {
+        Self {
+            contents: Default::default(),
+            show_emphasis: true,
+        }
+    }
+
     fn set(&self, new_contents: String) {
-        let Self { contents } = self;
+        let Self {
+            contents,
+            show_emphasis: _,
+        } = self;
         *contents.borrow_mut() = Some(new_contents);
     }
 
@@ -94,7 +112,10 @@ impl TestingScreenshot {
 
 impl Display for TestingScreenshot {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let Self { contents } = self;
+        let Self {
+            contents,
+            show_emphasis: _,
+        } = self;
         match contents.borrow().as_ref() {
             Some(contents) => write!(f, "{contents}"),
             None => write!(f, "<this screenshot was never assigned>"),
@@ -122,87 +143,71 @@ pub enum Event {
     FocusOuter,
     ToggleItem,
     ToggleItemAndAdvance,
+    EditItem,
+    EditorInsert(char),
+    EditorNewline,
+    EditorBackspace,
+    EditorAccept,
+    EditorCancel,
+    StartSearch,
+    StartFilter,
+    SearchNext,
+    SearchPrev,
+    ToggleMatches,
+    Help,
     Click { row: usize, column: usize },
 }
 
 impl From<crossterm::event::Event> for Event {
+    /// Convert the `crossterm` event using the default key bindings. See
+    /// [`Keymap`] to use custom key bindings.
     fn from(event: crossterm::event::Event) -> Self {
+        Keymap::default().event_for(event)
+    }
+}
+
+impl Event {
+    /// Convert the `crossterm` event into an event for the inline section
+    /// editor or the search/filter prompt, which are open while the user is
+    /// entering text. Most keys insert their corresponding character instead
+    /// of performing an action.
+    fn from_editor_event(event: crossterm::event::Event) -> Self {
         use crossterm::event::Event;
         match event {
             Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                modifiers: KeyModifiers::NONE,
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
                 kind: KeyEventKind::Press,
                 state: _,
-            }) => Self::QuitCancel,
+            }) => Self::QuitInterrupt,
 
             Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
+                code: KeyCode::Char('s'),
                 modifiers: KeyModifiers::CONTROL,
                 kind: KeyEventKind::Press,
                 state: _,
-            }) => Self::QuitInterrupt,
+            }) => Self::EditorAccept,
 
             Event::Key(KeyEvent {
-                code: KeyCode::Char('c'),
+                code: KeyCode::Esc,
                 modifiers: KeyModifiers::NONE,
                 kind: KeyEventKind::Press,
                 state: _,
-            }) => Self::QuitAccept,
+            }) => Self::EditorCancel,
 
             Event::Key(KeyEvent {
-                code: KeyCode::Char('y'),
-                modifiers: KeyModifiers::CONTROL,
+                code: KeyCode::Enter,
+                modifiers: KeyModifiers::NONE,
                 kind: KeyEventKind::Press,
                 state: _,
-            })
-            | Event::Mouse(MouseEvent {
-                kind: MouseEventKind::ScrollUp,
-                column: _,
-                row: _,
-                modifiers: _,
-            }) => Self::ScrollUp,
+            }) => Self::EditorNewline,
+
             Event::Key(KeyEvent {
-                code: KeyCode::Char('e'),
-                modifiers: KeyModifiers::CONTROL,
+                code: KeyCode::Backspace,
+                modifiers: KeyModifiers::NONE,
                 kind: KeyEventKind::Press,
                 state: _,
-            })
-            | Event::Mouse(MouseEvent {
-                kind: MouseEventKind::ScrollDown,
-                column: _,
-                row: _,
-                modifiers: _,
-            }) => Self::ScrollDown,
-
-            Event::Key(
-                KeyEvent {
-                    code: KeyCode::PageUp,
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: _,
-                }
-                | KeyEvent {
-                    code: KeyCode::Char('b'),
-                    modifiers: KeyModifiers::CONTROL,
-                    kind: KeyEventKind::Press,
-                    state: _,
-                },
-            ) => Self::PageUp,
-            Event::Key(
-                KeyEvent {
-                    code: KeyCode::PageDown,
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    state: _,
-                }
-                | KeyEvent {
-                    code: KeyCode::Char('f'),
-                    modifiers: KeyModifiers::CONTROL,
-                    kind: KeyEventKind::Press,
-                    state: _,
-                },
-            ) => Self::PageDown,
+            }) => Self::EditorBackspace,
 
             Event::Key(KeyEvent {
                 code: KeyCode::Up,
@@ -233,41 +238,11 @@ impl From<crossterm::event::Event> for Event {
             }) => Self::FocusInner,
 
             Event::Key(KeyEvent {
-                code: KeyCode::Char('u'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusPrevPage,
-            Event::Key(KeyEvent {
-                code: KeyCode::Char('d'),
-                modifiers: KeyModifiers::CONTROL,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::FocusNextPage,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Char(' '),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: _,
-            }) => Self::ToggleItem,
-
-            Event::Key(KeyEvent {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::NONE,
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
                 kind: KeyEventKind::Press,
                 state: _,
-            }) => Self::ToggleItemAndAdvance,
-
-            Event::Mouse(MouseEvent {
-                kind: MouseEventKind::Down(MouseButton::Left),
-                column,
-                row,
-                modifiers: _,
-            }) => Self::Click {
-                row: row.into(),
-                column: column.into(),
-            },
+            }) => Self::EditorInsert(c),
 
             _event => Self::None,
         }
@@ -306,11 +281,15 @@ impl EventSource {
         }
     }
 
-    fn next_event(&mut self) -> Result<Event, RecordError> {
+    fn next_event(&mut self, keymap: &Keymap, is_editing: bool) -> Result<Event, RecordError> {
         match self {
             EventSource::Crossterm => {
                 let event = crossterm::event::read().map_err(RecordError::ReadInput)?;
-                Ok(event.into())
+                if is_editing {
+                    Ok(Event::from_editor_event(event))
+                } else {
+                    Ok(keymap.event_for(event))
+                }
             }
             EventSource::Testing {
                 width: _,
@@ -322,16 +301,22 @@ impl EventSource {
 }
 
 /// Copied from internal implementation of `tui`.
-fn buffer_view(buffer: &Buffer) -> String {
+fn buffer_view(buffer: &Buffer, show_emphasis: bool) -> String {
     let mut view =
         String::with_capacity(buffer.content.len() + usize::from(buffer.area.height) * 3);
     for cells in buffer.content.chunks(buffer.area.width.into()) {
         let mut overwritten = vec![];
+        let mut emphasis_markers = String::new();
         let mut skip: usize = 0;
         view.push('"');
         for (x, c) in cells.iter().enumerate() {
             if skip == 0 {
                 view.push_str(&c.symbol);
+                emphasis_markers.push_str(&if c.bg == Color::Reset {
+                    " ".repeat(c.symbol.width())
+                } else {
+                    "^".repeat(c.symbol.width())
+                });
             } else {
                 overwritten.push((x, &c.symbol))
             }
@@ -347,6 +332,9 @@ fn buffer_view(buffer: &Buffer) -> String {
             .unwrap();
         }
         view.push('\n');
+        if show_emphasis && emphasis_markers.contains('^') {
+            writeln!(&mut view, " {}", emphasis_markers.trim_end()).unwrap();
+        }
     }
     view
 }
@@ -362,31 +350,79 @@ enum StateUpdate {
     SelectItem(SelectionKey),
     ToggleItem(SelectionKey),
     ToggleItemAndAdvance(SelectionKey, SelectionKey),
+    EditSection(SectionKey),
+    SetEditDialog(Option<EditDialog>),
+    AcceptEdit(EditDialog),
+    SetPrompt(Option<Prompt>),
+    AcceptPrompt(Prompt),
+    SetHelpDialog(Option<HelpDialog>),
+    ToggleMatches,
 }
 
 /// UI component to record the user's changes.
 pub struct Recorder<'a> {
     state: RecordState<'a>,
     event_source: EventSource,
+    keymap: Keymap,
     use_unicode: bool,
     selection_key: SelectionKey,
     quit_dialog: Option<QuitDialog>,
+    edit_dialog: Option<EditDialog>,
+    help_dialog: Option<HelpDialog>,
+    prompt: Option<Prompt>,
+    search: Option<Search>,
+    path_filter: Option<PathFilter>,
+    line_emphasis: HashMap<SectionKey, Vec<ChangedRanges>>,
     scroll_offset_y: isize,
 }
 
 impl<'a> Recorder<'a> {
     /// Constructor.
     pub fn new(state: RecordState<'a>, event_source: EventSource) -> Self {
+        let line_emphasis = state
+            .files
+            .iter()
+            .enumerate()
+            .flat_map(|(file_idx, file)| {
+                file.sections
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(section_idx, section)| match section {
+                        Section::Changed { lines } => Some((
+                            SectionKey {
+                                file_idx,
+                                section_idx,
+                            },
+                            section_emphasis(lines),
+                        )),
+                        Section::Unchanged { .. }
+                        | Section::FileMode { .. }
+                        | Section::Binary { .. } => None,
+                    })
+            })
+            .collect();
         Self {
             state,
             event_source,
+            keymap: Keymap::default(),
             use_unicode: true,
             selection_key: SelectionKey::None,
             quit_dialog: None,
+            edit_dialog: None,
+            help_dialog: None,
+            prompt: None,
+            search: None,
+            path_filter: None,
+            line_emphasis,
             scroll_offset_y: 0,
         }
     }
 
+    /// Use the provided key bindings instead of the default ones.
+    pub fn with_keymap(self, keymap: Keymap) -> Self {
+        Self { keymap, ..self }
+    }
+
     /// Run the terminal user interface and have the user interactively select
     /// changes.
     pub fn run(self) -> Result<RecordState<'a>, RecordError> {
@@ -468,7 +504,9 @@ impl<'a> Recorder<'a> {
 
         loop {
             let app = self.make_app(None);
-            let term_height = usize::from(term.get_frame().size().height);
+            // The status bar covers the last line of the terminal.
+            let term_height = usize::from(term.get_frame().size().height)
+                .saturating_sub(if app.status_bar.is_some() { 1 } else { 0 });
 
             let mut drawn_rects: Option<HashMap<ComponentId, Rect>> = None;
             term.draw(|frame| {
@@ -512,7 +550,8 @@ impl<'a> Recorder<'a> {
                 .map_err(RecordError::RenderFrame)?;
             }
 
-            let event = self.event_source.next_event()?;
+            let is_editing = self.edit_dialog.is_some() || self.prompt.is_some();
+            let event = self.event_source.next_event(&self.keymap, is_editing)?;
             match self.handle_event(event, term_height, &drawn_rects)? {
                 StateUpdate::None => {}
                 StateUpdate::SetQuitDialog(quit_dialog) => {
@@ -525,7 +564,7 @@ impl<'a> Recorder<'a> {
                     let test_backend = backend
                         .downcast_ref::<TestBackend>()
                         .expect("TakeScreenshot event generated for non-testing backend");
-                    screenshot.set(buffer_view(test_backend.buffer()));
+                    screenshot.set(buffer_view(test_backend.buffer(), screenshot.show_emphasis));
                 }
                 StateUpdate::ScrollTo(scroll_offset_y) => {
                     self.scroll_offset_y = scroll_offset_y
@@ -533,8 +572,10 @@ impl<'a> Recorder<'a> {
                 }
                 StateUpdate::SelectItem(selection_key) => {
                     self.selection_key = selection_key;
-                    self.scroll_offset_y =
-                        self.ensure_in_viewport(term_height, &drawn_rects, selection_key);
+                    if selection_key != SelectionKey::None {
+                        self.scroll_offset_y =
+                            self.ensure_in_viewport(term_height, &drawn_rects, selection_key);
+                    }
                 }
                 StateUpdate::ToggleItem(selection_key) => {
                     self.toggle_item(selection_key)?;
@@ -545,18 +586,360 @@ impl<'a> Recorder<'a> {
                     self.scroll_offset_y =
                         self.ensure_in_viewport(term_height, &drawn_rects, selection_key);
                 }
+                StateUpdate::EditSection(section_key) => {
+                    self.edit_section(term, section_key)?;
+                }
+                StateUpdate::SetEditDialog(edit_dialog) => {
+                    self.edit_dialog = edit_dialog;
+                }
+                StateUpdate::AcceptEdit(edit_dialog) => {
+                    let text = edit_dialog.text();
+                    self.edit_dialog = match self.apply_edit(edit_dialog.section_key, &text)? {
+                        Ok(()) => None,
+                        Err(error) => Some(EditDialog {
+                            error: Some(error),
+                            ..edit_dialog
+                        }),
+                    };
+                }
+                StateUpdate::SetPrompt(prompt) => {
+                    self.prompt = prompt;
+                }
+                StateUpdate::SetHelpDialog(help_dialog) => {
+                    self.help_dialog = help_dialog;
+                }
+                StateUpdate::AcceptPrompt(prompt) => {
+                    self.accept_prompt(prompt);
+                }
+                StateUpdate::ToggleMatches => {
+                    self.toggle_matches()?;
+                }
             }
         }
 
         Ok(self.state)
     }
 
+    fn accept_prompt(&mut self, prompt: Prompt) {
+        let Prompt { kind, text } = prompt;
+        self.prompt = None;
+        match kind {
+            PromptKind::Search => {
+                self.search = if text.is_empty() {
+                    None
+                } else {
+                    Some(Search::new(text))
+                };
+                // Jump to the first match at or after the current selection.
+                let (keys, index) = self.find_selection();
+                let index = index.unwrap_or_default();
+                if let Some(key) = self.find_match(&keys, index, true) {
+                    self.selection_key = key;
+                }
+            }
+            PromptKind::Filter => {
+                self.path_filter = if text.is_empty() {
+                    None
+                } else {
+                    Some(PathFilter::new(text))
+                };
+                // The selected item may have been hidden.
+                let (_keys, index) = self.find_selection();
+                if index.is_none() {
+                    self.selection_key = self.first_selection_key();
+                }
+                self.scroll_offset_y = 0;
+            }
+        }
+    }
+
+    /// Whether the file is shown, according to the current path filter.
+    fn is_file_visible(&self, file: &File) -> bool {
+        match &self.path_filter {
+            Some(path_filter) => path_filter.matches(&file.path),
+            None => true,
+        }
+    }
+
+    /// Whether the given item matches the current search. Only files and
+    /// changed lines can match.
+    fn is_match(&self, selection_key: SelectionKey) -> bool {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return false,
+        };
+        match selection_key {
+            SelectionKey::None | SelectionKey::Section(_) => false,
+            SelectionKey::File(FileKey { file_idx }) => {
+                search.is_match(&self.state.files[file_idx].path.to_string_lossy())
+            }
+            SelectionKey::Line(LineKey {
+                file_idx,
+                section_idx,
+                line_idx,
+            }) => match &self.state.files[file_idx].sections[section_idx] {
+                Section::Changed { lines } => search.is_match(&lines[line_idx].line),
+                Section::Unchanged { .. } | Section::FileMode { .. } | Section::Binary { .. } => {
+                    false
+                }
+            },
+        }
+    }
+
+    /// Find the next (or previous) item matching the current search, starting
+    /// from the item at `index` (inclusive) and wrapping around.
+    fn find_match(
+        &self,
+        keys: &[SelectionKey],
+        index: usize,
+        forwards: bool,
+    ) -> Option<SelectionKey> {
+        let num_keys = keys.len();
+        if num_keys == 0 {
+            return None;
+        }
+        (0..num_keys)
+            .map(|offset| {
+                if forwards {
+                    (index + offset) % num_keys
+                } else {
+                    (index + num_keys - offset) % num_keys
+                }
+            })
+            .map(|index| keys[index])
+            .find(|key| self.is_match(*key))
+    }
+
+    fn select_next_match(&self, forwards: bool) -> StateUpdate {
+        let (keys, index) = self.find_selection();
+        if keys.is_empty() {
+            return StateUpdate::None;
+        }
+        let index = match index {
+            Some(index) if forwards => (index + 1) % keys.len(),
+            Some(index) => (index + keys.len() - 1) % keys.len(),
+            None => 0,
+        };
+        match self.find_match(&keys, index, forwards) {
+            Some(key) => StateUpdate::SelectItem(key),
+            None => StateUpdate::None,
+        }
+    }
+
+    /// The items affected by `Event::ToggleMatches`: all items matching the
+    /// current search, or else all files matching the current path filter.
+    fn match_keys(&self) -> Vec<SelectionKey> {
+        let keys = self.all_selection_keys();
+        if self.search.is_some() {
+            keys.into_iter().filter(|key| self.is_match(*key)).collect()
+        } else if self.path_filter.is_some() {
+            keys.into_iter()
+                .filter(|key| match key {
+                    SelectionKey::File(_) => true,
+                    SelectionKey::None | SelectionKey::Section(_) | SelectionKey::Line(_) => false,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Select all of the matching items, or unselect them if they're all
+    /// already selected.
+    fn toggle_matches(&mut self) -> Result<(), RecordError> {
+        let keys = self.match_keys();
+        let mut all_checked = true;
+        for key in &keys {
+            match self.item_tristate(*key)? {
+                Tristate::Checked => {}
+                Tristate::Unchecked | Tristate::Partial => {
+                    all_checked = false;
+                }
+            }
+        }
+        for key in keys {
+            self.set_item_toggled(key, !all_checked)?;
+        }
+        Ok(())
+    }
+
+    fn num_matches(&self) -> (Option<usize>, usize) {
+        let keys = self.match_keys();
+        let index = keys.iter().position(|key| key == &self.selection_key);
+        (index, keys.len())
+    }
+
+    /// Open the given section for editing. If the user has configured an
+    /// editor with `$VISUAL` or `$EDITOR`, then the section is edited in that
+    /// editor; otherwise, it's edited in the inline editor.
+    fn edit_section(
+        &mut self,
+        term: &mut Terminal<impl Backend + Any>,
+        section_key: SectionKey,
+    ) -> Result<(), RecordError> {
+        let text = match self.section(section_key)? {
+            Section::Changed { lines } => render_section_text(lines),
+            Section::Unchanged { .. } | Section::FileMode { .. } | Section::Binary { .. } => {
+                return Ok(())
+            }
+        };
+        let title = format!(
+            "Edit section {}/{} of {}",
+            self.editable_section_num(section_key)?,
+            self.total_num_editable_sections(section_key)?,
+            self.file(FileKey {
+                file_idx: section_key.file_idx
+            })?
+            .path
+            .to_string_lossy()
+        );
+
+        let external_editor = match self.event_source {
+            EventSource::Crossterm => std::env::var("VISUAL")
+                .or_else(|_| std::env::var("EDITOR"))
+                .ok()
+                .filter(|editor| !editor.is_empty()),
+            EventSource::Testing { .. } => None,
+        };
+        let (text, error) = match external_editor {
+            Some(editor) => {
+                let edited_text = Self::run_external_editor(term, &editor, &text)?;
+                match edited_text {
+                    None => return Ok(()),
+                    Some(edited_text) => match self.apply_edit(section_key, &edited_text)? {
+                        Ok(()) => return Ok(()),
+                        Err(error) => (edited_text, Some(error)),
+                    },
+                }
+            }
+            None => (text, None),
+        };
+
+        self.edit_dialog = Some(EditDialog::new(section_key, title, &text, error));
+        Ok(())
+    }
+
+    /// Suspend the UI and edit the given text with the user's editor. Returns
+    /// `None` if the editor exited unsuccessfully.
+    fn run_external_editor(
+        term: &mut Terminal<impl Backend + Any>,
+        editor: &str,
+        text: &str,
+    ) -> Result<Option<String>, RecordError> {
+        let mut file = tempfile::Builder::new()
+            .prefix("scm-record-edit-")
+            .suffix(".diff")
+            .tempfile()
+            .map_err(RecordError::WriteFile)?;
+        io::Write::write_all(&mut file, format!("{EDIT_HELP_TEXT}{text}").as_bytes())
+            .map_err(RecordError::WriteFile)?;
+        let path = file.path();
+        let sh = get_sh().ok_or_else(|| {
+            RecordError::RunEditor(io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not find a shell to run the editor with",
+            ))
+        })?;
+
+        Self::clean_up_crossterm().map_err(RecordError::CleanUpTerminal)?;
+        // Run the editor through the shell so that `$EDITOR` can contain
+        // arguments, in the same way that Git does.
+        let status = process::Command::new(sh)
+            .arg("-c")
+            .arg(format!("{editor} \"$@\""))
+            .arg(editor)
+            .arg(path)
+            .status();
+        enable_raw_mode().map_err(RecordError::SetUpTerminal)?;
+        crossterm::execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+            .map_err(RecordError::SetUpTerminal)?;
+        term.clear().map_err(RecordError::SetUpTerminal)?;
+
+        let status = status.map_err(RecordError::RunEditor)?;
+        let result = if status.success() {
+            Some(fs::read_to_string(path).map_err(RecordError::ReadFile)?)
+        } else {
+            None
+        };
+        file.close().map_err(RecordError::WriteFile)?;
+        Ok(result)
+    }
+
+    /// Replace the changed lines of the given section with the ones parsed
+    /// from the edited text. Returns an error message for the user if the
+    /// text couldn't be parsed, in which case the section is left unchanged.
+    fn apply_edit(
+        &mut self,
+        section_key: SectionKey,
+        text: &str,
+    ) -> Result<Result<(), String>, RecordError> {
+        let new_lines = match self.section(section_key)? {
+            Section::Changed { lines } => match parse_section_text(lines, text) {
+                Ok(new_lines) => new_lines,
+                Err(error) => return Ok(Err(error)),
+            },
+            section @ (Section::Unchanged { .. }
+            | Section::FileMode { .. }
+            | Section::Binary { .. }) => {
+                return Err(RecordError::Bug(format!(
+                    "Tried to apply edit to uneditable section {section_key:?}: {section:?}"
+                )))
+            }
+        };
+
+        // The number of lines in the section may have changed, so make sure
+        // that the selection still points to a valid line.
+        if let SelectionKey::Line(LineKey {
+            file_idx,
+            section_idx,
+            line_idx: _,
+        }) = self.selection_key
+        {
+            if (SectionKey {
+                file_idx,
+                section_idx,
+            }) == section_key
+            {
+                self.selection_key = SelectionKey::Section(section_key);
+            }
+        }
+        self.line_emphasis
+            .insert(section_key, section_emphasis(&new_lines));
+        self.state.files[section_key.file_idx].sections[section_key.section_idx] =
+            Section::Changed { lines: new_lines };
+        Ok(Ok(()))
+    }
+
+    fn editable_section_num(&self, section_key: SectionKey) -> Result<usize, RecordError> {
+        let file = self.file(FileKey {
+            file_idx: section_key.file_idx,
+        })?;
+        Ok(file
+            .sections
+            .iter()
+            .take(section_key.section_idx + 1)
+            .filter(|section| section.is_editable())
+            .count())
+    }
+
+    fn total_num_editable_sections(&self, section_key: SectionKey) -> Result<usize, RecordError> {
+        let file = self.file(FileKey {
+            file_idx: section_key.file_idx,
+        })?;
+        Ok(file
+            .sections
+            .iter()
+            .filter(|section| section.is_editable())
+            .count())
+    }
+
     fn make_app(&'a self, debug_info: Option<AppDebugInfo>) -> App<'a> {
         let file_views: Vec<FileView> = self
             .state
             .files
             .iter()
             .enumerate()
+            .filter(|(_, file)| self.is_file_visible(file))
             .map(|(file_idx, file)| {
                 let file_key = FileKey { file_idx };
                 let file_tristate = self.file_tristate(file_key).unwrap();
@@ -575,6 +958,7 @@ impl<'a> Recorder<'a> {
                     },
                     is_header_selected: is_focused,
                     path: &file.path,
+                    search_regex: self.search.as_ref().map(|search| &search.regex),
                     section_views: {
                         let mut section_views = Vec::new();
                         let total_num_sections = file.sections.len();
@@ -597,6 +981,12 @@ impl<'a> Recorder<'a> {
                             }
                             section_views.push(SectionView {
                                 use_unicode: self.use_unicode,
+                                search_regex: self.search.as_ref().map(|search| &search.regex),
+                                line_emphasis: self
+                                    .line_emphasis
+                                    .get(&section_key)
+                                    .map(|line_emphasis| line_emphasis.as_slice())
+                                    .unwrap_or_default(),
                                 section_key,
                                 tristate_box: TristateBox {
                                     use_unicode: self.use_unicode,
@@ -666,6 +1056,42 @@ impl<'a> Recorder<'a> {
             debug_info: None,
             file_views,
             quit_dialog: self.quit_dialog.clone(),
+            edit_dialog: self.edit_dialog.clone(),
+            help_dialog: self.help_dialog.clone(),
+            status_bar: self.make_status_bar(),
+        }
+    }
+
+    fn make_status_bar(&self) -> Option<StatusBar> {
+        if let Some(Prompt { kind, text }) = &self.prompt {
+            let text = match kind {
+                PromptKind::Search => format!("/{text}"),
+                PromptKind::Filter => format!("Filter files by path glob: {text}"),
+            };
+            return Some(StatusBar { text });
+        }
+
+        let mut parts = Vec::new();
+        if let Some(search) = &self.search {
+            let (index, num_matches) = self.num_matches();
+            parts.push(match index {
+                Some(index) => format!("Search: {} ({}/{num_matches})", search.query, index + 1),
+                None => format!(
+                    "Search: {} ({num_matches} {})",
+                    search.query,
+                    if num_matches == 1 { "match" } else { "matches" }
+                ),
+            });
+        }
+        if let Some(path_filter) = &self.path_filter {
+            parts.push(format!("Filter: {}", path_filter.glob));
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(StatusBar {
+                text: parts.join("  "),
+            })
         }
     }
 
@@ -675,6 +1101,22 @@ impl<'a> Recorder<'a> {
         term_height: usize,
         drawn_rects: &HashMap<ComponentId, Rect>,
     ) -> Result<StateUpdate, RecordError> {
+        if let Some(edit_dialog) = &self.edit_dialog {
+            return Ok(edit_dialog.handle_event(event));
+        }
+        if let Some(prompt) = &self.prompt {
+            return Ok(prompt.handle_event(event));
+        }
+        if self.help_dialog.is_some() {
+            // Any key closes the help dialog.
+            return Ok(match event {
+                Event::None => StateUpdate::None,
+                Event::TakeScreenshot(screenshot) => StateUpdate::TakeScreenshot(screenshot),
+                Event::QuitInterrupt => StateUpdate::QuitCancel,
+                _ => StateUpdate::SetHelpDialog(None),
+            });
+        }
+
         let state_update = match (&self.quit_dialog, event) {
             (_, Event::None) => StateUpdate::None,
 
@@ -778,6 +1220,52 @@ impl<'a> Recorder<'a> {
                 StateUpdate::None
             }
             (None, Event::ToggleItem) => StateUpdate::ToggleItem(self.selection_key),
+            (None, Event::EditItem) => match self.selection_key {
+                SelectionKey::None | SelectionKey::File(_) => StateUpdate::None,
+                SelectionKey::Section(section_key) => StateUpdate::EditSection(section_key),
+                SelectionKey::Line(LineKey {
+                    file_idx,
+                    section_idx,
+                    line_idx: _,
+                }) => StateUpdate::EditSection(SectionKey {
+                    file_idx,
+                    section_idx,
+                }),
+            },
+            (None, Event::StartSearch) => StateUpdate::SetPrompt(Some(Prompt {
+                kind: PromptKind::Search,
+                text: String::new(),
+            })),
+            (None, Event::StartFilter) => StateUpdate::SetPrompt(Some(Prompt {
+                kind: PromptKind::Filter,
+                text: self
+                    .path_filter
+                    .as_ref()
+                    .map(|path_filter| path_filter.glob.clone())
+                    .unwrap_or_default(),
+            })),
+            (None, Event::SearchNext) => self.select_next_match(true),
+            (None, Event::SearchPrev) => self.select_next_match(false),
+            (None, Event::ToggleMatches) => StateUpdate::ToggleMatches,
+            (None, Event::Help) => StateUpdate::SetHelpDialog(Some(HelpDialog {
+                lines: self.keymap.help_lines(),
+            })),
+            // Editor events are only meaningful while the edit dialog is open.
+            (
+                _,
+                Event::EditItem
+                | Event::StartSearch
+                | Event::StartFilter
+                | Event::SearchNext
+                | Event::SearchPrev
+                | Event::ToggleMatches
+                | Event::Help
+                | Event::EditorInsert(_)
+                | Event::EditorNewline
+                | Event::EditorBackspace
+                | Event::EditorAccept
+                | Event::EditorCancel,
+            ) => StateUpdate::None,
             (None, Event::ToggleItemAndAdvance) => {
                 let advanced_key = self.advance_to_next_of_kind();
                 StateUpdate::ToggleItemAndAdvance(self.selection_key, advanced_key)
@@ -792,7 +1280,13 @@ impl<'a> Recorder<'a> {
     }
 
     fn first_selection_key(&self) -> SelectionKey {
-        match self.state.files.iter().enumerate().next() {
+        match self
+            .state
+            .files
+            .iter()
+            .enumerate()
+            .find(|(_, file)| self.is_file_visible(file))
+        {
             Some((file_idx, _)) => SelectionKey::File(FileKey { file_idx }),
             None => SelectionKey::None,
         }
@@ -815,6 +1309,9 @@ impl<'a> Recorder<'a> {
     fn all_selection_keys(&self) -> Vec<SelectionKey> {
         let mut result = Vec::new();
         for (file_idx, file) in self.state.files.iter().enumerate() {
+            if !self.is_file_visible(file) {
+                continue;
+            }
             result.push(SelectionKey::File(FileKey { file_idx }));
             for (section_idx, section) in file.sections.iter().enumerate() {
                 match section {
@@ -1035,7 +1532,10 @@ impl<'a> Recorder<'a> {
                         ComponentId::SelectableItem(_)
                         | ComponentId::TristateBox(_)
                         | ComponentId::QuitDialog
-                        | ComponentId::QuitDialogButton(_) => true,
+                        | ComponentId::QuitDialogButton(_)
+                        | ComponentId::EditDialog
+                        | ComponentId::HelpDialog
+                        | ComponentId::StatusBar => true,
                     }
             })
             .min_by_key(|(id, rect)| (rect.size().area(), (rect.y, rect.x), *id))
@@ -1045,7 +1545,11 @@ impl<'a> Recorder<'a> {
 
     fn click_component(&self, component_id: ComponentId) -> StateUpdate {
         match component_id {
-            ComponentId::App | ComponentId::QuitDialog => StateUpdate::None,
+            ComponentId::App
+            | ComponentId::QuitDialog
+            | ComponentId::EditDialog
+            | ComponentId::HelpDialog
+            | ComponentId::StatusBar => StateUpdate::None,
             ComponentId::SelectableItem(selection_key) => StateUpdate::SelectItem(selection_key),
             ComponentId::TristateBox(selection_key) => {
                 if self.selection_key == selection_key {
@@ -1062,69 +1566,81 @@ impl<'a> Recorder<'a> {
     }
 
     fn toggle_item(&mut self, selection: SelectionKey) -> Result<(), RecordError> {
+        let is_toggled_new = match self.item_tristate(selection)? {
+            Tristate::Unchecked => true,
+            Tristate::Partial | Tristate::Checked => false,
+        };
+        self.set_item_toggled(selection, is_toggled_new)
+    }
+
+    fn item_tristate(&self, selection: SelectionKey) -> Result<Tristate, RecordError> {
+        match selection {
+            SelectionKey::None => Ok(Tristate::Unchecked),
+            SelectionKey::File(file_key) => self.file_tristate(file_key),
+            SelectionKey::Section(section_key) => self.section_tristate(section_key),
+            SelectionKey::Line(LineKey {
+                file_idx,
+                section_idx,
+                line_idx,
+            }) => match self.section(SectionKey {
+                file_idx,
+                section_idx,
+            })? {
+                Section::Changed { lines } => match lines.get(line_idx) {
+                    Some(line) => Ok(Tristate::from(line.is_toggled)),
+                    None => Err(RecordError::Bug(format!(
+                        "Out-of-bounds line key: {selection:?}"
+                    ))),
+                },
+                section @ (Section::Unchanged { .. }
+                | Section::FileMode { .. }
+                | Section::Binary { .. }) => Err(RecordError::Bug(format!(
+                    "Bad line key {selection:?}, tried to index section {section:?}"
+                ))),
+            },
+        }
+    }
+
+    fn set_item_toggled(
+        &mut self,
+        selection: SelectionKey,
+        is_toggled_new: bool,
+    ) -> Result<(), RecordError> {
+        let set_section_toggled = |section: &mut Section| match section {
+            Section::Unchanged { .. } => {}
+            Section::Changed { lines } => {
+                for line in lines {
+                    line.is_toggled = is_toggled_new;
+                }
+            }
+            Section::FileMode {
+                is_toggled,
+                before: _,
+                after: _,
+            }
+            | Section::Binary {
+                is_toggled,
+                old_description: _,
+                new_description: _,
+            } => {
+                *is_toggled = is_toggled_new;
+            }
+        };
         match selection {
             SelectionKey::None => {}
             SelectionKey::File(file_key) => {
-                let tristate = self.file_tristate(file_key)?;
-                let is_toggled_new = match tristate {
-                    Tristate::Unchecked => true,
-                    Tristate::Partial | Tristate::Checked => false,
-                };
                 self.visit_file(file_key, |file| {
                     for section in file.sections.iter_mut() {
-                        match section {
-                            Section::Unchanged { .. } => {}
-                            Section::Changed { lines } => {
-                                for line in lines {
-                                    line.is_toggled = is_toggled_new;
-                                }
-                            }
-                            Section::FileMode {
-                                is_toggled,
-                                before: _,
-                                after: _,
-                            }
-                            | Section::Binary {
-                                is_toggled,
-                                old_description: _,
-                                new_description: _,
-                            } => {
-                                *is_toggled = is_toggled_new;
-                            }
-                        }
+                        set_section_toggled(section);
                     }
                 })?;
             }
             SelectionKey::Section(section_key) => {
-                let tristate = self.section_tristate(section_key)?;
-                let is_focused_new = match tristate {
-                    Tristate::Unchecked => true,
-                    Tristate::Partial | Tristate::Checked => false,
-                };
-                self.visit_section(section_key, |section| match section {
-                    Section::Unchanged { .. } => {}
-                    Section::Changed { lines } => {
-                        for line in lines {
-                            line.is_toggled = is_focused_new;
-                        }
-                    }
-                    Section::FileMode {
-                        is_toggled,
-                        before: _,
-                        after: _,
-                    }
-                    | Section::Binary {
-                        is_toggled,
-                        old_description: _,
-                        new_description: _,
-                    } => {
-                        *is_toggled = is_focused_new;
-                    }
-                })?;
+                self.visit_section(section_key, set_section_toggled)?;
             }
             SelectionKey::Line(line_key) => {
                 self.visit_line(line_key, |line| {
-                    line.is_toggled = !line.is_toggled;
+                    line.is_toggled = is_toggled_new;
                 })?;
             }
         }
@@ -1308,6 +1824,9 @@ enum ComponentId {
     TristateBox(SelectionKey),
     QuitDialog,
     QuitDialogButton(QuitDialogButtonId),
+    EditDialog,
+    HelpDialog,
+    StatusBar,
 }
 
 #[derive(Clone, Debug)]
@@ -1386,6 +1905,9 @@ struct App<'a> {
     debug_info: Option<AppDebugInfo>,
     file_views: Vec<FileView<'a>>,
     quit_dialog: Option<QuitDialog>,
+    edit_dialog: Option<EditDialog>,
+    help_dialog: Option<HelpDialog>,
+    status_bar: Option<StatusBar>,
 }
 
 impl App<'_> {
@@ -1394,6 +1916,9 @@ impl App<'_> {
             debug_info: _,
             file_views,
             quit_dialog: _,
+            edit_dialog: _,
+            help_dialog: _,
+            status_bar: _,
         } = self;
         file_views.iter().map(|file_view| file_view.height()).sum()
     }
@@ -1411,6 +1936,9 @@ impl Component for App<'_> {
             debug_info,
             file_views,
             quit_dialog,
+            edit_dialog,
+            help_dialog,
+            status_bar,
         } = self;
 
         if let Some(debug_info) = debug_info {
@@ -1430,6 +1958,18 @@ impl Component for App<'_> {
             }
         }
 
+        if let Some(status_bar) = status_bar {
+            viewport.draw_component(0, 0, status_bar);
+        }
+
+        if let Some(edit_dialog) = edit_dialog {
+            viewport.draw_component(0, 0, edit_dialog);
+        }
+
+        if let Some(help_dialog) = help_dialog {
+            viewport.draw_component(0, 0, help_dialog);
+        }
+
         if let Some(quit_dialog) = quit_dialog {
             viewport.draw_component(0, 0, quit_dialog);
         }
@@ -1443,6 +1983,7 @@ struct FileView<'a> {
     tristate_box: TristateBox<ComponentId>,
     is_header_selected: bool,
     path: &'a Path,
+    search_regex: Option<&'a Regex>,
     section_views: Vec<SectionView<'a>>,
 }
 
@@ -1469,14 +2010,16 @@ impl Component for FileView<'_> {
             file_key: _,
             tristate_box,
             path,
+            search_regex,
             section_views,
             is_header_selected,
         } = self;
         viewport.fill_rest_of_line(x, y);
 
         let tristate_box_rect = viewport.draw_component(x, y, tristate_box);
+        let path_x = x + tristate_box_rect.width.unwrap_isize() + 1;
         viewport.draw_span(
-            x + tristate_box_rect.width.unwrap_isize() + 1,
+            path_x,
             y,
             &Span::styled(
                 path.to_string_lossy(),
@@ -1487,6 +2030,9 @@ impl Component for FileView<'_> {
                 },
             ),
         );
+        if let Some(search_regex) = search_regex {
+            highlight_matches(viewport, path_x, y, &path.to_string_lossy(), search_regex);
+        }
         if *is_header_selected {
             highlight_line(viewport, y);
         }
@@ -1513,6 +2059,8 @@ enum SectionSelection {
 #[derive(Clone, Debug)]
 struct SectionView<'a> {
     use_unicode: bool,
+    search_regex: Option<&'a Regex>,
+    line_emphasis: &'a [ChangedRanges],
     section_key: SectionKey,
     tristate_box: TristateBox<ComponentId>,
     selection: Option<SectionSelection>,
@@ -1543,6 +2091,8 @@ impl Component for SectionView<'_> {
     fn draw(&self, viewport: &mut Viewport<Self::Id>, x: isize, y: isize) {
         let Self {
             use_unicode,
+            search_regex,
+            line_emphasis,
             section_key,
             tristate_box,
             selection,
@@ -1706,6 +2256,11 @@ impl Component for SectionView<'_> {
                             tristate_box,
                             change_type: *change_type,
                             line: line.as_ref(),
+                            search_regex: *search_regex,
+                            emphasis: line_emphasis
+                                .get(line_idx)
+                                .map(|emphasis| emphasis.as_slice())
+                                .unwrap_or_default(),
                         },
                     };
                     let y = y + line_idx.unwrap_isize();
@@ -1804,6 +2359,8 @@ enum SectionLineViewInner<'a> {
         tristate_box: TristateBox<ComponentId>,
         change_type: ChangeType,
         line: &'a str,
+        search_regex: Option<&'a Regex>,
+        emphasis: &'a [Range<usize>],
     },
 }
 
@@ -1843,17 +2400,44 @@ impl Component for SectionLineView<'_> {
                 tristate_box,
                 change_type,
                 line,
+                search_regex,
+                emphasis,
             } => {
                 let tristate_rect = viewport.draw_component(x, y, tristate_box);
                 let x = x + tristate_rect.width.unwrap_isize() + 1;
 
-                let (change_type_text, style) = match change_type {
-                    ChangeType::Added => ("+ ", Style::default().fg(Color::Green)),
-                    ChangeType::Removed => ("- ", Style::default().fg(Color::Red)),
+                let (change_type_text, style, emphasis_style) = match change_type {
+                    ChangeType::Added => (
+                        "+ ",
+                        Style::default().fg(Color::Green),
+                        Style::default().fg(Color::Black).bg(Color::Green),
+                    ),
+                    ChangeType::Removed => (
+                        "- ",
+                        Style::default().fg(Color::Red),
+                        Style::default().fg(Color::Black).bg(Color::Red),
+                    ),
                 };
                 viewport.draw_span(x, y, &Span::styled(change_type_text, style));
                 let x = x + change_type_text.width().unwrap_isize();
                 viewport.draw_span(x, y, &Span::styled(*line, style));
+                for range in emphasis.iter() {
+                    let width = line[range.clone()].width();
+                    if width > 0 {
+                        viewport.set_style(
+                            Rect {
+                                x: x + line[..range.start].width().unwrap_isize(),
+                                y,
+                                width,
+                                height: 1,
+                            },
+                            emphasis_style,
+                        );
+                    }
+                }
+                if let Some(search_regex) = search_regex {
+                    highlight_matches(viewport, x, y, line, search_regex);
+                }
             }
         }
     }
@@ -1915,6 +2499,407 @@ impl Component for QuitDialog {
     }
 }
 
+/// Inline editor for the changed lines of a section. See the `edit` module for
+/// the format of the text being edited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct EditDialog {
+    section_key: SectionKey,
+    title: String,
+    lines: Vec<String>,
+    cursor_row: usize,
+    /// The cursor column, in characters (not bytes).
+    cursor_col: usize,
+    error: Option<String>,
+}
+
+impl EditDialog {
+    fn new(section_key: SectionKey, title: String, text: &str, error: Option<String>) -> Self {
+        let mut lines: Vec<String> = text.lines().map(|line| line.to_owned()).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        Self {
+            section_key,
+            title,
+            lines,
+            cursor_row: 0,
+            cursor_col: 0,
+            error,
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut result = String::new();
+        for line in &self.lines {
+            result.push_str(line);
+            result.push('\n');
+        }
+        result
+    }
+
+    fn byte_idx(line: &str, char_idx: usize) -> usize {
+        line.char_indices()
+            .nth(char_idx)
+            .map(|(byte_idx, _c)| byte_idx)
+            .unwrap_or(line.len())
+    }
+
+    fn handle_event(&self, event: Event) -> StateUpdate {
+        let mut edit_dialog = self.clone();
+        let Self {
+            section_key: _,
+            title: _,
+            lines,
+            cursor_row,
+            cursor_col,
+            error: _,
+        } = &mut edit_dialog;
+        let line_len = |lines: &[String], row: usize| lines[row].chars().count();
+
+        match event {
+            Event::None => return StateUpdate::None,
+            Event::TakeScreenshot(screenshot) => return StateUpdate::TakeScreenshot(screenshot),
+            Event::EditorAccept | Event::QuitAccept => {
+                return StateUpdate::AcceptEdit(edit_dialog);
+            }
+            Event::EditorCancel | Event::QuitCancel | Event::QuitInterrupt => {
+                return StateUpdate::SetEditDialog(None);
+            }
+
+            Event::EditorInsert(c) => {
+                let line = &mut lines[*cursor_row];
+                line.insert(Self::byte_idx(line, *cursor_col), c);
+                *cursor_col += 1;
+            }
+            Event::EditorNewline => {
+                let line = &mut lines[*cursor_row];
+                let rest = line.split_off(Self::byte_idx(line, *cursor_col));
+                lines.insert(*cursor_row + 1, rest);
+                *cursor_row += 1;
+                *cursor_col = 0;
+            }
+            Event::EditorBackspace => {
+                if *cursor_col > 0 {
+                    let line = &mut lines[*cursor_row];
+                    line.remove(Self::byte_idx(line, *cursor_col - 1));
+                    *cursor_col -= 1;
+                } else if *cursor_row > 0 {
+                    let line = lines.remove(*cursor_row);
+                    *cursor_row -= 1;
+                    *cursor_col = line_len(lines, *cursor_row);
+                    lines[*cursor_row].push_str(&line);
+                }
+            }
+
+            Event::FocusPrev => {
+                *cursor_row = cursor_row.saturating_sub(1);
+                *cursor_col = (*cursor_col).min(line_len(lines, *cursor_row));
+            }
+            Event::FocusNext => {
+                *cursor_row = (*cursor_row + 1).min(lines.len() - 1);
+                *cursor_col = (*cursor_col).min(line_len(lines, *cursor_row));
+            }
+            Event::FocusOuter => {
+                *cursor_col = cursor_col.saturating_sub(1);
+            }
+            Event::FocusInner => {
+                *cursor_col = (*cursor_col + 1).min(line_len(lines, *cursor_row));
+            }
+
+            Event::ScrollUp
+            | Event::ScrollDown
+            | Event::PageUp
+            | Event::PageDown
+            | Event::FocusPrevPage
+            | Event::FocusNextPage
+            | Event::ToggleItem
+            | Event::ToggleItemAndAdvance
+            | Event::EditItem
+            | Event::StartSearch
+            | Event::StartFilter
+            | Event::SearchNext
+            | Event::SearchPrev
+            | Event::ToggleMatches
+            | Event::Help
+            | Event::Click { .. } => return StateUpdate::None,
+        }
+        StateUpdate::SetEditDialog(Some(edit_dialog))
+    }
+}
+
+impl Component for EditDialog {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::EditDialog
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self {
+            section_key: _,
+            title,
+            lines,
+            cursor_row,
+            cursor_col,
+            error,
+        } = self;
+        let rect = viewport.rect();
+        let tui_rect = viewport.translate_rect(rect);
+        viewport.draw_widget(tui_rect, Clear);
+        viewport.draw_widget(
+            tui_rect,
+            Block::default()
+                .title(title.as_str())
+                .borders(Borders::all()),
+        );
+
+        let x = rect.x + 1;
+        let width = rect.width.saturating_sub(2);
+        let mut height = rect.height.saturating_sub(2);
+        if let Some(error) = error {
+            // Reserve the last line for the error message.
+            height = height.saturating_sub(1);
+            viewport.draw_span(
+                x,
+                rect.y + 1 + height.unwrap_isize(),
+                &Span::styled(
+                    error.chars().take(width).collect::<String>(),
+                    Style::default().fg(Color::Red),
+                ),
+            );
+        }
+        let help = "ctrl-s: accept, esc: cancel";
+        viewport.draw_span(
+            rect.x + rect.width.unwrap_isize() - 1 - help.width().unwrap_isize(),
+            rect.y + rect.height.unwrap_isize() - 1,
+            &Span::styled(help, Style::default().add_modifier(Modifier::DIM)),
+        );
+
+        // Scroll so that the cursor is always visible.
+        let first_visible_row = (cursor_row + 1).saturating_sub(height);
+        for (dy, (row, line)) in lines
+            .iter()
+            .enumerate()
+            .skip(first_visible_row)
+            .take(height)
+            .enumerate()
+        {
+            let y = rect.y + 1 + dy.unwrap_isize();
+            let style = match line.chars().next() {
+                Some('+') => Style::default().fg(Color::Green),
+                Some('-') => Style::default().fg(Color::Red),
+                _ => Style::default(),
+            };
+            let visible: String = line.chars().take(width).collect();
+            viewport.draw_span(x, y, &Span::styled(visible, style));
+
+            if row == *cursor_row && *cursor_col < width {
+                let c = line.chars().nth(*cursor_col).unwrap_or(' ');
+                viewport.draw_span(
+                    x + cursor_col.unwrap_isize(),
+                    y,
+                    &Span::styled(
+                        c.to_string(),
+                        Style::default().add_modifier(Modifier::REVERSED),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PromptKind {
+    Search,
+    Filter,
+}
+
+/// A single-line text prompt displayed in the status bar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Prompt {
+    kind: PromptKind,
+    text: String,
+}
+
+impl Prompt {
+    fn handle_event(&self, event: Event) -> StateUpdate {
+        let mut text = self.text.clone();
+        match event {
+            Event::None => return StateUpdate::None,
+            Event::TakeScreenshot(screenshot) => return StateUpdate::TakeScreenshot(screenshot),
+            Event::EditorAccept | Event::EditorNewline | Event::QuitAccept => {
+                return StateUpdate::AcceptPrompt(self.clone());
+            }
+            Event::EditorCancel | Event::QuitCancel | Event::QuitInterrupt => {
+                return StateUpdate::SetPrompt(None);
+            }
+            Event::EditorInsert(c) => {
+                text.push(c);
+            }
+            Event::EditorBackspace => {
+                text.pop();
+            }
+
+            Event::ScrollUp
+            | Event::ScrollDown
+            | Event::PageUp
+            | Event::PageDown
+            | Event::FocusPrev
+            | Event::FocusNext
+            | Event::FocusPrevPage
+            | Event::FocusNextPage
+            | Event::FocusInner
+            | Event::FocusOuter
+            | Event::ToggleItem
+            | Event::ToggleItemAndAdvance
+            | Event::EditItem
+            | Event::StartSearch
+            | Event::StartFilter
+            | Event::SearchNext
+            | Event::SearchPrev
+            | Event::ToggleMatches
+            | Event::Help
+            | Event::Click { .. } => return StateUpdate::None,
+        }
+        StateUpdate::SetPrompt(Some(Prompt {
+            kind: self.kind,
+            text,
+        }))
+    }
+}
+
+/// The current search query. Files are matched by path and changed lines by
+/// their contents.
+#[derive(Clone, Debug)]
+struct Search {
+    query: String,
+    regex: Regex,
+}
+
+impl Search {
+    fn new(query: String) -> Self {
+        // If the query isn't a valid regex, then search for it literally.
+        let regex =
+            Regex::new(&query).unwrap_or_else(|_| Regex::new(&regex::escape(&query)).unwrap());
+        Self { query, regex }
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+/// Only files whose paths match the glob are shown.
+#[derive(Clone, Debug)]
+struct PathFilter {
+    glob: String,
+    pattern: glob::Pattern,
+}
+
+impl PathFilter {
+    fn new(glob: String) -> Self {
+        // If the glob isn't valid, then match it literally.
+        let pattern = glob::Pattern::new(&glob)
+            .unwrap_or_else(|_| glob::Pattern::new(&glob::Pattern::escape(&glob)).unwrap());
+        Self { glob, pattern }
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        self.pattern.matches_path(path)
+    }
+}
+
+/// Overlay listing the active key bindings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct HelpDialog {
+    /// `(chords, description)` pairs.
+    lines: Vec<(String, &'static str)>,
+}
+
+impl Component for HelpDialog {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::HelpDialog
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self { lines } = self;
+        let chords_width = lines
+            .iter()
+            .map(|(chords, _)| chords.width())
+            .max()
+            .unwrap_or_default();
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|(chords, description)| {
+                let padding = " ".repeat(chords_width - chords.width());
+                format!("{chords}{padding}  {description}")
+            })
+            .collect();
+
+        let border_size = 2;
+        let rect = centered_rect(
+            viewport.rect(),
+            RectSize {
+                width: lines
+                    .iter()
+                    .map(|line| line.width())
+                    .max()
+                    .unwrap_or_default()
+                    + border_size,
+                height: lines.len() + border_size,
+            },
+            60,
+            20,
+        );
+        let tui_rect = viewport.translate_rect(rect);
+        viewport.draw_widget(tui_rect, Clear);
+        viewport.draw_widget(
+            tui_rect,
+            Block::default()
+                .title("Key bindings (press any key to close)")
+                .borders(Borders::all()),
+        );
+        let height = rect.height.saturating_sub(border_size);
+        let width = rect.width.saturating_sub(border_size);
+        for (dy, line) in lines.iter().take(height).enumerate() {
+            let line: String = line.chars().take(width).collect();
+            viewport.draw_span(rect.x + 1, rect.y + 1 + dy.unwrap_isize(), &Span::raw(line));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct StatusBar {
+    text: String,
+}
+
+impl Component for StatusBar {
+    type Id = ComponentId;
+
+    fn id(&self) -> Self::Id {
+        ComponentId::StatusBar
+    }
+
+    fn draw(&self, viewport: &mut Viewport<Self::Id>, _x: isize, _y: isize) {
+        let Self { text } = self;
+        let rect = viewport.rect();
+        let y = rect.y + rect.height.unwrap_isize() - 1;
+        viewport.fill_rest_of_line(rect.x, y);
+        viewport.draw_span(rect.x, y, &Span::raw(text.as_str()));
+        viewport.set_style(
+            Rect {
+                x: rect.x,
+                y,
+                width: rect.width,
+                height: 1,
+            },
+            Style::default().add_modifier(Modifier::REVERSED),
+        );
+    }
+}
+
 struct Button<'a, Id> {
     id: Id,
     label: Cow<'a, str>,
@@ -2013,6 +2998,31 @@ impl<Id: Clone + Debug + Eq + Hash> Component for Dialog<'_, Id> {
     }
 }
 
+/// Emphasize the parts of `text`, drawn at `(x, y)`, which match the search.
+fn highlight_matches<Id: Clone + Debug + Eq + Hash>(
+    viewport: &mut Viewport<Id>,
+    x: isize,
+    y: isize,
+    text: &str,
+    search_regex: &Regex,
+) {
+    for m in search_regex.find_iter(text) {
+        let start_x = x + text[..m.start()].width().unwrap_isize();
+        let width = m.as_str().width();
+        if width > 0 {
+            viewport.set_style(
+                Rect {
+                    x: start_x,
+                    y,
+                    width,
+                    height: 1,
+                },
+                Style::default().add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+            );
+        }
+    }
+}
+
 fn highlight_line<Id: Clone + Debug + Eq + Hash>(viewport: &mut Viewport<Id>, y: isize) {
     viewport.set_style(
         Rect {
@@ -2035,9 +3045,13 @@ mod tests {
 
     #[test]
     fn test_event_source_testing() {
+        let keymap = Keymap::default();
         let mut event_source = EventSource::testing(80, 24, [Event::QuitCancel]);
-        assert_matches!(event_source.next_event(), Ok(Event::QuitCancel));
-        assert_matches!(event_source.next_event(), Ok(Event::None));
+        assert_matches!(
+            event_source.next_event(&keymap, false),
+            Ok(Event::QuitCancel)
+        );
+        assert_matches!(event_source.next_event(&keymap, false), Ok(Event::None));
     }
 
     #[test]
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 pub(crate) trait UsizeExt {
     fn unwrap_isize(self) -> isize;
     fn clamp_into_u16(self) -> u16;
@@ -37,3 +39,42 @@ impl IsizeExt for isize {
         }
     }
 }
+
+/// Returns the path to a shell suitable for running the user's editor. This
+/// mirrors how `git-branchless` finds a shell for running hooks: on Windows,
+/// the `bash.exe` which ships with Git is preferred.
+pub(crate) fn get_sh() -> Option<PathBuf> {
+    let exe_name = if cfg!(target_os = "windows") {
+        "bash.exe"
+    } else {
+        "sh"
+    };
+    if cfg!(target_os = "windows") {
+        // Git is typically installed at `C:\Program Files\Git\cmd\git.exe`,
+        // with its shell at `C:\Program Files\Git\bin\bash.exe`.
+        let git_bash = get_from_path("git.exe")
+            .as_deref()
+            .and_then(|git_path| git_path.parent())
+            .and_then(|cmd_dir| cmd_dir.parent())
+            .map(|git_dir| git_dir.join("bin").join(exe_name));
+        if let Some(git_bash) = git_bash {
+            if git_bash.is_file() {
+                return Some(git_bash);
+            }
+        }
+    }
+    get_from_path(exe_name)
+}
+
+fn get_from_path(exe_name: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths).find_map(|dir| {
+            let path = dir.join(exe_name);
+            if path.is_file() {
+                Some(path)
+            } else {
+                None
+            }
+        })
+    })
+}
@@ -0,0 +1,193 @@
+//! Word-level differences between changed lines, used to emphasize the parts
+//! of a line which actually changed.
+
+use std::ops::Range;
+
+use crate::types::{ChangeType, SectionChangedLine};
+
+/// Don't bother calculating the word diff for line pairs with more than this
+/// many pairs of tokens, since the calculation is quadratic.
+const MAX_TOKEN_PAIRS: usize = 100_000;
+
+/// The byte ranges of the changed parts of a line.
+pub(crate) type ChangedRanges = Vec<Range<usize>>;
+
+/// Split the line into tokens: runs of word characters, runs of whitespace,
+/// and individual punctuation characters. Returns the byte range of each
+/// token.
+fn tokenize(line: &str) -> Vec<Range<usize>> {
+    #[derive(PartialEq)]
+    enum Kind {
+        Word,
+        Whitespace,
+        Other,
+    }
+    let kind = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Kind::Word
+        } else if c.is_whitespace() {
+            Kind::Whitespace
+        } else {
+            Kind::Other
+        }
+    };
+
+    let mut result: Vec<Range<usize>> = Vec::new();
+    let mut prev_kind = None;
+    for (i, c) in line.char_indices() {
+        let c_kind = kind(c);
+        match (result.last_mut(), &prev_kind) {
+            (Some(last), Some(prev_kind)) if *prev_kind == c_kind && c_kind != Kind::Other => {
+                last.end = i + c.len_utf8();
+            }
+            _ => result.push(i..i + c.len_utf8()),
+        }
+        prev_kind = Some(c_kind);
+    }
+    result
+}
+
+/// Calculate which parts of `old` and `new` differ from each other, at word
+/// granularity. Returns the byte ranges of the changed parts of `old` and
+/// `new`, respectively, or `None` if the lines are too different for the
+/// emphasis to be useful.
+pub(crate) fn word_diff(old: &str, new: &str) -> Option<(ChangedRanges, ChangedRanges)> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len().saturating_mul(new_tokens.len()) > MAX_TOKEN_PAIRS {
+        return None;
+    }
+
+    // Longest common subsequence of tokens.
+    let n = old_tokens.len();
+    let m = new_tokens.len();
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_changed = Vec::new();
+    let mut new_changed = Vec::new();
+    let mut num_common_words = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+            if old[old_tokens[i].clone()]
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_')
+            {
+                num_common_words += 1;
+            }
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            push_range(&mut old_changed, old_tokens[i].clone());
+            i += 1;
+        } else {
+            push_range(&mut new_changed, new_tokens[j].clone());
+            j += 1;
+        }
+    }
+
+    if num_common_words == 0 {
+        // The lines have nothing in common, so emphasizing everything wouldn't
+        // help the user.
+        None
+    } else {
+        Some((old_changed, new_changed))
+    }
+}
+
+/// Append the range, merging it with the previous range if they're adjacent.
+fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => {
+            last.end = range.end;
+        }
+        _ => ranges.push(range),
+    }
+}
+
+/// Calculate the ranges to emphasize for each of the changed lines in a
+/// section. Each run of removed lines is paired up line-by-line with the run
+/// of added lines that immediately follows it.
+pub(crate) fn section_emphasis(lines: &[SectionChangedLine]) -> Vec<ChangedRanges> {
+    let mut result = vec![Vec::new(); lines.len()];
+    let mut i = 0;
+    while i < lines.len() {
+        let removed_start = i;
+        while i < lines.len() && lines[i].change_type == ChangeType::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].change_type == ChangeType::Added {
+            i += 1;
+        }
+        let num_removed = added_start - removed_start;
+        let num_added = i - added_start;
+        for offset in 0..num_removed.min(num_added) {
+            let old_idx = removed_start + offset;
+            let new_idx = added_start + offset;
+            let old = strip_newline(&lines[old_idx].line);
+            let new = strip_newline(&lines[new_idx].line);
+            if let Some((old_changed, new_changed)) = word_diff(old, new) {
+                result[old_idx] = old_changed;
+                result[new_idx] = new_changed;
+            }
+        }
+    }
+    result
+}
+
+fn strip_newline(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show_word_diff(old: &str, new: &str) -> String {
+        let mark = |line: &str, ranges: &[Range<usize>]| {
+            let mut result = String::new();
+            let mut last_end = 0;
+            for range in ranges {
+                result.push_str(&line[last_end..range.start]);
+                result.push('[');
+                result.push_str(&line[range.clone()]);
+                result.push(']');
+                last_end = range.end;
+            }
+            result.push_str(&line[last_end..]);
+            result
+        };
+        match word_diff(old, new) {
+            Some((old_changed, new_changed)) => {
+                format!("{} -> {}", mark(old, &old_changed), mark(new, &new_changed))
+            }
+            None => "<no emphasis>".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_word_diff() {
+        assert_eq!(
+            show_word_diff("let foo = bar(1, 2);", "let foo = baz(1, 3);"),
+            "let foo = [bar](1, [2]); -> let foo = [baz](1, [3]);"
+        );
+        assert_eq!(
+            show_word_diff("println!(\"hello\");", "println!(\"hello\"); dbg!(x);"),
+            "println!(\"hello\"); -> println!(\"hello\");[ dbg!(x);]"
+        );
+        assert_eq!(show_word_diff("foo", "bar"), "<no emphasis>");
+        assert_eq!(show_word_diff("foo();", "bar();"), "<no emphasis>");
+        assert_eq!(show_word_diff("", "bar"), "<no emphasis>");
+    }
+}
@@ -2,10 +2,11 @@ use std::borrow::Cow;
 use std::path::Path;
 
 use assert_matches::assert_matches;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use insta::{assert_debug_snapshot, assert_snapshot};
 use scm_record::{
-    helpers::make_binary_description, ChangeType, Event, EventSource, File, FileMode, RecordError,
-    RecordState, Recorder, Section, SectionChangedLine, TestingScreenshot,
+    helpers::make_binary_description, ChangeType, Event, EventSource, File, FileMode, Keymap,
+    RecordError, RecordState, Recorder, Section, SectionChangedLine, TestingScreenshot,
 };
 
 fn example_contents() -> RecordState<'static> {
@@ -1025,3 +1026,737 @@ fn test_mouse_click_dialog_buttons() -> eyre::Result<()> {
 
     Ok(())
 }
+
+fn edit_section_contents() -> RecordState<'static> {
+    RecordState {
+        files: vec![File {
+            path: Cow::Borrowed(Path::new("foo")),
+            file_mode: None,
+            sections: vec![
+                Section::Unchanged {
+                    lines: vec![Cow::Borrowed("fn foo() {\n")],
+                },
+                Section::Changed {
+                    lines: vec![
+                        SectionChangedLine {
+                            is_toggled: true,
+                            change_type: ChangeType::Removed,
+                            line: Cow::Borrowed("    old();\n"),
+                        },
+                        SectionChangedLine {
+                            is_toggled: true,
+                            change_type: ChangeType::Added,
+                            line: Cow::Borrowed("    new(); dbg!(x);\n"),
+                        },
+                        SectionChangedLine {
+                            is_toggled: true,
+                            change_type: ChangeType::Added,
+                            line: Cow::Borrowed("    other();\n"),
+                        },
+                    ],
+                },
+                Section::Unchanged {
+                    lines: vec![Cow::Borrowed("}\n")],
+                },
+            ],
+        }],
+    }
+}
+
+#[test]
+fn test_edit_section() -> eyre::Result<()> {
+    let editor_opened = TestingScreenshot::default();
+    let editor_edited = TestingScreenshot::default();
+    let after_edit = TestingScreenshot::default();
+    let mut events = vec![
+        Event::FocusNext,
+        Event::EditItem,
+        editor_opened.event(),
+        // Remove the debug print from the first added line.
+        Event::FocusNext,
+    ];
+    events.extend(std::iter::repeat(Event::FocusInner).take("+    new(); dbg!(x);".len()));
+    events.extend(std::iter::repeat(Event::EditorBackspace).take(" dbg!(x);".len()));
+    // Skip the second added line by joining it onto the end of the first line
+    // and then deleting it.
+    events.push(Event::FocusNext);
+    events.extend(std::iter::repeat(Event::EditorBackspace).take("+    other();".len() + 1));
+    events.extend([
+        editor_edited.event(),
+        Event::EditorAccept,
+        after_edit.event(),
+        Event::QuitAccept,
+    ]);
+    let event_source = EventSource::testing(80, 8, events);
+    let recorder = Recorder::new(edit_section_contents(), event_source);
+    let state = recorder.run()?;
+
+    insta::assert_display_snapshot!(editor_opened, @r###"
+    "┌Edit section 1/1 of foo───────────────────────────────────────────────────────┐"
+    "│-    old();                                                                   │"
+    "│+    new(); dbg!(x);                                                          │"
+    "│+    other();                                                                 │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "└───────────────────────────────────────────────────ctrl-s: accept, esc: cancel┘"
+    "###);
+    insta::assert_display_snapshot!(editor_edited, @r###"
+    "┌Edit section 1/1 of foo───────────────────────────────────────────────────────┐"
+    "│-    old();                                                                   │"
+    "│+    new();                                                                   │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "└───────────────────────────────────────────────────ctrl-s: accept, esc: cancel┘"
+    "###);
+    insta::assert_display_snapshot!(after_edit, @r###"
+    "[~] foo                                                                         "
+    "        1 fn foo() {                                                            "
+    "  (~) Section 1/1                                                               "
+    "    [×] -     old();                                                            "
+    "    [×] +     new();                                                            "
+    "    [ ] +     new(); dbg!(x);                                                   "
+    "    [ ] +     other();                                                          "
+    "        3 }                                                                     "
+    "###);
+
+    // The edited line is recorded, but the original line remains in the
+    // unselected contents.
+    assert_debug_snapshot!(state.files[0].get_selected_contents(), @r###"
+    (
+        Present {
+            contents: "fn foo() {\n    new();\n}\n",
+        },
+        Present {
+            contents: "fn foo() {\n    old();\n    new(); dbg!(x);\n    other();\n}\n",
+        },
+    )
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_edit_section_invalid() -> eyre::Result<()> {
+    let expect_error = TestingScreenshot::default();
+    let event_source = EventSource::testing(
+        80,
+        8,
+        [
+            Event::FocusNext,
+            Event::EditItem,
+            // Removed lines can't be edited.
+            Event::FocusInner,
+            Event::EditorInsert('x'),
+            Event::EditorAccept,
+            expect_error.event(),
+            Event::EditorCancel,
+            Event::QuitAccept,
+        ],
+    );
+    let recorder = Recorder::new(edit_section_contents(), event_source);
+    assert_eq!(recorder.run()?, edit_section_contents());
+    insta::assert_display_snapshot!(expect_error, @r###"
+    "┌Edit section 1/1 of foo───────────────────────────────────────────────────────┐"
+    "│-x    old();                                                                  │"
+    "│+    new(); dbg!(x);                                                          │"
+    "│+    other();                                                                 │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "│Line 1: removed lines can't be edited or reordered                            │"
+    "└───────────────────────────────────────────────────ctrl-s: accept, esc: cancel┘"
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_edit_section_multibyte_prefix() -> eyre::Result<()> {
+    let expect_error = TestingScreenshot::default();
+    let event_source = EventSource::testing(
+        80,
+        8,
+        [
+            Event::FocusNext,
+            Event::EditItem,
+            Event::EditorInsert('é'),
+            Event::EditorAccept,
+            expect_error.event(),
+            Event::EditorCancel,
+            Event::QuitAccept,
+        ],
+    );
+    let recorder = Recorder::new(edit_section_contents(), event_source);
+    assert_eq!(recorder.run()?, edit_section_contents());
+    insta::assert_display_snapshot!(expect_error, @r###"
+    "┌Edit section 1/1 of foo───────────────────────────────────────────────────────┐"
+    "│é-    old();                                                                  │"
+    "│+    new(); dbg!(x);                                                          │"
+    "│+    other();                                                                 │"
+    "│                                                                              │"
+    "│                                                                              │"
+    "│Line 1 must start with '+', '-', or ' ': é-    old();                         │"
+    "└───────────────────────────────────────────────────ctrl-s: accept, esc: cancel┘"
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_edit_section_empty_context_line() -> eyre::Result<()> {
+    let state = RecordState {
+        files: vec![File {
+            path: Cow::Borrowed(Path::new("foo")),
+            file_mode: None,
+            sections: vec![Section::Changed {
+                lines: vec![
+                    SectionChangedLine {
+                        is_toggled: true,
+                        change_type: ChangeType::Removed,
+                        line: Cow::Borrowed("\n"),
+                    },
+                    SectionChangedLine {
+                        is_toggled: true,
+                        change_type: ChangeType::Removed,
+                        line: Cow::Borrowed("old\n"),
+                    },
+                ],
+            }],
+        }],
+    };
+    // Change the leading '-' of the empty line to ' ', and then strip the
+    // trailing whitespace, as some editors do.
+    let event_source = EventSource::testing(
+        80,
+        8,
+        [
+            Event::FocusNext,
+            Event::EditItem,
+            Event::FocusInner,
+            Event::EditorBackspace,
+            Event::EditorAccept,
+            Event::QuitAccept,
+        ],
+    );
+    let recorder = Recorder::new(state, event_source);
+    let state = recorder.run()?;
+    assert_debug_snapshot!(state.files[0].get_selected_contents(), @r###"
+    (
+        Present {
+            contents: "\n",
+        },
+        Present {
+            contents: "old\n",
+        },
+    )
+    "###);
+    Ok(())
+}
+
+fn search_contents() -> RecordState<'static> {
+    let changed_file = |path: &'static str, lines: &[(ChangeType, &'static str)]| File {
+        path: Cow::Borrowed(Path::new(path)),
+        file_mode: None,
+        sections: vec![Section::Changed {
+            lines: lines
+                .iter()
+                .map(|(change_type, line)| SectionChangedLine {
+                    is_toggled: false,
+                    change_type: *change_type,
+                    line: Cow::Borrowed(line),
+                })
+                .collect(),
+        }],
+    };
+    RecordState {
+        files: vec![
+            changed_file(
+                "README.md",
+                &[(ChangeType::Added, "Call foo() to get started.\n")],
+            ),
+            changed_file(
+                "src/foo.rs",
+                &[
+                    (ChangeType::Removed, "fn old() {}\n"),
+                    (ChangeType::Added, "fn new() {}\n"),
+                ],
+            ),
+            changed_file(
+                "src/lib.rs",
+                &[
+                    (ChangeType::Added, "mod bar;\n"),
+                    (ChangeType::Added, "mod foo;\n"),
+                ],
+            ),
+        ],
+    }
+}
+
+#[test]
+fn test_search() -> eyre::Result<()> {
+    let typing_query = TestingScreenshot::default();
+    let first_match = TestingScreenshot::default();
+    let second_match = TestingScreenshot::default();
+    let wrapped_around = TestingScreenshot::default();
+    let previous_match = TestingScreenshot::default();
+    let mut events = vec![Event::StartSearch];
+    events.extend("fo+".chars().map(Event::EditorInsert));
+    events.extend([
+        typing_query.event(),
+        Event::EditorAccept,
+        first_match.event(),
+        Event::SearchNext,
+        second_match.event(),
+        Event::SearchNext,
+        Event::SearchNext,
+        wrapped_around.event(),
+        Event::SearchPrev,
+        previous_match.event(),
+        Event::QuitAccept,
+    ]);
+    let event_source = EventSource::testing(80, 12, events);
+    let recorder = Recorder::new(search_contents(), event_source);
+    recorder.run()?;
+
+    insta::assert_display_snapshot!(typing_query, @r###"
+    "( ) README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + Call foo() to get started.                                            "
+    "[ ] src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [ ] + mod foo;                                                              "
+    "/fo+                                                                            "
+    "###);
+    insta::assert_display_snapshot!(first_match, @r###"
+    "[ ] README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    ( ) + Call foo() to get started.                                            "
+    "[ ] src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [ ] + mod foo;                                                              "
+    "Search: fo+ (1/3)                                                               "
+    "###);
+    insta::assert_display_snapshot!(second_match, @r###"
+    "[ ] README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + Call foo() to get started.                                            "
+    "( ) src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [ ] + mod foo;                                                              "
+    "Search: fo+ (2/3)                                                               "
+    "###);
+    insta::assert_display_snapshot!(wrapped_around, @r###"
+    "[ ] README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    ( ) + Call foo() to get started.                                            "
+    "[ ] src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [ ] + mod foo;                                                              "
+    "Search: fo+ (1/3)                                                               "
+    "###);
+    insta::assert_display_snapshot!(previous_match, @r###"
+    "[ ] README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + Call foo() to get started.                                            "
+    "[ ] src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    ( ) + mod foo;                                                              "
+    "Search: fo+ (3/3)                                                               "
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_filter_files() -> eyre::Result<()> {
+    let filtered = TestingScreenshot::default();
+    let unfiltered = TestingScreenshot::default();
+    let mut events = vec![Event::StartFilter];
+    events.extend("src/*.rs".chars().map(Event::EditorInsert));
+    events.extend([Event::EditorAccept, filtered.event(), Event::StartFilter]);
+    events.extend(std::iter::repeat(Event::EditorBackspace).take("src/*.rs".len()));
+    events.extend([Event::EditorAccept, unfiltered.event(), Event::QuitAccept]);
+    let event_source = EventSource::testing(80, 10, events);
+    let recorder = Recorder::new(search_contents(), event_source);
+    recorder.run()?;
+
+    insta::assert_display_snapshot!(filtered, @r###"
+    "( ) src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [ ] + mod foo;                                                              "
+    "                                                                                "
+    "Filter: src/*.rs                                                                "
+    "###);
+    insta::assert_display_snapshot!(unfiltered, @r###"
+    "[ ] README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + Call foo() to get started.                                            "
+    "( ) src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_toggle_matches() -> eyre::Result<()> {
+    let after_toggle = TestingScreenshot::default();
+    let after_filtered_toggle = TestingScreenshot::default();
+    let mut events = vec![Event::StartSearch];
+    events.extend("foo".chars().map(Event::EditorInsert));
+    events.extend([
+        Event::EditorAccept,
+        Event::ToggleMatches,
+        after_toggle.event(),
+        // Clear the search, so that toggling applies to the filtered files.
+        Event::StartSearch,
+        Event::EditorAccept,
+        Event::StartFilter,
+    ]);
+    events.extend("src/*".chars().map(Event::EditorInsert));
+    events.extend([
+        Event::EditorAccept,
+        Event::ToggleMatches,
+        after_filtered_toggle.event(),
+        Event::QuitAccept,
+    ]);
+    let event_source = EventSource::testing(80, 12, events);
+    let recorder = Recorder::new(search_contents(), event_source);
+    let state = recorder.run()?;
+
+    insta::assert_display_snapshot!(after_toggle, @r###"
+    "[×] README.md                                                                   "
+    "  [×] Section 1/1                                                               "
+    "    (×) + Call foo() to get started.                                            "
+    "[×] src/foo.rs                                                                  "
+    "  [×] Section 1/1                                                               "
+    "    [×] - fn old() {}                                                           "
+    "    [×] + fn new() {}                                                           "
+    "[~] src/lib.rs                                                                  "
+    "  [~] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [×] + mod foo;                                                              "
+    "Search: foo (1/3)                                                               "
+    "###);
+    insta::assert_display_snapshot!(after_filtered_toggle, @r###"
+    "(×) src/foo.rs                                                                  "
+    "  [×] Section 1/1                                                               "
+    "    [×] - fn old() {}                                                           "
+    "    [×] + fn new() {}                                                           "
+    "[×] src/lib.rs                                                                  "
+    "  [×] Section 1/1                                                               "
+    "    [×] + mod bar;                                                              "
+    "    [×] + mod foo;                                                              "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "Filter: src/*                                                                   "
+    "###);
+    let selected_contents: Vec<_> = state
+        .files
+        .iter()
+        .map(|file| file.get_selected_contents().0)
+        .collect();
+    insta::assert_debug_snapshot!(selected_contents, @r###"
+    [
+        Present {
+            contents: "Call foo() to get started.\n",
+        },
+        Present {
+            contents: "fn new() {}\n",
+        },
+        Present {
+            contents: "mod bar;\nmod foo;\n",
+        },
+    ]
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_keymap_custom_bindings() -> eyre::Result<()> {
+    let key = |code, modifiers| crossterm::event::Event::Key(KeyEvent::new(code, modifiers));
+    let keymap = Keymap::from_bindings([("focusNext", "j, down"), ("FOCUSPREV", "k,ctrl-p")])?;
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Char('j'), KeyModifiers::NONE)),
+        Event::FocusNext
+    );
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Down, KeyModifiers::NONE)),
+        Event::FocusNext
+    );
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Char('p'), KeyModifiers::CONTROL)),
+        Event::FocusPrev
+    );
+    // The default binding is replaced.
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Up, KeyModifiers::NONE)),
+        Event::None
+    );
+    // Other actions keep their default bindings.
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Char('q'), KeyModifiers::NONE)),
+        Event::QuitCancel
+    );
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Char('N'), KeyModifiers::SHIFT)),
+        Event::SearchPrev
+    );
+
+    let path = std::env::temp_dir().join(format!("scm-record-keymap-{}", std::process::id()));
+    std::fs::write(
+        &path,
+        "# Emacs-style bindings\nscrollDown = ctrl-v\npageDown = pagedown\n",
+    )?;
+    let keymap = Keymap::from_file(&path);
+    std::fs::remove_file(&path)?;
+    let keymap = keymap?;
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Char('v'), KeyModifiers::CONTROL)),
+        Event::ScrollDown
+    );
+    assert_eq!(
+        keymap.event_for(key(KeyCode::Char('f'), KeyModifiers::CONTROL)),
+        Event::None
+    );
+    Ok(())
+}
+
+#[test]
+fn test_keymap_errors() {
+    insta::assert_display_snapshot!(
+        Keymap::from_bindings([("accept", "q")]).unwrap_err(),
+        @r###"
+    key q is bound to both accept and quit
+    "###
+    );
+    insta::assert_display_snapshot!(
+        Keymap::from_bindings([("toggleItem", "x"), ("toggleMatches", "X, x")]).unwrap_err(),
+        @r###"
+    key x is bound to both toggleItem and toggleMatches
+    "###
+    );
+    insta::assert_display_snapshot!(
+        Keymap::from_bindings([("frobnicate", "x")]).unwrap_err(),
+        @r###"
+    unknown action: frobnicate
+    "###
+    );
+    insta::assert_display_snapshot!(
+        Keymap::from_bindings([("accept", "hyper-x")]).unwrap_err(),
+        @r###"
+    invalid key chord for action accept: hyper-x
+    "###
+    );
+}
+
+#[test]
+fn test_help_dialog() -> eyre::Result<()> {
+    let help_dialog = TestingScreenshot::default();
+    let after_close = TestingScreenshot::default();
+    let event_source = EventSource::testing(
+        80,
+        30,
+        [
+            Event::Help,
+            help_dialog.event(),
+            Event::FocusNext,
+            after_close.event(),
+            Event::QuitAccept,
+        ],
+    );
+    let keymap = Keymap::from_bindings([("focusNext", "j, down"), ("focusPrev", "k, up")])?;
+    let recorder = Recorder::new(search_contents(), event_source).with_keymap(keymap);
+    recorder.run()?;
+    insta::assert_display_snapshot!(help_dialog, @r###"
+    "( ) README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + Call foo() to get started.                                            "
+    "[ ] sr┌Key bindings (press any key to close)─────────────────────────────┐      "
+    "  [ ] │c                 Confirm the selected changes                    │      "
+    "    [ │q                 Quit without confirming changes                 │      "
+    "    [ │ctrl-c            Quit immediately                                │      "
+    "[ ] sr│?                 Show the key bindings                           │      "
+    "  [ ] │k, up             Select the previous item                        │      "
+    "    [ │j, down           Select the next item                            │      "
+    "    [ │left              Select the outer item                           │      "
+    "      │right             Select the inner item                           │      "
+    "      │ctrl-u            Select an item half a page up                   │      "
+    "      │ctrl-d            Select an item half a page down                 │      "
+    "      │ctrl-y            Scroll up one line                              │      "
+    "      │ctrl-e            Scroll down one line                            │      "
+    "      │pageup, ctrl-b    Scroll up one page                              │      "
+    "      │pagedown, ctrl-f  Scroll down one page                            │      "
+    "      │space             Toggle the selected item                        │      "
+    "      │enter             Toggle the selected item and select the next one│      "
+    "      │e                 Edit the selected section as text               │      "
+    "      │/                 Search files and changed lines                  │      "
+    "      │n                 Select the next search match                    │      "
+    "      │N                 Select the previous search match                │      "
+    "      │f                 Filter files by path glob                       │      "
+    "      │a                 Toggle all search matches or filtered files     │      "
+    "      └──────────────────────────────────────────────────────────────────┘      "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "###);
+    insta::assert_display_snapshot!(after_close, @r###"
+    "( ) README.md                                                                   "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + Call foo() to get started.                                            "
+    "[ ] src/foo.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - fn old() {}                                                           "
+    "    [ ] + fn new() {}                                                           "
+    "[ ] src/lib.rs                                                                  "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] + mod bar;                                                              "
+    "    [ ] + mod foo;                                                              "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "                                                                                "
+    "###);
+    Ok(())
+}
+
+#[test]
+fn test_word_diff_emphasis() -> eyre::Result<()> {
+    let state = RecordState {
+        files: vec![File {
+            path: Cow::Borrowed(Path::new("src/main.rs")),
+            file_mode: None,
+            sections: vec![Section::Changed {
+                lines: vec![
+                    SectionChangedLine {
+                        is_toggled: false,
+                        change_type: ChangeType::Removed,
+                        line: Cow::Borrowed("let total = compute(items, 10);\n"),
+                    },
+                    SectionChangedLine {
+                        is_toggled: false,
+                        change_type: ChangeType::Removed,
+                        line: Cow::Borrowed("println!(\"done\");\n"),
+                    },
+                    SectionChangedLine {
+                        is_toggled: false,
+                        change_type: ChangeType::Added,
+                        line: Cow::Borrowed("let total = compute_all(items, 20);\n"),
+                    },
+                    SectionChangedLine {
+                        is_toggled: false,
+                        change_type: ChangeType::Added,
+                        line: Cow::Borrowed("return total;\n"),
+                    },
+                    SectionChangedLine {
+                        is_toggled: false,
+                        change_type: ChangeType::Added,
+                        line: Cow::Borrowed("// unpaired\n"),
+                    },
+                ],
+            }],
+        }],
+    };
+
+    let initial = TestingScreenshot::with_emphasis();
+    let toggled_line = TestingScreenshot::with_emphasis();
+    let event_source = EventSource::testing(
+        80,
+        7,
+        [
+            initial.event(),
+            Event::FocusNext,
+            Event::FocusNext,
+            Event::FocusNext,
+            Event::FocusNext,
+            Event::ToggleItem,
+            toggled_line.event(),
+            Event::QuitAccept,
+        ],
+    );
+    let recorder = Recorder::new(state, event_source);
+    let state = recorder.run()?;
+
+    insta::assert_display_snapshot!(initial, @r###"
+    "( ) src/main.rs                                                                 "
+    "  [ ] Section 1/1                                                               "
+    "    [ ] - let total = compute(items, 10);                                       "
+                           ^^^^^^^        ^^
+    "    [ ] - println!("done");                                                     "
+    "    [ ] + let total = compute_all(items, 20);                                   "
+                           ^^^^^^^^^^^        ^^
+    "    [ ] + return total;                                                         "
+    "    [ ] + // unpaired                                                           "
+    "###);
+    insta::assert_display_snapshot!(toggled_line, @r###"
+    "[~] src/main.rs                                                                 "
+    "  [~] Section 1/1                                                               "
+    "    [ ] - let total = compute(items, 10);                                       "
+                           ^^^^^^^        ^^
+    "    [ ] - println!("done");                                                     "
+    "    (×) + let total = compute_all(items, 20);                                   "
+                           ^^^^^^^^^^^        ^^
+    "    [ ] + return total;                                                         "
+    "    [ ] + // unpaired                                                           "
+    "###);
+
+    assert_debug_snapshot!(state.files[0].get_selected_contents(), @r###"
+    (
+        Present {
+            contents: "let total = compute(items, 10);\nprintln!(\"done\");\nlet total = compute_all(items, 20);\n",
+        },
+        Present {
+            contents: "return total;\n// unpaired\n",
+        },
+    )
+    "###);
+    Ok(())
+}